@@ -0,0 +1,301 @@
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::{parse_quote, Attribute, DataEnum, Fields, Ident, LitStr, Member, Path, Type};
+
+use crate::case::Case;
+
+// How an enum says which variant a value is.
+#[derive(Clone, PartialEq)]
+pub enum Tagging {
+    // {"Variant": content}, or "Variant" for a unit variant
+    External,
+    // {"tag": "Variant", ...fields}. A newtype variant's value gets the tag
+    // added when it is an object, anything else is written as
+    // {"tag": "Variant", "value": value}
+    Internal(String),
+    // {"tag": "Variant", "content": content}
+    Adjacent(String, String),
+    // just the content, read as the first variant that fits
+    Untagged
+}
+
+// Where an internally tagged newtype variant puts a value that is not an
+// object.
+pub const NEWTYPE_CONTENT: &str = "value";
+
+// Options from `#[json(...)]` on the struct or enum itself. rename_all
+// applies to the fields of a struct and to the variants of an enum, and
+// deny_unknown_fields to the keys of the struct or of every variant.
+#[derive(Default)]
+pub struct Container {
+    pub rename_all: Option<Case>,
+    pub tag: Option<String>,
+    pub content: Option<String>,
+    pub untagged: bool,
+    pub deny_unknown_fields: bool
+}
+
+impl Container {
+    pub fn tagging(&self) -> Tagging {
+        match (&self.tag, &self.content, self.untagged) {
+            (_, _, true) => return Tagging::Untagged,
+            (Some(tag), Some(content), _) => return Tagging::Adjacent(tag.clone(), content.clone()),
+            (Some(tag), None, _) => return Tagging::Internal(tag.clone()),
+            (None, _, _) => return Tagging::External
+        }
+    }
+}
+
+// A variant with its name resolved. rename_all on a variant applies to its
+// fields.
+pub struct Variant<'a> {
+    pub ident: &'a Ident,
+    pub name: String,
+    pub shape: &'a Fields,
+    pub fields: Vec<Field>
+}
+
+// What a missing field is filled in with.
+#[derive(Clone)]
+pub enum FieldDefault {
+    // Default::default()
+    Trait,
+    // a function taking nothing that returns the field's type
+    Function(Path)
+}
+
+// Options from `#[json(...)]` on a field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub rename: Option<String>,
+    pub aliases: Vec<String>,
+    pub default: Option<FieldDefault>,
+    pub flatten: bool,
+    pub skip: bool,
+    pub skip_serializing_if: Option<Path>,
+    pub serialize_with: Option<Path>,
+    pub deserialize_with: Option<Path>
+}
+
+// A field with its options resolved, ready for the impls to use.
+pub struct Field {
+    pub member: Member,
+    // the key written out and read first
+    pub key: String,
+    // other keys accepted when reading
+    pub aliases: Vec<String>,
+    pub default: Option<FieldDefault>,
+    // an Option, which is None when its key is missing
+    pub optional: bool,
+    // the field's own keys sit in the parent object rather than under its key
+    pub flatten: bool,
+    pub ty: Type,
+    // never written and always its default when read
+    pub skip: bool,
+    // a function given the field by reference, the field is left out when it
+    // returns true
+    pub skip_serializing_if: Option<Path>,
+    // functions used in place of ToJson and FromJson, with the same shape as
+    // the trait methods
+    pub serialize_with: Option<Path>,
+    pub deserialize_with: Option<Path>
+}
+
+fn json_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    return attrs.iter().filter(|attr| attr.path().is_ident("json"));
+}
+
+fn string(meta: &ParseNestedMeta) -> syn::Result<String> {
+    let value: LitStr = meta.value()?.parse()?;
+    return Ok(value.value());
+}
+
+fn case(meta: &ParseNestedMeta) -> syn::Result<Case> {
+    return Case::parse(&string(meta)?).ok_or_else(|| meta.error("unknown case, expected one of lowercase, UPPERCASE, PascalCase, camelCase, snake_case, SCREAMING_SNAKE_CASE, kebab-case or SCREAMING-KEBAB-CASE"));
+}
+
+pub fn container(attrs: &[Attribute]) -> syn::Result<Container> {
+    let mut container = Container::default();
+    for attr in json_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                container.rename_all = Some(case(&meta)?);
+            } else if meta.path.is_ident("tag") {
+                container.tag = Some(string(&meta)?);
+            } else if meta.path.is_ident("content") {
+                container.content = Some(string(&meta)?);
+            } else if meta.path.is_ident("untagged") {
+                container.untagged = true;
+            } else if meta.path.is_ident("deny_unknown_fields") {
+                container.deny_unknown_fields = true;
+            } else {
+                return Err(meta.error("unknown json attribute"));
+            }
+            return Ok(());
+        })?;
+
+        if container.content.is_some() && container.tag.is_none() {
+            return Err(syn::Error::new_spanned(attr, "content needs a tag"));
+        }
+        if container.untagged && container.tag.is_some() {
+            return Err(syn::Error::new_spanned(attr, "an untagged enum cannot have a tag"));
+        }
+    }
+    return Ok(container);
+}
+
+pub fn variants<'a>(data: &'a DataEnum, container: &Container) -> syn::Result<Vec<Variant<'a>>> {
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let mut rename: Option<String> = None;
+        let mut rename_all: Option<Case> = None;
+        for attr in json_attrs(&variant.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(string(&meta)?);
+                } else if meta.path.is_ident("rename_all") {
+                    rename_all = Some(case(&meta)?);
+                } else {
+                    return Err(meta.error("unknown json attribute"));
+                }
+                return Ok(());
+            })?;
+        }
+
+        // an internal tag sits among the other keys, so there has to be an
+        // object to put it in
+        if matches!(container.tagging(), Tagging::Internal(_)) && matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() > 1) {
+            return Err(syn::Error::new_spanned(variant, "tuple variants cannot be internally tagged"));
+        }
+        if let (Tagging::Internal(_), Fields::Unnamed(fields)) = (container.tagging(), &variant.fields) {
+            if let Some(field) = fields.unnamed.first().filter(|field| is_scalar(&field.ty)) {
+                return Err(syn::Error::new_spanned(&field.ty, "internally tagged newtype variants must hold a struct or map, use adjacent tagging for this type"));
+            }
+        }
+
+        let name = variant.ident.unraw().to_string();
+        variants.push(Variant {
+            ident: &variant.ident,
+            name: rename.unwrap_or_else(|| container.rename_all.map(|case| case.apply(&name)).unwrap_or(name)),
+            shape: &variant.fields,
+            fields: fields(&variant.fields, rename_all)?
+        });
+    }
+    return Ok(variants);
+}
+
+pub fn field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut field = FieldAttrs::default();
+    for attr in json_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                field.rename = Some(string(&meta)?);
+            } else if meta.path.is_ident("alias") {
+                field.aliases.push(string(&meta)?);
+            } else if meta.path.is_ident("flatten") {
+                field.flatten = true;
+            } else if meta.path.is_ident("skip") {
+                field.skip = true;
+            } else if meta.path.is_ident("skip_serializing_if") {
+                field.skip_serializing_if = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("serialize_with") {
+                field.serialize_with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("deserialize_with") {
+                field.deserialize_with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("with") {
+                // a module with to_json and from_json functions
+                let module: Path = meta.value()?.parse::<LitStr>()?.parse()?;
+                field.serialize_with = Some(parse_quote!(#module::to_json));
+                field.deserialize_with = Some(parse_quote!(#module::from_json));
+            } else if meta.path.is_ident("default") {
+                field.default = Some(match meta.value() {
+                    Ok(value) => FieldDefault::Function(value.parse::<LitStr>()?.parse()?),
+                    Err(_) => FieldDefault::Trait
+                });
+            } else {
+                return Err(meta.error("unknown json attribute"));
+            }
+            return Ok(());
+        })?;
+    }
+    return Ok(field);
+}
+
+// Going by the name alone, as a derive cannot resolve types. Covers
+// `Option<T>` however it is imported or qualified.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => return path.path.segments.last().is_some_and(|segment| segment.ident == "Option"),
+        _ => return false
+    }
+}
+
+// Types that are known never to be a JSON object, again by name: primitives,
+// strings, sequences, tuples and references to any of them.
+fn is_scalar(ty: &Type) -> bool {
+    const NAMES: [&str; 24] = [
+        "bool", "char", "str", "String", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128",
+        "usize", "f32", "f64", "Vec", "VecDeque", "HashSet", "BTreeSet", "Option", "PathBuf"
+    ];
+    match ty {
+        Type::Path(path) if path.qself.is_none() => return path.path.segments.last().is_some_and(|segment| NAMES.iter().any(|name| segment.ident == name)),
+        Type::Reference(reference) => return is_scalar(&reference.elem),
+        Type::Paren(inner) => return is_scalar(&inner.elem),
+        Type::Array(_) | Type::Slice(_) | Type::Tuple(_) => return true,
+        _ => return false
+    }
+}
+
+// Named fields are keyed by their name, after rename_all, unless renamed.
+// Tuple fields have no key.
+pub fn fields(fields: &Fields, rename_all: Option<Case>) -> syn::Result<Vec<Field>> {
+    let mut resolved = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = field_attrs(&field.attrs)?;
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.unraw().to_string()),
+            None => (Member::Unnamed(i.into()), i.to_string())
+        };
+        let key = match (attrs.rename, rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(case)) => case.apply(&name),
+            (None, None) => name
+        };
+        if (attrs.flatten || attrs.skip || attrs.skip_serializing_if.is_some()) && field.ident.is_none() {
+            return Err(syn::Error::new_spanned(field, "only named fields can be flattened or skipped"));
+        }
+        resolved.push(Field {
+            member,
+            key,
+            aliases: attrs.aliases,
+            default: attrs.default,
+            optional: is_option(&field.ty),
+            flatten: attrs.flatten,
+            ty: field.ty.clone(),
+            skip: attrs.skip,
+            skip_serializing_if: attrs.skip_serializing_if,
+            serialize_with: attrs.serialize_with,
+            deserialize_with: attrs.deserialize_with
+        });
+    }
+    return Ok(resolved);
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use crate::attr::is_scalar;
+
+    #[test]
+    fn scalar_types() {
+        assert!(is_scalar(&parse_quote!(f64)));
+        assert!(is_scalar(&parse_quote!(::std::string::String)));
+        assert!(is_scalar(&parse_quote!(Vec<Item>)));
+        assert!(is_scalar(&parse_quote!(&'a str)));
+        assert!(is_scalar(&parse_quote!((u8, u8))));
+        assert!(!is_scalar(&parse_quote!(Item)));
+        assert!(!is_scalar(&parse_quote!(HashMap<String, u8>)));
+        assert!(!is_scalar(&parse_quote!(Box<Item>)));
+    }
+}
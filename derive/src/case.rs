@@ -0,0 +1,86 @@
+// The spellings `rename_all` can convert field and variant names to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab
+}
+
+impl Case {
+    pub fn parse(s: &str) -> Option<Case> {
+        match s {
+            "lowercase" => return Some(Case::Lower),
+            "UPPERCASE" => return Some(Case::Upper),
+            "PascalCase" => return Some(Case::Pascal),
+            "camelCase" => return Some(Case::Camel),
+            "snake_case" => return Some(Case::Snake),
+            "SCREAMING_SNAKE_CASE" => return Some(Case::ScreamingSnake),
+            "kebab-case" => return Some(Case::Kebab),
+            "SCREAMING-KEBAB-CASE" => return Some(Case::ScreamingKebab),
+            _ => return None
+        }
+    }
+
+    // Works from either a snake_case field or a PascalCase variant.
+    pub fn apply(self, name: &str) -> String {
+        let words = words(name);
+        match self {
+            Case::Lower => return words.concat(),
+            Case::Upper => return words.concat().to_uppercase(),
+            Case::Pascal => return words.iter().map(|w| capitalize(w)).collect(),
+            Case::Camel => {
+                let mut out = words.first().cloned().unwrap_or_default();
+                out.extend(words.iter().skip(1).map(|w| capitalize(w)));
+                return out;
+            },
+            Case::Snake => return words.join("_"),
+            Case::ScreamingSnake => return words.join("_").to_uppercase(),
+            Case::Kebab => return words.join("-"),
+            Case::ScreamingKebab => return words.join("-").to_uppercase()
+        }
+    }
+}
+
+// Lowercase words split at underscores and at the start of each capital.
+fn words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for c in name.chars() {
+        if (c == '_' || c.is_uppercase()) && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if c != '_' {
+            current.extend(c.to_lowercase());
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    return words;
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    return chars.next().map(|c| c.to_uppercase().collect::<String>() + chars.as_str()).unwrap_or_default();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::case::Case;
+
+    #[test]
+    fn converts_fields_and_variants() {
+        assert_eq!("userName", Case::Camel.apply("user_name"));
+        assert_eq!("UserName", Case::Pascal.apply("user_name"));
+        assert_eq!("USER_NAME", Case::ScreamingSnake.apply("user_name"));
+        assert_eq!("user-name", Case::Kebab.apply("UserName"));
+        assert_eq!("user_name", Case::Snake.apply("UserName"));
+        assert_eq!("username", Case::Lower.apply("UserName"));
+        assert_eq!(None, Case::parse("Title Case"));
+    }
+}
@@ -0,0 +1,347 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Fields};
+
+use crate::attr::{self, Container, Field, FieldDefault, Tagging};
+
+// A field read from `value`, through its deserialize_with function if it has
+// one. It is read at the field's key or index, so errors get their path,
+// unless it has no place of its own, as for a newtype or a flattened field.
+fn from_json(field: &Field, value: TokenStream, segment: Option<TokenStream>) -> TokenStream {
+    let call = match &field.deserialize_with {
+        Some(path) => quote! { #path(#value) },
+        None => quote! { ::jsonrs::FromJson::from_json(#value) }
+    };
+    match segment {
+        Some(segment) => return quote! { ::jsonrs::traits::read_at(#segment, || #call)? },
+        None => return quote! { #call? }
+    }
+}
+
+fn key_segment(key: &str) -> TokenStream {
+    return quote! { ::jsonrs::PathSegment::Key(::std::string::String::from(#key)) };
+}
+
+// An expression building `ctor` from `value`, the reverse of to_json's
+// content. What a unit becomes depends on where it is, so it is passed in.
+// `reserved` are keys that belong to the enclosing enum, i.e. its tag. With
+// `deny` any other key no field claims is an error.
+fn read(shape: &Fields, fields: &[Field], ctor: &TokenStream, unit: TokenStream, reserved: Option<&str>, deny: bool) -> TokenStream {
+    match shape {
+        Fields::Named(_) => {
+            let claimed: Vec<&str> = fields.iter().filter(|field| !field.flatten && !field.skip).flat_map(|field| std::iter::once(&field.key).chain(&field.aliases)).map(String::as_str).chain(reserved).collect();
+            // the keys present that no field claims, which a missing key is
+            // most likely a typo of
+            let unclaimed = quote! { pairs.iter().map(|(k, _)| k.as_str()).filter(|k| ![#(#claimed),*].contains(k)) };
+
+            // flattened fields are read from the members no other field
+            // claims, copied into an object of their own
+            let rest = if fields.iter().any(|field| field.flatten) {
+                quote! {
+                    let rest = ::jsonrs::JsonExpression::Object(pairs.iter().filter(|(k, _)| ![#(#claimed),*].contains(&k.as_str())).cloned().collect());
+                }
+            } else {
+                quote! {}
+            };
+
+            let denied = if deny {
+                let keys = fields.iter().filter(|field| !field.skip).map(|field| &field.key);
+                quote! {
+                    if let ::std::option::Option::Some(unknown) = #unclaimed.next() {
+                        return ::std::result::Result::Err(::jsonrs::traits::did_you_mean(&::std::format!("Unknown field {}", unknown), unknown, [#(#keys),*]));
+                    }
+                }
+            } else {
+                quote! {}
+            };
+
+            // the key is tried along with its aliases, the last of them in
+            // the object wins as it does for lookups. A missing key falls
+            // back to the field's default, None for an Option, or is an error.
+            let inits = fields.iter().map(|field| {
+                let (member, key, aliases) = (&field.member, &field.key, &field.aliases);
+                if field.flatten {
+                    let read = from_json(field, quote! { &rest }, None);
+                    let others: Vec<&Field> = fields.iter().filter(|other| other.flatten && !std::ptr::eq(*other, field)).collect();
+                    if others.is_empty() {
+                        return quote! { #member: #read };
+                    }
+                    // keys another flattened field reads are left to it
+                    let others = field_keys(&others);
+                    return quote! {
+                        #member: {
+                            let others: ::std::vec::Vec<&str> = #others;
+                            let rest = ::jsonrs::JsonExpression::Object(rest.entries().filter(|(k, _)| !others.contains(k)).map(|(k, v)| (::std::string::String::from(k), ::std::boxed::Box::new(v.clone()))).collect());
+                            #read
+                        }
+                    };
+                }
+                let missing = match (&field.default, field.optional) {
+                    (Some(FieldDefault::Trait), _) => quote! { ::std::default::Default::default() },
+                    (None, _) if field.skip => quote! { ::std::default::Default::default() },
+                    (Some(FieldDefault::Function(path)), _) => quote! { #path() },
+                    (None, true) => quote! { ::std::option::Option::None },
+                    (None, false) => {
+                        let message = format!("Missing field {}", key);
+                        quote! { return ::std::result::Result::Err(::jsonrs::traits::did_you_mean(#message, #key, #unclaimed)) }
+                    }
+                };
+                if field.skip {
+                    return quote! { #member: #missing };
+                }
+                let read = from_json(field, quote! { v }, Some(key_segment(key)));
+                return quote! {
+                    #member: match field(&[#key #(, #aliases)*]) {
+                        ::std::option::Option::Some(v) => #read,
+                        ::std::option::Option::None => #missing
+                    }
+                };
+            });
+            return quote! {
+                let pairs = match value {
+                    ::jsonrs::JsonExpression::Object(pairs) => pairs,
+                    other => return ::std::result::Result::Err(::std::format!("Expected object, found {}...", other.type_name()))
+                };
+                let field = |names: &[&str]| pairs.iter().rev().find(|(k, _)| names.contains(&k.as_str())).map(|(_, v)| v.as_ref());
+                #denied
+                #rest
+                ::std::result::Result::Ok(#ctor { #(#inits),* })
+            };
+        },
+        Fields::Unnamed(_) if fields.len() == 1 => {
+            let read = from_json(&fields[0], quote! { value }, None);
+            return quote! { ::std::result::Result::Ok(#ctor(#read)) };
+        },
+        Fields::Unnamed(_) => {
+            let len = fields.len();
+            let wrong = format!("Expected array of {} elements, found {{}}...", len);
+            let reads = fields.iter().enumerate().map(|(i, field)| from_json(field, quote! { &elements[#i] }, Some(quote! { ::jsonrs::PathSegment::Index(#i) })));
+            return quote! {
+                match value {
+                    ::jsonrs::JsonExpression::Array(elements) if elements.len() == #len => {
+                        ::std::result::Result::Ok(#ctor(#(#reads),*))
+                    },
+                    other => ::std::result::Result::Err(::std::format!(#wrong, other.type_name()))
+                }
+            };
+        },
+        Fields::Unit => return unit
+    }
+}
+
+// An expression for the keys the flattened fields read, the fields read
+// through deserialize_with have no keys known to the trait.
+fn field_keys(flattened: &[&Field]) -> TokenStream {
+    let keys = flattened.iter().filter(|field| field.deserialize_with.is_none()).map(|field| {
+        let ty = &field.ty;
+        return quote! { <#ty as ::jsonrs::FromJson>::field_keys() };
+    });
+    return quote! {
+        {
+            let mut keys: ::std::vec::Vec<&'static str> = ::std::vec::Vec::new();
+            #(keys.extend(#keys);)*
+            keys
+        }
+    };
+}
+
+// A unit that has to be written as null.
+fn null(ctor: &TokenStream) -> TokenStream {
+    return quote! {
+        match value {
+            ::jsonrs::JsonExpression::Null => ::std::result::Result::Ok(#ctor),
+            other => ::std::result::Result::Err(::std::format!("Expected null, found {}...", other.type_name()))
+        }
+    };
+}
+
+// Each variant is read in a closure of its own so the early returns for its
+// errors stay inside it, which untagged enums rely on to try the next one.
+fn closure(body: TokenStream) -> TokenStream {
+    return quote! {
+        (|value: &::jsonrs::JsonExpression| -> ::std::result::Result<Self, ::std::string::String> { #body })
+    };
+}
+
+// Statements finding the tag of an internally or adjacently tagged enum and
+// binding it as `name`.
+fn tag_name(tag: &str) -> TokenStream {
+    let missing = format!("Missing tag {}...", tag);
+    let not_string = format!("Expected tag {} to be a string, found {{}}...", tag);
+    return quote! {
+        if !::std::matches!(value, ::jsonrs::JsonExpression::Object(_)) {
+            return ::std::result::Result::Err(::std::format!("Expected object, found {}...", value.type_name()));
+        }
+        let name = match value.entries().filter(|(k, _)| *k == #tag).last() {
+            ::std::option::Option::Some((_, ::jsonrs::JsonExpression::String(name))) => name.as_str(),
+            ::std::option::Option::Some((_, other)) => return ::std::result::Result::Err(::std::format!(#not_string, other.type_name())),
+            ::std::option::Option::None => return ::std::result::Result::Err(::std::string::String::from(#missing))
+        };
+    };
+}
+
+// A flattened field takes every key the others leave, so there would be no
+// unknown keys to deny.
+fn deny_with_flatten(input: &DeriveInput, container: &Container, fields: &[Field]) -> syn::Result<()> {
+    if container.deny_unknown_fields && fields.iter().any(|field| field.flatten) {
+        return Err(syn::Error::new_spanned(&input.ident, "deny_unknown_fields cannot be used with flattened fields"));
+    }
+    return Ok(());
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let container = attr::container(&input.attrs)?;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = attr::fields(&data.fields, container.rename_all)?;
+            deny_with_flatten(input, &container, &fields)?;
+            let ctor = quote! { Self };
+            read(&data.fields, &fields, &ctor, null(&ctor), None, container.deny_unknown_fields)
+        },
+        Data::Enum(data) => {
+            let variants = attr::variants(data, &container)?;
+            for variant in &variants {
+                deny_with_flatten(input, &container, &variant.fields)?;
+            }
+            let tagging = container.tagging();
+            let reserved = match &tagging {
+                Tagging::Internal(tag) => Some(tag.as_str()),
+                _ => None
+            };
+            let names = variants.iter().map(|variant| &variant.name);
+            let unknown = quote! {
+                other => ::std::result::Result::Err(::jsonrs::traits::did_you_mean(&::std::format!("Unknown variant {}", other), other, [#(#names),*]))
+            };
+            // every variant read from `value`, matched on its name. Errors are
+            // placed under the key the variant's value sits at, if any.
+            let arms: Vec<TokenStream> = variants.iter().map(|variant| {
+                let (ident, name) = (variant.ident, &variant.name);
+                let ctor = quote! { Self::#ident };
+                let unit = quote! { ::std::result::Result::Ok(#ctor) };
+                let read = closure(read(variant.shape, &variant.fields, &ctor, unit, reserved, container.deny_unknown_fields));
+                let segment = match &tagging {
+                    Tagging::External => Some(key_segment(name)),
+                    Tagging::Adjacent(_, content) => Some(key_segment(content)),
+                    _ => None
+                };
+                if let (Tagging::Internal(_), Fields::Unnamed(_)) = (&tagging, variant.shape) {
+                    // a value that was not an object sits next to the tag,
+                    // which is all the object then holds
+                    let content = attr::NEWTYPE_CONTENT;
+                    return quote! {
+                        #name => {
+                            let content = match value {
+                                ::jsonrs::JsonExpression::Object(pairs) if pairs.len() == 2 => pairs.iter().find(|(k, _)| k == #content).map(|(_, v)| v.as_ref()),
+                                _ => ::std::option::Option::None
+                            };
+                            match content.map(#read) {
+                                ::std::option::Option::Some(::std::result::Result::Ok(found)) => ::std::result::Result::Ok(found),
+                                _ => #read(value)
+                            }
+                        }
+                    };
+                }
+                match segment {
+                    Some(segment) => return quote! { #name => ::jsonrs::traits::read_at(#segment, || #read(value)) },
+                    None => return quote! { #name => #read(value) }
+                }
+            }).collect();
+
+            match &tagging {
+                Tagging::External => {
+                    let units = variants.iter().filter(|variant| matches!(variant.shape, Fields::Unit)).map(|variant| {
+                        let (ident, name) = (variant.ident, &variant.name);
+                        return quote! { #name => ::std::result::Result::Ok(Self::#ident) };
+                    });
+                    quote! {
+                        match value {
+                            ::jsonrs::JsonExpression::String(name) => match name.as_str() {
+                                #(#units,)*
+                                #unknown
+                            },
+                            ::jsonrs::JsonExpression::Object(pairs) if pairs.len() == 1 => {
+                                let value = pairs[0].1.as_ref();
+                                match pairs[0].0.as_str() {
+                                    #(#arms,)*
+                                    #unknown
+                                }
+                            },
+                            other => ::std::result::Result::Err(::std::format!("Expected string or object with one key, found {}...", other.type_name()))
+                        }
+                    }
+                },
+                Tagging::Internal(tag) => {
+                    let name = tag_name(tag);
+                    quote! {
+                        #name
+                        match name {
+                            #(#arms,)*
+                            #unknown
+                        }
+                    }
+                },
+                Tagging::Adjacent(tag, content) => {
+                    let name = tag_name(tag);
+                    quote! {
+                        #name
+                        // a unit variant has no content
+                        let value = value.entries().filter(|(k, _)| *k == #content).last().map(|(_, v)| v).unwrap_or(&::jsonrs::JsonExpression::Null);
+                        match name {
+                            #(#arms,)*
+                            #unknown
+                        }
+                    }
+                },
+                Tagging::Untagged => {
+                    let attempts = variants.iter().map(|variant| {
+                        let ident = variant.ident;
+                        let ctor = quote! { Self::#ident };
+                        let read = closure(read(variant.shape, &variant.fields, &ctor, null(&ctor), None, container.deny_unknown_fields));
+                        return quote! {
+                            if let ::std::result::Result::Ok(found) = #read(value) {
+                                return ::std::result::Result::Ok(found);
+                            }
+                        };
+                    });
+                    let message = format!("Expected a value matching a variant of {}...", input.ident);
+                    quote! {
+                        #(#attempts)*
+                        ::std::result::Result::Err(::std::string::String::from(#message))
+                    }
+                }
+            }
+        },
+        Data::Union(data) => return Err(syn::Error::new_spanned(data.union_token, "FromJson cannot be derived for unions"))
+    };
+
+    // a struct with named fields reads the keys of its fields and of the
+    // fields flattened into it
+    let keys = match &input.data {
+        Data::Struct(data) if matches!(data.fields, Fields::Named(_)) => {
+            let fields = attr::fields(&data.fields, container.rename_all)?;
+            let own = fields.iter().filter(|field| !field.flatten && !field.skip).flat_map(|field| std::iter::once(&field.key).chain(&field.aliases));
+            let flattened = field_keys(&fields.iter().filter(|field| field.flatten).collect::<Vec<&Field>>());
+            quote! {
+                fn field_keys() -> ::std::vec::Vec<&'static str> {
+                    let mut keys: ::std::vec::Vec<&'static str> = ::std::vec![#(#own),*];
+                    keys.extend(#flattened);
+                    return keys;
+                }
+            }
+        },
+        _ => quote! {}
+    };
+
+    let name = &input.ident;
+    let generics = crate::bound(&input.generics, parse_quote!(::jsonrs::FromJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    return Ok(quote! {
+        impl #impl_generics ::jsonrs::FromJson for #name #ty_generics #where_clause {
+            fn from_json(value: &::jsonrs::JsonExpression) -> ::std::result::Result<Self, ::std::string::String> {
+                #body
+            }
+
+            #keys
+        }
+    });
+}
@@ -0,0 +1,73 @@
+#![allow(clippy::needless_return)]
+
+// Derive macros for the FromJson and ToJson traits of jsonrs, enabled there
+// with the `derive` feature. Structs become objects keyed by field name,
+// newtype structs are their inner value, other tuple structs are arrays and
+// unit structs are null. Fields are configured with `#[json(...)]`:
+//
+//     #[derive(FromJson, ToJson)]
+//     #[json(rename_all = "camelCase")]
+//     struct User {
+//         user_name: String,
+//         #[json(rename = "e-mail", alias = "email")]
+//         mail: String,
+//         // missing keys are fine for Option fields and ones with a default
+//         nickname: Option<String>,
+//         #[json(default)]
+//         tags: Vec<String>,
+//         #[json(default = "User::default_limit")]
+//         limit: u32,
+//         // its keys sit in User's object, and it reads every key no plain
+//         // field claims, less the keys other flattened structs read
+//         #[json(flatten)]
+//         audit: Audit,
+//         // left out when None rather than written as null
+//         #[json(skip_serializing_if = "Option::is_none")]
+//         avatar: Option<String>,
+//         // never written, and its default when read
+//         #[json(skip)]
+//         session: Session,
+//         // converted by epoch_millis::to_json and epoch_millis::from_json,
+//         // serialize_with and deserialize_with name one function each
+//         #[json(with = "epoch_millis")]
+//         created: SystemTime
+//     }
+//
+// Enums are externally tagged unless told otherwise: a unit variant is its
+// name and any other is an object with the name as its only key. A container
+// level `tag = "type"` puts the name among the variant's own keys, adding
+// `content = "data"` puts the variant's value beside the tag instead, and
+// `untagged` writes only the value and reads the first variant that fits.
+//
+// Reading ignores keys no field claims unless the container has
+// `#[json(deny_unknown_fields)]`. Errors for an unknown key, a missing key or
+// an unknown variant suggest the closest name when one looks like a typo.
+
+mod attr;
+mod case;
+mod from_json;
+mod to_json;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, parse_quote, DeriveInput, Generics};
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return to_json::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into();
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    return from_json::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into();
+}
+
+// Every type parameter has to implement the trait being derived.
+fn bound(generics: &Generics, bound: syn::Path) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(#bound));
+    }
+    return generics;
+}
@@ -0,0 +1,154 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DeriveInput, Fields};
+
+use crate::attr::{self, Field, Tagging};
+
+// A field's value converted, through its serialize_with function if it has
+// one.
+fn to_json(field: &Field, value: TokenStream) -> TokenStream {
+    match &field.serialize_with {
+        Some(path) => return quote! { #path(#value) },
+        None => return quote! { ::jsonrs::ToJson::to_json(#value) }
+    }
+}
+
+// Statements collecting the named fields into `pairs`, `value` gives the
+// expression for a reference to each field. A flattened field adds its own
+// members when it is an object and nothing otherwise, so a None disappears.
+fn push_fields(fields: &[Field], value: &dyn Fn(usize, &Field) -> TokenStream) -> TokenStream {
+    let pushes = fields.iter().enumerate().filter(|(_, field)| !field.skip).map(|(i, field)| {
+        let (key, value) = (&field.key, value(i, field));
+        let json = to_json(field, value.clone());
+        let push = if field.flatten {
+            quote! {
+                if let ::jsonrs::JsonExpression::Object(inner) = #json {
+                    pairs.extend(inner);
+                }
+            }
+        } else {
+            quote! {
+                pairs.push((::std::string::String::from(#key), ::std::boxed::Box::new(#json)));
+            }
+        };
+        match &field.skip_serializing_if {
+            Some(skip) => return quote! { if !#skip(#value) { #push } },
+            None => return push
+        }
+    });
+    return quote! {
+        let mut pairs: ::std::vec::Vec<(::std::string::String, ::std::boxed::Box<::jsonrs::JsonExpression>)> = ::std::vec::Vec::new();
+        #(#pushes)*
+    };
+}
+
+// The value a struct or variant holds: an object for named fields, the inner
+// value for a newtype, an array for a tuple and null for a unit.
+fn content(shape: &Fields, fields: &[Field], value: &dyn Fn(usize, &Field) -> TokenStream) -> TokenStream {
+    match shape {
+        Fields::Named(_) => {
+            let push = push_fields(fields, value);
+            return quote! { { #push ::jsonrs::JsonExpression::Object(pairs) } };
+        },
+        Fields::Unnamed(_) if fields.len() == 1 => return to_json(&fields[0], value(0, &fields[0])),
+        Fields::Unnamed(_) => {
+            let values = fields.iter().enumerate().map(|(i, field)| to_json(field, value(i, field)));
+            return quote! { ::jsonrs::JsonExpression::Array(::std::vec![#(::std::boxed::Box::new(#values)),*]) };
+        },
+        Fields::Unit => return quote! { ::jsonrs::JsonExpression::Null }
+    }
+}
+
+fn pair(key: &str, value: TokenStream) -> TokenStream {
+    return quote! { (::std::string::String::from(#key), ::std::boxed::Box::new(#value)) };
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let container = attr::container(&input.attrs)?;
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = attr::fields(&data.fields, container.rename_all)?;
+            content(&data.fields, &fields, &|_, field| {
+                let member = &field.member;
+                return quote! { &self.#member };
+            })
+        },
+        Data::Enum(data) => {
+            let tagging = container.tagging();
+            let arms = attr::variants(data, &container)?.into_iter().map(|variant| {
+                let (ident, name) = (variant.ident, &variant.name);
+                // every shape of variant can be matched with braces, binding
+                // its fields in order
+                let members = variant.fields.iter().map(|field| &field.member);
+                // skipped fields are still bound, so keep the compiler from
+                // warning about them
+                let skipped = variant.fields.iter().enumerate().filter(|(_, field)| field.skip).map(|(i, _)| format_ident!("__field{}", i));
+                let bindings: Vec<_> = (0..variant.fields.len()).map(|i| format_ident!("__field{}", i)).collect();
+                let binding = |i: usize, _: &Field| {
+                    let binding = &bindings[i];
+                    return quote! { #binding };
+                };
+                let tag_value = quote! { ::jsonrs::JsonExpression::String(::std::string::String::from(#name)) };
+
+                let value = match (&tagging, variant.shape) {
+                    (Tagging::External, Fields::Unit) => tag_value,
+                    (Tagging::External, shape) => {
+                        let content = pair(name, content(shape, &variant.fields, &binding));
+                        quote! { ::jsonrs::JsonExpression::Object(::std::vec![#content]) }
+                    },
+                    (Tagging::Internal(tag), Fields::Named(_)) => {
+                        let push = push_fields(&variant.fields, &binding);
+                        let tag = pair(tag, tag_value);
+                        quote! { { #push pairs.insert(0, #tag); ::jsonrs::JsonExpression::Object(pairs) } }
+                    },
+                    // a newtype's value gets the tag added when it is an
+                    // object, anything else has nowhere to put it and goes
+                    // next to the tag instead
+                    (Tagging::Internal(tag), Fields::Unnamed(_)) => {
+                        let tag = pair(tag, tag_value);
+                        let json = to_json(&variant.fields[0], quote! { __field0 });
+                        let content = pair(attr::NEWTYPE_CONTENT, quote! { other });
+                        quote! {
+                            match #json {
+                                ::jsonrs::JsonExpression::Object(mut pairs) => {
+                                    pairs.insert(0, #tag);
+                                    ::jsonrs::JsonExpression::Object(pairs)
+                                },
+                                other => ::jsonrs::JsonExpression::Object(::std::vec![#tag, #content])
+                            }
+                        }
+                    },
+                    (Tagging::Internal(tag) | Tagging::Adjacent(tag, _), Fields::Unit) => {
+                        let tag = pair(tag, tag_value);
+                        quote! { ::jsonrs::JsonExpression::Object(::std::vec![#tag]) }
+                    },
+                    (Tagging::Adjacent(tag, content_key), shape) => {
+                        let tag = pair(tag, tag_value);
+                        let content = pair(content_key, content(shape, &variant.fields, &binding));
+                        quote! { ::jsonrs::JsonExpression::Object(::std::vec![#tag, #content]) }
+                    },
+                    (Tagging::Untagged, shape) => content(shape, &variant.fields, &binding)
+                };
+                return quote! { Self::#ident { #(#members: #bindings),* } => { #(let _ = #skipped;)* #value } };
+            }).collect::<Vec<_>>();
+
+            quote! {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        },
+        Data::Union(data) => return Err(syn::Error::new_spanned(data.union_token, "ToJson cannot be derived for unions"))
+    };
+
+    let name = &input.ident;
+    let generics = crate::bound(&input.generics, parse_quote!(::jsonrs::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    return Ok(quote! {
+        impl #impl_generics ::jsonrs::ToJson for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::jsonrs::JsonExpression {
+                #body
+            }
+        }
+    });
+}
@@ -0,0 +1,61 @@
+/* Generated with cbindgen from src/ffi.rs, regenerate with:
+ *   cbindgen --config cbindgen.toml --output include/jsonrs.h
+ */
+
+#ifndef JSONRS_H
+#define JSONRS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct JsonrsValue JsonrsValue;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * # Safety
+ *
+ * `source` must be a valid NUL terminated string. `error_out` may be null,
+ * otherwise it must be valid for a pointer write.
+ */
+JsonrsValue *jsonrs_parse(const char *source, char **error_out);
+
+/**
+ * # Safety
+ *
+ * `value` must come from this library and not have been freed, `path` must
+ * be a valid NUL terminated string.
+ */
+JsonrsValue *jsonrs_get(const JsonrsValue *value, const char *path);
+
+/**
+ * # Safety
+ *
+ * `value` must come from this library and not have been freed.
+ */
+char *jsonrs_to_string(const JsonrsValue *value, int pretty);
+
+/**
+ * # Safety
+ *
+ * `value` must be null or come from this library and not already be freed.
+ */
+void jsonrs_free(JsonrsValue *value);
+
+/**
+ * # Safety
+ *
+ * `s` must be null or a string returned by this library and not already be
+ * freed.
+ */
+void jsonrs_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* JSONRS_H */
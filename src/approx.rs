@@ -0,0 +1,87 @@
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+// Whether two numbers are within epsilon of each other, measured absolutely
+// for numbers below 1 and relative to the larger one above that.
+fn close(a: f64, b: f64, epsilon: f64) -> bool {
+    return (a - b).abs() <= epsilon * a.abs().max(b.abs()).max(1.0);
+}
+
+// Where two documents first differ, comparing numbers with close.
+fn difference(a: &JsonExpression, b: &JsonExpression, epsilon: f64, path: &mut JsonPath) -> Option<JsonPath> {
+    match (a, b) {
+        (JsonExpression::Number(x), JsonExpression::Number(y)) if close(*x, *y, epsilon) => return None,
+        (JsonExpression::Array(xs), JsonExpression::Array(ys)) if xs.len() == ys.len() => {
+            for (i, (x, y)) in xs.iter().zip(ys).enumerate() {
+                path.push(PathSegment::Index(i));
+                let found = difference(x, y, epsilon, path);
+                path.pop();
+                if found.is_some() {
+                    return found;
+                }
+            }
+            return None;
+        },
+        (JsonExpression::Object(xs), JsonExpression::Object(ys)) if xs.len() == ys.len() => {
+            for ((kx, x), (ky, y)) in xs.iter().zip(ys) {
+                if kx != ky {
+                    return Some(path.child(PathSegment::Key(kx.clone())));
+                }
+                path.push(PathSegment::Key(kx.clone()));
+                let found = difference(x, y, epsilon, path);
+                path.pop();
+                if found.is_some() {
+                    return found;
+                }
+            }
+            return None;
+        },
+        (JsonExpression::Number(_), _) | (JsonExpression::Array(_), _) | (JsonExpression::Object(_), _) => return Some(path.clone()),
+        _ if a == b => return None,
+        _ => return Some(path.clone())
+    }
+}
+
+// Structural equality like ==, member order included, except that numbers
+// only have to be within epsilon of each other: absolutely for numbers below
+// 1 and relative to the larger of the two above that. Meant for comparing
+// the output of floating point pipelines, where 0.1 + 0.2 should still match
+// 0.3.
+pub fn approx_eq(a: &JsonExpression, b: &JsonExpression, epsilon: f64) -> bool {
+    return difference(a, b, epsilon, &mut JsonPath::root()).is_none();
+}
+
+// Panics unless approx_eq holds, naming the first path where the documents
+// differ and the values found there, for use in tests.
+#[track_caller]
+pub fn assert_approx_eq(actual: &JsonExpression, expected: &JsonExpression, epsilon: f64) {
+    if let Some(path) = difference(actual, expected, epsilon, &mut JsonPath::root()) {
+        let show = |value: &JsonExpression| value.get_at(&path).map(|v| v.to_string()).unwrap_or_else(|| String::from("nothing"));
+        let at = if path.is_root() { String::from("the root") } else { path.to_string() };
+        panic!("Documents differ at {} by more than {}: found {}, expected {}...", at, epsilon, show(actual), show(expected));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::approx::{approx_eq, assert_approx_eq};
+    use crate::json;
+
+    #[test]
+    fn tolerates_small_differences() {
+        let a = json(String::from("{\"sum\": 0.30000000000000004, \"big\": 1000000.0, \"xs\": [1, \"a\", null]}")).unwrap();
+        let b = json(String::from("{\"sum\": 0.3, \"big\": 1000000.5, \"xs\": [1.0000001, \"a\", null]}")).unwrap();
+        assert!(approx_eq(&a, &b, 1e-6));
+        assert!(!approx_eq(&a, &b, 1e-9));
+        assert!(!approx_eq(&json(String::from("[1, 2]")).unwrap(), &json(String::from("[1]")).unwrap(), 1.0));
+        assert!(!approx_eq(&json(String::from("{\"a\": 1}")).unwrap(), &json(String::from("{\"b\": 1}")).unwrap(), 1.0));
+        assert!(!approx_eq(&json(String::from("1")).unwrap(), &json(String::from("\"1\"")).unwrap(), 1.0));
+        assert_approx_eq(&a, &b, 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Documents differ at xs[0] by more than 0.001: found 1, expected 1.5...")]
+    fn assert_names_the_difference() {
+        assert_approx_eq(&json(String::from("{\"xs\": [1]}")).unwrap(), &json(String::from("{\"xs\": [1.5]}")).unwrap(), 1e-3);
+    }
+}
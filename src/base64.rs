@@ -0,0 +1,126 @@
+use crate::JsonExpression;
+
+// Standard alphabet with padding (RFC 4648 section 4).
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        out.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+
+    return out;
+}
+
+pub fn decode(s: &str) -> Result<Vec<u8>, String> {
+    let input = s.trim_end_matches('=').as_bytes();
+    if s.len() - input.len() > 2 || input.len() % 4 == 1 {
+        return Err(String::from("Invalid base64 length..."));
+    }
+
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.chunks(4) {
+        let mut n: u32 = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = match ALPHABET.iter().position(|a| a == c) {
+                Some(v) => v as u32,
+                None => return Err(format!("Invalid base64 character '{}'...", *c as char))
+            };
+            n |= value << (18 - 6 * i);
+        }
+
+        out.push((n >> 16) as u8);
+        if chunk.len() > 2 {
+            out.push((n >> 8) as u8);
+        }
+        if chunk.len() > 3 {
+            out.push(n as u8);
+        }
+    }
+
+    return Ok(out);
+}
+
+// Binary data rides inside JSON as a base64 string. Wrapping a buffer in
+// Bytes lets it take part in the From/TryFrom conversions like any other
+// value, so it serializes as base64 and reads back from it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bytes(pub Vec<u8>);
+
+impl JsonExpression {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        return JsonExpression::String(encode(bytes));
+    }
+
+    pub fn as_bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            JsonExpression::String(s) => return decode(s),
+            _ => return Err(format!("Expected base64 string, found {}...", self.type_name()))
+        }
+    }
+}
+
+impl From<Bytes> for JsonExpression {
+    fn from(value: Bytes) -> Self {
+        return JsonExpression::from_bytes(&value.0);
+    }
+}
+
+impl From<&[u8]> for JsonExpression {
+    fn from(value: &[u8]) -> Self {
+        return JsonExpression::from_bytes(value);
+    }
+}
+
+impl TryFrom<&JsonExpression> for Bytes {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        return value.as_bytes().map(Bytes);
+    }
+}
+
+impl TryFrom<JsonExpression> for Bytes {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        return Bytes::try_from(&value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, Bytes};
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn round_trip() {
+        assert_eq!("", encode(b""));
+        assert_eq!("Zg==", encode(b"f"));
+        assert_eq!("Zm8=", encode(b"fo"));
+        assert_eq!("Zm9vYmFy", encode(b"foobar"));
+        assert_eq!(Ok(b"foobar".to_vec()), decode("Zm9vYmFy"));
+        assert_eq!(Ok(b"fo".to_vec()), decode("Zm8="));
+        assert!(decode("Zm9v!").is_err());
+    }
+
+    #[test]
+    fn binary_values() {
+        let expr: JsonExpression = vec![
+            (String::from("blob"), JsonExpression::from(Bytes(vec![0, 159, 255]))),
+        ].into_iter().collect();
+        assert_eq!("{\"blob\":\"AJ//\"}", expr.to_string());
+
+        let parsed = json(expr.to_string()).unwrap();
+        let blob = parsed.values().next().unwrap();
+        assert_eq!(Ok(vec![0, 159, 255]), blob.as_bytes());
+        assert_eq!(Ok(Bytes(vec![0, 159, 255])), Bytes::try_from(blob));
+        assert!(JsonExpression::Number(1.0).as_bytes().is_err());
+    }
+}
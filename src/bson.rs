@@ -0,0 +1,477 @@
+use crate::{base64, JsonExpression};
+
+// BSON types without a JSON equivalent use the MongoDB canonical extended JSON
+// (v2) form, e.g. an ObjectId reads as {"$oid": "..."} and writing that same
+// object back produces an ObjectId again.
+
+const DOUBLE: u8 = 0x01;
+const STRING: u8 = 0x02;
+const DOCUMENT: u8 = 0x03;
+const ARRAY: u8 = 0x04;
+const BINARY: u8 = 0x05;
+const UNDEFINED: u8 = 0x06;
+const OBJECT_ID: u8 = 0x07;
+const BOOLEAN: u8 = 0x08;
+const DATETIME: u8 = 0x09;
+const NULL: u8 = 0x0a;
+const REGEX: u8 = 0x0b;
+const INT32: u8 = 0x10;
+const TIMESTAMP: u8 = 0x11;
+const INT64: u8 = 0x12;
+const MIN_KEY: u8 = 0xff;
+const MAX_KEY: u8 = 0x7f;
+
+// Documents and arrays nested deeper than this are rejected, far past any
+// real document, since a value that deep would overflow the stack as soon as
+// it was walked or dropped.
+const MAX_DEPTH: usize = 1024;
+
+fn single_key<'a>(expression: &'a JsonExpression, key: &str) -> Option<&'a JsonExpression> {
+    match expression {
+        JsonExpression::Object(pairs) if pairs.len() == 1 && pairs[0].0 == key => return Some(&pairs[0].1),
+        _ => return None
+    }
+}
+
+fn field<'a>(expression: &'a JsonExpression, key: &str) -> Option<&'a JsonExpression> {
+    return expression.entries().find(|(k, _)| *k == key).map(|(_, v)| v);
+}
+
+fn field_str<'a>(expression: &'a JsonExpression, key: &str) -> Option<&'a str> {
+    match field(expression, key) {
+        Some(JsonExpression::String(s)) => return Some(s),
+        _ => return None
+    }
+}
+
+fn field_u32(expression: &JsonExpression, key: &str) -> Option<u32> {
+    match field(expression, key) {
+        Some(JsonExpression::Number(n)) if n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64 => return Some(*n as u32),
+        _ => return None
+    }
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    // from_str_radix alone would take a sign, so "+f" would pass
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    return (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect();
+}
+
+fn write_cstring(s: &str, out: &mut Vec<u8>) -> Result<(), String> {
+    if s.contains('\0') {
+        return Err(format!("BSON key {:?} contains a NUL byte...", s));
+    }
+
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    return Ok(());
+}
+
+fn write_string(s: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(s.len() as i32 + 1).to_le_bytes());
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+}
+
+// Returns the element type and writes the element body, or None when the
+// object isn't one of the extended JSON wrappers.
+fn write_extended(expression: &JsonExpression, out: &mut Vec<u8>) -> Result<Option<u8>, String> {
+    if let Some(JsonExpression::String(hex)) = single_key(expression, "$oid") {
+        match parse_hex(hex) {
+            Some(bytes) if bytes.len() == 12 => {
+                out.extend_from_slice(&bytes);
+                return Ok(Some(OBJECT_ID));
+            },
+            _ => return Err(format!("Invalid $oid {:?}...", hex))
+        }
+    }
+
+    if let Some(date) = single_key(expression, "$date") {
+        let millis = match date {
+            JsonExpression::Number(n) if n.fract() == 0.0 => *n as i64,
+            _ => match single_key(date, "$numberLong") {
+                Some(JsonExpression::String(s)) => s.parse::<i64>().map_err(|_| format!("Invalid $numberLong {:?}...", s))?,
+                _ => return Err(String::from("Expected $date to hold milliseconds or {\"$numberLong\": ...}..."))
+            }
+        };
+        out.extend_from_slice(&millis.to_le_bytes());
+        return Ok(Some(DATETIME));
+    }
+
+    if let Some(binary) = single_key(expression, "$binary") {
+        let bytes = match field_str(binary, "base64") {
+            Some(s) => base64::decode(s)?,
+            None => return Err(String::from("Expected $binary.base64 string..."))
+        };
+        let subtype = match field_str(binary, "subType").and_then(parse_hex) {
+            Some(b) if b.len() == 1 => b[0],
+            _ => return Err(String::from("Expected $binary.subType as a hex byte..."))
+        };
+
+        out.extend_from_slice(&(bytes.len() as i32).to_le_bytes());
+        out.push(subtype);
+        out.extend_from_slice(&bytes);
+        return Ok(Some(BINARY));
+    }
+
+    if let Some(timestamp) = single_key(expression, "$timestamp") {
+        match (field_u32(timestamp, "t"), field_u32(timestamp, "i")) {
+            (Some(t), Some(i)) => {
+                out.extend_from_slice(&i.to_le_bytes());
+                out.extend_from_slice(&t.to_le_bytes());
+                return Ok(Some(TIMESTAMP));
+            },
+            _ => return Err(String::from("Expected $timestamp to hold t and i..."))
+        }
+    }
+
+    if let Some(regex) = single_key(expression, "$regularExpression") {
+        match (field_str(regex, "pattern"), field_str(regex, "options")) {
+            (Some(pattern), Some(options)) => {
+                write_cstring(pattern, out)?;
+                write_cstring(options, out)?;
+                return Ok(Some(REGEX));
+            },
+            _ => return Err(String::from("Expected $regularExpression to hold pattern and options..."))
+        }
+    }
+
+    if let Some(JsonExpression::String(s)) = single_key(expression, "$numberLong") {
+        let n = s.parse::<i64>().map_err(|_| format!("Invalid $numberLong {:?}...", s))?;
+        out.extend_from_slice(&n.to_le_bytes());
+        return Ok(Some(INT64));
+    }
+
+    if single_key(expression, "$minKey").is_some() {
+        return Ok(Some(MIN_KEY));
+    }
+
+    if single_key(expression, "$maxKey").is_some() {
+        return Ok(Some(MAX_KEY));
+    }
+
+    return Ok(None);
+}
+
+// Writes the element body and returns its type byte.
+fn write_value(expression: &JsonExpression, out: &mut Vec<u8>) -> Result<u8, String> {
+    match expression {
+        JsonExpression::Number(n) => {
+            if n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 {
+                out.extend_from_slice(&(*n as i32).to_le_bytes());
+                return Ok(INT32);
+            }
+            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 {
+                out.extend_from_slice(&(*n as i64).to_le_bytes());
+                return Ok(INT64);
+            }
+            out.extend_from_slice(&n.to_le_bytes());
+            return Ok(DOUBLE);
+        },
+        JsonExpression::String(s) => {
+            write_string(s, out);
+            return Ok(STRING);
+        },
+        JsonExpression::Bool(b) => {
+            out.push(*b as u8);
+            return Ok(BOOLEAN);
+        },
+        JsonExpression::Null => return Ok(NULL),
+        JsonExpression::Array(elements) => {
+            let start = begin_document(out);
+            for (i, element) in elements.iter().enumerate() {
+                write_element(&i.to_string(), element, out)?;
+            }
+            end_document(start, out);
+            return Ok(ARRAY);
+        },
+        JsonExpression::Object(pairs) => {
+            if let Some(element_type) = write_extended(expression, out)? {
+                return Ok(element_type);
+            }
+
+            let start = begin_document(out);
+            for (key, value) in pairs {
+                write_element(key, value, out)?;
+            }
+            end_document(start, out);
+            return Ok(DOCUMENT);
+        },
+    }
+}
+
+fn write_element(key: &str, value: &JsonExpression, out: &mut Vec<u8>) -> Result<(), String> {
+    // the type byte comes first but isn't known until the value is written
+    let type_index = out.len();
+    out.push(0);
+    write_cstring(key, out)?;
+    out[type_index] = write_value(value, out)?;
+    return Ok(());
+}
+
+fn begin_document(out: &mut Vec<u8>) -> usize {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    return start;
+}
+
+fn end_document(start: usize, out: &mut Vec<u8>) {
+    out.push(0);
+    let length = (out.len() - start) as i32;
+    out[start..start + 4].copy_from_slice(&length.to_le_bytes());
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    current: usize
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.current < n {
+            return Err(String::from("Unexpected end of BSON input..."));
+        }
+
+        let bytes = &self.data[self.current..self.current + n];
+        self.current += n;
+        return Ok(bytes);
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        return Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()));
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        return Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()));
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        return usize::try_from(self.i32()?).map_err(|_| String::from("Negative BSON length..."));
+    }
+
+    fn cstring(&mut self) -> Result<String, String> {
+        let end = match self.data[self.current..].iter().position(|b| *b == 0) {
+            Some(end) => end,
+            None => return Err(String::from("Unterminated BSON cstring..."))
+        };
+
+        let bytes = self.take(end + 1)?;
+        return String::from_utf8(bytes[..end].to_vec()).map_err(|_| String::from("BSON string is not valid UTF-8..."));
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.length()?;
+        let bytes = self.take(length)?;
+        if length == 0 || bytes[length - 1] != 0 {
+            return Err(String::from("BSON string is missing its terminator..."));
+        }
+
+        return String::from_utf8(bytes[..length - 1].to_vec()).map_err(|_| String::from("BSON string is not valid UTF-8..."));
+    }
+
+    // The start of a document and the length it claims.
+    fn open(&mut self, key: String, array: bool) -> Result<Open, String> {
+        let start = self.current;
+        let length = self.length()?;
+        return Ok(Open { start, length, key, array, pairs: Vec::new() });
+    }
+
+    // Decodes with an explicit stack of the documents and arrays still being
+    // filled, so hostile nesting cannot run out of call stack.
+    fn document(&mut self) -> Result<Vec<(String, Box<JsonExpression>)>, String> {
+        let mut open: Vec<Open> = vec![self.open(String::new(), false)?];
+        loop {
+            let element_type = self.take(1)?[0];
+            let (key, value) = if element_type == 0 {
+                let done = open.pop().unwrap();
+                if self.current - done.start != done.length {
+                    return Err(String::from("BSON document length does not match its contents..."));
+                }
+                if open.is_empty() {
+                    return Ok(done.pairs);
+                }
+                done.close()
+            } else {
+                let key = self.cstring()?;
+                if element_type == DOCUMENT || element_type == ARRAY {
+                    // the root is not nested in anything
+                    if open.len() > MAX_DEPTH {
+                        return Err(format!("BSON nested deeper than {}...", MAX_DEPTH));
+                    }
+                    let document = self.open(key, element_type == ARRAY)?;
+                    open.push(document);
+                    continue;
+                }
+                (key, self.value(element_type)?)
+            };
+            open.last_mut().unwrap().pairs.push((key, Box::new(value)));
+        }
+    }
+
+    fn value(&mut self, element_type: u8) -> Result<JsonExpression, String> {
+        match element_type {
+            DOUBLE => return Ok(JsonExpression::Number(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))),
+            STRING => return Ok(JsonExpression::String(self.string()?)),
+            BINARY => {
+                let length = self.length()?;
+                let subtype = self.take(1)?[0];
+                let bytes = self.take(length)?;
+                return Ok(extended("$binary", extended_object(vec![
+                    ("base64", JsonExpression::String(base64::encode(bytes))),
+                    ("subType", JsonExpression::String(format!("{:02x}", subtype))),
+                ])));
+            },
+            UNDEFINED | NULL => return Ok(JsonExpression::Null),
+            OBJECT_ID => {
+                let hex: String = self.take(12)?.iter().map(|b| format!("{:02x}", b)).collect();
+                return Ok(extended("$oid", JsonExpression::String(hex)));
+            },
+            BOOLEAN => return Ok(JsonExpression::Bool(self.take(1)?[0] != 0)),
+            DATETIME => {
+                let millis = self.i64()?;
+                return Ok(extended("$date", extended("$numberLong", JsonExpression::String(millis.to_string()))));
+            },
+            REGEX => {
+                let pattern = self.cstring()?;
+                let options = self.cstring()?;
+                return Ok(extended("$regularExpression", extended_object(vec![
+                    ("pattern", JsonExpression::String(pattern)),
+                    ("options", JsonExpression::String(options)),
+                ])));
+            },
+            INT32 => return Ok(JsonExpression::Number(self.i32()? as f64)),
+            TIMESTAMP => {
+                let increment = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+                let time = u32::from_le_bytes(self.take(4)?.try_into().unwrap());
+                return Ok(extended("$timestamp", extended_object(vec![
+                    ("t", JsonExpression::Number(time as f64)),
+                    ("i", JsonExpression::Number(increment as f64)),
+                ])));
+            },
+            INT64 => return Ok(JsonExpression::Number(self.i64()? as f64)),
+            MIN_KEY => return Ok(extended("$minKey", JsonExpression::Number(1.0))),
+            MAX_KEY => return Ok(extended("$maxKey", JsonExpression::Number(1.0))),
+            _ => return Err(format!("Unsupported BSON element type 0x{:02x}...", element_type))
+        }
+    }
+}
+
+fn extended(key: &str, value: JsonExpression) -> JsonExpression {
+    return JsonExpression::Object(vec![(String::from(key), Box::new(value))]);
+}
+
+fn extended_object(pairs: Vec<(&str, JsonExpression)>) -> JsonExpression {
+    return JsonExpression::Object(pairs.into_iter().map(|(k, v)| (String::from(k), Box::new(v))).collect());
+}
+
+// A document or array being decoded, with the key it sits at in its parent.
+// An array's keys are its indexes and are dropped.
+struct Open {
+    start: usize,
+    length: usize,
+    key: String,
+    array: bool,
+    pairs: Vec<(String, Box<JsonExpression>)>
+}
+
+impl Open {
+    fn close(self) -> (String, JsonExpression) {
+        if self.array {
+            return (self.key, JsonExpression::Array(self.pairs.into_iter().map(|(_, v)| v).collect()));
+        }
+        return (self.key, JsonExpression::Object(self.pairs));
+    }
+}
+
+impl JsonExpression {
+    // Only objects can be BSON documents.
+    pub fn to_bson(&self) -> Result<Vec<u8>, String> {
+        let pairs = match self {
+            JsonExpression::Object(pairs) => pairs,
+            _ => return Err(format!("Expected object for BSON document, found {}...", self.type_name()))
+        };
+
+        let mut out = Vec::new();
+        let start = begin_document(&mut out);
+        for (key, value) in pairs {
+            write_element(key, value, &mut out)?;
+        }
+        end_document(start, &mut out);
+        return Ok(out);
+    }
+
+    pub fn from_bson(data: &[u8]) -> Result<JsonExpression, String> {
+        let mut decoder = Decoder { data, current: 0 };
+        let pairs = decoder.document()?;
+        if decoder.current != data.len() {
+            return Err(String::from("Trailing bytes after BSON document..."));
+        }
+
+        return Ok(JsonExpression::Object(pairs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bson::{parse_hex, DOCUMENT};
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn encodes_spec_example() {
+        // {"hello": "world"} from bsonspec.org
+        let expected = b"\x16\x00\x00\x00\x02hello\x00\x06\x00\x00\x00world\x00\x00".to_vec();
+        assert_eq!(Ok(expected), json(String::from("{\"hello\": \"world\"}")).unwrap().to_bson());
+        assert!(JsonExpression::Number(1.0).to_bson().is_err());
+    }
+
+    #[test]
+    fn round_trips_extended_json() {
+        let expr = json(String::from("{
+            \"_id\": {\"$oid\": \"507f1f77bcf86cd799439011\"},
+            \"created\": {\"$date\": {\"$numberLong\": \"1700000000000\"}},
+            \"blob\": {\"$binary\": {\"base64\": \"AQID\", \"subType\": \"00\"}},
+            \"tags\": [\"a\", 2, 2.5, false, null],
+            \"big\": 10000000000
+        }")).unwrap();
+
+        let bytes = expr.to_bson().unwrap();
+        assert_eq!(Ok(expr), JsonExpression::from_bson(&bytes));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let bytes = json(String::from("{\"a\": 1}")).unwrap().to_bson().unwrap();
+        assert!(JsonExpression::from_bson(&bytes[..bytes.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn limits_depth() {
+        // {"a": {"a": ... {}}} with depth documents inside the root
+        let nested = |depth: usize| {
+            let mut bytes = vec![5, 0, 0, 0, 0];
+            for _ in 0..depth {
+                let length = (bytes.len() + 8) as i32;
+                bytes = [&length.to_le_bytes()[..], &[DOCUMENT, b'a', 0], &bytes, &[0]].concat();
+            }
+            return bytes;
+        };
+        assert_eq!(Ok(json(String::from("{\"a\": {\"a\": {}}}")).unwrap()), JsonExpression::from_bson(&nested(2)));
+        assert!(JsonExpression::from_bson(&nested(1024)).is_ok());
+        assert_eq!(Err(String::from("BSON nested deeper than 1024...")), JsonExpression::from_bson(&nested(1025)));
+
+        // only the openings, as a hostile input would send
+        let openings: Vec<u8> = std::iter::repeat_n([0x10, 0, 0, 0, DOCUMENT, b'a', 0], 200_000).flatten().collect();
+        assert!(JsonExpression::from_bson(&openings).is_err());
+    }
+
+    #[test]
+    fn hex_is_only_digits() {
+        assert_eq!(Some(vec![0x0f, 0xa0]), parse_hex("0fA0"));
+        assert_eq!(None, parse_hex("+f"));
+        assert_eq!(None, parse_hex("-1"));
+        assert_eq!(None, parse_hex("abc"));
+    }
+}
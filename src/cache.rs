@@ -0,0 +1,239 @@
+use crate::JsonExpression;
+
+// A binary form of the value tree for persisting parsed documents, which
+// reads back much faster than parsing the text again: there is nothing to
+// tokenize, no escapes or numbers to decode, and every container says how
+// many children it has up front.
+//
+// The layout is private to this crate and only read by the same version that
+// wrote it. It starts with MAGIC and a version byte, bumped whenever the
+// layout changes, so a stale cache is rejected rather than misread. Each
+// value is a tag byte, then for a number its f64 in little endian, for a
+// string its length and UTF-8 bytes, for an array its length and elements,
+// and for an object its length and key value pairs. Lengths are LEB128.
+//
+// Reading rejects values nested deeper than MAX_DEPTH, far past any real
+// document, since a value that deep would overflow the stack as soon as it
+// was walked or dropped.
+
+const MAGIC: &[u8; 4] = b"JRSC";
+const VERSION: u8 = 1;
+const MAX_DEPTH: usize = 1024;
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const NUMBER: u8 = 3;
+const STRING: u8 = 4;
+const ARRAY: u8 = 5;
+const OBJECT: u8 = 6;
+
+fn write_length(mut n: usize, out: &mut Vec<u8>) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn write_str(s: &str, out: &mut Vec<u8>) {
+    write_length(s.len(), out);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn write_expression(expression: &JsonExpression, out: &mut Vec<u8>) {
+    match expression {
+        JsonExpression::Null => out.push(NULL),
+        JsonExpression::Bool(false) => out.push(FALSE),
+        JsonExpression::Bool(true) => out.push(TRUE),
+        JsonExpression::Number(n) => {
+            out.push(NUMBER);
+            out.extend_from_slice(&n.to_le_bytes());
+        },
+        JsonExpression::String(s) => {
+            out.push(STRING);
+            write_str(s, out);
+        },
+        JsonExpression::Array(elements) => {
+            out.push(ARRAY);
+            write_length(elements.len(), out);
+            for element in elements {
+                write_expression(element, out);
+            }
+        },
+        JsonExpression::Object(pairs) => {
+            out.push(OBJECT);
+            write_length(pairs.len(), out);
+            for (key, value) in pairs {
+                write_str(key, out);
+                write_expression(value, out);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    current: usize
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.current < n {
+            return Err(String::from("Unexpected end of cache data..."));
+        }
+
+        let bytes = &self.data[self.current..self.current + n];
+        self.current += n;
+        return Ok(bytes);
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let mut n: usize = 0;
+        for shift in (0..usize::BITS).step_by(7) {
+            let b = self.take(1)?[0];
+            n |= ((b & 0x7f) as usize) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        return Err(String::from("Cache length too large..."));
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let n = self.length()?;
+        let bytes = self.take(n)?;
+        return std::str::from_utf8(bytes).map(String::from).map_err(|_| String::from("Invalid UTF-8 in cache data..."));
+    }
+
+    // A corrupt length cannot reserve more than the bytes left could hold,
+    // every child takes at least one.
+    fn capacity(&self, n: usize) -> usize {
+        return n.min(self.data.len() - self.current);
+    }
+
+    // Decodes with an explicit stack of the containers still being filled,
+    // so hostile nesting cannot run out of call stack.
+    fn expression(&mut self) -> Result<JsonExpression, String> {
+        let mut open: Vec<Open> = Vec::new();
+        loop {
+            let value = match open.last_mut() {
+                Some(Open::Array(0, _) | Open::Object(0, ..)) => open.pop().unwrap().close(),
+                parent => {
+                    if let Some(Open::Object(_, _, key)) = parent {
+                        *key = self.string()?;
+                    }
+                    match self.take(1)?[0] {
+                        NULL => JsonExpression::Null,
+                        FALSE => JsonExpression::Bool(false),
+                        TRUE => JsonExpression::Bool(true),
+                        NUMBER => JsonExpression::Number(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+                        STRING => JsonExpression::String(self.string()?),
+                        tag @ (ARRAY | OBJECT) => {
+                            if open.len() == MAX_DEPTH {
+                                return Err(format!("Cache data nested deeper than {}...", MAX_DEPTH));
+                            }
+                            let n = self.length()?;
+                            let capacity = self.capacity(n);
+                            open.push(if tag == ARRAY { Open::Array(n, Vec::with_capacity(capacity)) } else { Open::Object(n, Vec::with_capacity(capacity), String::new()) });
+                            continue;
+                        },
+                        tag => return Err(format!("Invalid cache tag {}...", tag))
+                    }
+                }
+            };
+
+            match open.last_mut() {
+                None => return Ok(value),
+                Some(Open::Array(left, elements)) => {
+                    *left -= 1;
+                    elements.push(Box::new(value));
+                },
+                Some(Open::Object(left, pairs, key)) => {
+                    *left -= 1;
+                    pairs.push((std::mem::take(key), Box::new(value)));
+                }
+            }
+        }
+    }
+}
+
+// A container being decoded with the number of children still to come, and
+// for an object the key of the one being read.
+enum Open {
+    Array(usize, Vec<Box<JsonExpression>>),
+    Object(usize, Vec<(String, Box<JsonExpression>)>, String)
+}
+
+impl Open {
+    fn close(self) -> JsonExpression {
+        match self {
+            Open::Array(_, elements) => return JsonExpression::Array(elements),
+            Open::Object(_, pairs, _) => return JsonExpression::Object(pairs)
+        }
+    }
+}
+
+impl JsonExpression {
+    pub fn to_cache_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        write_expression(self, &mut out);
+        return out;
+    }
+
+    pub fn from_cache_bytes(data: &[u8]) -> Result<JsonExpression, String> {
+        let Some(header) = data.get(..MAGIC.len() + 1) else {
+            return Err(String::from("Not jsonrs cache data..."));
+        };
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(String::from("Not jsonrs cache data..."));
+        }
+        if header[MAGIC.len()] != VERSION {
+            return Err(format!("Unsupported cache version {}, expected {}...", header[MAGIC.len()], VERSION));
+        }
+
+        let mut decoder = Decoder { data, current: header.len() };
+        let expression = decoder.expression()?;
+        if decoder.current != data.len() {
+            return Err(String::from("Trailing bytes after cache data..."));
+        }
+
+        return Ok(expression);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn round_trip() {
+        let long = "x".repeat(300);
+        let expr = json(format!("{{\"a\": [1.5, -0, 1e300, true, false, null, \"é\"], \"b\": {{}}, \"\": [], \"{}\": \"{}\"}}", long, long)).unwrap();
+        let bytes = expr.to_cache_bytes();
+        assert_eq!(b"JRSC\x01\x06\x04", &bytes[..7]);
+        assert_eq!(Ok(expr), JsonExpression::from_cache_bytes(&bytes));
+    }
+
+    #[test]
+    fn rejects_bad_data() {
+        let bytes = JsonExpression::from("abc").to_cache_bytes();
+        assert_eq!(Err(String::from("Unexpected end of cache data...")), JsonExpression::from_cache_bytes(&bytes[..bytes.len() - 1]));
+        assert!(JsonExpression::from_cache_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(JsonExpression::from_cache_bytes(b"{\"a\": 1}").is_err());
+        assert_eq!(Err(String::from("Unsupported cache version 2, expected 1...")), JsonExpression::from_cache_bytes(b"JRSC\x02\x00"));
+        // a huge array length with nothing behind it
+        assert!(JsonExpression::from_cache_bytes(b"JRSC\x01\x05\xff\xff\xff\xff\x0f").is_err());
+        assert!(JsonExpression::from_cache_bytes(b"JRSC\x01\x04\x01\xff").is_err());
+    }
+
+    #[test]
+    fn limits_depth() {
+        let nested = |depth: usize| [b"JRSC\x01".as_slice(), &b"\x05\x01".repeat(depth), b"\x00"].concat();
+        assert!(JsonExpression::from_cache_bytes(&nested(1024)).is_ok());
+        assert_eq!(Err(String::from("Cache data nested deeper than 1024...")), JsonExpression::from_cache_bytes(&nested(1025)));
+        assert!(JsonExpression::from_cache_bytes(&nested(1_000_000)).is_err());
+    }
+}
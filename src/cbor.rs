@@ -0,0 +1,367 @@
+use crate::{base64, JsonExpression};
+
+// CBOR (RFC 8949) for the JSON compatible subset. Integral numbers are
+// written as CBOR integers and everything else as the smallest float that
+// holds the value exactly. On the way in, byte strings become base64 strings,
+// tags are dropped in favour of the tagged item and undefined reads as null.
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+const INDEFINITE: u8 = 31;
+const BREAK: u8 = 0xff;
+
+// Arrays and maps nested deeper than this are rejected, far past any real
+// document, since a value that deep would overflow the stack as soon as it
+// was walked or dropped.
+const MAX_DEPTH: usize = 1024;
+
+fn write_head(major: u8, n: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_number(n: f64, out: &mut Vec<u8>) {
+    if n.fract() == 0.0 && n >= 0.0 && n < u64::MAX as f64 {
+        write_head(MAJOR_UNSIGNED, n as u64, out);
+    } else if n.fract() == 0.0 && n < 0.0 && n >= -(u64::MAX as f64) {
+        write_head(MAJOR_NEGATIVE, (-1.0 - n) as u64, out);
+    } else if (n as f32) as f64 == n || n.is_nan() {
+        out.push(0xfa);
+        out.extend_from_slice(&(n as f32).to_be_bytes());
+    } else {
+        out.push(0xfb);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_expression(expression: &JsonExpression, out: &mut Vec<u8>) {
+    match expression {
+        JsonExpression::Number(n) => write_number(*n, out),
+        JsonExpression::String(s) => {
+            write_head(MAJOR_TEXT, s.len() as u64, out);
+            out.extend_from_slice(s.as_bytes());
+        },
+        JsonExpression::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+        JsonExpression::Null => out.push(0xf6),
+        JsonExpression::Array(elements) => {
+            write_head(MAJOR_ARRAY, elements.len() as u64, out);
+            for element in elements {
+                write_expression(element, out);
+            }
+        },
+        JsonExpression::Object(pairs) => {
+            write_head(MAJOR_MAP, pairs.len() as u64, out);
+            for (key, value) in pairs {
+                write_head(MAJOR_TEXT, key.len() as u64, out);
+                out.extend_from_slice(key.as_bytes());
+                write_expression(value, out);
+            }
+        },
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    current: usize
+}
+
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Result<u8, String> {
+        match self.data.get(self.current) {
+            Some(b) => {
+                self.current += 1;
+                return Ok(*b);
+            },
+            None => return Err(String::from("Unexpected end of CBOR input..."))
+        }
+    }
+
+    fn take(&mut self, n: u64) -> Result<&'a [u8], String> {
+        let n = usize::try_from(n).map_err(|_| String::from("CBOR length too large..."))?;
+        if self.data.len() - self.current < n {
+            return Err(String::from("Unexpected end of CBOR input..."));
+        }
+
+        let bytes = &self.data[self.current..self.current + n];
+        self.current += n;
+        return Ok(bytes);
+    }
+
+    // Reads the argument that follows an initial byte, None means indefinite length.
+    fn argument(&mut self, info: u8) -> Result<Option<u64>, String> {
+        match info {
+            0..=23 => return Ok(Some(info as u64)),
+            24 => return Ok(Some(self.byte()? as u64)),
+            25 => return Ok(Some(u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64)),
+            26 => return Ok(Some(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64)),
+            27 => return Ok(Some(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))),
+            INDEFINITE => return Ok(None),
+            _ => return Err(format!("Invalid CBOR additional info {}...", info))
+        }
+    }
+
+    fn at_break(&mut self) -> Result<bool, String> {
+        match self.data.get(self.current) {
+            Some(&BREAK) => {
+                self.current += 1;
+                return Ok(true);
+            },
+            Some(_) => return Ok(false),
+            None => return Err(String::from("Unexpected end of CBOR input..."))
+        }
+    }
+
+    // Byte and text strings share the same layout, indefinite ones are a
+    // sequence of definite chunks of the same major type.
+    fn string_bytes(&mut self, major: u8, length: Option<u64>) -> Result<Vec<u8>, String> {
+        if let Some(n) = length {
+            return Ok(self.take(n)?.to_vec());
+        }
+
+        let mut bytes = Vec::new();
+        while !self.at_break()? {
+            let initial = self.byte()?;
+            if initial >> 5 != major {
+                return Err(String::from("Mismatched chunk in indefinite CBOR string..."));
+            }
+
+            match self.argument(initial & 0x1f)? {
+                Some(n) => bytes.extend_from_slice(self.take(n)?),
+                None => return Err(String::from("Nested indefinite CBOR string..."))
+            }
+        }
+
+        return Ok(bytes);
+    }
+
+    fn text(&mut self, length: Option<u64>) -> Result<String, String> {
+        let bytes = self.string_bytes(MAJOR_TEXT, length)?;
+        return String::from_utf8(bytes).map_err(|_| String::from("CBOR text is not valid UTF-8..."));
+    }
+
+    fn key(value: JsonExpression) -> Result<String, String> {
+        match value {
+            JsonExpression::String(s) => return Ok(s),
+            JsonExpression::Number(n) => return Ok(n.to_string()),
+            other => return Err(format!("Unsupported CBOR map key of type {}...", other.type_name()))
+        }
+    }
+
+    // Decodes with an explicit stack of the arrays and maps still being
+    // filled, so hostile nesting cannot run out of call stack. Tags are
+    // skipped over and take no room at all.
+    fn expression(&mut self) -> Result<JsonExpression, String> {
+        let mut open: Vec<Open> = Vec::new();
+        loop {
+            let value = match open.last_mut() {
+                Some(Open::Array(Some(0), _) | Open::Map(Some(0), _, None)) => open.pop().unwrap().close(),
+                Some(Open::Array(None, _) | Open::Map(None, _, None)) if self.at_break()? => open.pop().unwrap().close(),
+                _ => {
+                    let initial = self.byte()?;
+                    let major = initial >> 5;
+                    let info = initial & 0x1f;
+                    match major {
+                        MAJOR_ARRAY | MAJOR_MAP => {
+                            if open.len() == MAX_DEPTH {
+                                return Err(format!("CBOR nested deeper than {}...", MAX_DEPTH));
+                            }
+                            let length = self.argument(info)?;
+                            open.push(if major == MAJOR_ARRAY { Open::Array(length, Vec::new()) } else { Open::Map(length, Vec::new(), None) });
+                            continue;
+                        },
+                        MAJOR_TAG => {
+                            self.argument(info)?;
+                            continue;
+                        },
+                        _ => self.scalar(major, info)?
+                    }
+                }
+            };
+
+            match open.last_mut() {
+                None => return Ok(value),
+                Some(Open::Array(left, elements)) => {
+                    if let Some(left) = left {
+                        *left -= 1;
+                    }
+                    elements.push(Box::new(value));
+                },
+                Some(Open::Map(left, pairs, key)) => match key.take() {
+                    None => *key = Some(Decoder::key(value)?),
+                    Some(key) => {
+                        if let Some(left) = left {
+                            *left -= 1;
+                        }
+                        pairs.push((key, Box::new(value)));
+                    }
+                }
+            }
+        }
+    }
+
+    // Any item other than an array, map or tag.
+    fn scalar(&mut self, major: u8, info: u8) -> Result<JsonExpression, String> {
+        match major {
+            MAJOR_UNSIGNED | MAJOR_NEGATIVE => {
+                let n = match self.argument(info)? {
+                    Some(n) => n as f64,
+                    None => return Err(String::from("Indefinite length CBOR integer..."))
+                };
+
+                if major == MAJOR_NEGATIVE {
+                    return Ok(JsonExpression::Number(-1.0 - n));
+                }
+                return Ok(JsonExpression::Number(n));
+            },
+            MAJOR_BYTES => {
+                let length = self.argument(info)?;
+                return Ok(JsonExpression::String(base64::encode(&self.string_bytes(MAJOR_BYTES, length)?)));
+            },
+            MAJOR_TEXT => {
+                let length = self.argument(info)?;
+                return Ok(JsonExpression::String(self.text(length)?));
+            },
+            _ => {
+                debug_assert_eq!(MAJOR_SIMPLE, major);
+                match info {
+                    20 => return Ok(JsonExpression::Bool(false)),
+                    21 => return Ok(JsonExpression::Bool(true)),
+                    22 | 23 => return Ok(JsonExpression::Null),
+                    25 => {
+                        let bits = u16::from_be_bytes(self.take(2)?.try_into().unwrap());
+                        return Ok(JsonExpression::Number(half_to_f64(bits)));
+                    },
+                    26 => {
+                        let bits = u32::from_be_bytes(self.take(4)?.try_into().unwrap());
+                        return Ok(JsonExpression::Number(f32::from_bits(bits) as f64));
+                    },
+                    27 => {
+                        let bits = u64::from_be_bytes(self.take(8)?.try_into().unwrap());
+                        return Ok(JsonExpression::Number(f64::from_bits(bits)));
+                    },
+                    _ => return Err(format!("Unsupported CBOR simple value {}...", info))
+                }
+            }
+        }
+    }
+}
+
+// An array or map being decoded with the number of items still to come, None
+// until the break of an indefinite one, and for a map the key of the value
+// being read once it has been.
+enum Open {
+    Array(Option<u64>, Vec<Box<JsonExpression>>),
+    Map(Option<u64>, Vec<(String, Box<JsonExpression>)>, Option<String>)
+}
+
+impl Open {
+    fn close(self) -> JsonExpression {
+        match self {
+            Open::Array(_, elements) => return JsonExpression::Array(elements),
+            Open::Map(_, pairs, _) => return JsonExpression::Object(pairs)
+        }
+    }
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f64;
+    let value = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mantissa + 1024.0) * 2f64.powi(exponent as i32 - 25)
+    };
+
+    if bits & 0x8000 != 0 {
+        return -value;
+    }
+    return value;
+}
+
+impl JsonExpression {
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_expression(self, &mut out);
+        return out;
+    }
+
+    pub fn from_cbor(data: &[u8]) -> Result<JsonExpression, String> {
+        let mut decoder = Decoder { data, current: 0 };
+        let expression = decoder.expression()?;
+        if decoder.current != data.len() {
+            return Err(String::from("Trailing bytes after CBOR item..."));
+        }
+
+        return Ok(expression);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn encodes_rfc_examples() {
+        assert_eq!(vec![0x18, 0x64], JsonExpression::Number(100.0).to_cbor());
+        assert_eq!(vec![0x38, 0x63], JsonExpression::Number(-100.0).to_cbor());
+        assert_eq!(vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a], JsonExpression::Number(1.1).to_cbor());
+        assert_eq!(vec![0x62, 0x22, 0x5c], JsonExpression::from("\"\\").to_cbor());
+        assert_eq!(
+            vec![0xa2, 0x61, 0x61, 0x01, 0x61, 0x62, 0x82, 0x02, 0x03],
+            json(String::from("{\"a\": 1, \"b\": [2, 3]}")).unwrap().to_cbor()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let expr = json(String::from("{\"a\": [1.5, -7, true, null, \"s\"], \"b\": {}}")).unwrap();
+        assert_eq!(Ok(expr.clone()), JsonExpression::from_cbor(&expr.to_cbor()));
+    }
+
+    #[test]
+    fn decodes_non_json_items() {
+        // indefinite map {"a": h'0102', "b": 1.5 (half)}, wrapped in tag 1
+        let data = [0xc1, 0xbf, 0x61, 0x61, 0x42, 0x01, 0x02, 0x61, 0x62, 0xf9, 0x3e, 0x00, 0xff];
+        assert_eq!(
+            Ok(json(String::from("{\"a\": \"AQI=\", \"b\": 1.5}")).unwrap()),
+            JsonExpression::from_cbor(&data)
+        );
+        assert!(JsonExpression::from_cbor(&[0x82, 0x01]).is_err());
+    }
+
+    #[test]
+    fn limits_depth() {
+        // arrays of one element each, around a 0
+        let arrays = |depth: usize| [vec![0x81; depth], vec![0x00]].concat();
+        assert_eq!(Ok(json(String::from("[[[0]]]")).unwrap()), JsonExpression::from_cbor(&arrays(3)));
+        assert!(JsonExpression::from_cbor(&arrays(1024)).is_ok());
+        assert_eq!(Err(String::from("CBOR nested deeper than 1024...")), JsonExpression::from_cbor(&arrays(1025)));
+        assert!(JsonExpression::from_cbor(&arrays(1_000_000)).is_err());
+        assert!(JsonExpression::from_cbor(&vec![0x9f; 1_000_000]).is_err());
+        // maps keyed by maps, and tags, which take no depth
+        assert!(JsonExpression::from_cbor(&vec![0xa1; 1_000_000]).is_err());
+        assert_eq!(Ok(JsonExpression::Number(0.0)), JsonExpression::from_cbor(&[vec![0xc1; 1_000_000], vec![0x00]].concat()));
+    }
+}
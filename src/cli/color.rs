@@ -0,0 +1,86 @@
+use jsonrs::lexer::{Lexer, Token};
+
+const RESET: &str = "\x1b[0m";
+const KEY: &str = "\x1b[1;34m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const BOOLEAN: &str = "\x1b[33m";
+const NULL: &str = "\x1b[35m";
+const PUNCTUATION: &str = "\x1b[2m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never
+}
+
+impl ColorChoice {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "auto" => return Ok(ColorChoice::Auto),
+            "always" => return Ok(ColorChoice::Always),
+            "never" => return Ok(ColorChoice::Never),
+            _ => return Err(format!("Expected auto, always or never for --color, found {:?}...", s))
+        }
+    }
+
+    // Auto colors only when writing to a terminal and NO_COLOR is not set.
+    pub fn enabled(&self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => return is_terminal && std::env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => return true,
+            ColorChoice::Never => return false
+        }
+    }
+}
+
+// Adds ANSI colors to serialized JSON. The text is lexed again so the layout
+// is left exactly as it was, a string followed by a colon is colored as a key.
+pub fn highlight(text: &str) -> String {
+    let mut tokens = Lexer::new(text.as_bytes()).map_while(Result::ok).peekable();
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut written: usize = 0;
+
+    while let Some(token) = tokens.next() {
+        let color = match token.value {
+            Token::StringLiteral(_) if matches!(tokens.peek().map(|t| &t.value), Some(Token::Colon)) => KEY,
+            Token::StringLiteral(_) => STRING,
+            Token::NumberLiteral(_) => NUMBER,
+            Token::BooleanLiteral(_) => BOOLEAN,
+            Token::NullLiteral => NULL,
+            _ => PUNCTUATION
+        };
+
+        out.push_str(&text[written..token.span.start]);
+        out.push_str(color);
+        out.push_str(&text[token.span.start..token.span.end]);
+        out.push_str(RESET);
+        written = token.span.end;
+    }
+
+    out.push_str(&text[written..]);
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::color::{highlight, ColorChoice};
+
+    #[test]
+    fn colors_tokens() {
+        assert_eq!(
+            "\x1b[2m{\x1b[0m\x1b[1;34m\"a\"\x1b[0m\x1b[2m:\x1b[0m \x1b[32m\"b\"\x1b[0m\x1b[2m,\x1b[0m\x1b[1;34m\"n\"\x1b[0m\x1b[2m:\x1b[0m\x1b[36m1\x1b[0m\x1b[2m}\x1b[0m",
+            highlight("{\"a\": \"b\",\"n\":1}")
+        );
+    }
+
+    #[test]
+    fn color_choice() {
+        assert_eq!(Ok(ColorChoice::Never), ColorChoice::parse("never"));
+        assert!(ColorChoice::parse("sometimes").is_err());
+        assert!(ColorChoice::Always.enabled(false));
+        assert!(!ColorChoice::Never.enabled(true));
+        assert!(!ColorChoice::Auto.enabled(false));
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{BufRead, Read};
+
+// Inputs compressed with gzip or zstd are decompressed before parsing, which
+// needs the compression feature. They are recognised by their magic bytes, so
+// compressed stdin works too, or by a .gz or .zst extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+// head is the start of the input, the first four bytes are enough.
+pub fn detect(name: Option<&str>, head: &[u8]) -> Option<Compression> {
+    let name = name.unwrap_or_default();
+    if head.starts_with(GZIP_MAGIC) || name.ends_with(".gz") {
+        return Some(Compression::Gzip);
+    }
+    if head.starts_with(ZSTD_MAGIC) || name.ends_with(".zst") {
+        return Some(Compression::Zstd);
+    }
+    return None;
+}
+
+// The input whole, decompressed when it needs to be.
+pub fn decompress(name: Option<&str>, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    if detect(name, &bytes).is_none() {
+        return Ok(bytes);
+    }
+
+    let mut decompressed = Vec::new();
+    reader(name, Box::new(bytes.as_slice()))?.read_to_end(&mut decompressed).map_err(|e| format!("Could not decompress: {}...", e))?;
+    return Ok(decompressed);
+}
+
+// Wraps the input in a decoder when it is compressed, peeking at its start
+// without consuming it.
+pub fn reader<'r>(name: Option<&str>, mut input: Box<dyn BufRead + 'r>) -> Result<Box<dyn Read + 'r>, String> {
+    let head = input.fill_buf().map_err(|e| format!("Could not read: {}...", e))?;
+    match detect(name, head) {
+        Some(compression) => return decoder(compression, input),
+        None => return Ok(input)
+    }
+}
+
+#[cfg(feature = "compression")]
+fn decoder<'r>(compression: Compression, input: Box<dyn BufRead + 'r>) -> Result<Box<dyn Read + 'r>, String> {
+    match compression {
+        // concatenated members are read as one stream, as gunzip does
+        Compression::Gzip => return Ok(Box::new(flate2::bufread::MultiGzDecoder::new(input))),
+        Compression::Zstd => {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(input).map_err(|e| format!("Could not decompress: {}...", e))?;
+            return Ok(Box::new(decoder));
+        }
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn decoder<'r>(_compression: Compression, _input: Box<dyn BufRead + 'r>) -> Result<Box<dyn Read + 'r>, String> {
+    return Err(String::from("Reading compressed input needs jsonrs built with the compression feature..."));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::compress::{decompress, detect, Compression};
+
+    #[test]
+    fn detects_compression() {
+        assert_eq!(Some(Compression::Gzip), detect(None, &[0x1f, 0x8b, 0x08, 0x00]));
+        assert_eq!(Some(Compression::Zstd), detect(Some("-"), &[0x28, 0xb5, 0x2f, 0xfd]));
+        assert_eq!(Some(Compression::Gzip), detect(Some("dump.json.gz"), b""));
+        assert_eq!(Some(Compression::Zstd), detect(Some("dump.json.zst"), b""));
+        assert_eq!(None, detect(Some("dump.json"), b"{\"a\": 1}"));
+        assert_eq!(Ok(b"[1]".to_vec()), decompress(Some("a.json"), b"[1]".to_vec()));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn decompresses() {
+        use std::io::Write;
+
+        let json = b"{\"a\": [1, 2, 3]}";
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(json).unwrap();
+        assert_eq!(Ok(json.to_vec()), decompress(None, gzip.finish().unwrap()));
+
+        let zstd = ruzstd::encoding::compress_to_vec(&json[..], ruzstd::encoding::CompressionLevel::Fastest);
+        assert_eq!(Ok(json.to_vec()), decompress(None, zstd));
+
+        assert!(decompress(Some("broken.gz"), b"[1]".to_vec()).is_err());
+    }
+}
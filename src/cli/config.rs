@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use jsonrs::{from_slice, JsonExpression};
+
+use super::Output;
+
+pub const FILE_NAME: &str = ".jsonrsfmt";
+
+// The closest .jsonrsfmt in the directory or any of its parents.
+pub fn find(start: &Path) -> Option<PathBuf> {
+    return start.ancestors().map(|dir| dir.join(FILE_NAME)).find(|path| path.is_file());
+}
+
+// Applies the settings in a config file, a JSON object such as
+// {"indent": 2, "sort_keys": true, "max_width": 100, "newline": "crlf"}.
+// The indent can also be given as a string, e.g. "\t".
+pub fn apply(path: &Path, output: &mut Output) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|e| format!("Could not read {}: {}...", path.display(), e))?;
+    let config = from_slice(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let invalid = |key: &str| format!("{}: invalid value for {}...", path.display(), key);
+
+    if !matches!(config, JsonExpression::Object(_)) {
+        return Err(format!("{}: expected an object...", path.display()));
+    }
+    for (key, value) in config.entries() {
+        match (key, value) {
+            ("indent", JsonExpression::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => output.pretty.indent = " ".repeat(*n as usize),
+            ("indent", JsonExpression::String(s)) if s.chars().all(|c| c == ' ' || c == '\t') => output.pretty.indent = s.clone(),
+            ("max_width", JsonExpression::Number(n)) if n.fract() == 0.0 && *n >= 0.0 => output.pretty.max_width = *n as usize,
+            ("sort_keys", JsonExpression::Bool(b)) => output.sort_keys = *b,
+            ("newline", JsonExpression::String(s)) => output.crlf = super::parse_newline(s).map_err(|_| invalid(key))?,
+            ("indent" | "max_width" | "sort_keys" | "newline", _) => return Err(invalid(key)),
+            _ => return Err(format!("{}: unknown setting {}...", path.display(), key))
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::cli::config::{apply, find, FILE_NAME};
+    use crate::cli::Output;
+
+    #[test]
+    fn finds_and_applies_config() {
+        let root = std::env::temp_dir().join(format!("jsonrs-config-{}", std::process::id()));
+        let nested = root.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(FILE_NAME), "{\"indent\": \"\\t\", \"sort_keys\": true, \"max_width\": 0, \"newline\": \"crlf\"}").unwrap();
+
+        let path = find(&nested).unwrap();
+        assert_eq!(root.join(FILE_NAME), path);
+
+        let mut output = Output::default();
+        apply(&path, &mut output).unwrap();
+        assert_eq!(("\t", 0, true, true), (output.pretty.indent.as_str(), output.pretty.max_width, output.sort_keys, output.crlf));
+
+        fs::write(root.join(FILE_NAME), "{\"indent\": -1}").unwrap();
+        assert!(apply(&path, &mut output).is_err());
+        fs::write(root.join(FILE_NAME), "{\"colour\": true}").unwrap();
+        assert!(apply(&path, &mut output).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use jsonrs::{JsonError, JsonExpression, ToJson};
+
+// How errors and validate results are reported, --output json is for editors
+// and CI.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Result<Format, String> {
+        match s {
+            "text" => return Ok(Format::Text),
+            "json" => return Ok(Format::Json),
+            _ => return Err(format!("Expected text or json, found {}...", s))
+        }
+    }
+}
+
+// One problem found in an input. The code is stable for tools to match on,
+// the message is for people. The snippet of source under the message is only
+// shown as text, tools reading json have the source themselves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    pub code: &'static str,
+    pub snippet: Option<String>
+}
+
+impl Diagnostic {
+    pub fn new(path: Option<&str>, message: String, code: &'static str) -> Diagnostic {
+        return Diagnostic { path: path.map(String::from), line: None, column: None, message, code, snippet: None };
+    }
+
+    // Like from_error, with the offending line of the source shown under it.
+    pub fn with_source(path: Option<&str>, error: &JsonError, source: &[u8]) -> Diagnostic {
+        let mut diagnostic = Diagnostic::from_error(path, error);
+        diagnostic.snippet = error.snippet(&String::from_utf8_lossy(source));
+        return diagnostic;
+    }
+
+    pub fn from_error(path: Option<&str>, error: &JsonError) -> Diagnostic {
+        let code = match error {
+            JsonError::UnexpectedEndOfInput => "unexpected-end",
+            JsonError::UnexpectedToken(..) => "unexpected-token",
+            JsonError::InvalidLiteral(..) => "invalid-literal",
+            JsonError::InvalidEscape(..) => "invalid-escape",
+            JsonError::InvalidUtf8(_) => "invalid-utf8",
+            JsonError::InvalidEncoding(_) => "invalid-encoding",
+            JsonError::Io(_) => "io",
+            JsonError::Conversion(_) => "conversion"
+        };
+        match error {
+            JsonError::UnexpectedToken(message, span) => return Diagnostic {
+                path: path.map(String::from),
+                line: Some(span.line),
+                column: Some(span.column),
+                message: message.clone(),
+                code,
+                snippet: None
+            },
+            // lexer errors keep their own wording and gain the place
+            other => {
+                let mut diagnostic = Diagnostic::new(path, other.to_string(), code);
+                diagnostic.line = other.span().map(|span| span.line);
+                diagnostic.column = other.span().map(|span| span.column);
+                return diagnostic;
+            }
+        }
+    }
+}
+
+impl ToJson for Diagnostic {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Object(vec![
+            (String::from("path"), Box::new(self.path.to_json())),
+            (String::from("line"), Box::new(self.line.to_json())),
+            (String::from("column"), Box::new(self.column.to_json())),
+            (String::from("message"), Box::new(self.message.to_json())),
+            (String::from("code"), Box::new(self.code.to_json()))
+        ]);
+    }
+}
+
+// The same text the parser errors have always printed, led by the path.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}: ", path)?;
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "Line {}, column {}: {}", line, column, self.message)?,
+            _ => write!(f, "{}", self.message)?
+        }
+        if let Some(snippet) = &self.snippet {
+            write!(f, "\n{}", snippet)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::diagnostic::Diagnostic;
+    use jsonrs::{json, JsonError, ToJson};
+
+    #[test]
+    fn reports_parse_errors() {
+        let error = json(String::from("{\"a\" 1}")).unwrap_err();
+        let diagnostic = Diagnostic::from_error(Some("a.json"), &error);
+        assert_eq!(format!("a.json: {}", error), diagnostic.to_string());
+        assert_eq!(
+            json(String::from("{\"path\": \"a.json\", \"line\": 1, \"column\": 6, \"message\": \"Expected colon, found number...\", \"code\": \"unexpected-token\"}")).unwrap(),
+            diagnostic.to_json()
+        );
+
+        let diagnostic = Diagnostic::with_source(Some("a.json"), &error, b"{\"a\" 1}");
+        assert_eq!("a.json: Line 1, column 6: Expected colon, found number...\n  --> line 1, column 6\n  |\n1 | {\"a\" 1}\n  |      ^", diagnostic.to_string());
+
+        let diagnostic = Diagnostic::from_error(None, &JsonError::UnexpectedEndOfInput);
+        assert_eq!("Unexpected end of input...", diagnostic.to_string());
+        assert_eq!(
+            json(String::from("{\"path\": null, \"line\": null, \"column\": null, \"message\": \"Unexpected end of input...\", \"code\": \"unexpected-end\"}")).unwrap(),
+            diagnostic.to_json()
+        );
+    }
+}
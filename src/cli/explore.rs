@@ -0,0 +1,435 @@
+// The tree model is plain code so it is tested in every build, only drawing
+// it needs the explore feature.
+#![cfg_attr(not(feature = "explore"), allow(dead_code))]
+
+use jsonrs::tape::{Tape, TapeRef};
+use jsonrs::{JsonPath, PathSegment};
+
+use super::Failure;
+
+// One visible line of the tree. Rows for the children of a container are
+// only made when it is expanded, so a large document costs nothing beyond
+// its tape until it is opened up.
+struct Row<'t> {
+    path: JsonPath,
+    node: TapeRef<'t>,
+    expanded: bool
+}
+
+impl Row<'_> {
+    fn depth(&self) -> usize {
+        return self.path.segments().len();
+    }
+}
+
+struct Explorer<'t> {
+    rows: Vec<Row<'t>>,
+    selected: usize,
+    // first row on screen
+    top: usize
+}
+
+// Whether the key or scalar value contains the query, ignoring case.
+fn matches(key: Option<&PathSegment>, node: TapeRef<'_>, query: &str) -> bool {
+    if let Some(PathSegment::Key(key)) = key {
+        if key.to_lowercase().contains(query) {
+            return true;
+        }
+    }
+    if node.len().is_some() {
+        return false;
+    }
+    let text = match node.as_str() {
+        Some(s) => s.to_lowercase(),
+        None => node.to_expression().to_string()
+    };
+    return text.contains(query);
+}
+
+// Visits every node in document order until visit returns true.
+fn walk<'t>(node: TapeRef<'t>, path: &mut JsonPath, visit: &mut impl FnMut(&JsonPath, TapeRef<'t>) -> bool) -> bool {
+    if visit(path, node) {
+        return true;
+    }
+    for (i, element) in node.elements().enumerate() {
+        path.push(PathSegment::Index(i));
+        let found = walk(element, path, visit);
+        path.pop();
+        if found {
+            return true;
+        }
+    }
+    for (key, value) in node.entries() {
+        path.push(PathSegment::Key(String::from(key)));
+        let found = walk(value, path, visit);
+        path.pop();
+        if found {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Keys go to the terminal as they are written, so control characters in
+// them are escaped the way they would be in a JSON string rather than let
+// through to move the cursor or set the clipboard. Values are already
+// printed as JSON.
+fn printable(text: &str) -> String {
+    if !text.chars().any(char::is_control) {
+        return String::from(text);
+    }
+    return text.chars().map(|c| if c.is_control() { format!("\\u{:04x}", c as u32) } else { c.to_string() }).collect();
+}
+
+// At most width characters, ending in … when cut.
+fn truncate(line: &str, width: usize) -> String {
+    if line.chars().count() <= width {
+        return String::from(line);
+    }
+    let mut cut: String = line.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    return cut;
+}
+
+impl<'t> Explorer<'t> {
+    fn new(tape: &'t Tape) -> Self {
+        let mut explorer = Explorer { rows: vec![Row { path: JsonPath::root(), node: tape.root(), expanded: false }], selected: 0, top: 0 };
+        explorer.expand(0);
+        return explorer;
+    }
+
+    fn expand(&mut self, index: usize) {
+        let row = &self.rows[index];
+        if row.expanded || row.node.len().is_none() {
+            return;
+        }
+
+        let node = row.node;
+        let children: Vec<Row<'t>> = node.elements().enumerate().map(|(i, element)| (PathSegment::Index(i), element))
+            .chain(node.entries().map(|(key, value)| (PathSegment::Key(String::from(key)), value)))
+            .map(|(segment, child)| Row { path: row.path.child(segment), node: child, expanded: false })
+            .collect();
+        self.rows[index].expanded = true;
+        self.rows.splice(index + 1..index + 1, children);
+    }
+
+    fn collapse(&mut self, index: usize) {
+        let depth = self.rows[index].depth();
+        let end = (index + 1..self.rows.len()).find(|&i| self.rows[i].depth() <= depth).unwrap_or(self.rows.len());
+        self.rows.drain(index + 1..end);
+        self.rows[index].expanded = false;
+    }
+
+    fn toggle(&mut self) {
+        if self.rows[self.selected].expanded {
+            self.collapse(self.selected);
+        } else {
+            self.expand(self.selected);
+        }
+    }
+
+    // Collapses the selected container, or moves to the parent when there
+    // is nothing to collapse.
+    fn back(&mut self) {
+        if self.rows[self.selected].expanded {
+            self.collapse(self.selected);
+            return;
+        }
+        let depth = self.rows[self.selected].depth();
+        if let Some(parent) = (0..self.selected).rev().find(|&i| self.rows[i].depth() < depth) {
+            self.selected = parent;
+        }
+    }
+
+    fn step(&mut self, by: isize) {
+        self.selected = self.selected.saturating_add_signed(by).min(self.rows.len() - 1);
+    }
+
+    fn pointer(&self) -> String {
+        return self.rows[self.selected].path.to_pointer();
+    }
+
+    // Expands everything on the way to the path and selects it.
+    fn reveal(&mut self, path: &JsonPath) {
+        let mut index = 0;
+        for depth in 1..=path.segments().len() {
+            self.expand(index);
+            let prefix = &path.segments()[..depth];
+            match (index + 1..self.rows.len()).find(|&i| self.rows[i].path.segments() == prefix) {
+                Some(found) => index = found,
+                None => break
+            }
+        }
+        self.selected = index;
+    }
+
+    // Selects the next node after the selected one, in document order and
+    // wrapping around, whose key or value contains the query. False when
+    // nothing does.
+    fn search(&mut self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        let current = self.rows[self.selected].path.clone();
+        let mut passed = false;
+        let mut first: Option<JsonPath> = None;
+        let mut next: Option<JsonPath> = None;
+
+        walk(self.rows[0].node, &mut JsonPath::root(), &mut |path, node| {
+            if *path == current {
+                passed = true;
+                return false;
+            }
+            if !matches(path.segments().last(), node, &query) {
+                return false;
+            }
+            if passed {
+                next = Some(path.clone());
+                return true;
+            }
+            first.get_or_insert_with(|| path.clone());
+            return false;
+        });
+
+        match next.or(first) {
+            Some(path) => {
+                self.reveal(&path);
+                return true;
+            },
+            None => return false
+        }
+    }
+
+    fn label(row: &Row<'_>) -> String {
+        let marker = match (row.node.len(), row.expanded) {
+            (Some(len), _) if len > 0 && !row.expanded => "▸ ",
+            (Some(len), _) if len > 0 => "▾ ",
+            _ => "  "
+        };
+        let name = match row.path.segments().last() {
+            Some(PathSegment::Key(key)) => format!("{}: ", printable(key)),
+            Some(PathSegment::Index(i)) => format!("[{}]: ", i),
+            None => String::new()
+        };
+        let value = match (row.node.type_name(), row.node.len()) {
+            ("object", Some(0)) => String::from("{}"),
+            ("array", Some(0)) => String::from("[]"),
+            ("object", Some(1)) => String::from("{1 key}"),
+            ("object", Some(n)) => format!("{{{} keys}}", n),
+            ("array", Some(1)) => String::from("[1 item]"),
+            ("array", Some(n)) => format!("[{} items]", n),
+            _ => row.node.to_expression().to_string()
+        };
+        return format!("{}{}{}{}", "  ".repeat(row.depth()), marker, name, value);
+    }
+
+    // The lines that fit on screen with whether each is selected, scrolling
+    // to keep the selection in view.
+    fn view(&mut self, width: usize, height: usize) -> Vec<(String, bool)> {
+        let height = height.max(1);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + height {
+            self.top = self.selected + 1 - height;
+        }
+        return self.rows[self.top..].iter().take(height).enumerate()
+            .map(|(i, row)| (truncate(&Explorer::label(row), width), self.top + i == self.selected))
+            .collect();
+    }
+}
+
+#[cfg(feature = "explore")]
+mod terminal {
+    use std::io::{self, Write};
+
+    use crossterm::cursor::{Hide, MoveTo, Show};
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+    use crossterm::style::{Attribute, Print, SetAttribute};
+    use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+    use crossterm::{execute, queue};
+
+    use super::Explorer;
+
+    const HELP: &str = "↑↓ move  ←→ fold  enter toggle  / search  n next  y copy pointer  q quit";
+
+    fn draw(out: &mut impl Write, explorer: &mut Explorer<'_>, status: &str) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, height) = (width as usize, height as usize);
+        queue!(out, Clear(ClearType::All))?;
+        for (y, (line, selected)) in explorer.view(width, height.saturating_sub(1)).into_iter().enumerate() {
+            queue!(out, MoveTo(0, y as u16))?;
+            if selected {
+                queue!(out, SetAttribute(Attribute::Reverse), Print(line), SetAttribute(Attribute::Reset))?;
+            } else {
+                queue!(out, Print(line))?;
+            }
+        }
+        let status = format!("{}  {}", super::printable(&explorer.pointer()), status);
+        queue!(out, MoveTo(0, height.saturating_sub(1) as u16), SetAttribute(Attribute::Dim), Print(super::truncate(&status, width)), SetAttribute(Attribute::Reset))?;
+        return out.flush();
+    }
+
+    // Terminals that support OSC 52 put the text on the system clipboard,
+    // others ignore it.
+    fn copy(out: &mut impl Write, text: &str) -> io::Result<()> {
+        write!(out, "\x1b]52;c;{}\x07", jsonrs::base64::encode(text.as_bytes()))?;
+        return out.flush();
+    }
+
+    fn events(out: &mut impl Write, explorer: &mut Explorer<'_>) -> io::Result<()> {
+        let mut status = String::from(HELP);
+        // Some while a search is being typed
+        let mut typing: Option<String> = None;
+        let mut query = String::new();
+
+        loop {
+            let shown = match &typing {
+                Some(text) => format!("/{}", text),
+                None => status.clone()
+            };
+            draw(out, explorer, &shown)?;
+
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(text) = &mut typing {
+                match key.code {
+                    KeyCode::Char(c) => text.push(c),
+                    KeyCode::Backspace => {
+                        text.pop();
+                    },
+                    KeyCode::Esc => typing = None,
+                    KeyCode::Enter => {
+                        query = typing.take().unwrap_or_default();
+                        status = if explorer.search(&query) { String::new() } else { format!("No match for {}", query) };
+                    },
+                    _ => {}
+                }
+                continue;
+            }
+
+            let page = terminal::size()?.1.saturating_sub(2).max(1) as isize;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => explorer.step(-1),
+                KeyCode::Down | KeyCode::Char('j') => explorer.step(1),
+                KeyCode::PageUp => explorer.step(-page),
+                KeyCode::PageDown => explorer.step(page),
+                KeyCode::Home | KeyCode::Char('g') => explorer.selected = 0,
+                KeyCode::End | KeyCode::Char('G') => explorer.selected = explorer.rows.len() - 1,
+                KeyCode::Right | KeyCode::Char('l') => explorer.expand(explorer.selected),
+                KeyCode::Left | KeyCode::Char('h') => explorer.back(),
+                KeyCode::Enter | KeyCode::Char(' ') => explorer.toggle(),
+                KeyCode::Char('/') => typing = Some(String::new()),
+                KeyCode::Char('n') if !query.is_empty() => {
+                    status = if explorer.search(&query) { String::new() } else { format!("No match for {}", query) };
+                },
+                KeyCode::Char('y') => {
+                    let pointer = explorer.pointer();
+                    copy(out, &pointer)?;
+                    status = if pointer.is_empty() { String::from("Copied the root pointer") } else { format!("Copied {}", super::printable(&pointer)) };
+                },
+                _ => {}
+            }
+        }
+    }
+
+    // The terminal is put back the way it was however the loop ends.
+    pub fn run(explorer: &mut Explorer<'_>) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        terminal::enable_raw_mode()?;
+        let result = execute!(out, EnterAlternateScreen, Hide).and_then(|_| events(&mut out, explorer));
+        let restored = execute!(out, Show, LeaveAlternateScreen).and(terminal::disable_raw_mode());
+        return result.and(restored);
+    }
+}
+
+// Shows the document as a tree to walk through with the keyboard.
+#[cfg(feature = "explore")]
+pub fn run(file: &Option<String>) -> Result<(), Failure> {
+    use std::io::IsTerminal;
+
+    use super::diagnostic::Diagnostic;
+    use super::{input_name, read_input};
+
+    if !std::io::stdout().is_terminal() {
+        return Err(Failure::from(String::from("explore needs a terminal to draw on...")));
+    }
+    let bytes = read_input(file)?;
+    let tape = Tape::parse(&bytes).map_err(|e| Failure::Invalid(Diagnostic::with_source(input_name(file), &e, &bytes)))?;
+    let mut explorer = Explorer::new(&tape);
+    return terminal::run(&mut explorer).map_err(|e| Failure::Io(Diagnostic::new(None, format!("Terminal error: {}...", e), "io")));
+}
+
+#[cfg(not(feature = "explore"))]
+pub fn run(_file: &Option<String>) -> Result<(), Failure> {
+    return Err(Failure::from(String::from("explore needs jsonrs built with the explore feature...")));
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrs::tape::Tape;
+
+    use crate::cli::explore::Explorer;
+
+    fn lines(explorer: &mut Explorer<'_>) -> Vec<String> {
+        return explorer.view(40, 20).into_iter().map(|(line, _)| line).collect();
+    }
+
+    #[test]
+    fn expands_and_collapses() {
+        let tape = Tape::parse(b"{\"users\": [{\"name\": \"Ada\", \"tags\": []}, {}], \"count\": 2}").unwrap();
+        let mut explorer = Explorer::new(&tape);
+        assert_eq!(vec!["▾ {2 keys}", "  ▸ users: [2 items]", "    count: 2"], lines(&mut explorer));
+
+        explorer.step(1);
+        explorer.toggle();
+        explorer.step(1);
+        explorer.expand(explorer.selected);
+        assert_eq!("/users/0", explorer.pointer());
+        assert_eq!(7, explorer.rows.len());
+        assert_eq!("        name: \"Ada\"", lines(&mut explorer)[3]);
+
+        // back collapses first, then goes to the parent
+        explorer.back();
+        assert_eq!(5, explorer.rows.len());
+        explorer.back();
+        assert_eq!("/users", explorer.pointer());
+        explorer.toggle();
+        assert_eq!(3, explorer.rows.len());
+    }
+
+    #[test]
+    fn searches() {
+        let tape = Tape::parse(b"{\"a\": {\"b\": [\"x\", \"needle\"]}, \"Needle\": 1, \"c\": \"needle too\"}").unwrap();
+        let mut explorer = Explorer::new(&tape);
+        assert!(explorer.search("NEEDLE"));
+        assert_eq!("/a/b/1", explorer.pointer());
+        assert!(explorer.search("needle"));
+        assert_eq!("/Needle", explorer.pointer());
+        assert!(explorer.search("needle"));
+        assert_eq!("/c", explorer.pointer());
+        // wraps around
+        assert!(explorer.search("needle"));
+        assert_eq!("/a/b/1", explorer.pointer());
+        assert!(!explorer.search("haystack"));
+    }
+
+    #[test]
+    fn escapes_keys() {
+        let tape = Tape::parse(b"{\"\\u001b]52;c;eA==\\u0007\": 1}").unwrap();
+        let mut explorer = Explorer::new(&tape);
+        assert_eq!("    \\u001b]52;c;eA==\\u0007: 1", lines(&mut explorer)[1]);
+        assert_eq!("plain / é", crate::cli::explore::printable("plain / é"));
+    }
+
+    #[test]
+    fn scrolls_and_truncates() {
+        let tape = Tape::parse(format!("[{}]", vec!["\"a long string value\""; 30].join(",")).as_bytes()).unwrap();
+        let mut explorer = Explorer::new(&tape);
+        explorer.step(25);
+        let view = explorer.view(16, 10);
+        assert_eq!(10, view.len());
+        assert_eq!(("    [24]: \"a lo…".to_string(), true), view[9]);
+    }
+}
@@ -0,0 +1,50 @@
+// Inputs named by an http or https URL are fetched instead of read from disk,
+// which needs the http feature. Responses other than 2xx are errors.
+pub fn is_url(name: &str) -> bool {
+    return name.starts_with("https://") || name.starts_with("http://");
+}
+
+#[cfg(feature = "http")]
+fn get(url: &str) -> Result<ureq::Body, String> {
+    let response = ureq::get(url)
+        .header("Accept", "application/json, application/*+json;q=0.9, */*;q=0.1")
+        .header("User-Agent", concat!("jsonrs/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| format!("Could not fetch: {}...", e))?;
+    return Ok(response.into_body());
+}
+
+#[cfg(feature = "http")]
+pub fn fetch(url: &str) -> Result<Vec<u8>, String> {
+    // the body is read whole like a file would be, without ureq's size cap
+    return get(url)?.into_with_config().limit(u64::MAX).read_to_vec().map_err(|e| format!("Could not fetch: {}...", e));
+}
+
+// The body as it arrives, for the commands that stream their input.
+#[cfg(feature = "http")]
+pub fn open(url: &str) -> Result<Box<dyn std::io::Read>, String> {
+    return Ok(Box::new(get(url)?.into_with_config().limit(u64::MAX).reader()));
+}
+
+#[cfg(not(feature = "http"))]
+pub fn fetch(_url: &str) -> Result<Vec<u8>, String> {
+    return Err(String::from("Fetching URLs needs jsonrs built with the http feature..."));
+}
+
+#[cfg(not(feature = "http"))]
+pub fn open(_url: &str) -> Result<Box<dyn std::io::Read>, String> {
+    return Err(String::from("Fetching URLs needs jsonrs built with the http feature..."));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::http::is_url;
+
+    #[test]
+    fn recognises_urls() {
+        assert!(is_url("https://example.com/a.json"));
+        assert!(is_url("http://localhost:8080"));
+        assert!(!is_url("https.json"));
+        assert!(!is_url("-"));
+    }
+}
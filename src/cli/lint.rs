@@ -0,0 +1,60 @@
+use std::io::{self, Write};
+
+use jsonrs::lint::{lint, Finding, LintConfig, Severity};
+use jsonrs::{JsonExpression, ToJson};
+
+use super::diagnostic::{Diagnostic, Format};
+use super::{input_name, read_input, write_failure, Failure};
+
+fn diagnostic(path: Option<&str>, finding: &Finding) -> Diagnostic {
+    let mut diagnostic = Diagnostic::new(path, format!("{}: {}", finding.severity, finding.message), finding.rule.code());
+    diagnostic.line = Some(finding.span.line);
+    diagnostic.column = Some(finding.span.column);
+    return diagnostic;
+}
+
+// Prints every finding, as text lines or one json object each with its
+// severity, and fails when any of them is an error.
+pub fn run(file: &Option<String>, config: &LintConfig, format: Format) -> Result<(), Failure> {
+    let bytes = read_input(file)?;
+    let findings = lint(&bytes, config).map_err(|e| Failure::Invalid(Diagnostic::with_source(input_name(file), &e, &bytes)))?;
+
+    let mut out = io::stdout().lock();
+    for finding in &findings {
+        let diagnostic = diagnostic(input_name(file), finding);
+        match format {
+            Format::Text => writeln!(out, "{}", diagnostic).map_err(write_failure)?,
+            Format::Json => {
+                // the severity has a field of its own
+                let mut json = Diagnostic { message: finding.message.clone(), ..diagnostic }.to_json();
+                if let JsonExpression::Object(pairs) = &mut json {
+                    pairs.push((String::from("severity"), Box::new(finding.severity.to_string().to_json())));
+                }
+                writeln!(out, "{}", json).map_err(write_failure)?;
+            }
+        }
+    }
+
+    let errors = findings.iter().filter(|f| f.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(Failure::Invalid(Diagnostic::new(input_name(file), format!("{} lint errors found...", errors), "lint-errors")));
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrs::lint::{lint, LintConfig};
+
+    use crate::cli::lint::diagnostic;
+
+    #[test]
+    fn formats_findings() {
+        let findings = lint(b"{\"a\": 1,\n \"a\": 2}", &LintConfig::default()).unwrap();
+        assert_eq!(
+            "a.json: Line 2, column 2: error: Key \"a\" is repeated, it first appears on line 1...",
+            diagnostic(Some("a.json"), &findings[0]).to_string()
+        );
+        assert_eq!("duplicate-key", diagnostic(None, &findings[0]).code);
+    }
+}
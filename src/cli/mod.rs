@@ -0,0 +1,547 @@
+mod color;
+mod compress;
+mod config;
+mod diagnostic;
+mod explore;
+mod http;
+mod lint;
+mod stats;
+mod validate;
+
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::ExitCode;
+
+use jsonrs::diff::{diff, render, RenderOptions};
+use jsonrs::filter::{compile, Filter};
+use jsonrs::lint::{LintConfig, Rule};
+use jsonrs::schema::infer_schema;
+use jsonrs::reformat::Style;
+use jsonrs::stream::array_elements;
+use jsonrs::{from_slice, reformat, JsonError, JsonExpression, JsonPath, PrettyOptions, ToJson};
+
+use color::{highlight, ColorChoice};
+use diagnostic::{Diagnostic, Format};
+
+const USAGE: &str = "Usage:
+    jsonrs fmt [--compact] [--indent <n>] [--width <n>] [--sort-keys] [--newline lf|crlf] [--color <when>] [--stream] [file]
+    jsonrs diff [--side-by-side] [--color <when>] <old> <new>
+    jsonrs get <path> [--compact] [--color <when>] [file]
+    jsonrs filter <expression> [--compact] [--color <when>] [file]
+    jsonrs typegen [--name <type>] [file]
+    jsonrs sort <key> [--at <pointer>] [--compact] [--color <when>] [file]
+    jsonrs split [--lines <n>] [--prefix <prefix>] [file]
+    jsonrs stats [--compact] [--color <when>] [file]
+    jsonrs schema [--compact] [--color <when>] [files...]
+    jsonrs validate [--watch] <paths...>
+    jsonrs lint [--disable <rule>]... [--max-depth <n>] [file]
+    jsonrs explore <file>
+
+Reads from stdin when no file is given or the file is -. <when> is auto,
+always or never. Files can be http:// or https:// URLs when built with the
+http feature, the response body is the input. Input compressed with gzip or
+zstd, recognised by its first bytes or a .gz or .zst extension, is
+decompressed first when built with the compression feature.
+fmt --stream rewrites the input as it is read without loading it, for files
+larger than memory, and puts every element on its own line, ignoring --width,
+--sort-keys and --color.
+Formatting defaults come from the nearest .jsonrsfmt in the current directory
+or its parents, flags override them.
+diff lists the paths whose values differ between two documents, as - and +
+lines or with --side-by-side as aligned columns. With --output json it prints
+an array of changes, each with its kind, its JSON Pointer path and the old
+and new values that exist.
+sort orders the array at the JSON Pointer given with --at, the whole input by
+default, by the value of a key in each element. Numbers and strings compare
+naturally, elements without the key go last and ties keep their order.
+split writes each element of a top level array on its own line, to stdout or
+with --lines into files of at most n lines named <prefix>0001.jsonl and on.
+validate checks files, or the .json files under directories, and with
+--watch checks them again each time they change.
+lint reports likely mistakes with their severity: duplicate-key (error),
+trailing-comma, precision-loss and nesting (warnings, nesting past 32 levels
+unless --max-depth says otherwise) and key-casing (info). --disable turns a
+rule off. Lint errors fail the command, warnings and info do not.
+explore shows the document as a tree to walk with the arrow keys, opening
+containers as they are expanded, when built with the explore feature. / searches
+keys and values, n finds the next match and y copies the JSON Pointer of the
+selected node to the clipboard.
+
+Every command takes --output text|json. With json, errors are written to
+stderr as objects with path, line, column, message and code, and validate
+prints one object per file with its diagnostics.
+
+Exit codes: 0 on success, 1 for invalid input or a failed command, 2 for bad
+usage or a bad .jsonrsfmt, 3 when a file could not be read or written.";
+
+// Options shared by the commands that print JSON.
+struct Output {
+    compact: bool,
+    pretty: PrettyOptions,
+    sort_keys: bool,
+    crlf: bool,
+    color: ColorChoice,
+    // name of the top level type for typegen
+    name: String,
+    // lines per file and the file name prefix for split
+    lines: Option<usize>,
+    prefix: String,
+    watch: bool,
+    // fmt without loading the input
+    stream: bool,
+    // diff in columns rather than lines
+    side_by_side: bool,
+    // JSON Pointer to the array sort works on
+    at: String,
+    // rules for lint
+    lint: LintConfig,
+    format: Format
+}
+
+enum Command {
+    Fmt { file: Option<String> },
+    Diff { old: String, new: String },
+    Get { path: JsonPath, file: Option<String> },
+    Filter { filter: Filter, file: Option<String> },
+    Typegen { file: Option<String> },
+    Sort { key: String, file: Option<String> },
+    Split { file: Option<String> },
+    Stats { file: Option<String> },
+    Schema { files: Vec<String> },
+    Validate { paths: Vec<String> },
+    Lint { file: Option<String> },
+    Explore { file: String }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        return Self {
+            compact: false,
+            pretty: PrettyOptions::default(),
+            sort_keys: false,
+            crlf: false,
+            color: ColorChoice::Auto,
+            name: String::from("Root"),
+            lines: None,
+            prefix: String::from("part-"),
+            watch: false,
+            stream: false,
+            side_by_side: false,
+            at: String::new(),
+            lint: LintConfig::default(),
+            format: Format::Text
+        };
+    }
+}
+
+fn parse_newline(s: &str) -> Result<bool, String> {
+    match s {
+        "lf" => return Ok(false),
+        "crlf" => return Ok(true),
+        _ => return Err(format!("Expected lf or crlf, found {}...", s))
+    }
+}
+
+// Flags are applied over the given output settings.
+fn parse_args(args: &[String], mut output: Output) -> Result<(Command, Output), String> {
+    let mut positional: Vec<&str> = Vec::new();
+
+    let mut iter = args.iter();
+    let name = iter.next().ok_or_else(|| String::from("Expected a command..."))?;
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value)),
+            _ => (arg.as_str(), None)
+        };
+
+        let mut value = |flag: &str| -> Result<String, String> {
+            return inline.map(String::from).or_else(|| iter.next().cloned()).ok_or_else(|| format!("Expected a value after {}...", flag));
+        };
+
+        match flag {
+            "--compact" => output.compact = true,
+            "--indent" => {
+                let n: usize = value(flag)?.parse().map_err(|_| String::from("Expected a number for --indent..."))?;
+                output.pretty.indent = " ".repeat(n);
+            },
+            "--width" => output.pretty.max_width = value(flag)?.parse().map_err(|_| String::from("Expected a number for --width..."))?,
+            "--color" => output.color = ColorChoice::parse(&value(flag)?)?,
+            "--sort-keys" => output.sort_keys = true,
+            "--newline" => output.crlf = parse_newline(&value(flag)?)?,
+            "--name" => output.name = value(flag)?,
+            "--lines" => match value(flag)?.parse() {
+                Ok(n) if n > 0 => output.lines = Some(n),
+                _ => return Err(String::from("Expected a positive number for --lines..."))
+            },
+            "--prefix" => output.prefix = value(flag)?,
+            "--output" => output.format = Format::parse(&value(flag)?)?,
+            "--watch" => output.watch = true,
+            "--stream" => output.stream = true,
+            "--side-by-side" => output.side_by_side = true,
+            "--at" => output.at = value(flag)?,
+            "--disable" => output.lint = output.lint.rule(Rule::parse(&value(flag)?)?, None),
+            "--max-depth" => output.lint.max_depth = value(flag)?.parse().map_err(|_| String::from("Expected a number for --max-depth..."))?,
+            "-" => positional.push(arg),
+            f if f.starts_with('-') => return Err(format!("Unknown option {}...", f)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let command = match (name.as_str(), positional.as_slice()) {
+        ("fmt", file) if file.len() <= 1 => Command::Fmt { file: file.first().map(|f| String::from(*f)) },
+        ("diff", [old, new]) => Command::Diff { old: String::from(*old), new: String::from(*new) },
+        ("get", [path, file @ ..]) if file.len() <= 1 => Command::Get {
+            path: path.parse()?,
+            file: file.first().map(|f| String::from(*f))
+        },
+        ("filter", [filter, file @ ..]) if file.len() <= 1 => Command::Filter {
+            filter: compile(filter)?,
+            file: file.first().map(|f| String::from(*f))
+        },
+        ("typegen", file) if file.len() <= 1 => Command::Typegen { file: file.first().map(|f| String::from(*f)) },
+        ("sort", [key, file @ ..]) if file.len() <= 1 => Command::Sort { key: String::from(*key), file: file.first().map(|f| String::from(*f)) },
+        ("split", file) if file.len() <= 1 => Command::Split { file: file.first().map(|f| String::from(*f)) },
+        ("stats", file) if file.len() <= 1 => Command::Stats { file: file.first().map(|f| String::from(*f)) },
+        ("schema", files) => Command::Schema { files: files.iter().map(|f| String::from(*f)).collect() },
+        ("validate", paths) if !paths.is_empty() => Command::Validate { paths: paths.iter().map(|p| String::from(*p)).collect() },
+        ("lint", file) if file.len() <= 1 => Command::Lint { file: file.first().map(|f| String::from(*f)) },
+        ("explore", [file]) => Command::Explore { file: String::from(*file) },
+        ("fmt" | "diff" | "get" | "filter" | "typegen" | "sort" | "split" | "stats" | "validate" | "lint" | "explore", _) => return Err(format!("Wrong number of arguments for {}...", name)),
+        _ => return Err(format!("Unknown command {}...", name))
+    };
+
+    return Ok((command, output));
+}
+
+// Why a command stopped early, which decides the exit code. Closed is the
+// reader of our output going away, e.g. `jsonrs fmt big.json | head`, which is
+// not an error.
+enum Failure {
+    Invalid(Diagnostic),
+    Io(Diagnostic),
+    Closed
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        return Failure::Invalid(Diagnostic::new(None, message, "error"));
+    }
+}
+
+fn write_failure(e: io::Error) -> Failure {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        return Failure::Closed;
+    }
+    return Failure::Io(Diagnostic::new(None, format!("Could not write output: {}...", e), "io"));
+}
+
+// The file name to blame in diagnostics, none for stdin.
+fn input_name(file: &Option<String>) -> Option<&str> {
+    return file.as_deref().filter(|f| *f != "-");
+}
+
+fn read_input(file: &Option<String>) -> Result<Vec<u8>, Failure> {
+    let mut bytes = Vec::new();
+    let read = match input_name(file) {
+        None => io::stdin().read_to_end(&mut bytes).map(|_| bytes),
+        Some(url) if http::is_url(url) => Ok(http::fetch(url).map_err(|e| Failure::Io(Diagnostic::new(Some(url), e, "io")))?),
+        Some(path) => fs::read(path)
+    };
+    let bytes = read.map_err(|e| Failure::Io(Diagnostic::new(input_name(file), format!("Could not read: {}...", e), "io")))?;
+    return compress::decompress(input_name(file), bytes).map_err(|e| Failure::Io(Diagnostic::new(input_name(file), e, "io")));
+}
+
+fn parse_input(file: &Option<String>) -> Result<JsonExpression, Failure> {
+    let bytes = read_input(file)?;
+    return from_slice(&bytes).map_err(|e| Failure::Invalid(Diagnostic::with_source(input_name(file), &e, &bytes)));
+}
+
+// For commands that stream their input rather than reading it all first.
+fn open_input(file: &Option<String>) -> Result<Box<dyn Read>, Failure> {
+    let input: Box<dyn io::BufRead> = match input_name(file) {
+        None => Box::new(io::stdin().lock()),
+        Some(url) if http::is_url(url) => Box::new(io::BufReader::new(http::open(url).map_err(|e| Failure::Io(Diagnostic::new(Some(url), e, "io")))?)),
+        Some(path) => {
+            let file = fs::File::open(path).map_err(|e| Failure::Io(Diagnostic::new(Some(path), format!("Could not read: {}...", e), "io")))?;
+            Box::new(io::BufReader::new(file))
+        }
+    };
+    return compress::reader(input_name(file), input).map_err(|e| Failure::Io(Diagnostic::new(input_name(file), e, "io")));
+}
+
+// Passes writes through, turning line breaks into CRLF when asked, and keeps
+// the kind of any write error since reformat reports those and read errors
+// alike.
+struct StreamOut<W: Write> {
+    out: W,
+    crlf: bool,
+    error: Option<io::ErrorKind>
+}
+
+impl<W: Write> Write for StreamOut<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // newlines inside strings are escaped, so every one here is a line break
+        let result = if self.crlf {
+            buf.split_inclusive(|&b| b == b'\n').try_for_each(|line| match line.strip_suffix(b"\n") {
+                Some(line) => self.out.write_all(line).and_then(|_| self.out.write_all(b"\r\n")),
+                None => self.out.write_all(line)
+            })
+        } else {
+            self.out.write_all(buf)
+        };
+        if let Err(e) = &result {
+            self.error = Some(e.kind());
+        }
+        return result.map(|_| buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.out.flush().inspect_err(|e| self.error = Some(e.kind()));
+    }
+}
+
+fn stream_fmt(file: &Option<String>, output: &Output) -> Result<(), Failure> {
+    let style = if output.compact { Style::Compact } else { Style::Indent(output.pretty.indent.clone()) };
+    let mut out = StreamOut { out: io::stdout().lock(), crlf: output.crlf, error: None };
+    if let Err(e) = reformat(open_input(file)?, &mut out, &style) {
+        match (out.error, e) {
+            (Some(kind), _) => return Err(write_failure(kind.into())),
+            (None, e @ JsonError::Io(_)) => return Err(Failure::Io(Diagnostic::from_error(input_name(file), &e))),
+            (None, e) => return Err(Failure::Invalid(Diagnostic::from_error(input_name(file), &e)))
+        }
+    }
+    return out.write_all(b"\n").and_then(|_| out.flush()).map_err(write_failure);
+}
+
+fn split(file: &Option<String>, output: &Output) -> Result<(), Failure> {
+    let mut out: Box<dyn Write> = Box::new(io::BufWriter::new(io::stdout().lock()));
+
+    for (written, element) in array_elements(open_input(file)?).enumerate() {
+        let element = element.map_err(|e| Failure::Invalid(Diagnostic::from_error(input_name(file), &e)))?;
+        if let Some(lines) = output.lines {
+            if written.is_multiple_of(lines) {
+                out.flush().map_err(write_failure)?;
+                let name = format!("{}{:04}.jsonl", output.prefix, written / lines + 1);
+                let file = fs::File::create(&name).map_err(|e| Failure::Io(Diagnostic::new(Some(&name), format!("Could not create: {}...", e), "io")))?;
+                out = Box::new(io::BufWriter::new(file));
+            }
+        }
+        writeln!(out, "{}", element).map_err(write_failure)?;
+    }
+
+    return out.flush().map_err(write_failure);
+}
+
+fn print(value: &JsonExpression, output: &Output) -> Result<(), Failure> {
+    let sorted;
+    let value = if output.sort_keys {
+        let mut copy = value.clone();
+        copy.transform(|node| {
+            if let JsonExpression::Object(pairs) = node {
+                pairs.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+        });
+        sorted = copy;
+        &sorted
+    } else {
+        value
+    };
+
+    let mut text = if output.compact { value.to_string() } else { value.to_string_pretty_with(&output.pretty) };
+    if output.color.enabled(io::stdout().is_terminal()) {
+        text = highlight(&text);
+    }
+    text.push('\n');
+    // newlines inside strings are escaped, so every one here is a line break
+    if output.crlf {
+        text = text.replace('\n', "\r\n");
+    }
+
+    return io::stdout().lock().write_all(text.as_bytes()).map_err(write_failure);
+}
+
+fn execute(command: Command, output: &Output) -> Result<(), Failure> {
+    match command {
+        Command::Fmt { file } if output.stream => return stream_fmt(&file, output),
+        Command::Fmt { file } => {
+            let value = parse_input(&file)?;
+            return print(&value, output);
+        },
+        Command::Diff { old, new } => {
+            let (old, new) = (parse_input(&Some(old))?, parse_input(&Some(new))?);
+            if output.format == Format::Json {
+                return print(&diff(&old, &new).to_json(), output);
+            }
+            let options = RenderOptions { side_by_side: output.side_by_side, color: output.color.enabled(io::stdout().is_terminal()) };
+            let mut text = render(&diff(&old, &new), &options);
+            if output.crlf {
+                text = text.replace('\n', "\r\n");
+            }
+            return io::stdout().lock().write_all(text.as_bytes()).map_err(write_failure);
+        },
+        Command::Get { path, file } => {
+            let value = parse_input(&file)?;
+            match value.get_at(&path) {
+                Some(found) => return print(found, output),
+                None => return Err(Failure::Invalid(Diagnostic::new(input_name(&file), format!("No value at {}...", path), "no-value")))
+            }
+        },
+        Command::Filter { filter, file } => {
+            let value = parse_input(&file)?;
+            for result in filter.eval(&value)? {
+                print(&result, output)?;
+            }
+            return Ok(());
+        },
+        Command::Typegen { file } => {
+            let value = parse_input(&file)?;
+            let code = value.to_rust_types(&output.name);
+            return io::stdout().lock().write_all(code.as_bytes()).map_err(write_failure);
+        },
+        Command::Sort { key, file } => {
+            let mut value = parse_input(&file)?;
+            value.sort_array_by_key(&output.at, &key)?;
+            return print(&value, output);
+        },
+        Command::Split { file } => return split(&file, output),
+        Command::Stats { file } => {
+            let value = parse_input(&file)?;
+            return print(&stats::report(&value), output);
+        },
+        Command::Schema { files } => {
+            let mut samples: Vec<JsonExpression> = Vec::new();
+            let inputs = if files.is_empty() { vec![None] } else { files.into_iter().map(Some).collect() };
+            for file in &inputs {
+                samples.push(parse_input(file)?);
+            }
+            return print(&infer_schema(&samples), output);
+        },
+        Command::Validate { paths } if output.watch => return validate::watch(&paths, output.format),
+        Command::Validate { paths } => return validate::validate(&paths, output.format),
+        Command::Lint { file } => return lint::run(&file, &output.lint, output.format),
+        Command::Explore { file } => return explore::run(&Some(file))
+    }
+}
+
+fn report(diagnostic: &Diagnostic, format: Format) {
+    match format {
+        Format::Text => eprintln!("{}", diagnostic),
+        Format::Json => eprintln!("{}", diagnostic.to_json())
+    }
+}
+
+// Exit codes are listed in USAGE. Usage errors are always text since the
+// output format may not be known yet.
+pub fn run(args: &[String]) -> ExitCode {
+    let mut defaults = Output::default();
+    let config = std::env::current_dir().ok().and_then(|dir| config::find(&dir));
+    if let Some(path) = config {
+        if let Err(e) = config::apply(&path, &mut defaults) {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        }
+    }
+
+    let (command, output) = match parse_args(args, defaults) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match execute(command, &output) {
+        Ok(()) | Err(Failure::Closed) => return ExitCode::SUCCESS,
+        Err(Failure::Invalid(diagnostic)) => {
+            report(&diagnostic, output.format);
+            return ExitCode::from(1);
+        },
+        Err(Failure::Io(diagnostic)) => {
+            report(&diagnostic, output.format);
+            return ExitCode::from(3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::color::ColorChoice;
+    use crate::cli::diagnostic::Format;
+    use crate::cli::{parse_args, Command, Output};
+
+    fn args(s: &str) -> Vec<String> {
+        return s.split_whitespace().map(String::from).collect();
+    }
+
+    #[test]
+    fn parses_commands() {
+        let (command, output) = parse_args(&args("fmt --indent 2 --color=never data.json"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Fmt { file: Some(f) } if f == "data.json"));
+        assert_eq!("  ", output.pretty.indent);
+        assert_eq!(ColorChoice::Never, output.color);
+        assert!(!output.stream);
+
+        let (command, output) = parse_args(&args("fmt --stream"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Fmt { file: None }));
+        assert!(output.stream);
+
+        let (command, output) = parse_args(&args("diff old.json new.json --side-by-side"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Diff { old, new } if old == "old.json" && new == "new.json"));
+        assert!(output.side_by_side);
+
+        let (command, output) = parse_args(&args("get a.b[1] --compact"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Get { path, file: None } if path.to_string() == "a.b[1]"));
+        assert!(output.compact);
+
+        let (command, _) = parse_args(&["filter".into(), ".a[] | select(. > 1)".into(), "-".into()], Output::default()).unwrap();
+        assert!(matches!(command, Command::Filter { file: Some(f), .. } if f == "-"));
+
+        let (command, output) = parse_args(&args("typegen --name User"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Typegen { file: None }));
+        assert_eq!("User", output.name);
+
+        let (command, output) = parse_args(&args("sort name --at /users data.json"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Sort { key, file: Some(f) } if key == "name" && f == "data.json"));
+        assert_eq!("/users", output.at);
+
+        let (command, output) = parse_args(&args("split big.json --lines=500 --prefix out/chunk-"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Split { file: Some(f) } if f == "big.json"));
+        assert_eq!((Some(500), "out/chunk-"), (output.lines, output.prefix.as_str()));
+
+        let (_, output) = parse_args(&args("fmt --sort-keys --newline crlf"), Output::default()).unwrap();
+        assert!(output.sort_keys && output.crlf);
+
+        let (command, output) = parse_args(&args("validate --watch config/"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Validate { paths } if paths == ["config/"]));
+        assert!(output.watch);
+
+        let (_, output) = parse_args(&args("validate --output=json a.json"), Output::default()).unwrap();
+        assert_eq!(Format::Json, output.format);
+
+        let (command, output) = parse_args(&args("lint --disable key-casing --max-depth 8 a.json"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Lint { file: Some(f) } if f == "a.json"));
+        assert_eq!((None, 8), (output.lint.key_casing, output.lint.max_depth));
+
+        let (command, _) = parse_args(&args("explore big.json"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Explore { file } if file == "big.json"));
+
+        let (command, _) = parse_args(&args("schema a.json b.json c.json"), Output::default()).unwrap();
+        assert!(matches!(command, Command::Schema { files } if files.len() == 3));
+    }
+
+    #[test]
+    fn rejects_bad_usage() {
+        assert!(parse_args(&args(""), Output::default()).is_err());
+        assert!(parse_args(&args("frobnicate"), Output::default()).is_err());
+        assert!(parse_args(&args("fmt --color sometimes"), Output::default()).is_err());
+        assert!(parse_args(&args("fmt a.json b.json"), Output::default()).is_err());
+        assert!(parse_args(&args("get"), Output::default()).is_err());
+        assert!(parse_args(&args("diff a.json"), Output::default()).is_err());
+        assert!(parse_args(&args("sort"), Output::default()).is_err());
+        assert!(parse_args(&args("filter .a["), Output::default()).is_err());
+        assert!(parse_args(&args("split --lines 0"), Output::default()).is_err());
+        assert!(parse_args(&args("validate --watch"), Output::default()).is_err());
+        assert!(parse_args(&args("fmt --output yaml"), Output::default()).is_err());
+        assert!(parse_args(&args("lint --disable tabs"), Output::default()).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use jsonrs::{JsonExpression, JsonPath, PathSegment};
+
+const TOP: usize = 10;
+
+fn number(n: usize) -> Box<JsonExpression> {
+    return Box::new(JsonExpression::from(n as f64));
+}
+
+fn pair(key: &str, value: Box<JsonExpression>) -> (String, Box<JsonExpression>) {
+    return (String::from(key), value);
+}
+
+// Node count of every container below the root, found in one walk.
+fn subtree_sizes(value: &JsonExpression, path: &mut JsonPath, sizes: &mut Vec<(String, usize)>) -> usize {
+    let mut size = 1;
+    match value {
+        JsonExpression::Array(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                path.push(PathSegment::Index(i));
+                size += subtree_sizes(element, path, sizes);
+                path.pop();
+            }
+        },
+        JsonExpression::Object(pairs) => {
+            for (key, value) in pairs {
+                path.push(PathSegment::Key(key.clone()));
+                size += subtree_sizes(value, path, sizes);
+                path.pop();
+            }
+        },
+        _ => return size
+    }
+
+    if !path.is_root() {
+        sizes.push((path.to_string(), size));
+    }
+    return size;
+}
+
+// Summary of a document for `jsonrs stats`: the figures from stats(), the
+// most used keys and the containers holding the most nodes.
+pub fn report(value: &JsonExpression) -> JsonExpression {
+    let stats = value.stats();
+
+    let mut keys: Vec<(&str, usize)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for (_, node) in value.nodes() {
+        for (key, _) in node.entries() {
+            let position = *positions.entry(key).or_insert_with(|| {
+                keys.push((key, 0));
+                return keys.len() - 1;
+            });
+            keys[position].1 += 1;
+        }
+    }
+    // most used first, ties in the order keys first appear
+    keys.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    keys.truncate(TOP);
+
+    let mut sizes: Vec<(String, usize)> = Vec::new();
+    subtree_sizes(value, &mut JsonPath::root(), &mut sizes);
+    sizes.sort_by_key(|(_, nodes)| std::cmp::Reverse(*nodes));
+    sizes.truncate(TOP);
+
+    let counts = vec![
+        pair("object", number(stats.objects)),
+        pair("array", number(stats.arrays)),
+        pair("string", number(stats.strings)),
+        pair("number", number(stats.numbers)),
+        pair("boolean", number(stats.bools)),
+        pair("null", number(stats.nulls))
+    ];
+    let top_keys = keys.into_iter()
+        .map(|(key, count)| Box::new(JsonExpression::Object(vec![pair("key", Box::new(JsonExpression::from(key))), pair("count", number(count))])))
+        .collect();
+    let largest = sizes.into_iter()
+        .map(|(path, nodes)| Box::new(JsonExpression::Object(vec![pair("path", Box::new(JsonExpression::from(path))), pair("nodes", number(nodes))])))
+        .collect();
+
+    return JsonExpression::Object(vec![
+        pair("nodes", number(stats.nodes())),
+        pair("depth", number(stats.max_depth)),
+        pair("counts", Box::new(JsonExpression::Object(counts))),
+        pair("string_bytes", number(stats.string_bytes)),
+        pair("key_bytes", number(stats.key_bytes)),
+        pair("largest_array", number(stats.largest_array)),
+        pair("largest_object", number(stats.largest_object)),
+        pair("top_keys", Box::new(JsonExpression::Array(top_keys))),
+        pair("largest_subtrees", Box::new(JsonExpression::Array(largest)))
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrs::json;
+
+    use crate::cli::stats::report;
+
+    #[test]
+    fn reports_keys_and_subtrees() {
+        let value = json(String::from("{\"users\": [{\"id\": 1, \"tags\": [\"a\"]}, {\"id\": 2}], \"meta\": {\"id\": 0}}")).unwrap();
+        let report = report(&value);
+        assert_eq!(Some("10"), report.get_path("nodes").map(|n| n.to_string()).as_deref());
+        assert_eq!(Some("4"), report.get_path("depth").map(|n| n.to_string()).as_deref());
+        assert_eq!("[{\"key\":\"id\",\"count\":3},{\"key\":\"users\",\"count\":1},{\"key\":\"meta\",\"count\":1},{\"key\":\"tags\",\"count\":1}]", report.get_path("top_keys").unwrap().to_string());
+        assert_eq!("{\"path\":\"users\",\"nodes\":7}", report.get_path("largest_subtrees[0]").unwrap().to_string());
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use jsonrs::{parse_recovering, JsonExpression, ToJson};
+
+use super::diagnostic::{Diagnostic, Format};
+use super::{compress, http, write_failure, Failure};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// The file itself, or every .json file under a directory.
+fn json_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return;
+    }
+
+    let mut entries: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => return
+    };
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "json") {
+            json_files(&entry, files);
+        }
+    }
+}
+
+// Every problem found, empty when the file is valid. `-` is stdin.
+fn check(path: &Path) -> Vec<Diagnostic> {
+    let name = path.display().to_string();
+    let read = if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes).map(|_| bytes).map_err(|e| format!("could not read: {}", e))
+    } else if http::is_url(&name) {
+        http::fetch(&name)
+    } else {
+        fs::read(path).map_err(|e| format!("could not read: {}", e))
+    };
+    let read = read.and_then(|bytes| compress::decompress(Some(&name), bytes));
+    let bytes = match read {
+        Ok(bytes) => bytes,
+        Err(e) => return vec![Diagnostic::new(Some(&name), e, "io")]
+    };
+    let (_, errors) = parse_recovering(&bytes);
+    return errors.iter().map(|e| Diagnostic::from_error(Some(&name), e)).collect();
+}
+
+// Text is a line per problem or "path: ok", json is one object per file.
+fn report(path: &Path, format: Format) -> Result<bool, Failure> {
+    let problems = check(path);
+    let mut out = io::stdout().lock();
+    match format {
+        Format::Text if problems.is_empty() => writeln!(out, "{}: ok", path.display()).map_err(write_failure)?,
+        Format::Text => {
+            for problem in &problems {
+                writeln!(out, "{}", problem).map_err(write_failure)?;
+            }
+        },
+        Format::Json => {
+            let result = JsonExpression::Object(vec![
+                (String::from("path"), Box::new(path.display().to_string().to_json())),
+                (String::from("valid"), Box::new(JsonExpression::Bool(problems.is_empty()))),
+                (String::from("diagnostics"), Box::new(problems.to_json()))
+            ]);
+            writeln!(out, "{}", result).map_err(write_failure)?;
+        }
+    }
+    return Ok(problems.is_empty());
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    return fs::metadata(path).and_then(|m| m.modified()).ok();
+}
+
+fn all_files(paths: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        json_files(Path::new(path), &mut files);
+    }
+    return files;
+}
+
+// Validates every file once and fails if any has problems.
+pub fn validate(paths: &[String], format: Format) -> Result<(), Failure> {
+    let files = all_files(paths);
+    let mut valid: usize = 0;
+    for file in &files {
+        if report(file, format)? {
+            valid += 1;
+        }
+    }
+    if valid < files.len() {
+        let message = format!("{} of {} files are invalid...", files.len() - valid, files.len());
+        return Err(Failure::Invalid(Diagnostic::new(None, message, "invalid-files")));
+    }
+    return Ok(());
+}
+
+// Validates everything, then polls for changes and validates each file again
+// whenever it is saved. New files under a watched directory are picked up.
+// Runs until interrupted.
+pub fn watch(paths: &[String], format: Format) -> Result<(), Failure> {
+    if paths.iter().any(|p| p == "-") {
+        return Err(Failure::from(String::from("Cannot watch stdin...")));
+    }
+    if let Some(url) = paths.iter().find(|p| http::is_url(p)) {
+        return Err(Failure::from(format!("Cannot watch {}, only files can be watched...", url)));
+    }
+
+    let mut seen: HashMap<PathBuf, Option<SystemTime>> = HashMap::new();
+    loop {
+        for file in all_files(paths) {
+            let time = modified(&file);
+            if seen.get(&file) != Some(&time) {
+                report(&file, format)?;
+                seen.insert(file, time);
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::cli::validate::{all_files, check};
+
+    #[test]
+    fn finds_and_checks_files() {
+        let root = std::env::temp_dir().join(format!("jsonrs-validate-{}", std::process::id()));
+        fs::create_dir_all(root.join("nested")).unwrap();
+        fs::write(root.join("good.json"), "{\"a\": 1}").unwrap();
+        fs::write(root.join("nested").join("bad.json"), "{\"a\" 1,\n \"b\": }").unwrap();
+        fs::write(root.join("notes.txt"), "not json").unwrap();
+
+        let files = all_files(&[root.display().to_string()]);
+        assert_eq!(vec![root.join("good.json"), root.join("nested").join("bad.json")], files);
+        assert!(check(&files[0]).is_empty());
+        assert_eq!(2, check(&files[1]).len());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
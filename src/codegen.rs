@@ -0,0 +1,253 @@
+use std::fmt::Write;
+
+use crate::infer::{shape_of, Field, Shape};
+use crate::JsonExpression;
+
+// Generates Rust structs from a sample document. Field types are inferred
+// from every value seen at that position: keys missing from some objects, or
+// null in some of them, become Option, numbers are i64 unless any of them has
+// a fraction, and anything that mixes types falls back to JsonExpression.
+// Each struct comes with FromJson and ToJson impls for this crate.
+
+// Written as raw identifiers, r#type.
+const KEYWORDS: [&str; 46] = [
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for", "gen",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "try", "typeof", "yield"
+];
+
+// Keywords that cannot be raw identifiers, fields get a trailing underscore.
+const PATH_KEYWORDS: [&str; 3] = ["self", "super", "crate"];
+
+// Type names the generated code refers to, a struct with one of these names
+// would shadow it, and Self cannot name a struct at all.
+const RESERVED_TYPES: [&str; 9] = ["Self", "String", "Vec", "Option", "Box", "Result", "JsonExpression", "FromJson", "ToJson"];
+
+fn words(key: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+
+    for c in key.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        // camelCase boundaries start a new word
+        if c.is_ascii_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    return words;
+}
+
+// A snake_case name for the key not among the fields named so far. Keys that
+// differ only in punctuation or case, or have no ASCII letters or digits at
+// all, get a numeric suffix.
+fn field_name(key: &str, taken: &[String]) -> String {
+    let mut name = words(key).join("_");
+    if name.is_empty() {
+        name = String::from("field");
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "field_");
+    } else if PATH_KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+
+    let raw = |name: String| if KEYWORDS.contains(&name.as_str()) { format!("r#{}", name) } else { name };
+    let mut unique = raw(name.clone());
+    let mut n = 2;
+    while taken.contains(&unique) {
+        unique = raw(format!("{}_{}", name.trim_end_matches('_'), n));
+        n += 1;
+    }
+    return unique;
+}
+
+fn type_name(key: &str) -> String {
+    let mut name: String = words(key).iter().map(|w| {
+        let mut chars = w.chars();
+        return chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default();
+    }).collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "Type");
+    }
+    return name;
+}
+
+// Element structs are named after the singular of the array key.
+fn singular(name: &str) -> String {
+    if name.ends_with("ies") && name.len() > 3 {
+        return format!("{}y", &name[..name.len() - 3]);
+    }
+    if name.ends_with('s') && !name.ends_with("ss") && name.len() > 1 {
+        return String::from(&name[..name.len() - 1]);
+    }
+    return format!("{}Item", name);
+}
+
+struct Generator {
+    // struct definitions in the order they are finished
+    structs: Vec<(String, String)>,
+    names: Vec<String>
+}
+
+impl Generator {
+    fn unique(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut n = 2;
+        while self.names.contains(&unique) {
+            unique = format!("{}{}", name, n);
+            n += 1;
+        }
+        self.names.push(unique.clone());
+        return unique;
+    }
+
+    fn rust_type(&mut self, shape: &Shape, name: &str) -> String {
+        match shape {
+            Shape::Unknown | Shape::Any => return String::from("JsonExpression"),
+            Shape::Null => return String::from("Option<JsonExpression>"),
+            Shape::Bool => return String::from("bool"),
+            Shape::Integer => return String::from("i64"),
+            Shape::Float => return String::from("f64"),
+            Shape::String => return String::from("String"),
+            Shape::Array(element) => return format!("Vec<{}>", self.rust_type(element, &singular(name))),
+            Shape::Optional(inner) => return format!("Option<{}>", self.rust_type(inner, name)),
+            Shape::Object(fields) => return self.object(fields, name)
+        }
+    }
+
+    fn object(&mut self, fields: &[Field], name: &str) -> String {
+        let name = self.unique(String::from(name));
+        let mut members: Vec<(String, &str, String)> = Vec::new();
+        let mut taken: Vec<String> = Vec::new();
+        for field in fields {
+            let mut ty = self.rust_type(&field.shape, &type_name(&field.key));
+            if field.optional && !ty.starts_with("Option<") {
+                ty = format!("Option<{}>", ty);
+            }
+            let field_name = field_name(&field.key, &taken);
+            taken.push(field_name.clone());
+            members.push((field_name, &field.key, ty));
+        }
+
+        let mut out = String::new();
+        writeln!(out, "#[derive(Debug, Clone, PartialEq)]").unwrap();
+        writeln!(out, "pub struct {} {{", name).unwrap();
+        for (field, _, ty) in &members {
+            writeln!(out, "    pub {}: {},", field, ty).unwrap();
+        }
+        writeln!(out, "}}\n").unwrap();
+
+        writeln!(out, "impl FromJson for {} {{", name).unwrap();
+        writeln!(out, "    fn from_json(value: &JsonExpression) -> Result<Self, String> {{").unwrap();
+        writeln!(out, "        let field = |key: &str| value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v).unwrap_or(&JsonExpression::Null);").unwrap();
+        writeln!(out, "        return Ok(Self {{").unwrap();
+        for (field, key, _) in &members {
+            writeln!(out, "            {}: FromJson::from_json(field({:?}))?,", field, key).unwrap();
+        }
+        writeln!(out, "        }});\n    }}\n}}\n").unwrap();
+
+        writeln!(out, "impl ToJson for {} {{", name).unwrap();
+        writeln!(out, "    fn to_json(&self) -> JsonExpression {{").unwrap();
+        writeln!(out, "        return JsonExpression::Object(vec![").unwrap();
+        for (field, key, _) in &members {
+            writeln!(out, "            (String::from({:?}), Box::new(self.{}.to_json())),", key, field).unwrap();
+        }
+        writeln!(out, "        ]);\n    }}\n}}").unwrap();
+
+        self.structs.push((name.clone(), out));
+        return name;
+    }
+}
+
+impl JsonExpression {
+    // Rust source for types matching this sample, the top level type is named
+    // `root`. A top level array describes its elements.
+    pub fn to_rust_types(&self, root: &str) -> String {
+        let mut generator = Generator { structs: vec![], names: RESERVED_TYPES.iter().map(|name| String::from(*name)).collect() };
+        let shape = match shape_of(self) {
+            Shape::Array(element) => *element,
+            shape => shape
+        };
+
+        let root_type = generator.rust_type(&shape, &type_name(root));
+        let mut out = String::from("use jsonrs::{FromJson, JsonExpression, ToJson};\n\n");
+        if !matches!(shape, Shape::Object(_)) {
+            let alias = generator.unique(type_name(root));
+            writeln!(out, "pub type {} = {};\n", alias, root_type).unwrap();
+        }
+
+        // the root was finished last, print it first and the rest in order
+        let structs = generator.structs;
+        for (_, definition) in structs.iter().rev() {
+            out.push_str(definition);
+            out.push('\n');
+        }
+        return String::from(out.trim_end()) + "\n";
+    }
+}
+
+#[cfg(test)]
+#[allow(dead_code)]
+mod generated {
+    // to_rust_types output for the sample in compiles_awkward_keys, built
+    // here so the test fails to compile if the generated code does not
+    include!("../tests/fixtures/typegen.rs");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::generated::Root;
+    use crate::{json, FromJson, ToJson};
+
+    #[test]
+    fn infers_struct_fields() {
+        let sample = json(String::from("{
+            \"userName\": \"a\",
+            \"type\": 1,
+            \"tags\": [\"x\"],
+            \"orders\": [
+                {\"id\": 1, \"total\": 2.5, \"note\": null},
+                {\"id\": 2, \"total\": 3, \"note\": \"gift\", \"coupon\": \"c\"}
+            ]
+        }")).unwrap();
+
+        let code = sample.to_rust_types("root");
+        assert!(code.starts_with("use jsonrs::{FromJson, JsonExpression, ToJson};\n\n#[derive(Debug, Clone, PartialEq)]\npub struct Root {\n"));
+        assert!(code.contains("    pub user_name: String,\n    pub r#type: i64,\n    pub tags: Vec<String>,\n    pub orders: Vec<Order>,\n"));
+        assert!(code.contains("pub struct Order {\n    pub id: i64,\n    pub total: f64,\n    pub note: Option<String>,\n    pub coupon: Option<String>,\n}"));
+        assert!(code.contains("            user_name: FromJson::from_json(field(\"userName\"))?,"));
+        assert!(code.contains("            (String::from(\"userName\"), Box::new(self.user_name.to_json())),"));
+    }
+
+    #[test]
+    fn mixed_and_top_level_arrays() {
+        let sample = json(String::from("[{\"v\": 1}, {\"v\": \"one\", \"w\": []}]")).unwrap();
+        let code = sample.to_rust_types("item");
+        assert!(code.contains("pub struct Item {\n    pub v: JsonExpression,\n    pub w: Option<Vec<JsonExpression>>,\n}"));
+
+        let scalars = json(String::from("[1, 2]")).unwrap();
+        assert!(scalars.to_rust_types("ids").contains("pub type Ids = i64;"));
+    }
+
+    #[test]
+    fn compiles_awkward_keys() {
+        let sample = json(String::from("{\"self\": {\"super\": 1}, \"Self\": 2, \"a-b\": \"x\", \"a_b\": \"y\", \"\": true, \"é\": false, \"type\": 1.5, \"string\": {\"crate\": \"c\"}, \"1st\": null, \"items\": [{\"id\": 1}], \"Item\": {\"n\": 1}}")).unwrap();
+        assert_eq!(include_str!("../tests/fixtures/typegen.rs"), sample.to_rust_types("root"));
+        assert_eq!(sample, Root::from_json(&sample).unwrap().to_json());
+
+        let scalars = json(String::from("[true]")).unwrap();
+        assert!(scalars.to_rust_types("self").contains("pub type Self2 = bool;"));
+    }
+}
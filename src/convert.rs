@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use crate::JsonExpression;
+
+impl JsonExpression {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonExpression::Number(_) => return "number",
+            JsonExpression::String(_) => return "string",
+            JsonExpression::Bool(_) => return "boolean",
+            JsonExpression::Null => return "null",
+            JsonExpression::Array(_) => return "array",
+            JsonExpression::Object(_) => return "object",
+        }
+    }
+}
+
+fn type_error(expected: &str, found: &JsonExpression) -> String {
+    return format!("Expected {}, found {}...", expected, found.type_name());
+}
+
+fn number_to_i64(n: f64) -> Result<i64, String> {
+    // i64::MAX isn't representable as f64, so the upper bound is exclusive
+    if n.fract() != 0.0 || n < i64::MIN as f64 || n >= i64::MAX as f64 {
+        return Err(format!("Expected integer, found {}...", n));
+    }
+
+    return Ok(n as i64);
+}
+
+fn number_to_u64(n: f64) -> Result<u64, String> {
+    if n.fract() != 0.0 || n < 0.0 || n >= u64::MAX as f64 {
+        return Err(format!("Expected unsigned integer, found {}...", n));
+    }
+
+    return Ok(n as u64);
+}
+
+impl TryFrom<JsonExpression> for String {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::String(s) => return Ok(s),
+            _ => return Err(type_error("string", &value))
+        }
+    }
+}
+
+impl TryFrom<&JsonExpression> for String {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        return <&str>::try_from(value).map(String::from);
+    }
+}
+
+impl<'a> TryFrom<&'a JsonExpression> for &'a str {
+    type Error = String;
+
+    fn try_from(value: &'a JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::String(s) => return Ok(s),
+            _ => return Err(type_error("string", value))
+        }
+    }
+}
+
+impl TryFrom<&JsonExpression> for f64 {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::Number(n) => return Ok(*n),
+            _ => return Err(type_error("number", value))
+        }
+    }
+}
+
+impl TryFrom<JsonExpression> for f64 {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        return f64::try_from(&value);
+    }
+}
+
+impl TryFrom<&JsonExpression> for i64 {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        return number_to_i64(f64::try_from(value)?);
+    }
+}
+
+impl TryFrom<JsonExpression> for i64 {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        return i64::try_from(&value);
+    }
+}
+
+impl TryFrom<&JsonExpression> for u64 {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        return number_to_u64(f64::try_from(value)?);
+    }
+}
+
+impl TryFrom<JsonExpression> for u64 {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        return u64::try_from(&value);
+    }
+}
+
+impl TryFrom<&JsonExpression> for bool {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::Bool(b) => return Ok(*b),
+            _ => return Err(type_error("boolean", value))
+        }
+    }
+}
+
+impl TryFrom<JsonExpression> for bool {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        return bool::try_from(&value);
+    }
+}
+
+impl TryFrom<JsonExpression> for Vec<JsonExpression> {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::Array(elements) => return Ok(elements.into_iter().map(|e| *e).collect()),
+            _ => return Err(type_error("array", &value))
+        }
+    }
+}
+
+impl TryFrom<&JsonExpression> for Vec<JsonExpression> {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::Array(elements) => return Ok(elements.iter().map(|e| e.as_ref().clone()).collect()),
+            _ => return Err(type_error("array", value))
+        }
+    }
+}
+
+impl TryFrom<JsonExpression> for Vec<(String, JsonExpression)> {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        match value {
+            JsonExpression::Object(pairs) => return Ok(pairs.into_iter().map(|(k, v)| (k, *v)).collect()),
+            _ => return Err(type_error("object", &value))
+        }
+    }
+}
+
+impl From<&str> for JsonExpression {
+    fn from(value: &str) -> Self {
+        return JsonExpression::String(String::from(value));
+    }
+}
+
+impl From<String> for JsonExpression {
+    fn from(value: String) -> Self {
+        return JsonExpression::String(value);
+    }
+}
+
+impl From<f64> for JsonExpression {
+    fn from(value: f64) -> Self {
+        return JsonExpression::Number(value);
+    }
+}
+
+impl From<i64> for JsonExpression {
+    fn from(value: i64) -> Self {
+        return JsonExpression::Number(value as f64);
+    }
+}
+
+impl From<bool> for JsonExpression {
+    fn from(value: bool) -> Self {
+        return JsonExpression::Bool(value);
+    }
+}
+
+impl<T: Into<JsonExpression>> From<Option<T>> for JsonExpression {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(v) => return v.into(),
+            None => return JsonExpression::Null
+        }
+    }
+}
+
+impl<T: Into<JsonExpression>> From<Vec<T>> for JsonExpression {
+    fn from(value: Vec<T>) -> Self {
+        return JsonExpression::Array(value.into_iter().map(|e| Box::new(e.into())).collect());
+    }
+}
+
+impl<T: Into<JsonExpression>> From<HashMap<String, T>> for JsonExpression {
+    fn from(value: HashMap<String, T>) -> Self {
+        let mut pairs: Vec<(String, Box<JsonExpression>)> = value.into_iter()
+            .map(|(k, v)| (k, Box::new(v.into())))
+            .collect();
+
+        // HashMap iteration order is random, sort so the output is stable
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        return JsonExpression::Object(pairs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn primitives() {
+        assert_eq!(Ok(String::from("hi")), String::try_from(JsonExpression::String(String::from("hi"))));
+        assert_eq!(Ok(1.5), f64::try_from(&JsonExpression::Number(1.5)));
+        assert_eq!(Ok(-3), i64::try_from(&JsonExpression::Number(-3.0)));
+        assert_eq!(Ok(true), bool::try_from(JsonExpression::Bool(true)));
+    }
+
+    #[test]
+    fn descriptive_errors() {
+        assert_eq!(
+            Err(String::from("Expected string, found number...")),
+            String::try_from(JsonExpression::Number(1.0))
+        );
+        assert_eq!(
+            Err(String::from("Expected integer, found 1.5...")),
+            i64::try_from(JsonExpression::Number(1.5))
+        );
+        assert!(u64::try_from(JsonExpression::Number(-1.0)).is_err());
+    }
+
+    #[test]
+    fn arrays() {
+        let expr = json(String::from("[1, null]")).unwrap();
+        assert_eq!(
+            Ok(vec![JsonExpression::Number(1.0), JsonExpression::Null]),
+            Vec::<JsonExpression>::try_from(expr)
+        );
+    }
+
+    #[test]
+    fn into_expression() {
+        let mut map: HashMap<String, JsonExpression> = HashMap::new();
+        map.insert(String::from("b"), vec![1i64, 2].into());
+        map.insert(String::from("a"), "x".into());
+        map.insert(String::from("c"), None::<bool>.into());
+
+        let expr: JsonExpression = map.into();
+        assert_eq!(json(String::from("{\"a\": \"x\", \"b\": [1, 2], \"c\": null}")).unwrap(), expr);
+        assert_eq!(JsonExpression::Bool(false), false.into());
+    }
+}
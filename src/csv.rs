@@ -0,0 +1,248 @@
+use crate::lexer::is_number_literal;
+use crate::JsonExpression;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NestedValues {
+    // arrays and objects are written as compact JSON in a single column
+    Inline,
+    // every leaf gets its own column named by its path, e.g. `address.city`
+    Flatten
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    pub nested: NestedValues,
+    // when reading, turn numbers, true/false and empty fields into numbers,
+    // booleans and null instead of keeping every field as a string
+    pub infer_types: bool
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        return Self { delimiter: ',', nested: NestedValues::Inline, infer_types: true };
+    }
+}
+
+fn cell(expression: &JsonExpression) -> String {
+    match expression {
+        JsonExpression::String(s) => return s.clone(),
+        JsonExpression::Null => return String::new(),
+        _ => return expression.to_string()
+    }
+}
+
+fn row_cells(row: &JsonExpression, options: &CsvOptions) -> Vec<(String, String)> {
+    match options.nested {
+        NestedValues::Inline => return row.entries().map(|(k, v)| (String::from(k), cell(v))).collect(),
+        NestedValues::Flatten => return row.leaves().map(|(path, v)| (path.to_string(), cell(v))).collect()
+    }
+}
+
+fn write_field(field: &str, delimiter: char, out: &mut String) {
+    if field.contains(delimiter) || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        out.push('"');
+        out.push_str(&field.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(field);
+    }
+}
+
+fn write_record<'a>(fields: impl Iterator<Item = &'a str>, delimiter: char, out: &mut String) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.push(delimiter);
+        }
+        write_field(field, delimiter, out);
+    }
+    out.push('\n');
+}
+
+// RFC 4180 records: quoted fields may contain delimiters, doubled quotes and
+// line breaks. Both \n and \r\n end a record.
+fn read_records(input: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut records: Vec<Vec<String>> = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut chars = input.chars().peekable();
+    let mut line: usize = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        },
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        },
+                        None => return Err(format!("Unterminated quoted field on line {}...", line))
+                    }
+                }
+
+                if !matches!(chars.peek(), None | Some('\n') | Some('\r')) && chars.peek() != Some(&delimiter) {
+                    return Err(format!("Unexpected character after quoted field on line {}...", line));
+                }
+            },
+            '\r' if chars.peek() == Some(&'\n') => {},
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+                line += 1;
+            },
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    return Ok(records);
+}
+
+fn sniff(field: String) -> JsonExpression {
+    match field.as_str() {
+        "" => return JsonExpression::Null,
+        "true" => return JsonExpression::Bool(true),
+        "false" => return JsonExpression::Bool(false),
+        _ => {}
+    }
+
+    // only fields spelled the way JSON spells a number are numbers, so codes
+    // like 007 or 00501 keep their leading zeros as strings
+    if !is_number_literal(field.as_bytes()) {
+        return JsonExpression::String(field);
+    }
+    match field.parse::<f64>() {
+        Ok(n) if n.is_finite() => return JsonExpression::Number(n),
+        _ => return JsonExpression::String(field)
+    }
+}
+
+impl JsonExpression {
+    // The header is the union of every row's columns in the order they are
+    // first seen, rows missing a column leave it empty.
+    pub fn to_csv(&self, options: &CsvOptions) -> Result<String, String> {
+        let rows = match self {
+            JsonExpression::Array(elements) => elements,
+            _ => return Err(format!("Expected array of objects for CSV, found {}...", self.type_name()))
+        };
+
+        let mut header: Vec<String> = Vec::new();
+        let mut table: Vec<Vec<(String, String)>> = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            if !matches!(row.as_ref(), JsonExpression::Object(_)) {
+                return Err(format!("Expected object for CSV row {}, found {}...", i, row.type_name()));
+            }
+
+            let cells = row_cells(row, options);
+            for (column, _) in &cells {
+                if !header.contains(column) {
+                    header.push(column.clone());
+                }
+            }
+            table.push(cells);
+        }
+
+        let mut out = String::new();
+        write_record(header.iter().map(|h| h.as_str()), options.delimiter, &mut out);
+        for cells in &table {
+            let fields = header.iter().map(|column| {
+                return cells.iter().rev().find(|(c, _)| c == column).map(|(_, v)| v.as_str()).unwrap_or("");
+            });
+            write_record(fields, options.delimiter, &mut out);
+        }
+
+        return Ok(out);
+    }
+
+    pub fn from_csv(input: &str, options: &CsvOptions) -> Result<JsonExpression, String> {
+        let mut records = read_records(input, options.delimiter)?.into_iter();
+        let header = match records.next() {
+            Some(header) => header,
+            None => return Ok(JsonExpression::Array(vec![]))
+        };
+
+        let mut rows: Vec<Box<JsonExpression>> = Vec::new();
+        for (i, record) in records.enumerate() {
+            if record.len() != header.len() {
+                return Err(format!("Expected {} fields on row {}, found {}...", header.len(), i + 1, record.len()));
+            }
+
+            let pairs = header.iter().cloned().zip(record).map(|(column, field)| {
+                let value = if options.infer_types { sniff(field) } else { JsonExpression::String(field) };
+                return (column, Box::new(value));
+            });
+            rows.push(Box::new(JsonExpression::Object(pairs.collect())));
+        }
+
+        return Ok(JsonExpression::Array(rows));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv::{CsvOptions, NestedValues};
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn to_csv_inline_and_flattened() {
+        let expr = json(String::from("[
+            {\"name\": \"a, b\", \"tags\": [1, 2]},
+            {\"name\": \"say \\\"hi\\\"\", \"extra\": null}
+        ]")).unwrap();
+
+        assert_eq!(
+            "name,tags,extra\n\"a, b\",\"[1,2]\",\n\"say \"\"hi\"\"\",,\n",
+            expr.to_csv(&CsvOptions::default()).unwrap()
+        );
+
+        let options = CsvOptions { nested: NestedValues::Flatten, ..CsvOptions::default() };
+        assert_eq!(
+            "name,tags[0],tags[1],extra\n\"a, b\",1,2,\n\"say \"\"hi\"\"\",,,\n",
+            expr.to_csv(&options).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_csv_with_type_sniffing() {
+        let input = "id,name,active,note\r\n1,\"x\ny\",true,\n2,z,false,007a\n";
+        assert_eq!(
+            json(String::from("[
+                {\"id\": 1, \"name\": \"x\\ny\", \"active\": true, \"note\": null},
+                {\"id\": 2, \"name\": \"z\", \"active\": false, \"note\": \"007a\"}
+            ]")).unwrap(),
+            JsonExpression::from_csv(input, &CsvOptions::default()).unwrap()
+        );
+
+        let options = CsvOptions { infer_types: false, ..CsvOptions::default() };
+        assert_eq!(
+            json(String::from("[{\"id\": \"1\"}]")).unwrap(),
+            JsonExpression::from_csv("id\n1\n", &options).unwrap()
+        );
+        assert!(JsonExpression::from_csv("a,b\n1\n", &options).is_err());
+    }
+
+    #[test]
+    fn sniffs_only_json_numbers() {
+        let input = "a,b,c,d,e,f,g,h,i\n007,00501,+1,1.,.5,0,-0.5,1e3,2E-2\n";
+        assert_eq!(
+            json(String::from("[{
+                \"a\": \"007\", \"b\": \"00501\", \"c\": \"+1\", \"d\": \"1.\", \"e\": \".5\",
+                \"f\": 0, \"g\": -0.5, \"h\": 1000, \"i\": 0.02
+            }]")).unwrap(),
+            JsonExpression::from_csv(input, &CsvOptions::default()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::mem;
+
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+// A zipper over a document it owns, focused on one value at a time. Moving
+// down takes the child out of its parent and keeps the parent, with a hole
+// where the child was, on a stack; moving up puts it back. Every move is a
+// constant amount of work however deep the focus is, and the focus can be
+// edited in place. `into_inner` closes the zipper and gives the document
+// back with any edits made.
+pub struct JsonCursor {
+    focus: JsonExpression,
+    // the ancestors from the root down, each with the position the focus was
+    // taken from
+    parents: Vec<(JsonExpression, usize)>
+}
+
+// The value at a position among an array's elements or an object's members.
+fn slot(parent: &mut JsonExpression, position: usize) -> Option<&mut JsonExpression> {
+    match parent {
+        JsonExpression::Array(elements) => return elements.get_mut(position).map(|e| e.as_mut()),
+        JsonExpression::Object(pairs) => return pairs.get_mut(position).map(|(_, v)| v.as_mut()),
+        _ => return None
+    }
+}
+
+impl JsonCursor {
+    pub fn new(root: JsonExpression) -> Self {
+        return Self { focus: root, parents: vec![] };
+    }
+
+    pub fn value(&self) -> &JsonExpression {
+        return &self.focus;
+    }
+
+    pub fn value_mut(&mut self) -> &mut JsonExpression {
+        return &mut self.focus;
+    }
+
+    // Swaps in a new value for the focus, returning the old one.
+    pub fn replace(&mut self, value: JsonExpression) -> JsonExpression {
+        return mem::replace(&mut self.focus, value);
+    }
+
+    // How many levels below the root the focus is.
+    pub fn depth(&self) -> usize {
+        return self.parents.len();
+    }
+
+    // The key the focus sits under, None in an array or at the root.
+    pub fn key(&self) -> Option<&str> {
+        match self.parents.last() {
+            Some((JsonExpression::Object(pairs), position)) => return Some(&pairs[*position].0),
+            _ => return None
+        }
+    }
+
+    // Where the focus is, from the root.
+    pub fn path(&self) -> JsonPath {
+        let mut path = JsonPath::root();
+        for (parent, position) in &self.parents {
+            match parent {
+                JsonExpression::Object(pairs) => path.push(PathSegment::Key(pairs[*position].0.clone())),
+                _ => path.push(PathSegment::Index(*position))
+            }
+        }
+        return path;
+    }
+
+    // Moves to the element or member at a position within the focus. False,
+    // staying put, when there is nothing there.
+    pub fn down(&mut self, position: usize) -> bool {
+        let child = match slot(&mut self.focus, position) {
+            Some(child) => mem::replace(child, JsonExpression::Null),
+            None => return false
+        };
+        let parent = mem::replace(&mut self.focus, child);
+        self.parents.push((parent, position));
+        return true;
+    }
+
+    // Moves to the value of a key, the last one when it repeats.
+    pub fn child(&mut self, key: &str) -> bool {
+        match &self.focus {
+            JsonExpression::Object(pairs) => match pairs.iter().rposition(|(k, _)| k == key) {
+                Some(position) => return self.down(position),
+                None => return false
+            },
+            _ => return false
+        }
+    }
+
+    // Moves to the parent, false at the root.
+    pub fn up(&mut self) -> bool {
+        let (mut parent, position) = match self.parents.pop() {
+            Some(parent) => parent,
+            None => return false
+        };
+        if let Some(hole) = slot(&mut parent, position) {
+            *hole = mem::replace(&mut self.focus, JsonExpression::Null);
+        }
+        self.focus = parent;
+        return true;
+    }
+
+    // Moves to the position `offset` away within the same parent.
+    fn across(&mut self, offset: isize) -> bool {
+        let target = match self.parents.last_mut() {
+            Some((parent, position)) => match position.checked_add_signed(offset) {
+                Some(target) if slot(parent, target).is_some() => target,
+                _ => return false
+            },
+            None => return false
+        };
+        return self.up() && self.down(target);
+    }
+
+    pub fn next_sibling(&mut self) -> bool {
+        return self.across(1);
+    }
+
+    pub fn prev_sibling(&mut self) -> bool {
+        return self.across(-1);
+    }
+
+    // Moves back up to the root.
+    pub fn top(&mut self) {
+        while self.up() {}
+    }
+
+    pub fn into_inner(mut self) -> JsonExpression {
+        self.top();
+        return self.focus;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cursor::JsonCursor;
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn navigates() {
+        let mut cursor = JsonCursor::new(json(String::from("{\"a\": [1, {\"b\": true}, 3], \"c\": null}")).unwrap());
+        assert!(cursor.child("a"));
+        assert!(cursor.down(1));
+        assert!(cursor.child("b"));
+        assert_eq!("a[1].b", cursor.path().to_string());
+        assert_eq!((3, Some("b")), (cursor.depth(), cursor.key()));
+        assert_eq!(&JsonExpression::Bool(true), cursor.value());
+
+        assert!(cursor.up());
+        assert!(cursor.next_sibling());
+        assert_eq!(&JsonExpression::Number(3.0), cursor.value());
+        assert!(!cursor.next_sibling());
+        assert!(cursor.prev_sibling() && cursor.prev_sibling());
+        assert!(!cursor.prev_sibling());
+        assert!(!cursor.down(0) && !cursor.child("x"));
+
+        cursor.top();
+        assert!(!cursor.up());
+        assert!(cursor.child("a") && cursor.next_sibling());
+        assert_eq!(Some("c"), cursor.key());
+    }
+
+    #[test]
+    fn edits_in_place() {
+        let mut cursor = JsonCursor::new(json(String::from("{\"items\": [{\"n\": 1}, {\"n\": 2}]}")).unwrap());
+        assert!(cursor.child("items") && cursor.down(0) && cursor.child("n"));
+        loop {
+            let n = f64::try_from(cursor.value()).unwrap();
+            *cursor.value_mut() = JsonExpression::Number(n * 10.0);
+            if !(cursor.up() && cursor.next_sibling() && cursor.child("n")) {
+                break;
+            }
+        }
+        assert_eq!(json(String::from("{\"items\": [{\"n\": 10}, {\"n\": 20}]}")).unwrap(), cursor.into_inner());
+    }
+}
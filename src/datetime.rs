@@ -0,0 +1,119 @@
+// Timestamps are RFC 3339 strings on the wire. Each backend is behind its own
+// feature so only the date library a project already uses gets pulled in.
+
+#[cfg(feature = "chrono")]
+mod chrono_support {
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+
+    use crate::{FromJson, JsonExpression, ToJson};
+
+    impl JsonExpression {
+        pub fn as_datetime(&self) -> Result<DateTime<FixedOffset>, String> {
+            match self {
+                JsonExpression::String(s) => {
+                    return DateTime::parse_from_rfc3339(s).map_err(|e| format!("Invalid RFC 3339 timestamp {:?}: {}...", s, e));
+                },
+                _ => return Err(format!("Expected RFC 3339 string, found {}...", self.type_name()))
+            }
+        }
+
+        pub fn from_datetime<Tz: TimeZone>(datetime: &DateTime<Tz>) -> Self
+        where
+            Tz::Offset: std::fmt::Display
+        {
+            return JsonExpression::String(datetime.to_rfc3339());
+        }
+    }
+
+    impl<Tz: TimeZone> ToJson for DateTime<Tz>
+    where
+        Tz::Offset: std::fmt::Display
+    {
+        fn to_json(&self) -> JsonExpression {
+            return JsonExpression::from_datetime(self);
+        }
+    }
+
+    impl FromJson for DateTime<FixedOffset> {
+        fn from_json(value: &JsonExpression) -> Result<Self, String> {
+            return value.as_datetime();
+        }
+    }
+
+    impl FromJson for DateTime<Utc> {
+        fn from_json(value: &JsonExpression) -> Result<Self, String> {
+            return value.as_datetime().map(|d| d.with_timezone(&Utc));
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        use crate::{FromJson, JsonExpression, ToJson};
+
+        #[test]
+        fn round_trip() {
+            let datetime = Utc.with_ymd_and_hms(2024, 2, 29, 12, 30, 0).unwrap();
+            let expr = datetime.to_json();
+            assert_eq!(JsonExpression::from("2024-02-29T12:30:00+00:00"), expr);
+            assert_eq!(Ok(datetime), DateTime::<Utc>::from_json(&expr));
+
+            let offset = JsonExpression::from("2024-02-29T14:30:00+02:00").as_datetime().unwrap();
+            assert_eq!(datetime, offset);
+            assert!(JsonExpression::from("yesterday").as_datetime().is_err());
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_support {
+    use time::format_description::well_known::Rfc3339;
+    use time::OffsetDateTime;
+
+    use crate::{FromJson, JsonExpression, ToJson};
+
+    impl JsonExpression {
+        pub fn as_offset_datetime(&self) -> Result<OffsetDateTime, String> {
+            match self {
+                JsonExpression::String(s) => {
+                    return OffsetDateTime::parse(s, &Rfc3339).map_err(|e| format!("Invalid RFC 3339 timestamp {:?}: {}...", s, e));
+                },
+                _ => return Err(format!("Expected RFC 3339 string, found {}...", self.type_name()))
+            }
+        }
+
+        // Only fails for years outside 0..=9999, which RFC 3339 can't express.
+        pub fn from_offset_datetime(datetime: &OffsetDateTime) -> Result<Self, String> {
+            return datetime.format(&Rfc3339).map(JsonExpression::String).map_err(|e| e.to_string());
+        }
+    }
+
+    impl ToJson for OffsetDateTime {
+        fn to_json(&self) -> JsonExpression {
+            return JsonExpression::from_offset_datetime(self).unwrap_or(JsonExpression::Null);
+        }
+    }
+
+    impl FromJson for OffsetDateTime {
+        fn from_json(value: &JsonExpression) -> Result<Self, String> {
+            return value.as_offset_datetime();
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use time::OffsetDateTime;
+
+        use crate::{FromJson, JsonExpression, ToJson};
+
+        #[test]
+        fn round_trip() {
+            let value = OffsetDateTime::from_unix_timestamp(1709209800).unwrap();
+            let expr = value.to_json();
+            assert_eq!(JsonExpression::from("2024-02-29T12:30:00Z"), expr);
+            assert_eq!(Ok(value), OffsetDateTime::from_json(&expr));
+            assert!(JsonExpression::Number(1.0).as_offset_datetime().is_err());
+        }
+    }
+}
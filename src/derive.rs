@@ -0,0 +1,297 @@
+// The FromJson and ToJson derive macros, they share their names with the
+// traits so `use jsonrs::{FromJson, ToJson}` brings in both.
+pub use jsonrs_derive::{FromJson, ToJson};
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::{json, FromJson, JsonExpression, ToJson};
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    #[json(rename_all = "camelCase")]
+    struct User {
+        user_name: String,
+        #[json(rename = "e-mail", alias = "email", alias = "mail")]
+        address: String,
+        r#type: u8,
+        scores: Vec<f64>
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Settings {
+        name: String,
+        nickname: Option<String>,
+        #[json(default)]
+        tags: Vec<String>,
+        #[json(default = "Settings::default_limit")]
+        limit: u32
+    }
+
+    impl Settings {
+        fn default_limit() -> u32 {
+            return 10;
+        }
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    #[json(deny_unknown_fields)]
+    struct Server {
+        host: String,
+        port: u16,
+        #[json(default)]
+        timeout: u32
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Page {
+        total: u32,
+        next: Option<String>
+    }
+
+    // the map comes first and the page may be missing
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Envelope {
+        #[json(flatten)]
+        extra: HashMap<String, JsonExpression>,
+        #[json(flatten)]
+        page: Option<Page>
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Response {
+        items: Vec<u32>,
+        #[json(flatten)]
+        page: Page,
+        #[json(flatten)]
+        extra: HashMap<String, JsonExpression>
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Patch {
+        #[json(skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[json(skip_serializing_if = "Vec::is_empty")]
+        labels: Vec<String>,
+        #[json(skip)]
+        dirty: bool
+    }
+
+    mod epoch_millis {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        use crate::{FromJson, JsonExpression};
+
+        pub fn to_json(time: &SystemTime) -> JsonExpression {
+            return JsonExpression::Number(time.duration_since(UNIX_EPOCH).unwrap().as_millis() as f64);
+        }
+
+        pub fn from_json(value: &JsonExpression) -> Result<SystemTime, String> {
+            return Ok(UNIX_EPOCH + Duration::from_millis(u64::from_json(value)?));
+        }
+    }
+
+    fn quoted_number(n: &u64) -> JsonExpression {
+        return JsonExpression::String(n.to_string());
+    }
+
+    fn unquoted_number(value: &JsonExpression) -> Result<u64, String> {
+        return String::from_json(value)?.parse().map_err(|_| String::from("Expected a number in a string..."));
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Record {
+        #[json(with = "epoch_millis")]
+        created: SystemTime,
+        #[json(serialize_with = "quoted_number", deserialize_with = "unquoted_number")]
+        id: u64
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Id(u64);
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    #[json(rename_all = "snake_case")]
+    enum Shape {
+        Empty,
+        Circle { radius: f64 },
+        Square(f64),
+        Line(f64, f64)
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    #[json(tag = "type")]
+    enum Event {
+        #[json(rename = "click", rename_all = "camelCase")]
+        Click { page_x: i32 },
+        Wrapped(Id2),
+        // written next to the tag, as it is not an object
+        Reading(Celsius),
+        Held(Holder),
+        Close
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Id2 {
+        id: u64
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Celsius(f64);
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Holder {
+        value: u8
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    #[json(tag = "t", content = "c")]
+    enum Message {
+        Text(String),
+        Ping
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    #[json(untagged)]
+    enum Value {
+        Number(f64),
+        Pair { a: String, b: String },
+        Nothing
+    }
+
+    #[derive(Debug, PartialEq, FromJson, ToJson)]
+    struct Point<T>(T, T);
+
+    #[test]
+    fn renamed_fields() {
+        let user = User { user_name: String::from("ada"), address: String::from("a@b.c"), r#type: 2, scores: vec![1.5] };
+        let expected = json(String::from("{\"userName\": \"ada\", \"e-mail\": \"a@b.c\", \"type\": 2, \"scores\": [1.5]}")).unwrap();
+        assert_eq!(expected, user.to_json());
+        assert_eq!(Ok(user), User::from_json(&expected));
+
+        let aliased = json(String::from("{\"userName\": \"ada\", \"email\": \"x@y.z\", \"type\": 2, \"scores\": []}")).unwrap();
+        assert_eq!("x@y.z", User::from_json(&aliased).unwrap().address);
+
+        let missing = json(String::from("{\"userName\": \"ada\", \"type\": 2, \"scores\": []}")).unwrap();
+        assert_eq!(Err(String::from("Missing field e-mail...")), User::from_json(&missing));
+        assert_eq!(Err(String::from("Expected object, found array...")), User::from_json(&JsonExpression::Array(vec![])));
+    }
+
+    #[test]
+    fn missing_fields() {
+        let partial = json(String::from("{\"name\": \"a\"}")).unwrap();
+        assert_eq!(Ok(Settings { name: String::from("a"), nickname: None, tags: vec![], limit: 10 }), Settings::from_json(&partial));
+
+        let full = json(String::from("{\"name\": \"a\", \"nickname\": null, \"tags\": [\"x\"], \"limit\": 3}")).unwrap();
+        assert_eq!(Ok(Settings { name: String::from("a"), nickname: None, tags: vec![String::from("x")], limit: 3 }), Settings::from_json(&full));
+
+        assert_eq!(Err(String::from("Missing field name...")), Settings::from_json(&JsonExpression::Object(vec![])));
+    }
+
+    #[test]
+    fn suggests_keys() {
+        let typo = json(String::from("{\"nmae\": \"a\"}")).unwrap();
+        assert_eq!(Err(String::from("Missing field name, did you mean nmae?...")), Settings::from_json(&typo));
+
+        let server = json(String::from("{\"host\": \"h\", \"port\": 80, \"timeout\": 5}")).unwrap();
+        assert_eq!(Ok(Server { host: String::from("h"), port: 80, timeout: 5 }), Server::from_json(&server));
+        let unknown = json(String::from("{\"host\": \"h\", \"port\": 80, \"timout\": 5}")).unwrap();
+        assert_eq!(Err(String::from("Unknown field timout, did you mean timeout?...")), Server::from_json(&unknown));
+        let unknown = json(String::from("{\"host\": \"h\", \"port\": 80, \"debug\": true}")).unwrap();
+        assert_eq!(Err(String::from("Unknown field debug...")), Server::from_json(&unknown));
+
+        assert_eq!(Err(String::from("Unknown variant circel, did you mean circle?...")), Shape::from_json(&json(String::from("{\"circel\": {\"radius\": 1}}")).unwrap()));
+    }
+
+    fn round_trip<T: FromJson + ToJson + std::fmt::Debug + PartialEq>(value: T, text: &str) {
+        let expected = json(String::from(text)).unwrap();
+        assert_eq!(expected, value.to_json(), "{}", text);
+        assert_eq!(Ok(value), T::from_json(&expected), "{}", text);
+    }
+
+    #[test]
+    fn tagged_enums() {
+        round_trip(Shape::Empty, "\"empty\"");
+        round_trip(Shape::Circle { radius: 2.0 }, "{\"circle\": {\"radius\": 2}}");
+        round_trip(Shape::Square(3.0), "{\"square\": 3}");
+        round_trip(Shape::Line(1.0, 2.0), "{\"line\": [1, 2]}");
+        assert_eq!(Err(String::from("Unknown variant hexagon...")), Shape::from_json(&JsonExpression::from("hexagon")));
+
+        round_trip(Event::Click { page_x: 5 }, "{\"type\": \"click\", \"pageX\": 5}");
+        round_trip(Event::Wrapped(Id2 { id: 9 }), "{\"type\": \"Wrapped\", \"id\": 9}");
+        round_trip(Event::Close, "{\"type\": \"Close\"}");
+        round_trip(Event::Reading(Celsius(21.5)), "{\"type\": \"Reading\", \"value\": 21.5}");
+        round_trip(Event::Held(Holder { value: 3 }), "{\"type\": \"Held\", \"value\": 3}");
+        assert_eq!(Err(String::from("Missing tag type...")), Event::from_json(&json(String::from("{\"pageX\": 5}")).unwrap()));
+
+        round_trip(Message::Text(String::from("hi")), "{\"t\": \"Text\", \"c\": \"hi\"}");
+        round_trip(Message::Ping, "{\"t\": \"Ping\"}");
+    }
+
+    #[test]
+    fn untagged_enums() {
+        round_trip(Value::Number(1.0), "1");
+        round_trip(Value::Pair { a: String::from("x"), b: String::from("y") }, "{\"a\": \"x\", \"b\": \"y\"}");
+        round_trip(Value::Nothing, "null");
+        assert_eq!(Err(String::from("Expected a value matching a variant of Value...")), Value::from_json(&JsonExpression::Bool(true)));
+    }
+
+    #[test]
+    fn flattened_fields() {
+        let text = "{\"items\": [1], \"total\": 1, \"next\": \"b\", \"trace\": \"x\"}";
+        let response = Response::from_json(&json(String::from(text)).unwrap()).unwrap();
+        assert_eq!(Page { total: 1, next: Some(String::from("b")) }, response.page);
+        // the map only gets the keys the page does not read
+        assert_eq!(Some(&JsonExpression::from("x")), response.extra.get("trace"));
+        assert_eq!(1, response.extra.len());
+        assert_eq!(json(String::from(text)).unwrap(), response.to_json());
+        assert_eq!(vec!["items", "total", "next"], Response::field_keys());
+
+        let envelope = Envelope::from_json(&json(String::from("{\"trace\": \"x\", \"total\": 2}")).unwrap()).unwrap();
+        assert_eq!(Some(Page { total: 2, next: None }), envelope.page);
+        assert_eq!(json(String::from("{\"trace\": \"x\", \"total\": 2, \"next\": null}")).unwrap(), envelope.to_json());
+    }
+
+    #[test]
+    fn skipped_fields() {
+        let patch = Patch { title: None, labels: vec![String::from("bug")], dirty: true };
+        assert_eq!(json(String::from("{\"labels\": [\"bug\"]}")).unwrap(), patch.to_json());
+        assert_eq!(JsonExpression::Object(vec![]), Patch { title: None, labels: vec![], dirty: false }.to_json());
+
+        let read = Patch::from_json(&json(String::from("{\"title\": \"t\", \"labels\": [], \"dirty\": true}")).unwrap()).unwrap();
+        assert_eq!(Patch { title: Some(String::from("t")), labels: vec![], dirty: false }, read);
+    }
+
+    #[test]
+    fn conversion_functions() {
+        let record = Record { created: UNIX_EPOCH + Duration::from_millis(1_700_000_000_123), id: 42 };
+        let text = "{\"created\": 1700000000123, \"id\": \"42\"}";
+        assert_eq!(json(String::from(text)).unwrap(), record.to_json());
+        assert_eq!(Ok(record), Record::from_json(&json(String::from(text)).unwrap()));
+        assert_eq!(Err(String::from("id: Expected a number in a string...")), Record::from_json(&json(String::from("{\"created\": 0, \"id\": \"x\"}")).unwrap()));
+    }
+
+    #[test]
+    fn tuple_structs() {
+        assert_eq!(JsonExpression::Number(7.0), Id(7).to_json());
+        assert_eq!(Ok(Id(7)), Id::from_json(&JsonExpression::Number(7.0)));
+
+        let point = json(String::from("[1, 2]")).unwrap();
+        assert_eq!(Ok(Point(1i32, 2)), point.to::<Point<i32>>());
+        assert_eq!(point, Point(1i32, 2).to_json());
+        assert!(json(String::from("[1]")).unwrap().to::<Point<i32>>().is_err());
+    }
+
+    #[test]
+    fn error_paths() {
+        let shapes = json(String::from("[\"empty\", {\"line\": [1, 2]}, {\"circle\": {\"radius\": \"x\"}}]")).unwrap();
+        assert_eq!(Err(String::from("[2].circle.radius: Expected number, found string...")), Vec::<Shape>::from_json(&shapes));
+
+        let lines = json(String::from("{\"a\": {\"line\": [1, null]}}")).unwrap();
+        assert_eq!(Err(String::from("a.line[1]: Expected number, found null...")), HashMap::<String, Shape>::from_json(&lines));
+
+        let message = json(String::from("{\"t\": \"Text\", \"c\": 1}")).unwrap();
+        assert_eq!(Err(String::from("c: Expected string, found number...")), Message::from_json(&message));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::path::{JsonPath, PathSegment};
+use crate::traits::ToJson;
+use crate::JsonExpression;
+
+const RESET: &str = "\x1b[0m";
+const REMOVED: &str = "\x1b[31m";
+const ADDED: &str = "\x1b[32m";
+
+// One difference between two documents, at a path in both.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added { path: JsonPath, value: JsonExpression },
+    Removed { path: JsonPath, value: JsonExpression },
+    Replaced { path: JsonPath, old: JsonExpression, new: JsonExpression }
+}
+
+impl Change {
+    pub fn path(&self) -> &JsonPath {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Replaced { path, .. } => return path
+        }
+    }
+
+    // The value before and after, None on the side it does not exist.
+    fn sides(&self) -> (Option<&JsonExpression>, Option<&JsonExpression>) {
+        match self {
+            Change::Added { value, .. } => return (None, Some(value)),
+            Change::Removed { value, .. } => return (Some(value), None),
+            Change::Replaced { old, new, .. } => return (Some(old), Some(new))
+        }
+    }
+}
+
+// For tools, e.g. {"change": "replaced", "path": "/a/0", "old": 1, "new": 2}
+// with the path as a JSON Pointer and only the sides that exist.
+impl ToJson for Change {
+    fn to_json(&self) -> JsonExpression {
+        let kind = match self {
+            Change::Added { .. } => "added",
+            Change::Removed { .. } => "removed",
+            Change::Replaced { .. } => "replaced"
+        };
+        let mut pairs = vec![
+            (String::from("change"), Box::new(kind.to_json())),
+            (String::from("path"), Box::new(self.path().to_pointer().to_json()))
+        ];
+        let (old, new) = self.sides();
+        if let Some(old) = old {
+            pairs.push((String::from("old"), Box::new(old.clone())));
+        }
+        if let Some(new) = new {
+            pairs.push((String::from("new"), Box::new(new.clone())));
+        }
+        return JsonExpression::Object(pairs);
+    }
+}
+
+// Lists what changed to get from `old` to `new`, in document order. Objects
+// are matched by key, ignoring member order, with the last of a repeated key
+// winning as it does for lookups. Arrays are matched by index, so an element
+// inserted near the front shows up as every later element being replaced.
+pub fn diff(old: &JsonExpression, new: &JsonExpression) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_node(old, new, &mut JsonPath::root(), &mut changes);
+    return changes;
+}
+
+fn last<'a>(pairs: &'a [(String, Box<JsonExpression>)], key: &str) -> Option<&'a JsonExpression> {
+    return pairs.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_ref());
+}
+
+// Each key once, where it last appears.
+fn distinct(pairs: &[(String, Box<JsonExpression>)]) -> impl Iterator<Item = &str> {
+    return pairs.iter().enumerate().filter(|(i, (key, _))| !pairs[i + 1..].iter().any(|(k, _)| k == key)).map(|(_, (key, _))| key.as_str());
+}
+
+fn diff_node(old: &JsonExpression, new: &JsonExpression, path: &mut JsonPath, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (JsonExpression::Object(before), JsonExpression::Object(after)) => {
+            for key in distinct(before) {
+                path.push(PathSegment::Key(String::from(key)));
+                let value = last(before, key).expect("a distinct key is present");
+                match last(after, key) {
+                    Some(other) => diff_node(value, other, path, changes),
+                    None => changes.push(Change::Removed { path: path.clone(), value: value.clone() })
+                }
+                path.pop();
+            }
+            for key in distinct(after).filter(|key| last(before, key).is_none()) {
+                let value = last(after, key).expect("a distinct key is present");
+                changes.push(Change::Added { path: path.child(PathSegment::Key(String::from(key))), value: value.clone() });
+            }
+        },
+        (JsonExpression::Array(before), JsonExpression::Array(after)) => {
+            for i in 0..before.len().max(after.len()) {
+                path.push(PathSegment::Index(i));
+                match (before.get(i), after.get(i)) {
+                    (Some(a), Some(b)) => diff_node(a, b, path, changes),
+                    (Some(a), None) => changes.push(Change::Removed { path: path.clone(), value: a.as_ref().clone() }),
+                    (None, Some(b)) => changes.push(Change::Added { path: path.clone(), value: b.as_ref().clone() }),
+                    (None, None) => {}
+                }
+                path.pop();
+            }
+        },
+        _ if old != new => changes.push(Change::Replaced { path: path.clone(), old: old.clone(), new: new.clone() }),
+        _ => {}
+    }
+}
+
+// How render lays out a list of changes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RenderOptions {
+    // one row per change with the path, the old value and the new value in
+    // aligned columns, rather than `-` and `+` lines
+    pub side_by_side: bool,
+    // ANSI colors, red for what was removed and green for what was added
+    pub color: bool
+}
+
+fn paint(text: &str, color: &str, options: &RenderOptions) -> String {
+    if options.color && !text.is_empty() {
+        return format!("{}{}{}", color, text, RESET);
+    }
+    return String::from(text);
+}
+
+// The root has no segments to show.
+fn label(path: &JsonPath) -> String {
+    if path.is_root() {
+        return String::from("(root)");
+    }
+    return path.to_string();
+}
+
+// Prints changes for people to read, values on one line each. Unified, the
+// default, gives `- path: old` and `+ path: new` lines; side by side gives
+// `path  old | new` rows with the columns lined up. Every line ends in a
+// newline, and no changes renders as nothing.
+pub fn render(changes: &[Change], options: &RenderOptions) -> String {
+    let mut out = String::new();
+    if !options.side_by_side {
+        for change in changes {
+            let (old, new) = change.sides();
+            if let Some(old) = old {
+                out.push_str(&paint(&format!("- {}: {}", label(change.path()), old), REMOVED, options));
+                out.push('\n');
+            }
+            if let Some(new) = new {
+                out.push_str(&paint(&format!("+ {}: {}", label(change.path()), new), ADDED, options));
+                out.push('\n');
+            }
+        }
+        return out;
+    }
+
+    let rows: Vec<(String, String, String)> = changes.iter().map(|change| {
+        let (old, new) = change.sides();
+        return (label(change.path()), old.map(|v| v.to_string()).unwrap_or_default(), new.map(|v| v.to_string()).unwrap_or_default());
+    }).collect();
+    let path_width = rows.iter().map(|(path, _, _)| path.chars().count()).max().unwrap_or(0);
+    let old_width = rows.iter().map(|(_, old, _)| old.chars().count()).max().unwrap_or(0);
+
+    for (path, old, new) in &rows {
+        let old_padding = " ".repeat(old_width - old.chars().count());
+        let line = format!("{:<path_width$}  {}{} | {}", path, paint(old, REMOVED, options), old_padding, paint(new, ADDED, options));
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    return out;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{diff, render, Change, RenderOptions};
+    use crate::traits::ToJson;
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn finds_changes() {
+        let old = json(String::from("{\"a\": 1, \"b\": {\"c\": [1, 2, 3]}, \"gone\": true, \"a\": 2}")).unwrap();
+        let new = json(String::from("{\"b\": {\"c\": [1, 5]}, \"a\": 2, \"new\": null}")).unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(vec!["b.c[1]", "b.c[2]", "gone", "new"], changes.iter().map(|c| c.path().to_string()).collect::<Vec<_>>());
+        assert_eq!(Change::Removed { path: "b.c[2]".parse().unwrap(), value: JsonExpression::Number(3.0) }, changes[1]);
+        assert!(diff(&old, &old).is_empty());
+        assert_eq!(1, diff(&JsonExpression::Null, &old).len());
+    }
+
+    #[test]
+    fn renders() {
+        let old = json(String::from("{\"name\": \"ada\", \"tags\": [1], \"old\": true}")).unwrap();
+        let new = json(String::from("{\"name\": \"bob\", \"tags\": [1, {\"x\": 2}]}")).unwrap();
+        let changes = diff(&old, &new);
+
+        let unified = render(&changes, &RenderOptions::default());
+        assert_eq!("- name: \"ada\"\n+ name: \"bob\"\n+ tags[1]: {\"x\":2}\n- old: true\n", unified);
+
+        let side_by_side = render(&changes, &RenderOptions { side_by_side: true, color: false });
+        assert_eq!("name     \"ada\" | \"bob\"\ntags[1]        | {\"x\":2}\nold      true  |\n", side_by_side);
+
+        let colored = render(&changes[..1], &RenderOptions { side_by_side: false, color: true });
+        assert_eq!("\x1b[31m- name: \"ada\"\x1b[0m\n\x1b[32m+ name: \"bob\"\x1b[0m\n", colored);
+        assert_eq!("", render(&[], &RenderOptions::default()));
+    }
+
+    #[test]
+    fn to_json() {
+        let old = json(String::from("{\"a\": [1], \"b c\": true}")).unwrap();
+        let new = json(String::from("{\"a\": [2, 3]}")).unwrap();
+        assert_eq!(
+            json(String::from("[
+                {\"change\": \"replaced\", \"path\": \"/a/0\", \"old\": 1, \"new\": 2},
+                {\"change\": \"added\", \"path\": \"/a/1\", \"new\": 3},
+                {\"change\": \"removed\", \"path\": \"/b c\", \"old\": true}
+            ]")).unwrap(),
+            diff(&old, &new).to_json()
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use sha2::{Digest, Sha256};
+
+use crate::JsonExpression;
+
+impl JsonExpression {
+    // SHA-256 of the canonical serialization, the same for any two documents
+    // holding the same content regardless of member order or formatting.
+    pub fn digest(&self) -> [u8; 32] {
+        return Sha256::digest(self.to_string_canonical().as_bytes()).into();
+    }
+
+    // HMAC-SHA256 of the canonical serialization under a secret key. Unlike
+    // a plain digest it cannot be reversed by hashing guesses, so it is safe
+    // for short or predictable values as long as the key stays secret.
+    pub fn keyed_digest(&self, key: &[u8]) -> [u8; 32] {
+        return hmac_sha256(key, self.to_string_canonical().as_bytes());
+    }
+}
+
+// RFC 2104 with SHA-256's 64 byte block
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > block.len() {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner = Sha256::new().chain_update(block.map(|b| b ^ 0x36)).chain_update(data).finalize();
+    return Sha256::new().chain_update(block.map(|b| b ^ 0x5c)).chain_update(inner).finalize().into();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::digest::hmac_sha256;
+    use crate::json;
+
+    #[test]
+    fn ignores_order_and_formatting() {
+        let a = json(String::from("{\"id\": 1, \"tags\": [\"x\", \"y\"]}")).unwrap();
+        let b = json(String::from("{\n  \"tags\" : [ \"x\",\"y\" ],\n  \"id\" : 1.0\n}")).unwrap();
+        let c = json(String::from("{\"id\": 1, \"tags\": [\"y\", \"x\"]}")).unwrap();
+        assert_eq!(a.digest(), b.digest());
+        assert_ne!(a.digest(), c.digest());
+    }
+
+    #[test]
+    fn known_value() {
+        // sha256 of "{}"
+        let hex: String = json(String::from("{ }")).unwrap().digest().iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!("44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a", hex);
+    }
+
+    #[test]
+    fn hmac_vectors() {
+        let hex = |bytes: [u8; 32]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        // RFC 4231 test cases 2 and 6, the second with a key longer than a block
+        assert_eq!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843", hex(hmac_sha256(b"Jefe", b"what do ya want for nothing?")));
+        assert_eq!(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            hex(hmac_sha256(&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First"))
+        );
+
+        let value = json(String::from("\"hunter2\"")).unwrap();
+        assert_ne!(value.keyed_digest(b"a"), value.keyed_digest(b"b"));
+        assert_ne!(value.digest(), value.keyed_digest(b""));
+    }
+}
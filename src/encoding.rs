@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+
+// JSON exchanged between systems must be UTF-8 (RFC 8259 section 8.1), but
+// files saved on Windows often start with a byte order mark or are UTF-16.
+// The encoding is taken from the BOM when there is one, otherwise from the
+// position of NUL bytes in the first two bytes (a JSON document starts with an
+// ASCII character, so UTF-16 puts a zero byte next to it).
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be
+}
+
+// Returns the detected encoding and the length of the BOM to skip.
+pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0xef, 0xbb, 0xbf, ..] => return (Encoding::Utf8, 3),
+        [0xff, 0xfe, ..] => return (Encoding::Utf16Le, 2),
+        [0xfe, 0xff, ..] => return (Encoding::Utf16Be, 2),
+        [0, b, ..] if *b != 0 => return (Encoding::Utf16Be, 0),
+        [b, 0, ..] if *b != 0 => return (Encoding::Utf16Le, 0),
+        _ => return (Encoding::Utf8, 0)
+    }
+}
+
+// Transcodes to UTF-8 text with any BOM removed, borrowing when the input is
+// already UTF-8.
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, String> {
+    let (encoding, bom) = detect(bytes);
+    let body = &bytes[bom..];
+
+    match encoding {
+        Encoding::Utf8 => {
+            return std::str::from_utf8(body)
+                .map(Cow::Borrowed)
+                .map_err(|e| format!("Invalid UTF-8 at byte {}...", bom + e.valid_up_to()));
+        },
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            if !body.len().is_multiple_of(2) {
+                return Err(String::from("UTF-16 input has an odd number of bytes..."));
+            }
+
+            let units = body.chunks(2).map(|pair| {
+                if encoding == Encoding::Utf16Le {
+                    return u16::from_le_bytes([pair[0], pair[1]]);
+                }
+                return u16::from_be_bytes([pair[0], pair[1]]);
+            });
+
+            return char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .map(Cow::Owned)
+                .map_err(|e| format!("Unpaired UTF-16 surrogate 0x{:04x}...", e.unpaired_surrogate()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encoding::{decode, detect, Encoding};
+    use crate::json;
+
+    fn utf16(s: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let units = if bom { vec![0xfeff] } else { vec![] };
+        for unit in units.into_iter().chain(s.encode_utf16()) {
+            let bytes = if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() };
+            out.extend_from_slice(&bytes);
+        }
+        return out;
+    }
+
+    #[test]
+    fn detects_encodings() {
+        assert_eq!((Encoding::Utf8, 3), detect(b"\xef\xbb\xbf{}"));
+        assert_eq!((Encoding::Utf16Le, 2), detect(&utf16("{}", true, true)));
+        assert_eq!((Encoding::Utf16Be, 0), detect(&utf16("{}", false, false)));
+        assert_eq!((Encoding::Utf8, 0), detect(b"{}"));
+    }
+
+    #[test]
+    fn transcodes_before_lexing() {
+        let source = "{\"name\": \"caf\u{e9} \u{1f600}\"}";
+        let expected = json(String::from(source)).unwrap();
+
+        for input in [utf16(source, true, true), utf16(source, false, true), utf16(source, true, false)] {
+            assert_eq!(expected, json(decode(&input).unwrap().into_owned()).unwrap());
+        }
+
+        let with_bom = [b"\xef\xbb\xbf".as_slice(), source.as_bytes()].concat();
+        assert_eq!(source, decode(&with_bom).unwrap());
+        assert!(decode(b"\xff\xfe{").is_err());
+    }
+
+    #[test]
+    fn lexer_skips_bom() {
+        assert_eq!(json(String::from("[]")).unwrap(), json(String::from("\u{feff}[]")).unwrap());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::JsonExpression;
+
+pub enum Entry<'a> {
+    Occupied(OccupiedEntry<'a>),
+    Vacant(VacantEntry<'a>)
+}
+
+pub struct OccupiedEntry<'a> {
+    value: &'a mut JsonExpression
+}
+
+pub struct VacantEntry<'a> {
+    pairs: &'a mut Vec<(String, Box<JsonExpression>)>,
+    key: String
+}
+
+impl<'a> Entry<'a> {
+    pub fn or_insert(self, default: JsonExpression) -> &'a mut JsonExpression {
+        match self {
+            Entry::Occupied(entry) => return entry.value,
+            Entry::Vacant(entry) => return entry.insert(default)
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> JsonExpression>(self, default: F) -> &'a mut JsonExpression {
+        match self {
+            Entry::Occupied(entry) => return entry.value,
+            Entry::Vacant(entry) => return entry.insert(default())
+        }
+    }
+
+    pub fn and_modify<F: FnOnce(&mut JsonExpression)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(entry) => {
+                f(entry.value);
+                return Entry::Occupied(entry);
+            },
+            Entry::Vacant(entry) => return Entry::Vacant(entry)
+        }
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn get(&self) -> &JsonExpression {
+        return self.value;
+    }
+
+    pub fn into_mut(self) -> &'a mut JsonExpression {
+        return self.value;
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &str {
+        return &self.key;
+    }
+
+    pub fn insert(self, value: JsonExpression) -> &'a mut JsonExpression {
+        self.pairs.push((self.key, Box::new(value)));
+        return self.pairs.last_mut().unwrap().1.as_mut();
+    }
+}
+
+impl JsonExpression {
+    // Panics if this isn't an object. When a key is duplicated the last
+    // occurrence is the one that gets returned, matching how lookups treat it.
+    pub fn entry(&mut self, key: &str) -> Entry<'_> {
+        let pairs = match self {
+            JsonExpression::Object(pairs) => pairs,
+            _ => panic!("Cannot take an entry of {}...", self.type_name())
+        };
+
+        match pairs.iter().rposition(|(k, _)| k == key) {
+            Some(index) => return Entry::Occupied(OccupiedEntry { value: pairs[index].1.as_mut() }),
+            None => return Entry::Vacant(VacantEntry { pairs, key: String::from(key) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn upserts_nested_keys() {
+        let mut expr = JsonExpression::Object(vec![]);
+        expr.entry("server")
+            .or_insert(JsonExpression::Object(vec![]))
+            .entry("port")
+            .or_insert(JsonExpression::Number(8080.0));
+        expr.entry("server")
+            .or_insert(JsonExpression::Null)
+            .entry("host")
+            .or_insert_with(|| JsonExpression::from("localhost"));
+
+        assert_eq!(
+            json(String::from("{\"server\": {\"port\": 8080, \"host\": \"localhost\"}}")).unwrap(),
+            expr
+        );
+    }
+
+    #[test]
+    fn and_modify_existing() {
+        let mut expr = json(String::from("{\"count\": 1}")).unwrap();
+        expr.entry("count")
+            .and_modify(|v| *v = JsonExpression::Number(2.0))
+            .or_insert(JsonExpression::Number(0.0));
+        assert_eq!(json(String::from("{\"count\": 2}")).unwrap(), expr);
+    }
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+
+use crate::lexer::{Span, Spanned, Token};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonError {
+    UnexpectedEndOfInput,
+    // the message says what the parser was expecting and what it found, e.g.
+    // "Expected colon, found number...", and the span is the token it found
+    UnexpectedToken(String, Span),
+    // a bare word that is neither a number nor true/false/null, with the
+    // span of the word
+    InvalidLiteral(String, Span),
+    // the span is the backslash and the character after it
+    InvalidEscape(char, Span),
+    // the span starts at the first invalid sequence
+    InvalidUtf8(Span),
+    InvalidEncoding(String),
+    // the input could not be read, e.g. a missing file
+    Io(String),
+    // the input parsed but a value did not convert to the type asked for
+    Conversion(String)
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnexpectedEndOfInput => return write!(f, "Unexpected end of input..."),
+            JsonError::UnexpectedToken(message, span) => return write!(f, "Line {}, column {}: {}", span.line, span.column, message),
+            JsonError::InvalidLiteral(literal, _) => return write!(f, "Invalid literal '{}'...", literal),
+            JsonError::InvalidEscape(c, _) => return write!(f, "Invalid escape '\\{}'...", c),
+            JsonError::InvalidUtf8(span) => return write!(f, "Invalid UTF-8 at byte {}...", span.start),
+            JsonError::InvalidEncoding(message) => return write!(f, "{}", message),
+            JsonError::Io(message) => return write!(f, "{}", message),
+            JsonError::Conversion(message) => return write!(f, "{}", message),
+        }
+    }
+}
+
+// Every variant carries its cause as text, so there is never an underlying
+// error to hand back from source().
+impl std::error::Error for JsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return None;
+    }
+}
+
+impl JsonError {
+    // A code for the kind of error that stays the same across versions, for
+    // tools to match on rather than the message. New kinds get new numbers,
+    // existing ones are never reused.
+    pub fn code(&self) -> &'static str {
+        match self {
+            JsonError::UnexpectedEndOfInput => return "E001_UNEXPECTED_EOF",
+            JsonError::UnexpectedToken(..) => return "E002_UNEXPECTED_TOKEN",
+            JsonError::InvalidLiteral(..) => return "E003_INVALID_LITERAL",
+            JsonError::InvalidEscape(..) => return "E004_INVALID_ESCAPE",
+            JsonError::InvalidUtf8(_) => return "E005_INVALID_UTF8",
+            JsonError::InvalidEncoding(_) => return "E006_INVALID_ENCODING",
+            JsonError::Io(_) => return "E007_IO",
+            JsonError::Conversion(_) => return "E008_CONVERSION"
+        }
+    }
+
+    // Errors for a token that is not one of those allowed, which are listed
+    // in `expected` as e.g. "comma or right bracket".
+    pub(crate) fn expected(expected: &str, found: &Spanned<Token>) -> JsonError {
+        return JsonError::UnexpectedToken(format!("Expected {}, found {}...", expected, found.value.describe()), found.span);
+    }
+
+    // Where in the input the error is, for those that come from a token.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            JsonError::UnexpectedToken(_, span) | JsonError::InvalidLiteral(_, span) | JsonError::InvalidEscape(_, span) | JsonError::InvalidUtf8(span) => return Some(*span),
+            _ => return None
+        }
+    }
+
+    // The same error moved to another place, for errors found in a slice of
+    // a larger input.
+    pub(crate) fn with_span(self, span: Span) -> JsonError {
+        match self {
+            JsonError::UnexpectedToken(message, _) => return JsonError::UnexpectedToken(message, span),
+            JsonError::InvalidLiteral(literal, _) => return JsonError::InvalidLiteral(literal, span),
+            JsonError::InvalidEscape(c, _) => return JsonError::InvalidEscape(c, span),
+            JsonError::InvalidUtf8(_) => return JsonError::InvalidUtf8(span),
+            other => return other
+        }
+    }
+
+    // Line and column the error points at in the source it came from, None
+    // for errors without a place.
+    fn location(&self, source: &str) -> Option<(usize, usize, usize)> {
+        match self {
+            JsonError::UnexpectedToken(..) | JsonError::InvalidLiteral(..) | JsonError::InvalidEscape(..) | JsonError::InvalidUtf8(_) => {
+                let span = self.span()?;
+                let width = source.get(span.start..span.end).map(|s| s.chars().take_while(|c| *c != '\n').count()).unwrap_or(1);
+                return Some((span.line, span.column, width.max(1)));
+            },
+            // just past the last thing in the input
+            JsonError::UnexpectedEndOfInput => {
+                let content = source.trim_end();
+                let line = content.matches('\n').count() + 1;
+                let column = content.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+                return Some((line, column, 1));
+            },
+            _ => return None
+        }
+    }
+
+    // The line of source the error points at with a caret under the
+    // offending token, or None when the error has no place in it. Line and
+    // column are counted from 1, a span left at 0 has no place either:
+    //
+    //      --> line 2, column 8
+    //       |
+    //     2 |   {"a" 2}
+    //       |        ^
+    pub fn snippet(&self, source: &str) -> Option<String> {
+        let (line, column, width) = self.location(source)?;
+        let text = source.lines().nth(line.checked_sub(1)?).unwrap_or("");
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+        // tabs are kept so the caret lines up however they are displayed
+        let pad: String = text.chars().take(column.checked_sub(1)?).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        return Some(format!(
+            "{} --> line {}, column {}\n{} |\n{} | {}\n{} | {}{}",
+            gutter, line, column, gutter, number, text, gutter, pad, "^".repeat(width)
+        ));
+    }
+
+    // The message followed by its snippet, for showing an error to someone
+    // looking at the source.
+    pub fn render(&self, source: &str) -> String {
+        let message = match self {
+            JsonError::UnexpectedToken(message, _) => message.clone(),
+            other => other.to_string()
+        };
+        match self.snippet(source) {
+            Some(snippet) => return format!("{}\n{}", message, snippet),
+            None => return message
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonError};
+
+    #[test]
+    fn works_as_std_error() {
+        fn parse(source: &str) -> Result<crate::JsonExpression, Box<dyn std::error::Error>> {
+            return Ok(json(String::from(source))?);
+        }
+
+        let error = parse("[1,").unwrap_err();
+        assert_eq!("Unexpected end of input...", error.to_string());
+        assert!(error.source().is_none());
+        assert_eq!(Some("E001_UNEXPECTED_EOF"), error.downcast_ref::<JsonError>().map(JsonError::code));
+        assert_eq!("E002_UNEXPECTED_TOKEN", json(String::from("[1 2]")).unwrap_err().code());
+    }
+
+    #[test]
+    fn renders_source() {
+        let source = "{\n\t\"a\" 22\n}";
+        let error = json(String::from(source)).unwrap_err();
+        assert_eq!("Expected colon, found number...\n  --> line 2, column 6\n  |\n2 | \t\"a\" 22\n  | \t    ^^", error.render(source));
+
+        let error = json(String::from("[1,\n 2 \n")).unwrap_err();
+        assert_eq!("Unexpected end of input...\n  --> line 2, column 3\n  |\n2 |  2 \n  |   ^", error.render("[1,\n 2 \n"));
+        assert_eq!(None, JsonError::Io(String::from("gone")).snippet("\"\\q\""));
+    }
+
+    #[test]
+    fn renders_recovered_lexer_errors() {
+        let source = "{tru: 1}";
+        let (_, errors) = crate::parse_recovering(source.as_bytes());
+        let snippet = "\n  --> line 1, column 2\n  |\n1 | {tru: 1}\n  |  ^^^";
+        let rendered: Vec<String> = errors.iter().map(|e| e.render(source)).collect();
+        assert_eq!(vec![format!("Invalid literal 'tru'...{}", snippet), format!("Expected string key, found null...{}", snippet)], rendered);
+        assert_eq!(None, JsonError::UnexpectedToken(String::from("x"), crate::Span::default()).snippet(source));
+    }
+}
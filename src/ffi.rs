@@ -0,0 +1,135 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use crate::{from_slice, JsonExpression, JsonPath};
+
+// Opaque to C callers, see include/jsonrs.h. Every JsonrsValue and string
+// handed out by these functions is owned by the caller and must be released
+// with jsonrs_free / jsonrs_string_free respectively.
+pub struct JsonrsValue {
+    expression: JsonExpression
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    // serialized JSON never contains a raw NUL, errors are our own messages
+    return CString::new(s).map(CString::into_raw).unwrap_or(ptr::null_mut());
+}
+
+/// # Safety
+///
+/// `source` must be a valid NUL terminated string. `error_out` may be null,
+/// otherwise it must be valid for a pointer write.
+#[no_mangle]
+pub unsafe extern "C" fn jsonrs_parse(source: *const c_char, error_out: *mut *mut c_char) -> *mut JsonrsValue {
+    if source.is_null() {
+        return ptr::null_mut();
+    }
+
+    match from_slice(CStr::from_ptr(source).to_bytes()) {
+        Ok(expression) => return Box::into_raw(Box::new(JsonrsValue { expression })),
+        Err(e) => {
+            if !error_out.is_null() {
+                *error_out = into_c_string(e.to_string());
+            }
+            return ptr::null_mut();
+        }
+    }
+}
+
+/// # Safety
+///
+/// `value` must come from this library and not have been freed, `path` must
+/// be a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn jsonrs_get(value: *const JsonrsValue, path: *const c_char) -> *mut JsonrsValue {
+    if value.is_null() || path.is_null() {
+        return ptr::null_mut();
+    }
+
+    let path: JsonPath = match CStr::from_ptr(path).to_str().ok().and_then(|p| p.parse().ok()) {
+        Some(p) => p,
+        None => return ptr::null_mut()
+    };
+
+    match (*value).expression.get_at(&path) {
+        Some(e) => return Box::into_raw(Box::new(JsonrsValue { expression: e.clone() })),
+        None => return ptr::null_mut()
+    }
+}
+
+/// # Safety
+///
+/// `value` must come from this library and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn jsonrs_to_string(value: *const JsonrsValue, pretty: c_int) -> *mut c_char {
+    if value.is_null() {
+        return ptr::null_mut();
+    }
+
+    let expression = &(*value).expression;
+    if pretty != 0 {
+        return into_c_string(expression.to_string_pretty());
+    }
+
+    return into_c_string(expression.to_string());
+}
+
+/// # Safety
+///
+/// `value` must be null or come from this library and not already be freed.
+#[no_mangle]
+pub unsafe extern "C" fn jsonrs_free(value: *mut JsonrsValue) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+/// # Safety
+///
+/// `s` must be null or a string returned by this library and not already be
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn jsonrs_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn parse_get_to_string() {
+        let source = CString::new("{\"a\": [1, \"two\"]}").unwrap();
+        let path = CString::new("a[1]").unwrap();
+        unsafe {
+            let value = jsonrs_parse(source.as_ptr(), ptr::null_mut());
+            assert!(!value.is_null());
+
+            let element = jsonrs_get(value, path.as_ptr());
+            let s = jsonrs_to_string(element, 0);
+            assert_eq!("\"two\"", CStr::from_ptr(s).to_str().unwrap());
+
+            jsonrs_string_free(s);
+            jsonrs_free(element);
+            jsonrs_free(value);
+        }
+    }
+
+    #[test]
+    fn parse_error() {
+        let source = CString::new("{\"a\" 1}").unwrap();
+        let mut error: *mut std::os::raw::c_char = ptr::null_mut();
+        unsafe {
+            let value = jsonrs_parse(source.as_ptr(), &mut error);
+            assert!(value.is_null());
+            assert_eq!("Line 1, column 6: Expected colon, found number...", CStr::from_ptr(error).to_str().unwrap());
+            jsonrs_string_free(error);
+        }
+    }
+}
@@ -0,0 +1,741 @@
+use std::cmp::Ordering;
+
+use crate::{from_slice, JsonExpression};
+
+// A small jq style filter language. A filter takes one input value and
+// produces any number of outputs:
+//
+//   .users[] | select(.age > 30) | .name
+//
+// Supported are `.`, `..`, `.key`, `."key"`, `.[expr]`, `.[]`, `?`, pipes,
+// commas, literals, array and object construction, arithmetic, comparisons,
+// `and`/`or`/`//` and a handful of builtins (see `call`).
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Dot,
+    DotDot,
+    LeftBracket,
+    RightBracket,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    Pipe,
+    Comma,
+    Colon,
+    Semicolon,
+    Question,
+    Op(&'static str),
+    Number(f64),
+    Str(String),
+    Ident(String)
+}
+
+const OPERATORS: [&str; 12] = ["==", "!=", "<=", ">=", "//", "<", ">", "+", "-", "*", "/", "%"];
+
+fn tokenize(source: &str) -> Result<Vec<Tok>, String> {
+    let bytes = source.as_bytes();
+    let mut tokens: Vec<Tok> = Vec::new();
+    let mut index: usize = 0;
+
+    while index < bytes.len() {
+        let rest = &source[index..];
+        let c = bytes[index];
+        let (token, len) = match c {
+            b' ' | b'\n' | b'\t' | b'\r' => {
+                index += 1;
+                continue;
+            },
+            b'.' if rest.starts_with("..") => (Tok::DotDot, 2),
+            b'.' => (Tok::Dot, 1),
+            b'[' => (Tok::LeftBracket, 1),
+            b']' => (Tok::RightBracket, 1),
+            b'(' => (Tok::LeftParen, 1),
+            b')' => (Tok::RightParen, 1),
+            b'{' => (Tok::LeftBrace, 1),
+            b'}' => (Tok::RightBrace, 1),
+            b'|' => (Tok::Pipe, 1),
+            b',' => (Tok::Comma, 1),
+            b':' => (Tok::Colon, 1),
+            b';' => (Tok::Semicolon, 1),
+            b'?' => (Tok::Question, 1),
+            b'"' => {
+                let mut end = index + 1;
+                while end < bytes.len() && bytes[end] != b'"' {
+                    end += if bytes[end] == b'\\' { 2 } else { 1 };
+                }
+                if end >= bytes.len() {
+                    return Err(String::from("Unterminated string in filter..."));
+                }
+
+                // string literals use JSON escapes, so the JSON parser reads them
+                match from_slice(&bytes[index..end + 1]) {
+                    Ok(JsonExpression::String(s)) => (Tok::Str(s), end + 1 - index),
+                    _ => return Err(format!("Invalid string {} in filter...", &source[index..end + 1]))
+                }
+            },
+            b'0'..=b'9' => {
+                let mut end = index;
+                while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.'
+                    || bytes[end] == b'e' || bytes[end] == b'E'
+                    || ((bytes[end] == b'+' || bytes[end] == b'-') && matches!(bytes[end - 1], b'e' | b'E'))) {
+                    end += 1;
+                }
+                match source[index..end].parse::<f64>() {
+                    Ok(n) => (Tok::Number(n), end - index),
+                    Err(_) => return Err(format!("Invalid number {} in filter...", &source[index..end]))
+                }
+            },
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+                (Tok::Ident(String::from(&rest[..len])), len)
+            },
+            _ => match OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                Some(op) => (Tok::Op(op), op.len()),
+                None => return Err(format!("Unexpected character {:?} in filter...", rest.chars().next().unwrap()))
+            }
+        };
+
+        tokens.push(token);
+        index += len;
+    }
+
+    return Ok(tokens);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Identity,
+    Recurse,
+    Literal(JsonExpression),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Alternative(Box<Expr>, Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(String, Expr)>),
+    Call(String, Vec<Expr>)
+}
+
+struct FilterParser {
+    tokens: Vec<Tok>,
+    current: usize
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&Tok> {
+        return self.tokens.get(self.current);
+    }
+
+    fn eat(&mut self, token: &Tok) -> bool {
+        if self.peek() == Some(token) {
+            self.current += 1;
+            return true;
+        }
+        return false;
+    }
+
+    fn expect(&mut self, token: &Tok, what: &str) -> Result<(), String> {
+        if !self.eat(token) {
+            return Err(format!("Expected {} in filter...", what));
+        }
+        return Ok(());
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if let Some(Tok::Ident(name)) = self.peek() {
+            if name == keyword {
+                self.current += 1;
+                return true;
+            }
+        }
+        return false;
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        if let Some(Tok::Op(op)) = self.peek() {
+            if let Some(found) = ops.iter().find(|o| *o == op) {
+                self.current += 1;
+                return Some(found);
+            }
+        }
+        return None;
+    }
+
+    fn pipe(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.comma()?;
+        while self.eat(&Tok::Pipe) {
+            lhs = Expr::Pipe(Box::new(lhs), Box::new(self.comma()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn comma(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.alternative()?;
+        while self.eat(&Tok::Comma) {
+            lhs = Expr::Comma(Box::new(lhs), Box::new(self.alternative()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn alternative(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.or()?;
+        while self.eat_op(&["//"]).is_some() {
+            lhs = Expr::Alternative(Box::new(lhs), Box::new(self.or()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.and()?;
+        while self.eat_keyword("or") {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.comparison()?;
+        while self.eat_keyword("and") {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.comparison()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let lhs = self.additive()?;
+        if let Some(op) = self.eat_op(&["==", "!=", "<=", ">=", "<", ">"]) {
+            return Ok(Expr::Binary(op, Box::new(lhs), Box::new(self.additive()?)));
+        }
+        return Ok(lhs);
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.multiplicative()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.multiplicative()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.postfix()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.postfix()?));
+        }
+        return Ok(lhs);
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(Tok::Dot) => {
+                    self.current += 1;
+                    match self.peek().cloned() {
+                        Some(Tok::Ident(name)) | Some(Tok::Str(name)) => {
+                            self.current += 1;
+                            expr = Expr::Field(Box::new(expr), name);
+                        },
+                        Some(Tok::LeftBracket) => {},
+                        _ => return Err(String::from("Expected key after . in filter..."))
+                    }
+                },
+                Some(Tok::LeftBracket) => {
+                    self.current += 1;
+                    if self.eat(&Tok::RightBracket) {
+                        expr = Expr::Iterate(Box::new(expr));
+                    } else {
+                        let index = self.pipe()?;
+                        self.expect(&Tok::RightBracket, "]")?;
+                        expr = Expr::Index(Box::new(expr), Box::new(index));
+                    }
+                },
+                Some(Tok::Question) => {
+                    self.current += 1;
+                    expr = Expr::Optional(Box::new(expr));
+                },
+                _ => return Ok(expr)
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = match self.peek().cloned() {
+            Some(token) => token,
+            None => return Err(String::from("Unexpected end of filter..."))
+        };
+        self.current += 1;
+
+        match token {
+            Tok::DotDot => return Ok(Expr::Recurse),
+            Tok::Dot => {
+                match self.peek().cloned() {
+                    Some(Tok::Ident(name)) | Some(Tok::Str(name)) => {
+                        self.current += 1;
+                        return Ok(Expr::Field(Box::new(Expr::Identity), name));
+                    },
+                    _ => return Ok(Expr::Identity)
+                }
+            },
+            Tok::Number(n) => return Ok(Expr::Literal(JsonExpression::Number(n))),
+            Tok::Str(s) => return Ok(Expr::Literal(JsonExpression::String(s))),
+            Tok::LeftParen => {
+                let inner = self.pipe()?;
+                self.expect(&Tok::RightParen, ")")?;
+                return Ok(inner);
+            },
+            Tok::LeftBracket => {
+                if self.eat(&Tok::RightBracket) {
+                    return Ok(Expr::Array(None));
+                }
+                let inner = self.pipe()?;
+                self.expect(&Tok::RightBracket, "]")?;
+                return Ok(Expr::Array(Some(Box::new(inner))));
+            },
+            Tok::LeftBrace => {
+                let mut entries: Vec<(String, Expr)> = Vec::new();
+                while !self.eat(&Tok::RightBrace) {
+                    if !entries.is_empty() {
+                        self.expect(&Tok::Comma, ", or }")?;
+                    }
+                    let key = match self.peek().cloned() {
+                        Some(Tok::Ident(name)) | Some(Tok::Str(name)) => name,
+                        _ => return Err(String::from("Expected object key in filter..."))
+                    };
+                    self.current += 1;
+
+                    // {name} is short for {name: .name}
+                    let value = if self.eat(&Tok::Colon) {
+                        self.alternative()?
+                    } else {
+                        Expr::Field(Box::new(Expr::Identity), key.clone())
+                    };
+                    entries.push((key, value));
+                }
+                return Ok(Expr::Object(entries));
+            },
+            Tok::Op("-") => {
+                let operand = self.postfix()?;
+                return Ok(Expr::Binary("-", Box::new(Expr::Literal(JsonExpression::Number(0.0))), Box::new(operand)));
+            },
+            Tok::Ident(name) => {
+                match name.as_str() {
+                    "true" => return Ok(Expr::Literal(JsonExpression::Bool(true))),
+                    "false" => return Ok(Expr::Literal(JsonExpression::Bool(false))),
+                    "null" => return Ok(Expr::Literal(JsonExpression::Null)),
+                    _ => {}
+                }
+
+                let mut args: Vec<Expr> = Vec::new();
+                if self.eat(&Tok::LeftParen) {
+                    args.push(self.pipe()?);
+                    while self.eat(&Tok::Semicolon) {
+                        args.push(self.pipe()?);
+                    }
+                    self.expect(&Tok::RightParen, ")")?;
+                }
+                return Ok(Expr::Call(name, args));
+            },
+            other => return Err(format!("Unexpected {:?} in filter...", other))
+        }
+    }
+}
+
+// jq ordering: null < false < true < numbers < strings < arrays < objects,
+// objects compare by their sorted keys and then values.
+fn compare(a: &JsonExpression, b: &JsonExpression) -> Ordering {
+    fn rank(value: &JsonExpression) -> u8 {
+        match value {
+            JsonExpression::Null => return 0,
+            JsonExpression::Bool(false) => return 1,
+            JsonExpression::Bool(true) => return 2,
+            JsonExpression::Number(_) => return 3,
+            JsonExpression::String(_) => return 4,
+            JsonExpression::Array(_) => return 5,
+            JsonExpression::Object(_) => return 6
+        }
+    }
+
+    match (a, b) {
+        (JsonExpression::Number(x), JsonExpression::Number(y)) => return x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (JsonExpression::String(x), JsonExpression::String(y)) => return x.cmp(y),
+        (JsonExpression::Array(x), JsonExpression::Array(y)) => {
+            for (l, r) in x.iter().zip(y.iter()) {
+                let ordering = compare(l, r);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            return x.len().cmp(&y.len());
+        },
+        (JsonExpression::Object(_), JsonExpression::Object(_)) => {
+            let keys_a = sorted_keys(a);
+            let keys_b = sorted_keys(b);
+            if keys_a != keys_b {
+                return keys_a.cmp(&keys_b);
+            }
+            for key in keys_a {
+                let ordering = compare(field(a, &key).unwrap(), field(b, &key).unwrap());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            return Ordering::Equal;
+        },
+        _ => return rank(a).cmp(&rank(b))
+    }
+}
+
+// duplicate keys resolve to the last occurrence, as everywhere else
+fn field<'a>(value: &'a JsonExpression, key: &str) -> Option<&'a JsonExpression> {
+    return value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v);
+}
+
+fn sorted_keys(value: &JsonExpression) -> Vec<String> {
+    let mut keys: Vec<String> = value.keys().map(String::from).collect();
+    keys.sort();
+    keys.dedup();
+    return keys;
+}
+
+fn truthy(value: &JsonExpression) -> bool {
+    return !matches!(value, JsonExpression::Null | JsonExpression::Bool(false));
+}
+
+fn arithmetic(op: &str, lhs: &JsonExpression, rhs: &JsonExpression) -> Result<JsonExpression, String> {
+    match (op, lhs, rhs) {
+        ("+", JsonExpression::Null, other) | ("+", other, JsonExpression::Null) => return Ok(other.clone()),
+        ("+", JsonExpression::Number(a), JsonExpression::Number(b)) => return Ok(JsonExpression::Number(a + b)),
+        ("+", JsonExpression::String(a), JsonExpression::String(b)) => return Ok(JsonExpression::String(format!("{}{}", a, b))),
+        ("+", JsonExpression::Array(a), JsonExpression::Array(b)) => return Ok(JsonExpression::Array(a.iter().chain(b.iter()).cloned().collect())),
+        ("+", JsonExpression::Object(_), JsonExpression::Object(b)) => {
+            let mut merged = lhs.clone();
+            for (key, value) in b {
+                *merged.entry(key).or_insert(JsonExpression::Null) = (**value).clone();
+            }
+            return Ok(merged);
+        },
+        ("-", JsonExpression::Number(a), JsonExpression::Number(b)) => return Ok(JsonExpression::Number(a - b)),
+        ("-", JsonExpression::Array(a), JsonExpression::Array(b)) => {
+            return Ok(JsonExpression::Array(a.iter().filter(|e| !b.contains(e)).cloned().collect()));
+        },
+        ("*", JsonExpression::Number(a), JsonExpression::Number(b)) => return Ok(JsonExpression::Number(a * b)),
+        ("/", JsonExpression::Number(_), JsonExpression::Number(b)) if *b == 0.0 => return Err(String::from("Division by zero in filter...")),
+        ("/", JsonExpression::Number(a), JsonExpression::Number(b)) => return Ok(JsonExpression::Number(a / b)),
+        ("/", JsonExpression::String(a), JsonExpression::String(b)) => {
+            return Ok(JsonExpression::Array(a.split(b.as_str()).map(|s| Box::new(JsonExpression::from(s))).collect()));
+        },
+        ("%", JsonExpression::Number(_), JsonExpression::Number(b)) if *b as i64 == 0 => return Err(String::from("Division by zero in filter...")),
+        // numbers past the i64 range saturate, and i64::MIN % -1 overflows
+        ("%", JsonExpression::Number(a), JsonExpression::Number(b)) => match (*a as i64).checked_rem(*b as i64) {
+            Some(r) => return Ok(JsonExpression::Number(r as f64)),
+            None => return Err(format!("Remainder of {} by {} is out of range in filter...", a, b))
+        },
+        _ => return Err(format!("Cannot apply {} to {} and {}...", op, lhs.type_name(), rhs.type_name()))
+    }
+}
+
+fn index(target: &JsonExpression, key: &JsonExpression) -> Result<JsonExpression, String> {
+    match (target, key) {
+        (JsonExpression::Null, _) => return Ok(JsonExpression::Null),
+        (JsonExpression::Object(_), JsonExpression::String(k)) => return Ok(field(target, k).cloned().unwrap_or(JsonExpression::Null)),
+        (JsonExpression::Array(elements), JsonExpression::Number(n)) => {
+            let i = if *n < 0.0 { elements.len() as f64 + n.floor() } else { n.floor() };
+            if i < 0.0 {
+                return Ok(JsonExpression::Null);
+            }
+            return Ok(elements.get(i as usize).map(|e| (**e).clone()).unwrap_or(JsonExpression::Null));
+        },
+        _ => return Err(format!("Cannot index {} with {}...", target.type_name(), key.type_name()))
+    }
+}
+
+fn recurse(value: &JsonExpression, out: &mut Vec<JsonExpression>) {
+    out.push(value.clone());
+    for child in value.iter() {
+        recurse(child, out);
+    }
+}
+
+fn one(expr: &Expr, input: &JsonExpression, name: &str) -> Result<JsonExpression, String> {
+    let mut outputs = eval(expr, input)?;
+    if outputs.len() != 1 {
+        return Err(format!("Argument to {} must produce exactly one value...", name));
+    }
+    return Ok(outputs.remove(0));
+}
+
+fn call(name: &str, args: &[Expr], input: &JsonExpression) -> Result<Vec<JsonExpression>, String> {
+    match (name, args) {
+        ("empty", []) => return Ok(vec![]),
+        ("not", []) => return Ok(vec![JsonExpression::Bool(!truthy(input))]),
+        ("type", []) => return Ok(vec![JsonExpression::from(input.type_name())]),
+        ("length", []) => {
+            let n = match input {
+                JsonExpression::Null => 0.0,
+                JsonExpression::Number(n) => n.abs(),
+                JsonExpression::String(s) => s.chars().count() as f64,
+                JsonExpression::Bool(_) => return Err(String::from("boolean has no length...")),
+                _ => input.iter().count() as f64
+            };
+            return Ok(vec![JsonExpression::Number(n)]);
+        },
+        ("keys", []) => {
+            match input {
+                JsonExpression::Object(_) => return Ok(vec![JsonExpression::from(sorted_keys(input))]),
+                JsonExpression::Array(elements) => return Ok(vec![JsonExpression::Array((0..elements.len()).map(|i| Box::new(JsonExpression::Number(i as f64))).collect())]),
+                _ => return Err(format!("{} has no keys...", input.type_name()))
+            }
+        },
+        ("has", [key]) => {
+            let key = one(key, input, name)?;
+            match (input, &key) {
+                (JsonExpression::Object(_), JsonExpression::String(k)) => return Ok(vec![JsonExpression::Bool(field(input, k).is_some())]),
+                (JsonExpression::Array(elements), JsonExpression::Number(n)) => return Ok(vec![JsonExpression::Bool(*n >= 0.0 && (*n as usize) < elements.len())]),
+                _ => return Err(format!("Cannot check whether {} has a {} key...", input.type_name(), key.type_name()))
+            }
+        },
+        ("select", [condition]) => {
+            let keep = eval(condition, input)?.iter().any(truthy);
+            return Ok(if keep { vec![input.clone()] } else { vec![] });
+        },
+        ("map", [f]) => {
+            let mut mapped: Vec<Box<JsonExpression>> = Vec::new();
+            for element in iterate(input)? {
+                mapped.extend(eval(f, &element)?.into_iter().map(Box::new));
+            }
+            return Ok(vec![JsonExpression::Array(mapped)]);
+        },
+        ("add", []) => {
+            let mut total = JsonExpression::Null;
+            for element in iterate(input)? {
+                total = arithmetic("+", &total, &element)?;
+            }
+            return Ok(vec![total]);
+        },
+        ("sort", []) | ("reverse", []) | ("min", []) | ("max", []) | ("first", []) | ("last", []) => {
+            let mut elements = match input {
+                JsonExpression::Array(elements) => elements.iter().map(|e| (**e).clone()).collect::<Vec<JsonExpression>>(),
+                _ => return Err(format!("Cannot {} {}...", name, input.type_name()))
+            };
+            match name {
+                "sort" => elements.sort_by(compare),
+                "reverse" => elements.reverse(),
+                "min" => return Ok(vec![elements.into_iter().min_by(compare).unwrap_or(JsonExpression::Null)]),
+                "max" => return Ok(vec![elements.into_iter().max_by(compare).unwrap_or(JsonExpression::Null)]),
+                "first" => return Ok(vec![elements.into_iter().next().unwrap_or(JsonExpression::Null)]),
+                _ => return Ok(vec![elements.pop().unwrap_or(JsonExpression::Null)]),
+            }
+            return Ok(vec![JsonExpression::Array(elements.into_iter().map(Box::new).collect())]);
+        },
+        ("tostring", []) => {
+            match input {
+                JsonExpression::String(_) => return Ok(vec![input.clone()]),
+                _ => return Ok(vec![JsonExpression::String(input.to_string())])
+            }
+        },
+        ("tonumber", []) => {
+            match input {
+                JsonExpression::Number(_) => return Ok(vec![input.clone()]),
+                JsonExpression::String(s) => return s.trim().parse::<f64>().map(|n| vec![JsonExpression::Number(n)]).map_err(|_| format!("Cannot parse {:?} as a number...", s)),
+                _ => return Err(format!("Cannot convert {} to a number...", input.type_name()))
+            }
+        },
+        _ => return Err(format!("Unknown function {}/{}...", name, args.len()))
+    }
+}
+
+fn iterate(value: &JsonExpression) -> Result<Vec<JsonExpression>, String> {
+    match value {
+        JsonExpression::Array(_) | JsonExpression::Object(_) => return Ok(value.iter().cloned().collect()),
+        _ => return Err(format!("Cannot iterate over {}...", value.type_name()))
+    }
+}
+
+fn eval(expr: &Expr, input: &JsonExpression) -> Result<Vec<JsonExpression>, String> {
+    match expr {
+        Expr::Identity => return Ok(vec![input.clone()]),
+        Expr::Recurse => {
+            let mut out = Vec::new();
+            recurse(input, &mut out);
+            return Ok(out);
+        },
+        Expr::Literal(value) => return Ok(vec![value.clone()]),
+        Expr::Field(target, key) => {
+            let key = JsonExpression::String(key.clone());
+            return eval(target, input)?.iter().map(|t| index(t, &key)).collect();
+        },
+        Expr::Index(target, key) => {
+            let mut out = Vec::new();
+            for t in eval(target, input)? {
+                for k in eval(key, input)? {
+                    out.push(index(&t, &k)?);
+                }
+            }
+            return Ok(out);
+        },
+        Expr::Iterate(target) => {
+            let mut out = Vec::new();
+            for t in eval(target, input)? {
+                out.extend(iterate(&t)?);
+            }
+            return Ok(out);
+        },
+        Expr::Optional(inner) => return Ok(eval(inner, input).unwrap_or_default()),
+        Expr::Pipe(lhs, rhs) => {
+            let mut out = Vec::new();
+            for value in eval(lhs, input)? {
+                out.extend(eval(rhs, &value)?);
+            }
+            return Ok(out);
+        },
+        Expr::Comma(lhs, rhs) => {
+            let mut out = eval(lhs, input)?;
+            out.extend(eval(rhs, input)?);
+            return Ok(out);
+        },
+        Expr::Binary(op, lhs, rhs) => {
+            let mut out = Vec::new();
+            for r in eval(rhs, input)? {
+                for l in eval(lhs, input)? {
+                    let result = match *op {
+                        "==" => JsonExpression::Bool(compare(&l, &r) == Ordering::Equal),
+                        "!=" => JsonExpression::Bool(compare(&l, &r) != Ordering::Equal),
+                        "<" => JsonExpression::Bool(compare(&l, &r) == Ordering::Less),
+                        "<=" => JsonExpression::Bool(compare(&l, &r) != Ordering::Greater),
+                        ">" => JsonExpression::Bool(compare(&l, &r) == Ordering::Greater),
+                        ">=" => JsonExpression::Bool(compare(&l, &r) != Ordering::Less),
+                        _ => arithmetic(op, &l, &r)?
+                    };
+                    out.push(result);
+                }
+            }
+            return Ok(out);
+        },
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            let is_and = matches!(expr, Expr::And(_, _));
+            let mut out = Vec::new();
+            for l in eval(lhs, input)? {
+                // short circuits like jq, the right side only runs when needed
+                if truthy(&l) != is_and {
+                    out.push(JsonExpression::Bool(!is_and));
+                    continue;
+                }
+                for r in eval(rhs, input)? {
+                    out.push(JsonExpression::Bool(truthy(&r)));
+                }
+            }
+            return Ok(out);
+        },
+        Expr::Alternative(lhs, rhs) => {
+            let found: Vec<JsonExpression> = eval(lhs, input).unwrap_or_default().into_iter().filter(truthy).collect();
+            if found.is_empty() {
+                return eval(rhs, input);
+            }
+            return Ok(found);
+        },
+        Expr::Array(inner) => {
+            let elements = match inner {
+                Some(inner) => eval(inner, input)?,
+                None => vec![]
+            };
+            return Ok(vec![JsonExpression::Array(elements.into_iter().map(Box::new).collect())]);
+        },
+        Expr::Object(entries) => {
+            // every combination of the entry outputs makes one object
+            let mut objects: Vec<Vec<(String, Box<JsonExpression>)>> = vec![vec![]];
+            for (key, value) in entries {
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for object in &objects {
+                    for v in &values {
+                        let mut extended = object.clone();
+                        extended.push((key.clone(), Box::new(v.clone())));
+                        next.push(extended);
+                    }
+                }
+                objects = next;
+            }
+            return Ok(objects.into_iter().map(JsonExpression::Object).collect());
+        },
+        Expr::Call(name, args) => return call(name, args, input)
+    }
+}
+
+// A compiled filter, reusable across inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    expr: Expr
+}
+
+impl Filter {
+    pub fn eval(&self, value: &JsonExpression) -> Result<Vec<JsonExpression>, String> {
+        return eval(&self.expr, value);
+    }
+}
+
+pub fn compile(source: &str) -> Result<Filter, String> {
+    let mut parser = FilterParser { tokens: tokenize(source)?, current: 0 };
+    let expr = parser.pipe()?;
+    if parser.current < parser.tokens.len() {
+        return Err(format!("Unexpected {:?} in filter...", parser.tokens[parser.current]));
+    }
+    return Ok(Filter { expr });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::filter::compile;
+    use crate::{json, JsonExpression};
+
+    fn run(filter: &str, input: &str) -> Vec<JsonExpression> {
+        return compile(filter).unwrap().eval(&json(String::from(input)).unwrap()).unwrap();
+    }
+
+    fn values(source: &str) -> Vec<JsonExpression> {
+        return json(String::from(source)).unwrap().iter().cloned().collect();
+    }
+
+    #[test]
+    fn paths_pipes_and_select() {
+        let users = "{\"users\": [{\"name\": \"a\", \"age\": 40}, {\"name\": \"b\", \"age\": 20}, {\"name\": \"c\", \"age\": 31}]}";
+        assert_eq!(values("[\"a\", \"c\"]"), run(".users[] | select(.age > 30) | .name", users));
+        assert_eq!(values("[\"b\"]"), run(".users[1].name", users));
+        assert_eq!(values("[\"c\"]"), run(".users[-1][\"name\"]", users));
+        assert_eq!(values("[[40, 20, 31], 3]"), run("[.users[].age], (.users | length)", users));
+        assert_eq!(values("[{\"n\": \"a\", \"old\": true}]"), run(".users[0] | {n: .name, old: (.age >= 40)}", users));
+        assert_eq!(values("[null]"), run(".missing.deeper", users));
+    }
+
+    #[test]
+    fn builtins_and_operators() {
+        assert_eq!(values("[6, [1, 2, 3], [\"a\", \"b\"], \"x\"]"), run("(.n | add), (.n | sort), (.o | keys), (.o.a // \"x\")", "{\"n\": [3, 1, 2], \"o\": {\"b\": 1, \"a\": null}}"));
+        assert_eq!(values("[[2, 4], true, false, \"number\", 2]"), run("map(. * 2), (first == 1), has(5), (.[0] | type), (7 % 5)", "[1, 2]"));
+        assert_eq!(values("[1, 2, [2]]"), run("[..] | .[1], .[3], .[2]", "{\"a\": 1, \"b\": [2]}"));
+        assert_eq!(values("[]"), run(".[]?", "3"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(compile(".a |").is_err());
+        assert!(compile(".[1").is_err());
+        assert!(compile("nosuch(1)").unwrap().eval(&JsonExpression::Null).is_err());
+        assert!(compile(".[]").unwrap().eval(&JsonExpression::Number(1.0)).is_err());
+        assert!(compile(".a").unwrap().eval(&JsonExpression::Bool(true)).is_err());
+
+        let remainder = compile(".a % .b").unwrap();
+        assert_eq!(
+            Err(String::from("Remainder of -10000000000000000000 by -1 is out of range in filter...")),
+            remainder.eval(&json(String::from("{\"a\": -1e19, \"b\": -1}")).unwrap())
+        );
+        assert!(remainder.eval(&json(String::from("{\"a\": 1, \"b\": 0.5}")).unwrap()).is_err());
+    }
+}
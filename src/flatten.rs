@@ -0,0 +1,220 @@
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexStyle {
+    // `a[2]`
+    Brackets,
+    // `a.2`, using the separator
+    Separator
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlattenOptions {
+    pub separator: String,
+    pub index_style: IndexStyle
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        return Self { separator: String::from("."), index_style: IndexStyle::Brackets };
+    }
+}
+
+fn flat_key(path: &JsonPath, options: &FlattenOptions) -> String {
+    let mut key = String::new();
+    for (i, segment) in path.segments().iter().enumerate() {
+        match (segment, options.index_style) {
+            (PathSegment::Index(index), IndexStyle::Brackets) => {
+                key.push_str(&format!("[{}]", index));
+                continue;
+            },
+            _ if i > 0 => key.push_str(&options.separator),
+            _ => {}
+        }
+        match segment {
+            PathSegment::Key(k) => key.push_str(k),
+            PathSegment::Index(index) => key.push_str(&index.to_string())
+        }
+    }
+    return key;
+}
+
+fn parse_key(key: &str, options: &FlattenOptions) -> Result<Vec<PathSegment>, String> {
+    let mut segments: Vec<PathSegment> = Vec::new();
+    if key.is_empty() {
+        return Ok(segments);
+    }
+
+    for part in key.split(options.separator.as_str()) {
+        if options.index_style == IndexStyle::Separator {
+            match part.parse::<usize>() {
+                Ok(index) if part.bytes().all(|b| b.is_ascii_digit()) => segments.push(PathSegment::Index(index)),
+                _ => segments.push(PathSegment::Key(String::from(part)))
+            }
+            continue;
+        }
+
+        let (name, mut brackets) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if !name.is_empty() || brackets.is_empty() {
+            segments.push(PathSegment::Key(String::from(name)));
+        }
+        while !brackets.is_empty() {
+            let index = brackets.strip_prefix('[')
+                .and_then(|b| b.split_once(']'))
+                .and_then(|(digits, rest)| Some((digits.parse::<usize>().ok()?, rest)));
+            match index {
+                Some((index, rest)) => {
+                    segments.push(PathSegment::Index(index));
+                    brackets = rest;
+                },
+                None => return Err(format!("Invalid array index in key {}...", key))
+            }
+        }
+    }
+    return Ok(segments);
+}
+
+// Tree being rebuilt by unflatten, None is a position nothing was put in yet.
+enum Node {
+    Value(JsonExpression),
+    Object(Vec<(String, Option<Node>)>),
+    Array(Vec<Option<Node>>)
+}
+
+fn insert(slot: &mut Option<Node>, segments: &[PathSegment], value: JsonExpression, key: &str, limit: usize) -> Result<(), String> {
+    let conflict = || format!("Conflicting keys at {}...", key);
+    match segments.split_first() {
+        None if slot.is_none() => {
+            *slot = Some(Node::Value(value));
+            return Ok(());
+        },
+        None => return Err(conflict()),
+        Some((PathSegment::Key(k), rest)) => {
+            let pairs = match slot.get_or_insert_with(|| Node::Object(vec![])) {
+                Node::Object(pairs) => pairs,
+                _ => return Err(conflict())
+            };
+            let index = match pairs.iter().position(|(existing, _)| existing == k) {
+                Some(index) => index,
+                None => {
+                    pairs.push((k.clone(), None));
+                    pairs.len() - 1
+                }
+            };
+            return insert(&mut pairs[index].1, rest, value, key, limit);
+        },
+        Some((PathSegment::Index(i), rest)) => {
+            let elements = match slot.get_or_insert_with(|| Node::Array(vec![])) {
+                Node::Array(elements) => elements,
+                _ => return Err(conflict())
+            };
+            // an index past the number of keys could never be filled in
+            if *i >= limit {
+                return Err(format!("Array index {} in key {} is out of range...", i, key));
+            }
+            if elements.len() <= *i {
+                elements.resize_with(i + 1, || None);
+            }
+            return insert(&mut elements[*i], rest, value, key, limit);
+        }
+    }
+}
+
+fn build(node: Option<Node>) -> JsonExpression {
+    match node {
+        None => return JsonExpression::Null,
+        Some(Node::Value(value)) => return value,
+        Some(Node::Object(pairs)) => return JsonExpression::Object(pairs.into_iter().map(|(k, v)| (k, Box::new(build(v)))).collect()),
+        Some(Node::Array(elements)) => return JsonExpression::Array(elements.into_iter().map(|e| Box::new(build(e))).collect())
+    }
+}
+
+impl JsonExpression {
+    pub fn flatten(&self) -> JsonExpression {
+        return self.flatten_with(&FlattenOptions::default());
+    }
+
+    // A single level object with one member per leaf, keyed by its path.
+    // Empty arrays and objects are leaves and kept as they are. Keys that
+    // contain the separator or brackets make the result ambiguous.
+    pub fn flatten_with(&self, options: &FlattenOptions) -> JsonExpression {
+        let pairs = self.leaves().map(|(path, value)| (flat_key(&path, options), Box::new(value.clone()))).collect();
+        return JsonExpression::Object(pairs);
+    }
+
+    pub fn unflatten(&self) -> Result<JsonExpression, String> {
+        return self.unflatten_with(&FlattenOptions::default());
+    }
+
+    // Rebuilds the nested tree from an object of path keys as made by
+    // flatten_with with the same options. A key that is also the prefix of
+    // another, the same key twice, or a path that uses one container both as
+    // an object and an array is an error. Array positions no key fills are
+    // null. With the separator index style every all digit part is an index.
+    pub fn unflatten_with(&self, options: &FlattenOptions) -> Result<JsonExpression, String> {
+        let pairs = match self {
+            JsonExpression::Object(pairs) => pairs,
+            _ => return Err(format!("Expected object to unflatten, found {}...", self.type_name()))
+        };
+
+        let mut root: Option<Node> = None;
+        for (key, value) in pairs {
+            let segments = parse_key(key, options)?;
+            insert(&mut root, &segments, value.as_ref().clone(), key, pairs.len())?;
+        }
+        return Ok(build(root.or(Some(Node::Object(vec![])))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flatten::{FlattenOptions, IndexStyle};
+    use crate::json;
+
+    #[test]
+    fn dotted_keys() {
+        let expr = json(String::from("{\"a\": {\"b\": [1, {\"c\": true}], \"d\": []}, \"e\": null}")).unwrap();
+        assert_eq!(
+            json(String::from("{\"a.b[0]\": 1, \"a.b[1].c\": true, \"a.d\": [], \"e\": null}")).unwrap(),
+            expr.flatten()
+        );
+
+        let options = FlattenOptions { separator: String::from("/"), index_style: IndexStyle::Separator };
+        assert_eq!(
+            json(String::from("{\"a/b/0\": 1, \"a/b/1/c\": true, \"a/d\": [], \"e\": null}")).unwrap(),
+            expr.flatten_with(&options)
+        );
+    }
+
+    #[test]
+    fn unflatten_round_trips() {
+        let expr = json(String::from("{\"a\": {\"b\": [1, {\"c\": true}], \"d\": []}, \"e\": null, \"f\": [[2]]}")).unwrap();
+        assert_eq!(expr, expr.flatten().unflatten().unwrap());
+
+        let options = FlattenOptions { separator: String::from("/"), index_style: IndexStyle::Separator };
+        assert_eq!(expr, expr.flatten_with(&options).unflatten_with(&options).unwrap());
+
+        let sparse = json(String::from("{\"x[1]\": 1, \"y\": 2}")).unwrap();
+        assert_eq!(json(String::from("{\"x\": [null, 1], \"y\": 2}")).unwrap(), sparse.unflatten().unwrap());
+    }
+
+    #[test]
+    fn unflatten_conflicts() {
+        let prefix = json(String::from("{\"a.b\": 1, \"a.b.c\": 2}")).unwrap();
+        assert_eq!(Err(String::from("Conflicting keys at a.b.c...")), prefix.unflatten());
+
+        let mixed = json(String::from("{\"a[0]\": 1, \"a.b\": 2}")).unwrap();
+        assert_eq!(Err(String::from("Conflicting keys at a.b...")), mixed.unflatten());
+
+        assert!(json(String::from("{\"a[x]\": 1}")).unwrap().unflatten().is_err());
+        assert!(json(String::from("{\"a[99]\": 1}")).unwrap().unflatten().is_err());
+        assert!(json(String::from("[1]")).unwrap().unflatten().is_err());
+    }
+
+    #[test]
+    fn top_level_array() {
+        let expr = json(String::from("[[1], 2]")).unwrap();
+        assert_eq!(json(String::from("{\"[0][0]\": 1, \"[1]\": 2}")).unwrap(), expr.flatten());
+    }
+}
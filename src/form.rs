@@ -0,0 +1,243 @@
+use crate::JsonExpression;
+
+// application/x-www-form-urlencoded with the bracket convention most web
+// frameworks use: `a[b]=1` nests objects, `a[0]=x` indexes arrays and
+// `a[]=x` appends. Form values carry no types so everything parses as a
+// string.
+
+// How far past the end of an array an index may reach. Form bodies come
+// from anyone, and a gap is padded with nulls, so `a[99999999999]=x` would
+// otherwise ask for an array of that size.
+const MAX_GAP: usize = 20;
+
+// How many bracket segments one key may have, each is a level of nesting
+// and `a[b][b]...` is cheap to send.
+const MAX_SEGMENTS: usize = 32;
+
+enum KeySegment {
+    Key(String),
+    Index(usize),
+    Append
+}
+
+fn percent_decode(s: &str) -> Result<String, String> {
+    let bytes = s.as_bytes();
+    let mut out: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut index: usize = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = s.get(index + 1..index + 3).and_then(|h| u8::from_str_radix(h, 16).ok());
+                match hex {
+                    Some(b) => out.push(b),
+                    None => return Err(format!("Invalid percent escape in {:?}...", s))
+                }
+                index += 2;
+            },
+            b => out.push(b),
+        }
+        index += 1;
+    }
+
+    return String::from_utf8(out).map_err(|_| format!("Percent decoded {:?} is not valid UTF-8...", s));
+}
+
+fn percent_encode(s: &str, out: &mut String) {
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b' ' => out.push('+'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+}
+
+fn parse_key(key: &str) -> Result<(String, Vec<KeySegment>), String> {
+    let (name, mut rest) = match key.find('[') {
+        Some(start) if start > 0 => (&key[..start], &key[start..]),
+        _ => return Ok((String::from(key), vec![]))
+    };
+
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        let end = match (rest.starts_with('['), rest.find(']')) {
+            (true, Some(end)) => end,
+            _ => return Err(format!("Malformed brackets in form key {:?}...", key))
+        };
+
+        if segments.len() == MAX_SEGMENTS {
+            return Err(format!("Form key {:?} is nested more than {} levels deep...", key, MAX_SEGMENTS));
+        }
+        let inner = &rest[1..end];
+        if inner.is_empty() {
+            segments.push(KeySegment::Append);
+        } else if inner.bytes().all(|b| b.is_ascii_digit()) {
+            segments.push(KeySegment::Index(inner.parse().map_err(|_| format!("Index too large in form key {:?}...", key))?));
+        } else {
+            segments.push(KeySegment::Key(String::from(inner)));
+        }
+        rest = &rest[end + 1..];
+    }
+
+    return Ok((String::from(name), segments));
+}
+
+fn empty_container(next: Option<&KeySegment>) -> JsonExpression {
+    match next {
+        Some(KeySegment::Key(_)) => return JsonExpression::Object(vec![]),
+        Some(_) => return JsonExpression::Array(vec![]),
+        None => return JsonExpression::Null
+    }
+}
+
+fn insert(target: &mut JsonExpression, segments: &[KeySegment], value: JsonExpression, key: &str) -> Result<(), String> {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            *target = value;
+            return Ok(());
+        }
+    };
+
+    let child = match (segment, target) {
+        (KeySegment::Key(k), JsonExpression::Object(pairs)) => {
+            if !pairs.iter().any(|(existing, _)| existing == k) {
+                pairs.push((k.clone(), Box::new(empty_container(rest.first()))));
+            }
+            pairs.iter_mut().rev().find(|(existing, _)| existing == k).unwrap().1.as_mut()
+        },
+        (KeySegment::Index(i), JsonExpression::Array(elements)) => {
+            // sparse indexes are padded with null so positions are kept
+            if *i > elements.len() + MAX_GAP {
+                return Err(format!("Index {} in form key {:?} is more than {} past the end of the array...", i, key, MAX_GAP));
+            }
+            while elements.len() <= *i {
+                elements.push(Box::new(JsonExpression::Null));
+            }
+            if matches!(*elements[*i], JsonExpression::Null) {
+                *elements[*i] = empty_container(rest.first());
+            }
+            elements[*i].as_mut()
+        },
+        (KeySegment::Append, JsonExpression::Array(elements)) => {
+            elements.push(Box::new(empty_container(rest.first())));
+            elements.last_mut().unwrap().as_mut()
+        },
+        _ => return Err(format!("Form key {:?} conflicts with an earlier key...", key))
+    };
+
+    return insert(child, rest, value, key);
+}
+
+fn write_pairs(prefix: &str, value: &JsonExpression, out: &mut Vec<String>) {
+    match value {
+        JsonExpression::Array(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                write_pairs(&format!("{}[{}]", prefix, i), element, out);
+            }
+        },
+        JsonExpression::Object(pairs) => {
+            for (key, child) in pairs {
+                let mut encoded = String::new();
+                percent_encode(key, &mut encoded);
+                write_pairs(&format!("{}[{}]", prefix, encoded), child, out);
+            }
+        },
+        _ => {
+            let mut pair = format!("{}=", prefix);
+            match value {
+                JsonExpression::String(s) => percent_encode(s, &mut pair),
+                JsonExpression::Null => {},
+                _ => percent_encode(&value.to_string(), &mut pair)
+            }
+            out.push(pair);
+        }
+    }
+}
+
+impl JsonExpression {
+    pub fn from_form_urlencoded(input: &str) -> Result<JsonExpression, String> {
+        let mut root = JsonExpression::Object(vec![]);
+
+        for pair in input.split('&').filter(|p| !p.is_empty()) {
+            let (raw_key, raw_value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = percent_decode(raw_key)?;
+            let value = JsonExpression::String(percent_decode(raw_value)?);
+
+            let (name, segments) = parse_key(&key)?;
+            let slot = root.entry(&name).or_insert_with(|| empty_container(segments.first()));
+            insert(slot, &segments, value, &key)?;
+        }
+
+        return Ok(root);
+    }
+
+    // Empty arrays and objects have no form representation and are left out.
+    pub fn to_form_urlencoded(&self) -> Result<String, String> {
+        let pairs = match self {
+            JsonExpression::Object(pairs) => pairs,
+            _ => return Err(format!("Expected object for form data, found {}...", self.type_name()))
+        };
+
+        let mut out: Vec<String> = Vec::new();
+        for (key, value) in pairs {
+            let mut encoded = String::new();
+            percent_encode(key, &mut encoded);
+            write_pairs(&encoded, value, &mut out);
+        }
+
+        return Ok(out.join("&"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn parses_bracketed_keys() {
+        let expr = JsonExpression::from_form_urlencoded("a=1&b[0]=x&b[1]=y&c[d][]=p+q&c[d][]=%26&e").unwrap();
+        assert_eq!(
+            json(String::from("{\"a\": \"1\", \"b\": [\"x\", \"y\"], \"c\": {\"d\": [\"p q\", \"&\"]}, \"e\": \"\"}")).unwrap(),
+            expr
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let expr = json(String::from("{\"q\": \"a b&c\", \"tags\": [\"x\", \"y\"], \"user\": {\"name\": \"é\"}}")).unwrap();
+        let encoded = expr.to_form_urlencoded().unwrap();
+        assert_eq!("q=a+b%26c&tags[0]=x&tags[1]=y&user[name]=%C3%A9", encoded);
+        assert_eq!(expr, JsonExpression::from_form_urlencoded(&encoded).unwrap());
+    }
+
+    #[test]
+    fn conflicting_keys() {
+        assert!(JsonExpression::from_form_urlencoded("a=1&a[b]=2").is_err());
+        assert!(JsonExpression::from_form_urlencoded("a[b=1").is_err());
+    }
+
+    #[test]
+    fn limits_sparse_indexes() {
+        let expr = JsonExpression::from_form_urlencoded("a[2]=x&a[23]=y").unwrap();
+        assert!(matches!(expr.get_path("a"), Some(JsonExpression::Array(a)) if a.len() == 24));
+        assert_eq!(
+            Err(String::from("Index 99999999999 in form key \"a[99999999999]\" is more than 20 past the end of the array...")),
+            JsonExpression::from_form_urlencoded("a[99999999999]=x")
+        );
+        assert!(JsonExpression::from_form_urlencoded("a[0]=x&a[22]=y").is_err());
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let key = |depth: usize| format!("a{}", "[b]".repeat(depth));
+        assert!(JsonExpression::from_form_urlencoded(&format!("{}=1", key(32))).is_ok());
+        assert_eq!(
+            Err(format!("Form key {:?} is nested more than 32 levels deep...", key(33))),
+            JsonExpression::from_form_urlencoded(&format!("{}=1", key(33)))
+        );
+        assert!(JsonExpression::from_form_urlencoded(&format!("{}=1", key(300_000))).is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use std::hash::{Hash, Hasher};
+
+use crate::JsonExpression;
+
+// Equality is structural with f64 comparison, so object member order matters
+// and 0.0 equals -0.0. A value holding NaN is not equal to itself and will
+// never be found in a set or map, which is the one place Eq's contract bends.
+impl Eq for JsonExpression {}
+
+impl Hash for JsonExpression {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            // +0.0 to fold -0.0 into 0.0, they compare equal
+            JsonExpression::Number(n) => (n + 0.0).to_bits().hash(state),
+            JsonExpression::String(s) => s.hash(state),
+            JsonExpression::Bool(b) => b.hash(state),
+            JsonExpression::Null => {},
+            JsonExpression::Array(elements) => elements.hash(state),
+            JsonExpression::Object(pairs) => pairs.hash(state)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn dedups_equal_values() {
+        let mut set: HashSet<JsonExpression> = HashSet::new();
+        assert!(set.insert(json(String::from("{\"a\": [1, \"x\", null]}")).unwrap()));
+        assert!(!set.insert(json(String::from("{ \"a\" : [1.0, \"x\", null] }")).unwrap()));
+        // member order is part of equality
+        assert!(set.insert(json(String::from("{\"b\": 1, \"a\": 2}")).unwrap()));
+        assert!(set.insert(json(String::from("{\"a\": 2, \"b\": 1}")).unwrap()));
+        assert_eq!(3, set.len());
+    }
+
+    #[test]
+    fn signed_zero() {
+        let mut set: HashSet<JsonExpression> = HashSet::new();
+        set.insert(JsonExpression::Number(0.0));
+        assert!(set.contains(&JsonExpression::Number(-0.0)));
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::error::JsonError;
+use crate::path::{array_index, pointer_tokens, PathSegment};
+use crate::stream::{walk, Event};
+use crate::{from_slice, JsonExpression};
+
+// Where one member or element sits in the input, as byte offsets from the
+// start. Children are only filled in down to the depth the index was built
+// with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub segment: PathSegment,
+    pub start: u64,
+    pub end: u64,
+    pub children: Vec<IndexEntry>
+}
+
+// Byte offsets of the values near the top of a document, built in one
+// streaming pass so a file too large to parse whole can later have a single
+// subtree read straight from disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OffsetIndex {
+    // the members or elements of the top level value
+    pub entries: Vec<IndexEntry>,
+    // how many levels below the top entries were recorded for
+    pub depth: usize
+}
+
+// Collects entries as walk hands out events.
+struct Builder {
+    depth: usize,
+    // the containers that are open, true for an object, with how many values
+    // have been started in each
+    stack: Vec<(bool, usize)>,
+    key: Option<String>,
+    // entries for the open containers that are being recorded
+    open: Vec<IndexEntry>,
+    entries: Vec<IndexEntry>
+}
+
+impl Builder {
+    // Puts a finished entry under the container holding it.
+    fn finish(&mut self, entry: IndexEntry) {
+        match self.open.last_mut() {
+            Some(parent) if self.stack.len() > 1 => parent.children.push(entry),
+            _ => self.entries.push(entry)
+        }
+    }
+
+    fn event(&mut self, event: Event, start: u64, end: u64) {
+        match event {
+            Event::Key(key) => self.key = Some(key),
+            Event::EndObject | Event::EndArray => {
+                if (1..=self.depth).contains(&(self.stack.len() - 1)) {
+                    let mut entry = self.open.pop().expect("an entry for every recorded container");
+                    entry.end = end;
+                    self.stack.pop();
+                    self.finish(entry);
+                } else {
+                    self.stack.pop();
+                }
+            },
+            Event::StartObject | Event::StartArray | Event::Value(_) => {
+                let recorded = (1..=self.depth).contains(&self.stack.len());
+                let segment = match self.stack.last_mut() {
+                    Some((true, count)) => {
+                        *count += 1;
+                        PathSegment::Key(self.key.take().unwrap_or_default())
+                    },
+                    Some((false, count)) => {
+                        *count += 1;
+                        PathSegment::Index(*count - 1)
+                    },
+                    None => PathSegment::Index(0)
+                };
+                let entry = IndexEntry { segment, start, end, children: vec![] };
+                match event {
+                    Event::Value(_) if recorded => self.finish(entry),
+                    Event::Value(_) => {},
+                    _ => {
+                        if recorded {
+                            self.open.push(entry);
+                        }
+                        self.stack.push((matches!(event, Event::StartObject), 0));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl OffsetIndex {
+    // Indexes a UTF-8 document without building it, recording every member of
+    // the top level value and, for a depth above 1, theirs too. The document is
+    // checked as it is read.
+    pub fn build<R: Read>(reader: R, depth: usize) -> Result<Self, JsonError> {
+        let mut builder = Builder { depth, stack: vec![], key: None, open: vec![], entries: vec![] };
+        walk(reader, |event, span| {
+            builder.event(event, span.start as u64, span.end as u64);
+            return Ok(());
+        })?;
+        return Ok(Self { entries: builder.entries, depth });
+    }
+
+    // The value at a JSON Pointer, found by following the index as far as it
+    // goes, then seeking to that subtree and parsing only it. The reader has
+    // to hold the same bytes the index was built from. None when nothing is
+    // at the pointer, which the index can often tell without reading at all.
+    pub fn get_at_pointer<R: Read + Seek>(&self, reader: &mut R, pointer: &str) -> Result<Option<JsonExpression>, JsonError> {
+        let tokens = match pointer_tokens(pointer) {
+            Ok(tokens) => tokens,
+            Err(_) => return Ok(None)
+        };
+
+        let mut entries = &self.entries;
+        let mut found: Option<&IndexEntry> = None;
+        let mut used = 0;
+        for token in &tokens {
+            if used >= self.depth {
+                break;
+            }
+            // the last of a repeated key wins, as it does for lookups
+            let entry = entries.iter().rev().find(|entry| match &entry.segment {
+                PathSegment::Key(key) => key == token,
+                PathSegment::Index(i) => array_index(token) == Some(*i)
+            });
+            match entry {
+                Some(entry) => {
+                    found = Some(entry);
+                    entries = &entry.children;
+                    used += 1;
+                },
+                // every member down to the index's depth was recorded
+                None => return Ok(None)
+            }
+        }
+
+        let mut bytes = Vec::new();
+        let read = match found {
+            Some(entry) => reader.seek(SeekFrom::Start(entry.start)).and_then(|_| reader.take(entry.end - entry.start).read_to_end(&mut bytes)),
+            None => reader.rewind().and_then(|_| reader.read_to_end(&mut bytes))
+        };
+        read.map_err(|e| JsonError::Io(e.to_string()))?;
+
+        let subtree = from_slice(&bytes)?;
+        let rest: String = tokens[used..].iter().map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1"))).collect();
+        return Ok(subtree.pointer(&rest).cloned());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::index::OffsetIndex;
+    use crate::{json, JsonExpression, PathSegment};
+
+    const INPUT: &str = "{\"users\": [{\"name\": \"ada\", \"tags\": [1, 2]}, {\"name\": \"bob\"}], \"a/b\": {\"c\": true}, \"n\": 5}";
+
+    #[test]
+    fn records_offsets() {
+        let index = OffsetIndex::build(INPUT.as_bytes(), 2).unwrap();
+        let keys: Vec<&PathSegment> = index.entries.iter().map(|entry| &entry.segment).collect();
+        assert_eq!(vec![&PathSegment::Key(String::from("users")), &PathSegment::Key(String::from("a/b")), &PathSegment::Key(String::from("n"))], keys);
+
+        let users = &index.entries[0];
+        assert_eq!("[{\"name\": \"ada\", \"tags\": [1, 2]}, {\"name\": \"bob\"}]", &INPUT[users.start as usize..users.end as usize]);
+        assert_eq!(2, users.children.len());
+        assert_eq!("{\"name\": \"bob\"}", &INPUT[users.children[1].start as usize..users.children[1].end as usize]);
+        // only two levels deep
+        assert!(users.children[0].children.is_empty());
+        assert_eq!("5", &INPUT[index.entries[2].start as usize..index.entries[2].end as usize]);
+    }
+
+    #[test]
+    fn reads_subtrees() {
+        let index = OffsetIndex::build(INPUT.as_bytes(), 2).unwrap();
+        let mut file = Cursor::new(INPUT.as_bytes());
+        let get = |file: &mut Cursor<&[u8]>, pointer: &str| index.get_at_pointer(file, pointer).unwrap();
+
+        assert_eq!(Some(JsonExpression::from("bob")), get(&mut file, "/users/1/name"));
+        assert_eq!(Some(json(String::from("[1, 2]")).unwrap()), get(&mut file, "/users/0/tags"));
+        assert_eq!(Some(JsonExpression::Bool(true)), get(&mut file, "/a~1b/c"));
+        assert_eq!(Some(json(String::from(INPUT)).unwrap()), get(&mut file, ""));
+        assert_eq!(None, get(&mut file, "/missing"));
+        assert_eq!(None, get(&mut file, "/users/2"));
+        assert_eq!(None, get(&mut file, "/users/0/tags/5"));
+    }
+}
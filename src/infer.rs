@@ -0,0 +1,85 @@
+use crate::JsonExpression;
+
+// What a set of sample values have in common, built by taking the shape of
+// each and merging them. Used to generate types and schemas from data.
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Shape {
+    // nothing seen yet, e.g. the elements of an empty array
+    Unknown,
+    Null,
+    Bool,
+    Integer,
+    Float,
+    String,
+    Array(Box<Shape>),
+    Object(Vec<Field>),
+    Optional(Box<Shape>),
+    Any
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Field {
+    pub key: String,
+    pub shape: Shape,
+    // missing from some of the objects merged
+    pub optional: bool
+}
+
+pub(crate) fn shape_of(value: &JsonExpression) -> Shape {
+    match value {
+        JsonExpression::Null => return Shape::Null,
+        JsonExpression::Bool(_) => return Shape::Bool,
+        JsonExpression::Number(n) if n.fract() == 0.0 && n.abs() < 9.0e15 => return Shape::Integer,
+        JsonExpression::Number(_) => return Shape::Float,
+        JsonExpression::String(_) => return Shape::String,
+        JsonExpression::Array(elements) => {
+            let element = elements.iter().fold(Shape::Unknown, |shape, e| merge(shape, shape_of(e)));
+            return Shape::Array(Box::new(element));
+        },
+        JsonExpression::Object(_) => {
+            let mut fields: Vec<Field> = Vec::new();
+            for (key, value) in value.entries() {
+                let shape = shape_of(value);
+                match fields.iter_mut().find(|f| f.key == key) {
+                    Some(field) => field.shape = shape,
+                    None => fields.push(Field { key: String::from(key), shape, optional: false })
+                }
+            }
+            return Shape::Object(fields);
+        }
+    }
+}
+
+pub(crate) fn merge(a: Shape, b: Shape) -> Shape {
+    match (a, b) {
+        (Shape::Unknown, other) | (other, Shape::Unknown) => return other,
+        (Shape::Null, Shape::Null) => return Shape::Null,
+        (Shape::Null, Shape::Optional(other)) | (Shape::Optional(other), Shape::Null) => return Shape::Optional(other),
+        (Shape::Null, other) | (other, Shape::Null) => return Shape::Optional(Box::new(other)),
+        (Shape::Optional(a), Shape::Optional(b)) => return Shape::Optional(Box::new(merge(*a, *b))),
+        (Shape::Optional(a), b) | (b, Shape::Optional(a)) => return Shape::Optional(Box::new(merge(*a, b))),
+        (Shape::Integer, Shape::Float) | (Shape::Float, Shape::Integer) => return Shape::Float,
+        (Shape::Array(a), Shape::Array(b)) => return Shape::Array(Box::new(merge(*a, *b))),
+        (Shape::Object(a), Shape::Object(b)) => {
+            let mut fields = a;
+            for field in &mut fields {
+                if !b.iter().any(|f| f.key == field.key) {
+                    field.optional = true;
+                }
+            }
+            for other in b {
+                match fields.iter_mut().find(|f| f.key == other.key) {
+                    Some(field) => {
+                        field.shape = merge(std::mem::replace(&mut field.shape, Shape::Unknown), other.shape);
+                        field.optional |= other.optional;
+                    },
+                    None => fields.push(Field { optional: true, ..other })
+                }
+            }
+            return Shape::Object(fields);
+        },
+        (a, b) if a == b => return a,
+        _ => return Shape::Any
+    }
+}
@@ -0,0 +1,266 @@
+use std::slice;
+use std::vec;
+
+use crate::JsonExpression;
+
+// Iteration over a JsonExpression walks its direct children: the elements of
+// an array or the values of an object. Scalars have no children and yield
+// nothing. Use entries()/keys() when the object keys are needed.
+pub enum Iter<'a> {
+    Array(slice::Iter<'a, Box<JsonExpression>>),
+    Object(slice::Iter<'a, (String, Box<JsonExpression>)>),
+    Empty
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a JsonExpression;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::Array(elements) => return elements.next().map(|e| e.as_ref()),
+            Iter::Object(pairs) => return pairs.next().map(|(_, v)| v.as_ref()),
+            Iter::Empty => return None
+        }
+    }
+}
+
+pub enum IterMut<'a> {
+    Array(slice::IterMut<'a, Box<JsonExpression>>),
+    Object(slice::IterMut<'a, (String, Box<JsonExpression>)>),
+    Empty
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = &'a mut JsonExpression;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            IterMut::Array(elements) => return elements.next().map(|e| e.as_mut()),
+            IterMut::Object(pairs) => return pairs.next().map(|(_, v)| v.as_mut()),
+            IterMut::Empty => return None
+        }
+    }
+}
+
+pub enum IntoIter {
+    Array(vec::IntoIter<Box<JsonExpression>>),
+    Object(vec::IntoIter<(String, Box<JsonExpression>)>),
+    Empty
+}
+
+impl Iterator for IntoIter {
+    type Item = JsonExpression;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            IntoIter::Array(elements) => return elements.next().map(|e| *e),
+            IntoIter::Object(pairs) => return pairs.next().map(|(_, v)| *v),
+            IntoIter::Empty => return None
+        }
+    }
+}
+
+impl JsonExpression {
+    pub fn iter(&self) -> Iter<'_> {
+        match self {
+            JsonExpression::Array(elements) => return Iter::Array(elements.iter()),
+            JsonExpression::Object(pairs) => return Iter::Object(pairs.iter()),
+            _ => return Iter::Empty
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        match self {
+            JsonExpression::Array(elements) => return IterMut::Array(elements.iter_mut()),
+            JsonExpression::Object(pairs) => return IterMut::Object(pairs.iter_mut()),
+            _ => return IterMut::Empty
+        }
+    }
+
+    // The object helpers below yield nothing for anything that isn't an object.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        return self.object_pairs().iter().map(|(k, _)| k.as_str());
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &JsonExpression> {
+        return self.object_pairs().iter().map(|(_, v)| v.as_ref());
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut JsonExpression> {
+        return self.entries_mut().map(|(_, v)| v);
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &JsonExpression)> {
+        return self.object_pairs().iter().map(|(k, v)| (k.as_str(), v.as_ref()));
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = (&str, &mut JsonExpression)> {
+        let pairs: &mut [(String, Box<JsonExpression>)] = match self {
+            JsonExpression::Object(pairs) => pairs,
+            _ => &mut []
+        };
+
+        return pairs.iter_mut().map(|(k, v)| (k.as_str(), v.as_mut()));
+    }
+
+    // Every value under the key in the order they appear. Parsing keeps
+    // repeated keys unless ParserOptions::duplicate_keys is off, and lookups
+    // elsewhere resolve to the last one, this is the way to see the rest.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a JsonExpression> {
+        return self.object_pairs().iter().filter(move |(k, _)| k == key).map(|(_, v)| v.as_ref());
+    }
+
+    // Keys that appear more than once, each listed once in the order of its
+    // first appearance.
+    pub fn duplicate_keys(&self) -> Vec<&str> {
+        let pairs = self.object_pairs();
+        let mut duplicates: Vec<&str> = Vec::new();
+        for (i, (key, _)) in pairs.iter().enumerate() {
+            if !duplicates.contains(&key.as_str()) && pairs[i + 1..].iter().any(|(k, _)| k == key) {
+                duplicates.push(key);
+            }
+        }
+        return duplicates;
+    }
+
+    fn object_pairs(&self) -> &[(String, Box<JsonExpression>)] {
+        match self {
+            JsonExpression::Object(pairs) => return pairs,
+            _ => return &[]
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a JsonExpression {
+    type Item = &'a JsonExpression;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter();
+    }
+}
+
+impl<'a> IntoIterator for &'a mut JsonExpression {
+    type Item = &'a mut JsonExpression;
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.iter_mut();
+    }
+}
+
+impl IntoIterator for JsonExpression {
+    type Item = JsonExpression;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            JsonExpression::Array(elements) => return IntoIter::Array(elements.into_iter()),
+            JsonExpression::Object(pairs) => return IntoIter::Object(pairs.into_iter()),
+            _ => return IntoIter::Empty
+        }
+    }
+}
+
+impl FromIterator<JsonExpression> for JsonExpression {
+    fn from_iter<I: IntoIterator<Item = JsonExpression>>(iter: I) -> Self {
+        return JsonExpression::Array(iter.into_iter().map(Box::new).collect());
+    }
+}
+
+impl FromIterator<(String, JsonExpression)> for JsonExpression {
+    fn from_iter<I: IntoIterator<Item = (String, JsonExpression)>>(iter: I) -> Self {
+        return JsonExpression::Object(iter.into_iter().map(|(k, v)| (k, Box::new(v))).collect());
+    }
+}
+
+// Extending only makes sense for the matching container, anything else is a
+// programming error in the same way indexing a Vec out of bounds is.
+impl Extend<JsonExpression> for JsonExpression {
+    fn extend<I: IntoIterator<Item = JsonExpression>>(&mut self, iter: I) {
+        match self {
+            JsonExpression::Array(elements) => elements.extend(iter.into_iter().map(Box::new)),
+            _ => panic!("Cannot extend {} with array elements...", self.type_name())
+        }
+    }
+}
+
+impl Extend<(String, JsonExpression)> for JsonExpression {
+    fn extend<I: IntoIterator<Item = (String, JsonExpression)>>(&mut self, iter: I) {
+        match self {
+            JsonExpression::Object(pairs) => pairs.extend(iter.into_iter().map(|(k, v)| (k, Box::new(v)))),
+            _ => panic!("Cannot extend {} with key value pairs...", self.type_name())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn object_keys_and_values() {
+        let expr = json(String::from("{\"a\": 1, \"b\": \"two\"}")).unwrap();
+        assert_eq!(vec!["a", "b"], expr.keys().collect::<Vec<&str>>());
+        assert_eq!(
+            vec![&JsonExpression::Number(1.0), &JsonExpression::String(String::from("two"))],
+            expr.values().collect::<Vec<&JsonExpression>>()
+        );
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let expr = json(String::from("{\"a\": 1, \"b\": 2, \"a\": 3, \"c\": 4, \"b\": 5, \"a\": 6}")).unwrap();
+        assert_eq!(
+            vec![&JsonExpression::Number(1.0), &JsonExpression::Number(3.0), &JsonExpression::Number(6.0)],
+            expr.get_all("a").collect::<Vec<&JsonExpression>>()
+        );
+        assert_eq!(1, expr.get_all("c").count());
+        assert_eq!(0, expr.get_all("d").count());
+        assert_eq!(vec!["a", "b"], expr.duplicate_keys());
+        assert!(JsonExpression::Null.duplicate_keys().is_empty());
+    }
+
+    #[test]
+    fn for_loops_over_arrays() {
+        let mut expr = json(String::from("[1, 2, 3]")).unwrap();
+        for element in &mut expr {
+            if let JsonExpression::Number(n) = element {
+                *n *= 2.0;
+            }
+        }
+
+        let mut total = 0.0;
+        for element in expr {
+            if let JsonExpression::Number(n) = element {
+                total += n;
+            }
+        }
+        assert_eq!(12.0, total);
+    }
+
+    #[test]
+    fn scalars_have_no_children() {
+        let expr = JsonExpression::Number(1.0);
+        assert_eq!(0, expr.iter().count());
+        assert_eq!(0, expr.keys().count());
+    }
+
+    #[test]
+    fn collect_and_extend() {
+        let mut array: JsonExpression = (1..3).map(|n| JsonExpression::Number(n as f64)).collect();
+        array.extend(vec![JsonExpression::Null]);
+        assert_eq!(json(String::from("[1, 2, null]")).unwrap(), array);
+
+        let mut object: JsonExpression = vec![(String::from("a"), JsonExpression::Bool(true))].into_iter().collect();
+        object.extend(vec![(String::from("b"), JsonExpression::Number(1.0))]);
+        assert_eq!(json(String::from("{\"a\": true, \"b\": 1}")).unwrap(), object);
+    }
+
+    #[test]
+    #[should_panic]
+    fn extend_wrong_container() {
+        let mut object = JsonExpression::Object(vec![]);
+        object.extend(vec![JsonExpression::Null]);
+    }
+}
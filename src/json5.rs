@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use crate::serialize::{short_escape, write_number, NumberFormat};
+use crate::JsonExpression;
+
+// Output for config files that people edit by hand. Every knob is on by
+// default, and with them on the output reads back with
+// ParserOptions::lenient() unless it holds NaN or an infinity, which JSON5
+// writes as NaN and Infinity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json5Options {
+    // None writes everything on one line
+    pub indent: Option<String>,
+    // write keys that are plain identifiers without quotes, `{name: 1}`
+    pub unquoted_keys: bool,
+    // quote strings with ' rather than "
+    pub single_quotes: bool,
+    // put a comma after the last element or member, so adding another line
+    // later only changes that line, only when indented
+    pub trailing_commas: bool
+}
+
+impl Default for Json5Options {
+    fn default() -> Self {
+        return Self { indent: Some(String::from("    ")), unquoted_keys: true, single_quotes: true, trailing_commas: true };
+    }
+}
+
+// ASCII letters, digits, `_` and `$`, not starting with a digit. JSON5 allows
+// more but this is what the lexer reads back.
+fn is_identifier(key: &str) -> bool {
+    return key.starts_with(|c: char| !c.is_ascii_digit()) && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+}
+
+fn write_string(s: &str, quote: char, out: &mut String) {
+    out.push(quote);
+    for c in s.chars() {
+        match c {
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            },
+            // the other quote needs no escape
+            '"' | '\'' => out.push(c),
+            c if c.is_ascii() && short_escape(c as u8).is_some() => out.push_str(short_escape(c as u8).unwrap()),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c)
+        }
+    }
+    out.push(quote);
+}
+
+struct Writer<'o> {
+    options: &'o Json5Options,
+    quote: char,
+    out: String
+}
+
+impl Writer<'_> {
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = &self.options.indent {
+            self.out.push('\n');
+            for _ in 0..depth {
+                self.out.push_str(indent);
+            }
+        }
+    }
+
+    // Called after each element or member.
+    fn separator(&mut self, last: bool) {
+        match (&self.options.indent, last) {
+            (None, false) => self.out.push_str(", "),
+            (None, true) => {},
+            (Some(_), false) => self.out.push(','),
+            (Some(_), true) if self.options.trailing_commas => self.out.push(','),
+            (Some(_), true) => {}
+        }
+    }
+
+    fn value(&mut self, value: &JsonExpression, depth: usize) {
+        match value {
+            JsonExpression::Number(n) if n.is_nan() => self.out.push_str("NaN"),
+            JsonExpression::Number(n) if n.is_infinite() => self.out.push_str(if *n > 0.0 { "Infinity" } else { "-Infinity" }),
+            JsonExpression::Number(n) => write_number(*n, &NumberFormat::default(), &mut self.out),
+            JsonExpression::String(s) => write_string(s, self.quote, &mut self.out),
+            JsonExpression::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            JsonExpression::Null => self.out.push_str("null"),
+            JsonExpression::Array(elements) if elements.is_empty() => self.out.push_str("[]"),
+            JsonExpression::Object(pairs) if pairs.is_empty() => self.out.push_str("{}"),
+            JsonExpression::Array(elements) => {
+                self.out.push('[');
+                for (i, element) in elements.iter().enumerate() {
+                    self.newline(depth + 1);
+                    self.value(element, depth + 1);
+                    self.separator(i + 1 == elements.len());
+                }
+                self.newline(depth);
+                self.out.push(']');
+            },
+            JsonExpression::Object(pairs) => {
+                self.out.push('{');
+                for (i, (key, member)) in pairs.iter().enumerate() {
+                    self.newline(depth + 1);
+                    if self.options.unquoted_keys && is_identifier(key) {
+                        self.out.push_str(key);
+                    } else {
+                        write_string(key, self.quote, &mut self.out);
+                    }
+                    self.out.push_str(": ");
+                    self.value(member, depth + 1);
+                    self.separator(i + 1 == pairs.len());
+                }
+                self.newline(depth);
+                self.out.push('}');
+            }
+        }
+    }
+}
+
+impl JsonExpression {
+    pub fn to_json5(&self) -> String {
+        return self.to_json5_with(&Json5Options::default());
+    }
+
+    pub fn to_json5_with(&self, options: &Json5Options) -> String {
+        let quote = if options.single_quotes { '\'' } else { '"' };
+        let mut writer = Writer { options, quote, out: String::new() };
+        writer.value(self, 0);
+        return writer.out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json5::Json5Options;
+    use crate::{json, json_with, JsonExpression, ParserOptions};
+
+    #[test]
+    fn writes_json5() {
+        let value = json(String::from("{\"name\": \"it's \\\"x\\\"\", \"2nd\": [1, 2.5], \"$ok\": {}, \"a-b\": true}")).unwrap();
+        assert_eq!(
+            "{\n    name: 'it\\'s \"x\"',\n    '2nd': [\n        1,\n        2.5,\n    ],\n    $ok: {},\n    'a-b': true,\n}",
+            value.to_json5()
+        );
+        assert_eq!(Ok(value.clone()), json_with(value.to_json5(), &ParserOptions::lenient()));
+
+        let plain = Json5Options { indent: None, unquoted_keys: false, single_quotes: false, trailing_commas: true };
+        assert_eq!("{\"name\": \"it's \\\"x\\\"\", \"2nd\": [1, 2.5], \"$ok\": {}, \"a-b\": true}", value.to_json5_with(&plain));
+
+        let numbers = JsonExpression::Array(vec![Box::new(JsonExpression::Number(f64::NAN)), Box::new(JsonExpression::Number(f64::NEG_INFINITY))]);
+        assert_eq!("[NaN, -Infinity]", numbers.to_json5_with(&Json5Options { indent: None, ..Json5Options::default() }));
+    }
+}
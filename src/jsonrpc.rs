@@ -0,0 +1,357 @@
+use std::fmt;
+
+use crate::path::PathSegment;
+use crate::traits::read_at;
+use crate::{from_slice_with, FromJson, JsonExpression, ParserOptions, ToJson};
+
+// Error codes reserved by the JSON-RPC 2.0 spec. -32000 to -32099 are left
+// for server defined errors.
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+// A request id. Null is only meant for responses to a request whose id could
+// not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Id {
+    Number(i64),
+    String(String),
+    Null
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub id: Id,
+    pub method: String,
+    // an array or an object when present
+    pub params: Option<JsonExpression>
+}
+
+// A request without an id, which never gets a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub method: String,
+    pub params: Option<JsonExpression>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<JsonExpression>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub id: Id,
+    pub result: Result<JsonExpression, Error>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Request(Request),
+    Notification(Notification),
+    Response(Response)
+}
+
+// What a server reads off the wire. Anything that is not a valid message is
+// already the error the spec says to answer it with, a batch has one per
+// member that failed. An empty batch is a single invalid request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Incoming {
+    Single(Result<Message, Error>),
+    Batch(Vec<Result<Message, Error>>)
+}
+
+impl Request {
+    pub fn new(id: Id, method: &str, params: Option<JsonExpression>) -> Self {
+        return Self { id, method: String::from(method), params };
+    }
+}
+
+impl Notification {
+    pub fn new(method: &str, params: Option<JsonExpression>) -> Self {
+        return Self { method: String::from(method), params };
+    }
+}
+
+impl Error {
+    pub fn new(code: i64, message: &str) -> Self {
+        return Self { code, message: String::from(message), data: None };
+    }
+
+    pub fn with_data(mut self, data: JsonExpression) -> Self {
+        self.data = Some(data);
+        return self;
+    }
+
+    pub fn parse_error() -> Self {
+        return Self::new(PARSE_ERROR, "Parse error");
+    }
+
+    pub fn invalid_request() -> Self {
+        return Self::new(INVALID_REQUEST, "Invalid Request");
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        return Self::new(METHOD_NOT_FOUND, "Method not found").with_data(JsonExpression::from(method));
+    }
+
+    pub fn invalid_params() -> Self {
+        return Self::new(INVALID_PARAMS, "Invalid params");
+    }
+
+    pub fn internal_error() -> Self {
+        return Self::new(INTERNAL_ERROR, "Internal error");
+    }
+}
+
+impl Response {
+    pub fn success(id: Id, result: JsonExpression) -> Self {
+        return Self { id, result: Ok(result) };
+    }
+
+    pub fn error(id: Id, error: Error) -> Self {
+        return Self { id, result: Err(error) };
+    }
+}
+
+impl Incoming {
+    // Parses the text of a message or batch. Text that is not JSON is a parse
+    // error, a member that is not a message an invalid request with the
+    // reason as its data. Messages come from peers, so the text is read with
+    // ParserOptions::untrusted() and nesting past its limit is a parse error
+    // as well.
+    pub fn parse(text: &str) -> Self {
+        let invalid = |e: String| Error::invalid_request().with_data(JsonExpression::from(e));
+        match from_slice_with(text.as_bytes(), &ParserOptions::untrusted()) {
+            Err(_) => return Incoming::Single(Err(Error::parse_error())),
+            Ok(JsonExpression::Array(members)) if members.is_empty() => return Incoming::Single(Err(invalid(String::from("Empty batch...")))),
+            Ok(JsonExpression::Array(members)) => return Incoming::Batch(members.iter().map(|m| Message::from_json(m).map_err(invalid)).collect()),
+            Ok(value) => return Incoming::Single(Message::from_json(&value).map_err(invalid))
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{} ({})", self.message, self.code);
+    }
+}
+
+// The last value under a key, as lookups elsewhere do.
+fn member<'a>(value: &'a JsonExpression, key: &str) -> Option<&'a JsonExpression> {
+    return value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v);
+}
+
+fn field<T: FromJson>(value: &JsonExpression, key: &str) -> Result<T, String> {
+    match member(value, key) {
+        Some(v) => return read_at(PathSegment::Key(String::from(key)), || T::from_json(v)),
+        None => return Err(format!("Missing field {}...", key))
+    }
+}
+
+// Checks the version marker every message carries.
+fn version(value: &JsonExpression) -> Result<(), String> {
+    if !matches!(value, JsonExpression::Object(_)) {
+        return Err(format!("Expected object, found {}...", value.type_name()));
+    }
+    match member(value, "jsonrpc") {
+        Some(JsonExpression::String(v)) if v == "2.0" => return Ok(()),
+        _ => return Err(String::from("Expected jsonrpc to be \"2.0\"..."))
+    }
+}
+
+fn params(value: &JsonExpression) -> Result<Option<JsonExpression>, String> {
+    match member(value, "params") {
+        None => return Ok(None),
+        Some(params @ (JsonExpression::Array(_) | JsonExpression::Object(_))) => return Ok(Some(params.clone())),
+        Some(other) => return Err(format!("Expected params to be an array or object, found {}...", other.type_name()))
+    }
+}
+
+fn message(mut pairs: Vec<(&str, JsonExpression)>, id: Option<&Id>) -> JsonExpression {
+    pairs.insert(0, ("jsonrpc", JsonExpression::from("2.0")));
+    if let Some(id) = id {
+        pairs.push(("id", id.to_json()));
+    }
+    return JsonExpression::Object(pairs.into_iter().map(|(k, v)| (String::from(k), Box::new(v))).collect());
+}
+
+fn call(method: &str, params: &Option<JsonExpression>) -> Vec<(&'static str, JsonExpression)> {
+    let mut pairs = vec![("method", JsonExpression::from(method))];
+    if let Some(params) = params {
+        pairs.push(("params", params.clone()));
+    }
+    return pairs;
+}
+
+impl ToJson for Id {
+    fn to_json(&self) -> JsonExpression {
+        match self {
+            Id::Number(n) => return JsonExpression::from(*n),
+            Id::String(s) => return JsonExpression::from(s.as_str()),
+            Id::Null => return JsonExpression::Null
+        }
+    }
+}
+
+impl FromJson for Id {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        match value {
+            JsonExpression::Number(_) => return i64::from_json(value).map(Id::Number),
+            JsonExpression::String(s) => return Ok(Id::String(s.clone())),
+            JsonExpression::Null => return Ok(Id::Null),
+            other => return Err(format!("Expected number, string or null id, found {}...", other.type_name()))
+        }
+    }
+}
+
+impl ToJson for Request {
+    fn to_json(&self) -> JsonExpression {
+        return message(call(&self.method, &self.params), Some(&self.id));
+    }
+}
+
+impl FromJson for Request {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        version(value)?;
+        return Ok(Self { id: field(value, "id")?, method: field(value, "method")?, params: params(value)? });
+    }
+}
+
+impl ToJson for Notification {
+    fn to_json(&self) -> JsonExpression {
+        return message(call(&self.method, &self.params), None);
+    }
+}
+
+impl FromJson for Notification {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        version(value)?;
+        if member(value, "id").is_some() {
+            return Err(String::from("Unexpected id in a notification..."));
+        }
+        return Ok(Self { method: field(value, "method")?, params: params(value)? });
+    }
+}
+
+impl ToJson for Error {
+    fn to_json(&self) -> JsonExpression {
+        let mut pairs = vec![
+            (String::from("code"), Box::new(JsonExpression::from(self.code))),
+            (String::from("message"), Box::new(JsonExpression::from(self.message.as_str())))
+        ];
+        if let Some(data) = &self.data {
+            pairs.push((String::from("data"), Box::new(data.clone())));
+        }
+        return JsonExpression::Object(pairs);
+    }
+}
+
+impl FromJson for Error {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        if !matches!(value, JsonExpression::Object(_)) {
+            return Err(format!("Expected object, found {}...", value.type_name()));
+        }
+        return Ok(Self { code: field(value, "code")?, message: field(value, "message")?, data: member(value, "data").cloned() });
+    }
+}
+
+impl ToJson for Response {
+    fn to_json(&self) -> JsonExpression {
+        let outcome = match &self.result {
+            Ok(result) => ("result", result.clone()),
+            Err(error) => ("error", error.to_json())
+        };
+        return message(vec![outcome], Some(&self.id));
+    }
+}
+
+impl FromJson for Response {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        version(value)?;
+        let result = match (member(value, "result"), member(value, "error")) {
+            (Some(result), None) => Ok(result.clone()),
+            (None, Some(_)) => Err(field(value, "error")?),
+            _ => return Err(String::from("Expected exactly one of result and error..."))
+        };
+        return Ok(Self { id: field(value, "id")?, result });
+    }
+}
+
+impl ToJson for Message {
+    fn to_json(&self) -> JsonExpression {
+        match self {
+            Message::Request(request) => return request.to_json(),
+            Message::Notification(notification) => return notification.to_json(),
+            Message::Response(response) => return response.to_json()
+        }
+    }
+}
+
+// A call with an id is a request and one without a notification, anything
+// with a result or error is a response.
+impl FromJson for Message {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        version(value)?;
+        match (member(value, "method"), member(value, "id")) {
+            (Some(_), Some(_)) => return Request::from_json(value).map(Message::Request),
+            (Some(_), None) => return Notification::from_json(value).map(Message::Notification),
+            (None, _) => return Response::from_json(value).map(Message::Response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsonrpc::{Error, Id, Incoming, Message, Notification, Request, Response, METHOD_NOT_FOUND};
+    use crate::{json, FromJson, ToJson};
+
+    #[test]
+    fn round_trip() {
+        let request = Request::new(Id::Number(1), "subtract", Some(json(String::from("[42, 23]")).unwrap()));
+        assert_eq!("{\"jsonrpc\":\"2.0\",\"method\":\"subtract\",\"params\":[42,23],\"id\":1}", request.to_json().to_string());
+        assert_eq!(Ok(Message::Request(request.clone())), Message::from_json(&request.to_json()));
+
+        let notification = Notification::new("update", None);
+        assert_eq!("{\"jsonrpc\":\"2.0\",\"method\":\"update\"}", notification.to_json().to_string());
+        assert_eq!(Ok(Message::Notification(notification)), Message::from_json(&json(String::from("{\"jsonrpc\": \"2.0\", \"method\": \"update\"}")).unwrap()));
+
+        let response = Response::error(Id::String(String::from("a")), Error::method_not_found("foo"));
+        assert_eq!("{\"jsonrpc\":\"2.0\",\"error\":{\"code\":-32601,\"message\":\"Method not found\",\"data\":\"foo\"},\"id\":\"a\"}", response.to_json().to_string());
+        let read = Response::from_json(&response.to_json()).unwrap();
+        assert_eq!(Some(METHOD_NOT_FOUND), read.result.err().map(|e| e.code));
+    }
+
+    #[test]
+    fn rejects_invalid_messages() {
+        let read = |text: &str| Message::from_json(&json(String::from(text)).unwrap());
+        assert_eq!(Err(String::from("Expected jsonrpc to be \"2.0\"...")), read("{\"jsonrpc\": \"1.0\", \"method\": \"a\", \"id\": 1}"));
+        assert_eq!(Err(String::from("id: Expected integer, found 1.5...")), read("{\"jsonrpc\": \"2.0\", \"method\": \"a\", \"id\": 1.5}"));
+        assert_eq!(Err(String::from("Expected params to be an array or object, found number...")), read("{\"jsonrpc\": \"2.0\", \"method\": \"a\", \"params\": 1}"));
+        assert_eq!(Err(String::from("Expected exactly one of result and error...")), read("{\"jsonrpc\": \"2.0\", \"id\": 1}"));
+    }
+
+    #[test]
+    fn batches() {
+        let batch = Incoming::parse("[{\"jsonrpc\": \"2.0\", \"method\": \"a\", \"id\": 1}, 1, {\"jsonrpc\": \"2.0\", \"method\": \"b\"}]");
+        match batch {
+            Incoming::Batch(members) => {
+                assert_eq!(3, members.len());
+                assert!(matches!(members[0], Ok(Message::Request(_))));
+                assert_eq!(Some(-32600), members[1].as_ref().err().map(|e| e.code));
+                assert!(matches!(members[2], Ok(Message::Notification(_))));
+            },
+            other => panic!("{:?}", other)
+        }
+        assert_eq!(Incoming::Single(Err(Error::parse_error())), Incoming::parse("{\"jsonrpc\": \"2.0\", \"method\""));
+        assert!(matches!(Incoming::parse("[]"), Incoming::Single(Err(Error { code: -32600, .. }))));
+
+        // too deep to be a message from a well behaved peer
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(Incoming::Single(Err(Error::parse_error())), Incoming::parse(&deep));
+    }
+}
@@ -0,0 +1,693 @@
+use std::collections::HashMap;
+
+use crate::error::JsonError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Colon,
+    NumberLiteral(f64),
+    StringLiteral(String),
+    BooleanLiteral(bool),
+    NullLiteral
+}
+
+impl Token {
+    // What the token is called in error messages.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Token::LeftBrace => return "left brace",
+            Token::RightBrace => return "right brace",
+            Token::LeftBracket => return "left bracket",
+            Token::RightBracket => return "right bracket",
+            Token::Comma => return "comma",
+            Token::Colon => return "colon",
+            Token::NumberLiteral(_) => return "number",
+            Token::StringLiteral(_) => return "string",
+            Token::BooleanLiteral(true) => return "true",
+            Token::BooleanLiteral(false) => return "false",
+            Token::NullLiteral => return "null"
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TokenType {
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Comma,
+    Colon,
+    NumberLiteral,
+    StringLiteral,
+    BooleanLiteral,
+    NullLiteral
+}
+
+// Where a token came from. start and end are byte offsets into the input,
+// line and column are 1 based with the column counted in characters.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span
+}
+
+// Anything the parser can pull tokens from one at a time. Ok(None) means the
+// input is exhausted.
+pub trait TokenSource {
+    fn next_token(&mut self) -> Result<Option<Spanned<Token>>, JsonError>;
+}
+
+// Tokens that were already lexed up front.
+impl TokenSource for std::vec::IntoIter<Spanned<Token>> {
+    fn next_token(&mut self) -> Result<Option<Spanned<Token>>, JsonError> {
+        return Ok(self.next());
+    }
+}
+
+// Works on the raw bytes of the input. Everything outside of string literals
+// is ASCII, so UTF-8 only needs checking inside strings, which happens as each
+// run of unescaped bytes is copied out.
+pub struct Lexer<'a> {
+    pub tokens: Vec<Spanned<Token>>,
+    source: &'a [u8],
+    index: usize,
+    // line and column of `scanned`, only moved forward when a span is made
+    scanned: usize,
+    line: usize,
+    column: usize,
+    // raw bytes of escape free keys to their decoded form, None when off
+    keys: Option<HashMap<&'a [u8], String>>,
+    // when false strings are checked but come out empty, so nothing allocates
+    copy_strings: bool,
+    comments: bool,
+    // raw bytes below 0x20 are allowed inside strings
+    control_characters: bool,
+    // invalid UTF-8 in strings becomes U+FFFD rather than an error
+    lossy_utf8: bool,
+    // strings may be delimited by ' as well as "
+    single_quotes: bool,
+    // identifiers followed by a colon are keys
+    unquoted_keys: bool
+}
+
+impl<'a> Lexer<'a> {
+
+    pub fn new(source: &'a [u8]) -> Self {
+
+        // a byte order mark is allowed to lead the document
+        let index = if source.starts_with(b"\xef\xbb\xbf") { 3 } else { 0 };
+
+        return Self{
+            tokens: vec![],
+            source,
+            index,
+            scanned: index,
+            line: 1,
+            column: 1,
+            keys: None,
+            copy_strings: true,
+            comments: false,
+            control_characters: false,
+            lossy_utf8: false,
+            single_quotes: false,
+            unquoted_keys: false
+        };
+    }
+
+    // For checking input without keeping it: string literals are validated
+    // as usual but their tokens hold empty strings.
+    pub fn validating(mut self) -> Self {
+        self.copy_strings = false;
+        self.keys = None;
+        return self;
+    }
+
+    // Object keys without escapes are cached by their raw bytes, a key seen
+    // before is cloned from the cache instead of being validated and built
+    // up again.
+    pub fn intern_keys(mut self, on: bool) -> Self {
+        self.keys = if on { Some(HashMap::new()) } else { None };
+        return self;
+    }
+
+    // Treats `//` to the end of the line and `/* ... */` as whitespace.
+    pub fn comments(mut self, on: bool) -> Self {
+        self.comments = on;
+        return self;
+    }
+
+    // RFC 8259 has control characters in strings escaped, this lets raw tabs,
+    // newlines and the like through as they are.
+    pub fn control_characters(mut self, on: bool) -> Self {
+        self.control_characters = on;
+        return self;
+    }
+
+    // Replaces each invalid UTF-8 sequence in a string with U+FFFD, the way
+    // String::from_utf8_lossy does, so a mangled record can still be read.
+    // A \u escape of a surrogate without its other half becomes U+FFFD too.
+    pub fn lossy_utf8(mut self, on: bool) -> Self {
+        self.lossy_utf8 = on;
+        return self;
+    }
+
+    // Accepts `'single quoted'` strings as JavaScript does. A `"` inside one
+    // needs no escape and `\'` is a quote, the token is the same
+    // StringLiteral either way.
+    pub fn single_quotes(mut self, on: bool) -> Self {
+        self.single_quotes = on;
+        return self;
+    }
+
+    // Accepts object keys written as bare identifiers, `{foo: 1}`, as in a
+    // JavaScript object literal. A word made of ASCII letters, digits, `_` and
+    // `$`, not starting with a digit and followed by a colon, becomes a
+    // StringLiteral, so `{null: 1}` has the key "null".
+    pub fn unquoted_keys(mut self, on: bool) -> Self {
+        self.unquoted_keys = on;
+        return self;
+    }
+
+    // Moves past whitespace, and comments when they are on. An unterminated
+    // block comment is an error of its own, running out of input after a
+    // complete document is not.
+    fn skip_blank(&mut self) -> Result<(), JsonError> {
+        loop {
+            while self.index < self.source.len() && matches!(self.source[self.index], b' ' | b'\n' | b'\t' | b'\r') {
+                self.index += 1;
+            }
+            if !self.comments || self.source.get(self.index) != Some(&b'/') {
+                return Ok(());
+            }
+
+            let rest = &self.source[self.index..];
+            match rest.get(1) {
+                Some(b'/') => self.index += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len()),
+                Some(b'*') => match rest[2..].windows(2).position(|w| w == b"*/") {
+                    Some(end) => self.index += end + 4,
+                    None => {
+                        let span = self.span(self.index, self.source.len());
+                        self.index = self.source.len();
+                        return Err(JsonError::UnexpectedToken(String::from("Unterminated comment..."), span));
+                    }
+                },
+                _ => return Ok(())
+            }
+        }
+    }
+
+    // index is on the opening quote. None leaves the string to string_literal.
+    fn interned_key(&mut self, quote: u8) -> Option<Token> {
+        let source = self.source;
+        let keys = self.keys.as_mut()?;
+
+        let start = self.index + 1;
+        let end = start + source[start..].iter().position(|&b| b == quote || b == b'\\')?;
+        if source[end] != quote {
+            return None;
+        }
+        // only keys are worth caching and a key is followed by a colon
+        if source[end + 1..].iter().find(|b| !matches!(b, b' ' | b'\n' | b'\t' | b'\r')) != Some(&b':') {
+            return None;
+        }
+
+        let raw = &source[start..end];
+        let key = match keys.get(raw) {
+            Some(key) => key.clone(),
+            None => {
+                // invalid UTF-8 and control characters are reported by
+                // string_literal
+                if !self.control_characters && raw.iter().any(|&b| b < 0x20) {
+                    return None;
+                }
+                let key = String::from(std::str::from_utf8(raw).ok()?);
+                keys.insert(raw, key.clone());
+                key
+            }
+        };
+
+        self.index = end + 1;
+        return Some(Token::StringLiteral(key));
+    }
+
+    fn span(&mut self, start: usize, end: usize) -> Span {
+        for &b in &self.source[self.scanned..start] {
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if b & 0xC0 != 0x80 {
+                // UTF-8 continuation bytes do not start a new character
+                self.column += 1;
+            }
+        }
+        self.scanned = start;
+
+        return Span { start, end, line: self.line, column: self.column };
+    }
+
+    // Lexes the whole input into `tokens`. The parser normally pulls tokens
+    // through TokenSource, and anything else can iterate the lexer, so the
+    // full vector is never built.
+    pub fn lex(&mut self) -> Result<(), JsonError> {
+        // most documents average well over eight bytes per token, so this
+        // avoids regrowing the token vector without over reserving much
+        self.tokens.reserve(self.source.len() / 8);
+
+        while let Some(token) = self.next_token()? {
+            self.tokens.push(token);
+        }
+
+        return Ok(());
+    }
+
+    // index is on the opening quote, which is also the closing one.
+    fn string_literal(&mut self, quote: u8) -> Result<Token, JsonError> {
+        if let Some(key) = self.interned_key(quote) {
+            return Ok(key);
+        }
+
+        let mut index: usize = self.index + 1;
+        let mut s = String::new();
+        loop {
+            let start: usize = index;
+            while index < self.source.len() && self.source[index] != quote && self.source[index] != b'\\' && (self.control_characters || self.source[index] >= 0x20) {
+                index += 1;
+            }
+
+            match std::str::from_utf8(&self.source[start..index]) {
+                Ok(run) if self.copy_strings => s.push_str(run),
+                Ok(_) => {},
+                Err(_) if self.lossy_utf8 => {
+                    if self.copy_strings {
+                        s.push_str(&String::from_utf8_lossy(&self.source[start..index]));
+                    }
+                },
+                Err(e) => {
+                    let at = start + e.valid_up_to();
+                    return Err(JsonError::InvalidUtf8(self.span(at, at + e.error_len().unwrap_or(index - at))));
+                }
+            }
+
+            if index >= self.source.len() {
+                return Err(JsonError::UnexpectedEndOfInput);
+            }
+
+            if self.source[index] < 0x20 {
+                let span = self.span(index, index + 1);
+                return Err(JsonError::UnexpectedToken(String::from("Unescaped control character in string..."), span));
+            }
+
+            if self.source[index] == quote {
+                break;
+            }
+
+            index += 1;
+            if index >= self.source.len() {
+                return Err(JsonError::UnexpectedEndOfInput);
+            }
+
+            let c = match self.source[index] {
+                b'"' => '"',
+                b'\\' => '\\',
+                b'/' => '/',
+                b'\'' if self.single_quotes => '\'',
+                b'b' => '\u{8}',
+                b'f' => '\u{c}',
+                b'n' => '\n',
+                b'r' => '\r',
+                b't' => '\t',
+                b'u' => self.unicode_escape(&mut index)?,
+                _ => {
+                    let rest = String::from_utf8_lossy(&self.source[index..(index + 4).min(self.source.len())]).into_owned();
+                    let c = rest.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER);
+                    let span = self.span(index - 1, index + c.len_utf8());
+                    return Err(JsonError::InvalidEscape(c, span));
+                }
+            };
+            if self.copy_strings {
+                s.push(c);
+            }
+
+            index += 1;
+        }
+
+        self.index = index + 1;
+        return Ok(Token::StringLiteral(s));
+    }
+
+    fn quoted(&mut self, quote: u8) -> Result<Token, JsonError> {
+        return self.string_literal(quote).inspect_err(|_| self.skip_string(quote));
+    }
+
+    // Moves past a string that failed to lex so lexing can carry on after it.
+    fn skip_string(&mut self, quote: u8) {
+        let mut index: usize = self.index + 1;
+        while index < self.source.len() && self.source[index] != quote {
+            index += if self.source[index] == b'\\' { 2 } else { 1 };
+        }
+        self.index = (index + 1).min(self.source.len());
+    }
+
+    fn ends_word(&self, b: u8) -> bool {
+        return is_delim(b) || self.comments && b == b'/';
+    }
+
+    fn bare_word(&mut self) -> Result<Token, JsonError> {
+        let start: usize = self.index;
+        // the first byte is always taken so a stray `/` cannot stall the lexer
+        while self.index < self.source.len() && (self.index == start || !self.ends_word(self.source[self.index])) {
+            self.index += 1;
+        }
+
+        let word = &self.source[start..self.index];
+        if self.unquoted_keys && is_identifier(word) && self.source[self.index..].iter().find(|b| !matches!(b, b' ' | b'\n' | b'\t' | b'\r')) == Some(&b':') {
+            // identifiers are ASCII
+            return Ok(Token::StringLiteral(String::from_utf8_lossy(word).into_owned()));
+        }
+        match word {
+            b"true" => return Ok(Token::BooleanLiteral(true)),
+            b"false" => return Ok(Token::BooleanLiteral(false)),
+            b"null" => return Ok(Token::NullLiteral),
+            _ => {}
+        }
+
+        if let Some(n) = parse_number(word) {
+            return Ok(Token::NumberLiteral(n));
+        }
+        match std::str::from_utf8(word) {
+            Ok(s) => return Err(JsonError::InvalidLiteral(String::from(s), self.span(start, self.index))),
+            Err(e) => {
+                let at = start + e.valid_up_to();
+                return Err(JsonError::InvalidUtf8(self.span(at, at + e.error_len().unwrap_or(self.index - at))));
+            }
+        }
+    }
+
+    // index points at the 'u' of a \uXXXX escape and is left on its last hex
+    // digit. Surrogate pairs are joined. Anything but four hex digits is an
+    // error, and so is a surrogate without its other half unless lossy_utf8
+    // turns it into U+FFFD.
+    fn unicode_escape(&mut self, index: &mut usize) -> Result<char, JsonError> {
+        let start = *index - 1;
+        let Some(high) = self.hex4(*index + 1) else {
+            // the \u and the hex digits that are there
+            let end = *index + 1 + self.source[*index + 1..].iter().take(4).take_while(|b| b.is_ascii_hexdigit()).count();
+            return Err(JsonError::InvalidEscape('u', self.span(start, end)));
+        };
+        *index += 4;
+
+        if (0xD800..0xDC00).contains(&high) {
+            let is_pair = *index + 2 < self.source.len() && self.source[*index + 1] == b'\\' && self.source[*index + 2] == b'u';
+            if let Some(low) = self.hex4(*index + 3).filter(|low| is_pair && (0xDC00..0xE000).contains(low)) {
+                *index += 6;
+                let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                // a joined pair is always a valid character
+                return Ok(char::from_u32(c).unwrap());
+            }
+        }
+
+        match char::from_u32(high) {
+            Some(c) => return Ok(c),
+            None if self.lossy_utf8 => return Ok(char::REPLACEMENT_CHARACTER),
+            None => return Err(JsonError::InvalidEscape('u', self.span(start, *index + 1)))
+        }
+    }
+
+    fn hex4(&self, start: usize) -> Option<u32> {
+        let digits = self.source.get(start..start + 4)?;
+        if !digits.iter().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        return u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok();
+    }
+}
+
+impl TokenSource for Lexer<'_> {
+    fn next_token(&mut self) -> Result<Option<Spanned<Token>>, JsonError> {
+        self.skip_blank()?;
+        if self.index >= self.source.len() {
+            return Ok(None);
+        }
+
+        let start: usize = self.index;
+        let token = match self.source[start] {
+            b'{' => Token::LeftBrace,
+            b'}' => Token::RightBrace,
+            b'[' => Token::LeftBracket,
+            b']' => Token::RightBracket,
+            b',' => Token::Comma,
+            b':' => Token::Colon,
+            quote @ b'"' => self.quoted(quote)?,
+            quote @ b'\'' if self.single_quotes => self.quoted(quote)?,
+            _ => self.bare_word()?,
+        };
+
+        // literals move the index past themselves, punctuation is one byte
+        if self.index == start {
+            self.index += 1;
+        }
+
+        let span = self.span(start, self.index);
+        return Ok(Some(Spanned { value: token, span }));
+    }
+}
+
+// Tokens on demand, each one is only lexed when asked for. An error does not
+// end the iteration, lexing carries on after the bad token the same way the
+// recovering parser relies on.
+impl Iterator for Lexer<'_> {
+    type Item = Result<Spanned<Token>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.next_token().transpose();
+    }
+}
+
+// Converts the bytes of a number literal, None unless they follow the RFC
+// 8259 number grammar. Integers short enough to be exact in an f64 are the
+// common case and are summed up directly, anything else goes to the float
+// parser.
+fn parse_number(bytes: &[u8]) -> Option<f64> {
+    if !is_number_literal(bytes) {
+        return None;
+    }
+    let (negative, digits) = match bytes.split_first() {
+        Some((b'-', rest)) => (true, rest),
+        _ => (false, bytes)
+    };
+    if !digits.is_empty() && digits.len() <= 15 && digits.iter().all(u8::is_ascii_digit) {
+        let n = digits.iter().fold(0u64, |n, d| n * 10 + (d - b'0') as u64) as f64;
+        return Some(if negative { -n } else { n });
+    }
+
+    #[cfg(feature = "fast-float")]
+    return fast_float2::parse(bytes).ok();
+    #[cfg(not(feature = "fast-float"))]
+    return std::str::from_utf8(bytes).ok()?.parse().ok();
+}
+
+// The number grammar of RFC 8259: an optional minus, no leading zeros, at
+// least one digit after a dot and after an exponent. The float parsers take
+// more than this, NaN, inf, +1, .5 and 1. among it.
+pub(crate) fn is_number_literal(bytes: &[u8]) -> bool {
+    let digits = |i: usize| bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut i = usize::from(bytes.first() == Some(&b'-'));
+
+    match bytes.get(i) {
+        Some(b'0') => i += 1,
+        Some(b'1'..=b'9') => i += digits(i),
+        _ => return false
+    }
+    if bytes.get(i) == Some(&b'.') {
+        let n = digits(i + 1);
+        if n == 0 {
+            return false;
+        }
+        i += 1 + n;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        i += 1;
+        if matches!(bytes.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        let n = digits(i);
+        if n == 0 {
+            return false;
+        }
+        i += n;
+    }
+
+    return i == bytes.len();
+}
+
+fn is_identifier(word: &[u8]) -> bool {
+    return word.first().is_some_and(|b| !b.is_ascii_digit()) && word.iter().all(|&b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$');
+}
+
+pub(crate) fn is_delim(c: u8) -> bool {
+    return matches!(c, b',' | b'{' | b'}' | b'[' | b']' | b':' | b' ' | b'\n' | b'\t' | b'\r');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::JsonError;
+    use crate::lexer::{Lexer, Span, Token, TokenSource};
+
+    #[test]
+    fn pulls_tokens_on_demand() {
+        let mut lexer = Lexer::new(b"[1, \"a\"] \x01");
+        let mut next = || lexer.next_token().map(|t| t.map(|t| t.value));
+        assert_eq!(Ok(Some(Token::LeftBracket)), next());
+        assert_eq!(Ok(Some(Token::NumberLiteral(1.0))), next());
+        assert_eq!(Ok(Some(Token::Comma)), next());
+        assert_eq!(Ok(Some(Token::StringLiteral(String::from("a")))), next());
+        assert_eq!(Ok(Some(Token::RightBracket)), next());
+        // the bad literal is only seen when it is reached
+        assert!(lexer.next_token().is_err());
+    }
+
+    #[test]
+    fn iterates_past_errors() {
+        let tokens: Vec<Result<Token, JsonError>> = Lexer::new(b"[tru, \"\xff\", 2] /").comments(true).map(|t| t.map(|t| t.value)).collect();
+        assert_eq!(vec![
+            Ok(Token::LeftBracket),
+            Err(JsonError::InvalidLiteral(String::from("tru"), Span { start: 1, end: 4, line: 1, column: 2 })),
+            Ok(Token::Comma),
+            Err(JsonError::InvalidUtf8(Span { start: 7, end: 8, line: 1, column: 8 })),
+            Ok(Token::Comma),
+            Ok(Token::NumberLiteral(2.0)),
+            Ok(Token::RightBracket),
+            Err(JsonError::InvalidLiteral(String::from("/"), Span { start: 14, end: 15, line: 1, column: 15 }))
+        ], tokens);
+
+        assert_eq!(2, Lexer::new(b"1 /* 2 */ 3 // 4").comments(true).count());
+    }
+
+    #[test]
+    fn lex_collects_everything() {
+        let mut lexer = Lexer::new(b"\xef\xbb\xbf{\"k\": null}");
+        lexer.lex().unwrap();
+        assert_eq!(
+            vec![Token::LeftBrace, Token::StringLiteral(String::from("k")), Token::Colon, Token::NullLiteral, Token::RightBrace],
+            lexer.tokens.into_iter().map(|t| t.value).collect::<Vec<Token>>()
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let mut lexer = Lexer::new(b"[0, -0, 42, -123456789012345, 1234567890123456789, 2.5e-3, -1E2, 0.1]");
+        lexer.lex().unwrap();
+        let numbers: Vec<f64> = lexer.tokens.into_iter().filter_map(|t| match t.value {
+            Token::NumberLiteral(n) => Some(n),
+            _ => None
+        }).collect();
+        assert_eq!(vec![0.0, -0.0, 42.0, -123456789012345.0, 1234567890123456789.0, 2.5e-3, -100.0, 0.1], numbers);
+        assert!(numbers[1].is_sign_negative());
+
+        assert_eq!(Err(JsonError::InvalidLiteral(String::from("1.2.3"), Span { start: 0, end: 5, line: 1, column: 1 })), Lexer::new(b"1.2.3").next_token());
+    }
+
+    #[test]
+    fn interned_keys() {
+        let source = "[{\"id\": \"id\", \"n\\u00e9\" : 1}, {\"id\" :\"x\", \"né\": 2}]".as_bytes();
+        let mut plain = Lexer::new(source);
+        plain.lex().unwrap();
+        let mut interned = Lexer::new(source).intern_keys(true);
+        interned.lex().unwrap();
+        assert_eq!(plain.tokens, interned.tokens);
+        // only the two escape free keys are cached
+        assert_eq!(2, interned.keys.unwrap().len());
+
+        let mut bad = Lexer::new(b"{\"\xff\": 1}").intern_keys(true);
+        bad.next_token().unwrap();
+        assert_eq!(Err(JsonError::InvalidUtf8(Span { start: 2, end: 3, line: 1, column: 3 })), bad.next_token());
+    }
+
+    #[test]
+    fn escapes() {
+        let string = |source: &[u8]| Lexer::new(source).next_token().map(|t| t.unwrap().value);
+        assert_eq!(Ok(Token::StringLiteral(String::from("\"\\/\u{8}\u{c}\n\r\té😀"))), string(br#""\"\\\/\b\f\n\r\t\u00e9\ud83d\ude00""#));
+
+        // only the escapes RFC 8259 lists
+        assert_eq!(Err(JsonError::InvalidEscape('q', Span { start: 1, end: 3, line: 1, column: 2 })), string(br#""\q""#));
+        assert_eq!(Err(JsonError::InvalidEscape('x', Span { start: 2, end: 4, line: 1, column: 3 })), string(br#""a\x41""#));
+        assert_eq!(Err(JsonError::InvalidEscape('\'', Span { start: 1, end: 3, line: 1, column: 2 })), string(br#""\'""#));
+        assert_eq!(Err(JsonError::InvalidEscape('u', Span { start: 1, end: 3, line: 1, column: 2 })), string(br#""\uZZZZ""#));
+        assert_eq!(Err(JsonError::InvalidEscape('u', Span { start: 1, end: 5, line: 1, column: 2 })), string(br#""\u12""#));
+
+        // surrogates need their other half unless lossy
+        assert_eq!(Err(JsonError::InvalidEscape('u', Span { start: 1, end: 7, line: 1, column: 2 })), string(br#""\ud800""#));
+        assert_eq!(Err(JsonError::InvalidEscape('u', Span { start: 1, end: 7, line: 1, column: 2 })), string(br#""\udc00\ud800""#));
+        assert_eq!(Err(JsonError::InvalidEscape('u', Span { start: 1, end: 7, line: 1, column: 2 })), string(br#""\ud800\u0041""#));
+        let lossy = Lexer::new(br#""\ud800\u0041\udc00""#).lossy_utf8(true).next_token().map(|t| t.unwrap().value);
+        assert_eq!(Ok(Token::StringLiteral(String::from("\u{fffd}A\u{fffd}"))), lossy);
+    }
+
+    #[test]
+    fn number_grammar() {
+        let number = |source: &str| Lexer::new(source.as_bytes()).next_token().map(|t| t.unwrap().value);
+        for (source, n) in [("0", 0.0), ("-0", -0.0), ("12", 12.0), ("-1.5", -1.5), ("0.25", 0.25), ("1e3", 1000.0), ("2E-2", 0.02), ("-3e+1", -30.0)] {
+            assert_eq!(Ok(Token::NumberLiteral(n)), number(source), "{}", source);
+        }
+
+        // forms the float parser takes but JSON does not
+        for source in ["NaN", "inf", "-inf", "Infinity", "+1", ".5", "1.", "01", "-01", "1e", "1.e3", "-", "0x10"] {
+            assert_eq!(Err(JsonError::InvalidLiteral(String::from(source), Span { start: 0, end: source.len(), line: 1, column: 1 })), number(source), "{}", source);
+        }
+        assert!(crate::json_with(String::from("[01]"), &crate::ParserOptions::strict()).is_err());
+        assert!(crate::from_slice_with(b"{\"a\": NaN}", &crate::ParserOptions::untrusted()).is_err());
+    }
+
+    #[test]
+    fn single_quotes() {
+        let source = b"{'a': 'it\\'s \"x\"', \"b\": ['', 'c']}";
+        let tokens: Vec<Token> = Lexer::new(source).single_quotes(true).intern_keys(true).map(|t| t.unwrap().value).collect();
+        assert_eq!(Token::StringLiteral(String::from("a")), tokens[1]);
+        assert_eq!(Token::StringLiteral(String::from("it's \"x\"")), tokens[3]);
+        assert_eq!(Token::StringLiteral(String::new()), tokens[8]);
+        assert_eq!(Token::StringLiteral(String::from("c")), tokens[10]);
+
+        assert_eq!(Err(JsonError::InvalidLiteral(String::from("'a'"), Span { start: 0, end: 3, line: 1, column: 1 })), Lexer::new(b"'a'").next_token());
+        assert_eq!(Err(JsonError::UnexpectedEndOfInput), Lexer::new(b"'a").single_quotes(true).next_token());
+    }
+
+    #[test]
+    fn unquoted_keys() {
+        let tokens: Vec<Token> = Lexer::new(b"{foo: 1, $b_2 : true, null:null, 1a: x}").unquoted_keys(true).map(|t| t.map(|t| t.value).unwrap_or(Token::NullLiteral)).collect();
+        assert_eq!(Token::StringLiteral(String::from("foo")), tokens[1]);
+        assert_eq!(Token::StringLiteral(String::from("$b_2")), tokens[5]);
+        assert_eq!(Token::StringLiteral(String::from("null")), tokens[9]);
+        assert_eq!(Token::NullLiteral, tokens[11]);
+        // not followed by a colon, not a key
+        assert_eq!(Token::NullLiteral, tokens[15]);
+
+        assert_eq!(Err(JsonError::InvalidLiteral(String::from("foo"), Span { start: 0, end: 3, line: 1, column: 1 })), Lexer::new(b"foo: 1").next_token());
+    }
+
+    #[test]
+    fn spans() {
+        let mut lexer = Lexer::new("{\n  \"é\": true,\n  \"b\": 12\n}".as_bytes());
+        lexer.lex().unwrap();
+        let spans: Vec<Span> = lexer.tokens.iter().map(|t| t.span).collect();
+        assert_eq!(Span { start: 0, end: 1, line: 1, column: 1 }, spans[0]);
+        assert_eq!(Span { start: 4, end: 8, line: 2, column: 3 }, spans[1]);
+        assert_eq!(Span { start: 10, end: 14, line: 2, column: 8 }, spans[3]);
+        assert_eq!(Span { start: 23, end: 25, line: 3, column: 8 }, spans[7]);
+        assert_eq!(Span { start: 26, end: 27, line: 4, column: 1 }, spans[8]);
+    }
+}
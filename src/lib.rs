@@ -0,0 +1,424 @@
+#![allow(clippy::needless_return, clippy::vec_box)]
+
+// lets the derive macros, which name everything through ::jsonrs, be used
+// inside this crate too
+extern crate self as jsonrs;
+
+pub mod approx;
+pub mod base64;
+pub mod bson;
+pub mod cache;
+pub mod cbor;
+pub mod codegen;
+pub mod convert;
+pub mod csv;
+pub mod cursor;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod datetime;
+#[cfg(feature = "derive")]
+mod derive;
+pub mod diff;
+#[cfg(feature = "digest")]
+pub mod digest;
+pub mod encoding;
+pub mod entry;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod flatten;
+pub mod form;
+pub mod hash;
+pub mod index;
+mod infer;
+pub mod iter;
+pub mod json5;
+pub mod jsonrpc;
+pub mod lint;
+pub mod lexer;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "rayon")]
+pub mod ndjson;
+#[cfg(feature = "unicode")]
+pub mod normalize;
+pub mod options;
+mod parser;
+pub mod path;
+pub mod prune;
+pub mod redact;
+pub mod reformat;
+pub mod schema;
+pub mod schema_ref;
+pub mod seq;
+pub mod serialize;
+pub mod shared;
+pub mod size;
+pub mod sort;
+pub mod spanned;
+pub mod stats;
+pub mod stream;
+pub mod summary;
+pub mod tape;
+#[cfg(feature = "tracing")]
+pub mod telemetry;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod traits;
+#[cfg(feature = "uuid")]
+pub mod uuid;
+pub mod validate;
+pub mod visitor;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod writer;
+pub mod xml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+#[cfg(feature = "derive")]
+pub use derive::{FromJson, ToJson};
+pub use cursor::JsonCursor;
+pub use error::JsonError;
+pub use lexer::{Span, Spanned, Token, TokenSource, TokenType};
+#[cfg(feature = "mmap")]
+pub use mmap::from_file;
+#[cfg(feature = "unicode")]
+pub use normalize::Normalization;
+pub use options::ParserOptions;
+pub use parser::JsonParser;
+pub use path::{from_json_at, JsonPath, PathSegment};
+pub use reformat::reformat;
+pub use serialize::{NumberFormat, PrettyOptions};
+pub use shared::SharedJson;
+pub use spanned::{parse_spanned, parse_spanned_with, SpannedValue};
+pub use traits::{FromJson, ToJson};
+pub use validate::{validate, validate_reader, validate_slice};
+pub use visitor::{Visitor, VisitorMut};
+pub use writer::{JsonStringWriter, JsonWriter};
+
+use lexer::Lexer;
+use parser::Parser;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonExpression {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Null,
+    Array(Vec<Box<JsonExpression>>),
+    Object(Vec<(String, Box<JsonExpression>)>)
+}
+
+pub fn json(source: String) -> Result<JsonExpression, JsonError> {
+    return from_slice(source.as_bytes());
+}
+
+pub fn json_with(source: String, options: &ParserOptions) -> Result<JsonExpression, JsonError> {
+    return from_slice_with(source.as_bytes(), options);
+}
+
+// Parses straight from the input bytes, UTF-8 is checked as strings are lexed
+// so there is no need to build a String first. UTF-16 input is detected from
+// its BOM or NUL bytes and transcoded before lexing.
+pub fn from_slice(bytes: &[u8]) -> Result<JsonExpression, JsonError> {
+    return from_slice_with(bytes, &ParserOptions::default());
+}
+
+pub fn from_slice_with(bytes: &[u8], options: &ParserOptions) -> Result<JsonExpression, JsonError> {
+    return JsonParser::new(*options).parse(bytes);
+}
+
+// A lexer set up the way the options ask.
+fn lexer_for<'a>(bytes: &'a [u8], options: &ParserOptions) -> Lexer<'a> {
+    return Lexer::new(bytes)
+        .intern_keys(options.intern_keys)
+        .comments(options.comments)
+        .control_characters(options.control_characters)
+        .lossy_utf8(options.lossy_utf8)
+        .single_quotes(options.single_quotes)
+        .unquoted_keys(options.unquoted_keys);
+}
+
+// Parses the document at the start of the input and returns it along with
+// the number of bytes it used, so anything following it can be handled by the
+// caller. The input has to be UTF-8, a leading BOM is counted as used.
+pub fn parse_partial(bytes: &[u8]) -> Result<(JsonExpression, usize), JsonError> {
+    let mut parser = Parser::new(Lexer::new(bytes).intern_keys(true), ParserOptions::default());
+    return parser.parse_partial();
+}
+
+// parse_partial for text: parses the value at the start of the input and
+// returns it with the number of bytes it took, so it can be pulled out of a
+// log line or template and the caller can carry on from `&input[used..]`.
+pub fn parse_prefix(input: &str) -> Result<(JsonExpression, usize), JsonError> {
+    return parse_partial(input.as_bytes());
+}
+
+// For editors and linters: keeps going after an error, returning whatever
+// could be parsed along with every error found. The value is None only when
+// not even the start of a document could be parsed.
+pub fn parse_recovering(bytes: &[u8]) -> (Option<JsonExpression>, Vec<JsonError>) {
+    let bytes = match transcode(bytes) {
+        Ok(bytes) => bytes,
+        Err(e) => return (None, vec![e])
+    };
+
+    let mut parser = Parser::recovering(Lexer::new(&bytes).intern_keys(true), ParserOptions::default());
+    let result = parser.parse();
+    let mut errors = parser.errors;
+    match result {
+        Ok(expression) => return (Some(expression), errors),
+        Err(e) => {
+            errors.push(e);
+            return (None, errors);
+        }
+    }
+}
+
+// UTF-8 input is borrowed as is, UTF-16 is transcoded.
+fn transcode(bytes: &[u8]) -> Result<std::borrow::Cow<'_, [u8]>, JsonError> {
+    let (encoding, _) = encoding::detect(bytes);
+    if encoding == encoding::Encoding::Utf8 {
+        return Ok(std::borrow::Cow::Borrowed(bytes));
+    }
+
+    let text = encoding::decode(bytes).map_err(JsonError::InvalidEncoding)?;
+    return Ok(std::borrow::Cow::Owned(text.into_owned().into_bytes()));
+}
+
+impl std::str::FromStr for JsonExpression {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return json(String::from(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_slice, from_slice_with, json, json_with, parse_partial, JsonParser, parse_prefix, parse_recovering, validate, JsonError, JsonExpression, ParserOptions, Span};
+
+    #[test]
+    fn empty_object() {
+        let expr = json(String::from("{}")).unwrap();
+        assert_eq!(JsonExpression::Object(vec![]), expr);
+    }
+
+    #[test]
+    fn empty_array() {
+        let expr = json(String::from("[]")).unwrap();
+        assert_eq!(JsonExpression::Array(vec![]), expr);
+    }
+
+    #[test]
+    fn object() {
+        let expr = json(String::from("
+        {
+            \"one\": 1,
+            \"two\": 2.0
+        }
+        ")).unwrap();
+        assert_eq!(
+            JsonExpression::Object(vec![
+                (String::from("one"), Box::new(JsonExpression::Number(1.0))),
+                (String::from("two"), Box::new(JsonExpression::Number(2.0))),
+            ]), 
+            expr
+        ); 
+    }
+
+    #[test]
+    fn literals() {
+        let expr = json(String::from("[true, false, null]")).unwrap();
+        assert_eq!(
+            JsonExpression::Array(vec![
+                Box::new(JsonExpression::Bool(true)),
+                Box::new(JsonExpression::Bool(false)),
+                Box::new(JsonExpression::Null),
+            ]),
+            expr
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        let expr = "{\"a\":1}".parse::<JsonExpression>().unwrap();
+        assert_eq!(
+            JsonExpression::Object(vec![(String::from("a"), Box::new(JsonExpression::Number(1.0)))]),
+            expr
+        );
+    }
+
+    #[test]
+    fn from_slice_bytes() {
+        assert_eq!(json(String::from("{\"é\": [1, 2]}")).unwrap(), from_slice("{\"é\": [1, 2]}".as_bytes()).unwrap());
+        assert_eq!(Err(JsonError::InvalidUtf8(Span { start: 3, end: 4, line: 1, column: 4 })), from_slice(b"[\"a\xff\"]"));
+        assert_eq!(Err(JsonError::UnexpectedEndOfInput), from_slice(b"{\"a\": [1,"));
+        assert_eq!(Err(JsonError::UnexpectedEndOfInput), from_slice(b"[\"abc"));
+    }
+
+    #[test]
+    fn error_location() {
+        let error = json(String::from("{\n  \"a\": 1,\n  \"b\" 2\n}")).unwrap_err();
+        assert_eq!(JsonError::UnexpectedToken(String::from("Expected colon, found number..."), Span { start: 18, end: 19, line: 3, column: 7 }), error);
+        assert_eq!("Line 3, column 7: Expected colon, found number...", error.to_string());
+    }
+
+    #[test]
+    fn from_slice_utf16() {
+        let utf16: Vec<u8> = "[\"a\", 1]".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert_eq!(json(String::from("[\"a\", 1]")).unwrap(), from_slice(&utf16).unwrap());
+    }
+
+    #[test]
+    fn recovers_from_errors() {
+        let (value, errors) = parse_recovering(b"{\"a\": [1, 2 3, ], \"b\" 4, \"c\": tru, \"d\": {\"e\": 5}}");
+        assert_eq!(json(String::from("{\"a\": [1, 2], \"c\": null, \"d\": {\"e\": 5}}")).unwrap(), value.unwrap());
+        assert_eq!(
+            vec![
+                "Line 1, column 13: Expected comma or right bracket, found number...",
+                "Line 1, column 16: Expected value, found right bracket...",
+                "Line 1, column 23: Expected colon, found number...",
+                "Invalid literal 'tru'...",
+            ],
+            errors.iter().map(|e| e.to_string()).collect::<Vec<String>>()
+        );
+
+        // errors after a bad literal point at it rather than the start
+        let (_, errors) = parse_recovering(b"[1, tru tru]");
+        assert_eq!(Some(Span { start: 4, end: 7, line: 1, column: 5 }), errors[0].span());
+        assert_eq!("Line 1, column 9: Expected comma or right bracket, found null...", errors[2].to_string());
+
+        let (value, errors) = parse_recovering(b"[1, {\"a\": [2");
+        assert_eq!(json(String::from("[1, {\"a\": [2]}]")).unwrap(), value.unwrap());
+        assert_eq!(vec![JsonError::UnexpectedEndOfInput], errors);
+    }
+
+    #[test]
+    fn trailing_content() {
+        assert_eq!(
+            Err(JsonError::UnexpectedToken(String::from("Unexpected content after document..."), Span { start: 3, end: 4, line: 1, column: 4 })),
+            json(String::from("{} ["))
+        );
+        assert!(json(String::from("{} garbage")).is_err());
+        assert!(json(String::from("{} \n\t")).is_ok());
+
+        let input = b"[1, 2] {\"a\": 3}";
+        let (first, used) = parse_partial(input).unwrap();
+        assert_eq!(json(String::from("[1, 2]")).unwrap(), first);
+        assert_eq!(6, used);
+        assert_eq!((json(String::from("{\"a\": 3}")).unwrap(), 9), parse_partial(&input[used..]).unwrap());
+    }
+
+    #[test]
+    fn embedded_values() {
+        let line = "{\"level\": \"warn\", \"msg\": \"disk é\"} took 12ms";
+        let (value, used) = parse_prefix(line).unwrap();
+        assert_eq!(json(String::from("{\"level\": \"warn\", \"msg\": \"disk é\"}")).unwrap(), value);
+        assert_eq!(" took 12ms", &line[used..]);
+
+        assert_eq!((JsonExpression::Number(7.0), 3), parse_prefix("  7, 8").unwrap());
+        assert!(parse_prefix("{\"a\": ").is_err());
+    }
+
+    #[test]
+    fn scalar_root() {
+        assert_eq!(Ok(JsonExpression::Number(42.0)), json(String::from("42")));
+        assert_eq!(Ok(JsonExpression::String(String::from("hi"))), json(String::from(" \"hi\" ")));
+        assert_eq!(Ok(JsonExpression::Null), json(String::from("null")));
+
+        let strict = ParserOptions { scalar_root: false, ..ParserOptions::default() };
+        assert!(from_slice_with(b"42", &strict).is_err());
+        assert!(from_slice_with(b"[42]", &strict).is_ok());
+    }
+
+    #[test]
+    fn option_presets() {
+        let relaxed = "// settings\n{\"a\": [1, 2,], /* old: 3 */ \"b\": true,}";
+        assert!(json(String::from(relaxed)).is_err());
+        assert_eq!(json(String::from("{\"a\": [1, 2], \"b\": true}")), json_with(String::from(relaxed), &ParserOptions::lenient()));
+        assert_eq!(
+            Err(JsonError::UnexpectedToken(String::from("Unterminated comment..."), Span { start: 4, end: 11, line: 1, column: 5 })),
+            json_with(String::from("[1] /* open"), &ParserOptions::lenient())
+        );
+        assert!(json_with(String::from("[1,,]"), &ParserOptions::lenient()).is_err());
+
+        let repeated = String::from("{\"a\": 1, \"b\": 2, \"a\": 3}");
+        assert!(json(repeated.clone()).is_ok());
+        assert_eq!(
+            Err(JsonError::UnexpectedToken(String::from("Duplicate key \"a\"..."), Span { start: 17, end: 20, line: 1, column: 18 })),
+            json_with(repeated, &ParserOptions::strict())
+        );
+
+        let nested = "[".repeat(129) + &"]".repeat(129);
+        assert!(json(nested.clone()).is_ok());
+        assert!(matches!(json_with(nested, &ParserOptions::untrusted()), Err(JsonError::UnexpectedToken(message, _)) if message == "Maximum nesting depth exceeded..."));
+        assert!(json_with(String::from("{\"a\": {\"b\": []}}"), &ParserOptions::default().max_depth(Some(3))).is_ok());
+        assert!(json_with(String::from("{\"a\": {\"b\": []}}"), &ParserOptions::default().max_depth(Some(2))).is_err());
+    }
+
+    #[test]
+    fn control_characters() {
+        let raw = String::from("{\"a\tb\": \"line\nbreak\"}");
+        assert_eq!(
+            Err(JsonError::UnexpectedToken(String::from("Unescaped control character in string..."), Span { start: 3, end: 4, line: 1, column: 4 })),
+            json(raw.clone())
+        );
+        assert!(validate(&raw).is_err());
+
+        let parsed = json_with(raw, &ParserOptions::lenient()).unwrap();
+        assert_eq!(Some(&JsonExpression::from("line\nbreak")), parsed.get_path("a\tb"));
+        assert_eq!("{\"a\\tb\":\"line\\nbreak\"}", parsed.to_string());
+    }
+
+    #[test]
+    fn lossy_utf8() {
+        let mangled: &[u8] = b"{\"caf\xe9\": \"na\xefve \xf0\x9f\"}";
+        assert_eq!(Err(JsonError::InvalidUtf8(Span { start: 5, end: 6, line: 1, column: 6 })), from_slice(mangled));
+        assert_eq!(
+            json(String::from("{\"caf\u{fffd}\": \"na\u{fffd}ve \u{fffd}\"}")),
+            from_slice_with(mangled, &ParserOptions::default().lossy_utf8(true))
+        );
+    }
+
+    #[test]
+    fn single_quotes() {
+        let quoted = String::from("{'name': 'O\\'Brien', \"tags\": ['a \"b\"']}");
+        assert!(json(quoted.clone()).is_err());
+        assert_eq!(
+            json(String::from("{\"name\": \"O'Brien\", \"tags\": [\"a \\\"b\\\"\"]}")),
+            json_with(quoted, &ParserOptions::lenient())
+        );
+    }
+
+    #[test]
+    fn unquoted_keys() {
+        let literal = String::from("{name: 'x', _id: 1, nested: {true: false}}");
+        assert!(json(literal.clone()).is_err());
+        assert_eq!(
+            json(String::from("{\"name\": \"x\", \"_id\": 1, \"nested\": {\"true\": false}}")),
+            json_with(literal, &ParserOptions::lenient())
+        );
+    }
+
+    #[test]
+    fn reused_parser() {
+        let mut parser = JsonParser::new(ParserOptions::strict());
+        assert_eq!(json(String::from("{\"a\": [1, {\"b\": 2}]}")), parser.parse(b"{\"a\": [1, {\"b\": 2}]}"));
+        // a failure part way through leaves nothing behind for the next parse
+        assert!(parser.parse(b"[[1, 2], {\"a\": 1, \"a\": 2}]").is_err());
+        assert_eq!(json(String::from("[[], {}]")), parser.parse(b"[[], {}]"));
+        assert_eq!(Ok(JsonExpression::Null), parser.parse(b"\xff\xfen\0u\0l\0l\0"));
+    }
+
+    #[test]
+    fn containers_sized_exactly() {
+        let expr = json(String::from("{\"a\": [1, [2, 3, 4], 5, 6, 7], \"b\": {\"c\": null}}")).unwrap();
+        let JsonExpression::Object(members) = &expr else { panic!() };
+        assert_eq!(members.len(), members.capacity());
+        let JsonExpression::Array(elements) = members[0].1.as_ref() else { panic!() };
+        assert_eq!(5, elements.capacity());
+        assert_eq!(&JsonExpression::Array(vec![Box::new(JsonExpression::Number(2.0)), Box::new(JsonExpression::Number(3.0)), Box::new(JsonExpression::Number(4.0))]), elements[1].as_ref());
+    }
+}
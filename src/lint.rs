@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::JsonError;
+use crate::lexer::{Span, Spanned, Token};
+use crate::options::ParserOptions;
+use crate::spanned::{parse_spanned_with, SpannedValue};
+use crate::{lexer_for, transcode};
+
+// Checks for things that parse but are likely mistakes or will cause trouble
+// elsewhere. Each rule can be turned off or given its own severity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    // a key repeated within one object
+    DuplicateKeys,
+    // a key written in a different style, e.g. camelCase among snake_case
+    KeyCasing,
+    // a number with more digits than an f64 can hold
+    PrecisionLoss,
+    // containers nested deeper than the configured limit
+    Nesting,
+    // a comma before a closing bracket or brace
+    TrailingCommas
+}
+
+impl Rule {
+    pub const ALL: [Rule; 5] = [Rule::DuplicateKeys, Rule::KeyCasing, Rule::PrecisionLoss, Rule::Nesting, Rule::TrailingCommas];
+
+    // Stable name for tools and command line flags.
+    pub fn code(self) -> &'static str {
+        match self {
+            Rule::DuplicateKeys => return "duplicate-key",
+            Rule::KeyCasing => return "key-casing",
+            Rule::PrecisionLoss => return "precision-loss",
+            Rule::Nesting => return "nesting",
+            Rule::TrailingCommas => return "trailing-comma"
+        }
+    }
+
+    pub fn parse(code: &str) -> Result<Rule, String> {
+        return Rule::ALL.into_iter().find(|rule| rule.code() == code).ok_or_else(|| format!("Unknown lint rule {}...", code));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => return write!(f, "info"),
+            Severity::Warning => return write!(f, "warning"),
+            Severity::Error => return write!(f, "error")
+        }
+    }
+}
+
+// Which rules run and how seriously their findings are taken, None turns a
+// rule off. The input is parsed with `options`, which allow trailing commas
+// by default so that rule has something to report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LintConfig {
+    pub options: ParserOptions,
+    pub duplicate_keys: Option<Severity>,
+    pub key_casing: Option<Severity>,
+    pub precision_loss: Option<Severity>,
+    pub nesting: Option<Severity>,
+    pub trailing_commas: Option<Severity>,
+    // deepest nesting the nesting rule allows
+    pub max_depth: usize
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        return Self {
+            options: ParserOptions::default().trailing_commas(true),
+            duplicate_keys: Some(Severity::Error),
+            key_casing: Some(Severity::Info),
+            precision_loss: Some(Severity::Warning),
+            nesting: Some(Severity::Warning),
+            trailing_commas: Some(Severity::Warning),
+            max_depth: 32
+        };
+    }
+}
+
+impl LintConfig {
+    pub fn severity(&self, rule: Rule) -> Option<Severity> {
+        match rule {
+            Rule::DuplicateKeys => return self.duplicate_keys,
+            Rule::KeyCasing => return self.key_casing,
+            Rule::PrecisionLoss => return self.precision_loss,
+            Rule::Nesting => return self.nesting,
+            Rule::TrailingCommas => return self.trailing_commas
+        }
+    }
+
+    pub fn rule(mut self, rule: Rule, severity: Option<Severity>) -> Self {
+        match rule {
+            Rule::DuplicateKeys => self.duplicate_keys = severity,
+            Rule::KeyCasing => self.key_casing = severity,
+            Rule::PrecisionLoss => self.precision_loss = severity,
+            Rule::Nesting => self.nesting = severity,
+            Rule::TrailingCommas => self.trailing_commas = severity
+        }
+        return self;
+    }
+
+    pub fn options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        return self;
+    }
+
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        return self;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span
+}
+
+// Every finding in the input in the order they appear. Input that does not
+// parse with the configured options is an error rather than a finding.
+//
+// The tree is only built one level past max_depth, so input nested deeper
+// than that cannot overflow the stack. It gets a nesting finding and trailing
+// commas are still checked, the other rules need the tree and are skipped.
+pub fn lint(bytes: &[u8], config: &LintConfig) -> Result<Vec<Finding>, JsonError> {
+    let bytes = transcode(bytes)?;
+    let bound = config.max_depth + 1;
+    let options = match config.options.max_depth {
+        Some(max) if max <= bound => config.options,
+        _ => config.options.max_depth(Some(bound))
+    };
+
+    let root = parse_spanned_with(&bytes, &options);
+
+    let mut linter = Linter { source: &bytes, config, findings: Vec::new(), keys: Vec::new() };
+    match &root {
+        Ok(root) => {
+            linter.walk(root, 0);
+            linter.key_casing();
+        },
+        Err(e) => match linter.too_deep() {
+            Some((container, blamed)) if e.span() == Some(blamed) => linter.nesting(container),
+            _ => return Err(e.clone())
+        }
+    }
+    linter.trailing_commas();
+
+    let mut findings = linter.findings;
+    findings.sort_by_key(|finding| finding.span.start);
+    return Ok(findings);
+}
+
+struct Linter<'a> {
+    source: &'a [u8],
+    config: &'a LintConfig,
+    findings: Vec<Finding>,
+    // every key with the casing it was written in, for key_casing
+    keys: Vec<(&'a Spanned<String>, Casing)>
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: Rule, message: String, span: Span) {
+        if let Some(severity) = self.config.severity(rule) {
+            self.findings.push(Finding { rule, severity, message, span });
+        }
+    }
+
+    fn nesting(&mut self, span: Span) {
+        self.report(Rule::Nesting, format!("Nested deeper than the limit of {}...", self.config.max_depth), span);
+    }
+
+    // The first container at max_depth that holds another container, and
+    // that inner container, which is where the bounded parse gave up. Past
+    // that point the input is only checked for tokens and matching brackets,
+    // None when those are wrong so the parse error is returned instead.
+    fn too_deep(&self) -> Option<(Span, Span)> {
+        let mut open: Vec<(Token, Span)> = Vec::new();
+        let mut found = None;
+        for token in lexer_for(self.source, &self.config.options) {
+            let token = token.ok()?;
+            match token.value {
+                Token::LeftBracket | Token::LeftBrace => {
+                    if open.len() > self.config.max_depth && found.is_none() {
+                        found = Some((open[self.config.max_depth].1, token.span));
+                    }
+                    let close = if token.value == Token::LeftBracket { Token::RightBracket } else { Token::RightBrace };
+                    open.push((close, token.span));
+                },
+                Token::RightBracket | Token::RightBrace => {
+                    open.pop().filter(|(close, _)| *close == token.value)?;
+                },
+                _ => {}
+            }
+        }
+
+        if !open.is_empty() {
+            return None;
+        }
+        return found;
+    }
+
+    // depth is how many containers hold this value
+    fn walk(&mut self, value: &'a Spanned<SpannedValue>, depth: usize) {
+        if matches!(value.value, SpannedValue::Array(_) | SpannedValue::Object(_)) && depth == self.config.max_depth {
+            // the rest of the subtree is only more of the same
+            self.nesting(value.span);
+            return;
+        }
+
+        match &value.value {
+            SpannedValue::Number(n) => self.precision_loss(*n, value.span),
+            SpannedValue::Array(elements) => {
+                for element in elements {
+                    self.walk(element, depth + 1);
+                }
+            },
+            SpannedValue::Object(members) => {
+                // where each key first appears
+                let mut seen: HashMap<&str, &Span> = HashMap::new();
+                for (key, member) in members {
+                    let first = seen.entry(key.value.as_str()).or_insert(&key.span);
+                    if *first != &key.span {
+                        let message = format!("Key \"{}\" is repeated, it first appears on line {}...", key.value, first.line);
+                        self.report(Rule::DuplicateKeys, message, key.span);
+                    }
+                    if let Some(casing) = Casing::of(&key.value) {
+                        self.keys.push((key, casing));
+                    }
+                    self.walk(member, depth + 1);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // The literal is compared with the shortest digits that give back the
+    // same f64, any digit that differs was lost.
+    fn precision_loss(&mut self, n: f64, span: Span) {
+        let literal = String::from_utf8_lossy(&self.source[span.start..span.end]);
+        let kept = if n.is_finite() { ryu::Buffer::new().format_finite(n).to_owned() } else { String::from("inf") };
+        if !n.is_finite() || significant_digits(&literal) != significant_digits(&kept) {
+            self.report(Rule::PrecisionLoss, format!("Number {} cannot be held exactly and is read as {}...", literal, kept), span);
+        }
+    }
+
+    // Keys not in the style most keys in the document use. Single lowercase
+    // words fit every style and are never reported.
+    fn key_casing(&mut self) {
+        let mut counts: Vec<(Casing, usize)> = Vec::new();
+        for (_, casing) in &self.keys {
+            match counts.iter_mut().find(|(c, _)| c == casing) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((*casing, 1))
+            }
+        }
+        // ties go to the style seen first
+        let Some(&(usual, _)) = counts.iter().rev().max_by_key(|(_, count)| *count) else { return };
+
+        for (key, casing) in std::mem::take(&mut self.keys) {
+            if casing != usual {
+                self.report(Rule::KeyCasing, format!("Key \"{}\" is {} but most keys are {}...", key.value, casing.name(), usual.name()), key.span);
+            }
+        }
+    }
+
+    fn trailing_commas(&mut self) {
+        if self.config.trailing_commas.is_none() {
+            return;
+        }
+
+        let mut comma: Option<Span> = None;
+        // the input already parsed, so lexing cannot fail
+        for token in lexer_for(self.source, &self.config.options).map_while(Result::ok) {
+            match token.value {
+                Token::RightBracket | Token::RightBrace if comma.is_some() => {
+                    self.report(Rule::TrailingCommas, String::from("Trailing comma..."), comma.unwrap());
+                },
+                _ => {}
+            }
+            comma = if token.value == Token::Comma { Some(token.span) } else { None };
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Casing {
+    Camel,
+    Pascal,
+    Snake,
+    ScreamingSnake,
+    Kebab
+}
+
+impl Casing {
+    // None for single lowercase words and anything that fits no style.
+    fn of(key: &str) -> Option<Casing> {
+        if !key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return None;
+        }
+        let lower = key.chars().any(char::is_lowercase);
+        let upper = key.chars().any(char::is_uppercase);
+
+        match (key.contains('_'), key.contains('-')) {
+            (true, false) if !upper => return Some(Casing::Snake),
+            (true, false) if !lower => return Some(Casing::ScreamingSnake),
+            (false, true) if !upper => return Some(Casing::Kebab),
+            (false, false) if lower && upper => {
+                if key.starts_with(char::is_uppercase) {
+                    return Some(Casing::Pascal);
+                }
+                return Some(Casing::Camel);
+            },
+            _ => return None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Casing::Camel => return "camelCase",
+            Casing::Pascal => return "PascalCase",
+            Casing::Snake => return "snake_case",
+            Casing::ScreamingSnake => return "SCREAMING_SNAKE_CASE",
+            Casing::Kebab => return "kebab-case"
+        }
+    }
+}
+
+// The digits of a number literal without sign, point, exponent or the zeros
+// around them, e.g. "1200" and "0.0012e6" both give "12".
+fn significant_digits(literal: &str) -> String {
+    let mantissa = literal.split(['e', 'E']).next().unwrap_or_default();
+    let digits: String = mantissa.chars().filter(char::is_ascii_digit).collect();
+    return String::from(digits.trim_start_matches('0').trim_end_matches('0'));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lint::{lint, LintConfig, Rule, Severity};
+    use crate::Span;
+
+    fn rules(source: &str, config: &LintConfig) -> Vec<(Rule, usize)> {
+        return lint(source.as_bytes(), config).unwrap().into_iter().map(|f| (f.rule, f.span.start)).collect();
+    }
+
+    #[test]
+    fn finds_problems() {
+        let source = "{\"user_id\": 9007199254740993, \"user_id\": 0.1, \"createdAt\": [1, 2,], \"name\": 1e400}";
+        let findings = lint(source.as_bytes(), &LintConfig::default()).unwrap();
+        assert_eq!(
+            vec![(Rule::PrecisionLoss, 12), (Rule::DuplicateKeys, 30), (Rule::KeyCasing, 46), (Rule::TrailingCommas, 64), (Rule::PrecisionLoss, 76)],
+            findings.iter().map(|f| (f.rule, f.span.start)).collect::<Vec<_>>()
+        );
+        assert_eq!("Number 9007199254740993 cannot be held exactly and is read as 9007199254740992.0...", findings[0].message);
+        assert_eq!(Severity::Error, findings[1].severity);
+        assert_eq!("Key \"createdAt\" is camelCase but most keys are snake_case...", findings[2].message);
+    }
+
+    #[test]
+    fn leaves_good_input_alone() {
+        let source = "{\"id\": 1234567890123456, \"ratio\": 0.30000000000000004, \"tags\": [\"a\"], \"big\": 1.5e300, \"n\": -0.0}";
+        assert_eq!(Vec::<(Rule, usize)>::new(), rules(source, &LintConfig::default()));
+    }
+
+    #[test]
+    fn configurable() {
+        let nested = "[[[1]], [2,]]";
+        assert_eq!(vec![(Rule::Nesting, 2), (Rule::TrailingCommas, 10)], rules(nested, &LintConfig::default().max_depth(2)));
+        assert_eq!(vec![(Rule::Nesting, 2)], rules(nested, &LintConfig::default().max_depth(2).rule(Rule::TrailingCommas, None)));
+
+        let findings = lint(b"{\"a\": 1, \"a\": 2}", &LintConfig::default().rule(Rule::DuplicateKeys, Some(Severity::Info))).unwrap();
+        assert_eq!((Severity::Info, Span { start: 9, end: 12, line: 1, column: 10 }), (findings[0].severity, findings[0].span));
+
+        assert!(lint(b"[1,]", &LintConfig::default().options(crate::ParserOptions::default())).is_err());
+        assert_eq!(Ok(Rule::KeyCasing), Rule::parse("key-casing"));
+        assert!(Rule::parse("tabs").is_err());
+    }
+
+    #[test]
+    fn deep_input() {
+        // past the limit the tree is not built, trailing commas are still found
+        let deep = format!("[[1,], {}{}]", "[".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(vec![(Rule::TrailingCommas, 3), (Rule::Nesting, 7)], rules(&deep, &LintConfig::default().max_depth(1)));
+        assert_eq!(vec![(Rule::Nesting, 32)], rules(&format!("{}{}", "[".repeat(100_000), "]".repeat(100_000)), &LintConfig::default()));
+        assert!(lint("[".repeat(100_000).as_bytes(), &LintConfig::default()).is_err());
+        // a tighter limit in the parser options is still an error
+        assert!(lint(b"[[[[]]]]", &LintConfig::default().max_depth(2).options(crate::ParserOptions::default().max_depth(Some(2)))).is_err());
+        assert!(lint(b"[[[[]]]", &LintConfig::default().max_depth(2)).is_err());
+
+        let many = format!("{{{}\"k0\": 0}}", (0..20_000).map(|i| format!("\"k{}\": 0, ", i)).collect::<String>());
+        assert_eq!(vec![(Rule::DuplicateKeys, many.len() - 8)], rules(&many, &LintConfig::default()));
+    }
+}
@@ -0,0 +1,45 @@
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{from_slice, JsonError, JsonExpression};
+
+// Maps the file into memory and lexes straight from the mapping, so the raw
+// document is never copied onto the heap. Only the parsed value is allocated.
+// The file must not be modified by another process while it is being parsed.
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<JsonExpression, JsonError> {
+    let path = path.as_ref();
+    let file = File::open(path).map_err(|e| JsonError::Io(format!("Could not open {}: {}...", path.display(), e)))?;
+
+    // mapping a zero length file fails on some platforms
+    if file.metadata().map(|m| m.len() == 0).unwrap_or(false) {
+        return Err(JsonError::UnexpectedEndOfInput);
+    }
+
+    // Safety: the mapping is only read for the duration of this call.
+    let mapping = unsafe { Mmap::map(&file) }.map_err(|e| JsonError::Io(format!("Could not map {}: {}...", path.display(), e)))?;
+    return from_slice(&mapping);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{from_file, json, JsonError};
+
+    #[test]
+    fn parses_mapped_file() {
+        let path = std::env::temp_dir().join(format!("jsonrs-mmap-{}.json", std::process::id()));
+        fs::write(&path, "{\"a\": [1, 2, {\"b\": null}]}").unwrap();
+
+        let parsed = from_file(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(json(String::from("{\"a\": [1, 2, {\"b\": null}]}")), parsed);
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(matches!(from_file("/nonexistent/jsonrs.json"), Err(JsonError::Io(_))));
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{BufRead, Split};
+
+use rayon::prelude::*;
+
+use crate::{from_slice_with, JsonError, JsonExpression, ParserOptions};
+
+// Lines are read in batches of this size and each batch is parsed across the
+// rayon thread pool before any of its results are handed out.
+const BATCH_SIZE: usize = 1024;
+
+// JSON Lines input parsed on multiple threads. Results come back in input
+// order, blank lines are skipped and a trailing \r is ignored. Lines are read
+// as bytes, so one that is not UTF-8 is an error for that record alone.
+pub struct ParLines<R: BufRead> {
+    lines: Split<R>,
+    batch: std::vec::IntoIter<Result<JsonExpression, JsonError>>,
+    done: bool,
+    options: ParserOptions
+}
+
+pub fn par_lines<R: BufRead>(reader: R) -> ParLines<R> {
+    return ParLines {
+        lines: reader.split(b'\n'),
+        batch: Vec::new().into_iter(),
+        done: false,
+        options: ParserOptions::default()
+    };
+}
+
+impl<R: BufRead> ParLines<R> {
+    // Replaces invalid UTF-8 in strings with U+FFFD, so a record with a
+    // mangled byte is still read.
+    pub fn lossy_utf8(mut self, on: bool) -> Self {
+        self.options.lossy_utf8 = on;
+        return self;
+    }
+
+    fn fill(&mut self) {
+        let mut lines: Vec<Vec<u8>> = Vec::with_capacity(BATCH_SIZE);
+        let mut read_error: Option<JsonError> = None;
+
+        while lines.len() < BATCH_SIZE {
+            match self.lines.next() {
+                Some(Ok(line)) if line.iter().all(u8::is_ascii_whitespace) => {},
+                Some(Ok(line)) => lines.push(line),
+                Some(Err(e)) => {
+                    read_error = Some(JsonError::Io(format!("Could not read line: {}...", e)));
+                    self.done = true;
+                    break;
+                },
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+
+        let mut results: Vec<Result<JsonExpression, JsonError>> = lines.par_iter()
+            .map(|line| from_slice_with(line.strip_suffix(b"\r").unwrap_or(line), &self.options))
+            .collect();
+
+        if let Some(e) = read_error {
+            results.push(Err(e));
+        }
+
+        self.batch = results.into_iter();
+    }
+}
+
+impl<R: BufRead> Iterator for ParLines<R> {
+    type Item = Result<JsonExpression, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.batch.next() {
+                return Some(result);
+            }
+            if self.done {
+                return None;
+            }
+            self.fill();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ndjson::par_lines;
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn keeps_input_order() {
+        let input: String = (0..5000).map(|i| format!("{{\"n\": {}}}\n", i)).collect();
+        let parsed: Vec<JsonExpression> = par_lines(input.as_bytes()).map(|r| r.unwrap()).collect();
+
+        assert_eq!(5000, parsed.len());
+        for (i, expr) in parsed.iter().enumerate() {
+            assert_eq!(json(format!("{{\"n\": {}}}", i)).unwrap(), *expr);
+        }
+    }
+
+    #[test]
+    fn skips_blank_lines_and_reports_errors() {
+        let input = "[1]\r\n\n{\"a\" 1}\n[2]";
+        let results: Vec<_> = par_lines(input.as_bytes()).collect();
+        assert_eq!(3, results.len());
+        assert_eq!(json(String::from("[1]")), results[0]);
+        assert!(results[1].is_err());
+        assert_eq!(json(String::from("[2]")), results[2]);
+    }
+
+    #[test]
+    fn lossy_lines() {
+        let input: &[u8] = b"[\"a\xc3\"]\n[1]\n";
+        let results: Vec<_> = par_lines(input).collect();
+        assert!(results[0].is_err());
+        assert_eq!(json(String::from("[1]")), results[1]);
+        let results: Vec<_> = par_lines(input).lossy_utf8(true).collect();
+        assert_eq!(json(String::from("[\"a\u{fffd}\"]")), results[0]);
+    }
+}
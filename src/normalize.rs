@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use unicode_normalization::{is_nfc_quick, is_nfd_quick, IsNormalized, UnicodeNormalization};
+
+use crate::JsonExpression;
+
+// Unicode normalization forms. Text from different sources can spell the same
+// character composed (é as U+00E9) or decomposed (e followed by U+0301), which
+// compare unequal until both are brought to one form.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+    // composed, what most text on the web already is
+    Nfc,
+    // decomposed
+    Nfd
+}
+
+impl Normalization {
+    // The string in this form. Most strings already are, which a quick check
+    // finds without allocating.
+    pub fn normalize(self, s: &str) -> Cow<'_, str> {
+        match self {
+            Normalization::Nfc if is_nfc_quick(s.chars()) == IsNormalized::Yes => return Cow::Borrowed(s),
+            Normalization::Nfd if is_nfd_quick(s.chars()) == IsNormalized::Yes => return Cow::Borrowed(s),
+            Normalization::Nfc => return Cow::Owned(s.nfc().collect()),
+            Normalization::Nfd => return Cow::Owned(s.nfd().collect())
+        }
+    }
+
+    // normalize for a string that is owned already, which is kept when it
+    // needs no change.
+    pub fn apply(self, s: String) -> String {
+        match self.normalize(&s) {
+            Cow::Borrowed(_) => return s,
+            Cow::Owned(normalized) => return normalized
+        }
+    }
+}
+
+impl JsonExpression {
+    // Brings every object key in the tree to the given form, for trees that
+    // were not parsed with normalize_keys.
+    pub fn normalize_keys(&mut self, form: Normalization) {
+        match self {
+            JsonExpression::Array(elements) => elements.iter_mut().for_each(|e| e.normalize_keys(form)),
+            JsonExpression::Object(pairs) => {
+                for (key, value) in pairs.iter_mut() {
+                    *key = form.apply(std::mem::take(key));
+                    value.normalize_keys(form);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // A copy with every key and string in the given form.
+    pub fn normalized(&self, form: Normalization) -> JsonExpression {
+        let mut copy = self.clone();
+        copy.normalize_keys(form);
+        copy.normalize_strings(form);
+        return copy;
+    }
+
+    // Brings every string value in the tree to the given form, keys are left
+    // as they are. Doing both before hashing or comparing makes the result
+    // independent of how the sources composed their text.
+    pub fn normalize_strings(&mut self, form: Normalization) {
+        match self {
+            JsonExpression::String(s) => *s = form.apply(std::mem::take(s)),
+            JsonExpression::Array(elements) => elements.iter_mut().for_each(|e| e.normalize_strings(form)),
+            JsonExpression::Object(pairs) => pairs.iter_mut().for_each(|(_, value)| value.normalize_strings(form)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::normalize::Normalization;
+    use crate::writer::JsonWriter;
+    use crate::{json, json_with, JsonExpression, ParserOptions};
+
+    #[test]
+    fn normalizes_keys() {
+        // the same key composed, decomposed and escaped
+        let source = String::from("{\"caf\u{e9}\": 1, \"cafe\u{301}\": 2, \"caf\\u00e9\": 3}");
+        let plain = json(source.clone()).unwrap();
+        assert_eq!(2, plain.entries().filter(|(k, _)| *k == "caf\u{e9}").count());
+
+        let options = ParserOptions::default().normalize_keys(Some(Normalization::Nfc));
+        let parsed = json_with(source.clone(), &options).unwrap();
+        assert_eq!(vec!["caf\u{e9}"; 3], parsed.entries().map(|(k, _)| k).collect::<Vec<&str>>());
+        assert_eq!(Some(&JsonExpression::Number(3.0)), parsed.get_path("caf\u{e9}"));
+        assert!(json_with(source, &options.duplicate_keys(false)).is_err());
+
+        let mut decomposed = plain.clone();
+        decomposed.normalize_keys(Normalization::Nfd);
+        assert!(decomposed.entries().all(|(k, _)| k == "cafe\u{301}"));
+    }
+
+    #[test]
+    fn normalizes_strings() {
+        let source = String::from("[\"cafe\u{301}\", {\"cafe\u{301}\": \"caf\u{e9}\"}]");
+        let options = ParserOptions::default().normalize_strings(Some(Normalization::Nfc));
+        let parsed = json_with(source.clone(), &options).unwrap();
+        assert_eq!(json(String::from("[\"caf\u{e9}\", {\"cafe\u{301}\": \"caf\u{e9}\"}]")).unwrap(), parsed);
+
+        let mut tree = json(source).unwrap();
+        tree.normalize_strings(Normalization::Nfd);
+        assert_eq!(json(String::from("[\"cafe\u{301}\", {\"cafe\u{301}\": \"cafe\u{301}\"}]")).unwrap(), tree);
+
+        let mut writer = JsonWriter::new(Vec::new()).normalization(Some(Normalization::Nfc));
+        writer.start_object().unwrap();
+        writer.key("cafe\u{301}").unwrap();
+        writer.value(&JsonExpression::from("cafe\u{301}")).unwrap();
+        writer.end_object().unwrap();
+        assert_eq!("{\"caf\u{e9}\":\"caf\u{e9}\"}".as_bytes(), writer.finish().unwrap().as_slice());
+    }
+}
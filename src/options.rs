@@ -0,0 +1,148 @@
+#[cfg(feature = "unicode")]
+use crate::normalize::Normalization;
+
+// Knobs for how strictly input is parsed. The defaults follow RFC 8259 and
+// leave the rest permissive the way the parser always was: duplicate keys are
+// kept and nesting is unbounded. The presets cover the usual cases and each
+// knob also has a chaining setter, e.g. `ParserOptions::strict().max_depth(Some(32))`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParserOptions {
+    // allow a bare string, number, boolean or null as the whole document,
+    // when false it has to be an object or array as RFC 4627 required
+    pub scalar_root: bool,
+    // cache object keys as they are lexed so repeated keys are copied from
+    // the cache rather than decoded again, worth turning off when keys are
+    // mostly unique
+    pub intern_keys: bool,
+    // skip `// line` and `/* block */` comments wherever whitespace may go
+    pub comments: bool,
+    // allow a comma before the closing bracket or brace
+    pub trailing_commas: bool,
+    // allow raw control characters such as tabs and newlines inside strings
+    // rather than only their escapes
+    pub control_characters: bool,
+    // replace invalid UTF-8 in strings, and \u escapes of unpaired
+    // surrogates, with U+FFFD instead of failing
+    pub lossy_utf8: bool,
+    // allow strings in single quotes, as JavaScript writes them
+    pub single_quotes: bool,
+    // allow object keys written as bare identifiers, `{foo: 1}`
+    pub unquoted_keys: bool,
+    // allow a key to appear more than once in an object, when false a repeat
+    // is an error
+    pub duplicate_keys: bool,
+    // deepest nesting of arrays and objects allowed, None for no limit
+    pub max_depth: Option<usize>,
+    // bring object keys to one normalization form as they are parsed, so
+    // keys that differ only in how their characters are composed are the
+    // same key for lookups and duplicate checks
+    #[cfg(feature = "unicode")]
+    pub normalize_keys: Option<Normalization>,
+    // the same for string values
+    #[cfg(feature = "unicode")]
+    pub normalize_strings: Option<Normalization>
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        return Self {
+            scalar_root: true,
+            intern_keys: true,
+            comments: false,
+            trailing_commas: false,
+            control_characters: false,
+            lossy_utf8: false,
+            single_quotes: false,
+            unquoted_keys: false,
+            duplicate_keys: true,
+            max_depth: None,
+            #[cfg(feature = "unicode")]
+            normalize_keys: None,
+            #[cfg(feature = "unicode")]
+            normalize_strings: None
+        };
+    }
+}
+
+impl ParserOptions {
+    // Exactly RFC 8259, numbers and escapes included, and a repeated key is
+    // rejected rather than silently resolved.
+    pub fn strict() -> Self {
+        return Self { duplicate_keys: false, ..Self::default() };
+    }
+
+    // For hand written files such as config: comments, trailing commas,
+    // single quoted strings, unquoted keys and raw tabs or newlines in
+    // strings.
+    pub fn lenient() -> Self {
+        return Self { comments: true, trailing_commas: true, control_characters: true, single_quotes: true, unquoted_keys: true, ..Self::default() };
+    }
+
+    // For input from outside, strict and with nesting bounded so a deeply
+    // nested document cannot exhaust the stack.
+    pub fn untrusted() -> Self {
+        return Self { max_depth: Some(128), ..Self::strict() };
+    }
+
+    pub fn scalar_root(mut self, on: bool) -> Self {
+        self.scalar_root = on;
+        return self;
+    }
+
+    pub fn intern_keys(mut self, on: bool) -> Self {
+        self.intern_keys = on;
+        return self;
+    }
+
+    pub fn comments(mut self, on: bool) -> Self {
+        self.comments = on;
+        return self;
+    }
+
+    pub fn trailing_commas(mut self, on: bool) -> Self {
+        self.trailing_commas = on;
+        return self;
+    }
+
+    pub fn control_characters(mut self, on: bool) -> Self {
+        self.control_characters = on;
+        return self;
+    }
+
+    pub fn lossy_utf8(mut self, on: bool) -> Self {
+        self.lossy_utf8 = on;
+        return self;
+    }
+
+    pub fn single_quotes(mut self, on: bool) -> Self {
+        self.single_quotes = on;
+        return self;
+    }
+
+    pub fn unquoted_keys(mut self, on: bool) -> Self {
+        self.unquoted_keys = on;
+        return self;
+    }
+
+    pub fn duplicate_keys(mut self, on: bool) -> Self {
+        self.duplicate_keys = on;
+        return self;
+    }
+
+    pub fn max_depth(mut self, max: Option<usize>) -> Self {
+        self.max_depth = max;
+        return self;
+    }
+
+    #[cfg(feature = "unicode")]
+    pub fn normalize_keys(mut self, form: Option<Normalization>) -> Self {
+        self.normalize_keys = form;
+        return self;
+    }
+
+    #[cfg(feature = "unicode")]
+    pub fn normalize_strings(mut self, form: Option<Normalization>) -> Self {
+        self.normalize_strings = form;
+        return self;
+    }
+}
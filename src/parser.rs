@@ -0,0 +1,397 @@
+use std::collections::HashSet;
+
+use crate::error::JsonError;
+use crate::lexer::{Span, Spanned, Token, TokenSource};
+use crate::options::ParserOptions;
+use crate::{lexer_for, transcode, JsonExpression};
+
+// Pulls tokens from the source as it needs them, holding at most one token of
+// lookahead, so lexing and parsing happen in a single pass.
+//
+// In recovery mode errors are collected in `errors` instead of ending the
+// parse. The parser skips to the next comma or closing bracket at the same
+// depth and carries on, so the result is a best effort tree of everything
+// that did parse.
+//
+// Elements and members of the containers being parsed collect on shared
+// stacks and are moved into a Vec of exactly the right size once the
+// container closes, so a container costs one allocation rather than one per
+// doubling of its capacity. This stands in for inline small strings and small
+// vectors, which would change the String and Vec payloads of JsonExpression
+// that callers match on.
+pub struct Parser<S: TokenSource> {
+    source: S,
+    options: ParserOptions,
+    peeked: Option<Spanned<Token>>,
+    recover: bool,
+    // byte offset just past the last token taken
+    consumed: usize,
+    // containers currently open
+    depth: usize,
+    elements: Vec<Box<JsonExpression>>,
+    members: Vec<(String, Box<JsonExpression>)>,
+    pub errors: Vec<JsonError>,
+}
+
+// The parser's working memory, kept by JsonParser between parses so the
+// stacks keep the capacity they grew to.
+#[derive(Default)]
+pub(crate) struct Scratch {
+    elements: Vec<Box<JsonExpression>>,
+    members: Vec<(String, Box<JsonExpression>)>
+}
+
+impl<S: TokenSource> Parser<S> {
+    pub fn new(source: S, options: ParserOptions) -> Self {
+        return Self { source, options, peeked: None, recover: false, consumed: 0, depth: 0, elements: vec![], members: vec![], errors: vec![] };
+    }
+
+    pub(crate) fn with_scratch(source: S, options: ParserOptions, scratch: Scratch) -> Self {
+        return Self { elements: scratch.elements, members: scratch.members, ..Self::new(source, options) };
+    }
+
+    // Hands the stacks back empty, a failed parse can leave values on them.
+    pub(crate) fn into_scratch(mut self) -> Scratch {
+        self.elements.clear();
+        self.members.clear();
+        return Scratch { elements: self.elements, members: self.members };
+    }
+
+    pub fn recovering(source: S, options: ParserOptions) -> Self {
+        return Self { source, options, peeked: None, recover: true, consumed: 0, depth: 0, elements: vec![], members: vec![], errors: vec![] };
+    }
+
+    // running out of tokens means the input was cut short
+    fn peek(&mut self) -> Result<&Token, JsonError> {
+        if self.peeked.is_none() {
+            match self.source.next_token() {
+                Ok(token) => self.peeked = token,
+                // when recovering a token that fails to lex stands in as null,
+                // in the place of the bad token
+                Err(e) if self.recover => {
+                    let span = e.span().unwrap_or_default();
+                    self.errors.push(e);
+                    self.peeked = Some(Spanned { value: Token::NullLiteral, span });
+                },
+                Err(e) => return Err(e),
+            }
+        }
+        return self.peeked.as_ref().map(|t| &t.value).ok_or(JsonError::UnexpectedEndOfInput);
+    }
+
+    fn next(&mut self) -> Result<Spanned<Token>, JsonError> {
+        self.peek()?;
+        let token = self.peeked.take().unwrap();
+        self.consumed = token.span.end;
+        return Ok(token);
+    }
+
+    fn advance(&mut self) {
+        if let Some(token) = self.peeked.take() {
+            self.consumed = token.span.end;
+        }
+    }
+
+    // error pointing at the peeked token
+    fn error(&self, message: &str) -> JsonError {
+        let span = self.peeked.as_ref().map(|t| t.span).unwrap_or_default();
+        return JsonError::UnexpectedToken(String::from(message), span);
+    }
+
+    // the peeked token is not one of those expected
+    fn unexpected(&self, expected: &str) -> JsonError {
+        match &self.peeked {
+            Some(token) => return JsonError::expected(expected, token),
+            None => return JsonError::UnexpectedEndOfInput
+        }
+    }
+
+    // Hands the error back when not recovering. Otherwise it is recorded and
+    // tokens are skipped up to the next comma or close at this depth.
+    fn recover(&mut self, error: JsonError) -> Result<(), JsonError> {
+        if !self.recover {
+            return Err(error);
+        }
+
+        // every open container runs into the end, only report it once
+        if error == JsonError::UnexpectedEndOfInput {
+            if self.errors.last() != Some(&error) {
+                self.errors.push(error);
+            }
+            return Ok(());
+        }
+
+        self.errors.push(error);
+        let mut depth: usize = 0;
+        loop {
+            match self.peek() {
+                Ok(Token::LeftBrace | Token::LeftBracket) => depth += 1,
+                Ok(Token::RightBrace | Token::RightBracket) if depth > 0 => depth -= 1,
+                Ok(Token::Comma | Token::RightBrace | Token::RightBracket) => return Ok(()),
+                Ok(_) => {},
+                Err(_) => return Ok(())
+            }
+            self.advance();
+        }
+    }
+
+    // Called after each element. Consumes a comma and returns true when
+    // another element follows, or consumes the close and returns false.
+    fn separator(&mut self, close: &Token, expected: &str) -> Result<bool, JsonError> {
+        loop {
+            match self.peek() {
+                Ok(Token::Comma) => {
+                    self.advance();
+                    if self.options.trailing_commas && matches!(self.peek(), Ok(token) if token == close) {
+                        self.advance();
+                        return Ok(false);
+                    }
+                    return Ok(true);
+                },
+                Ok(token) if token == close => {
+                    self.advance();
+                    return Ok(false);
+                },
+                Ok(_) => {
+                    let error = self.unexpected(expected);
+                    self.recover(error)?;
+
+                    // a close for some outer container ends this one too
+                    match self.peek() {
+                        Ok(Token::Comma) => {},
+                        Ok(token) if token == close => {},
+                        _ => return Ok(false)
+                    }
+                },
+                Err(e) => {
+                    self.recover(e)?;
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    // The whole input has to be a single document, anything after it other
+    // than whitespace is an error.
+    pub fn parse(&mut self) -> Result<JsonExpression, JsonError> {
+        let root = self.parse_root()?;
+
+        match self.peek() {
+            Err(JsonError::UnexpectedEndOfInput) => {},
+            Err(e) => return Err(e),
+            Ok(_) => {
+                let error = self.error("Unexpected content after document...");
+                self.recover(error)?;
+            }
+        }
+
+        return Ok(root);
+    }
+
+    // Parses the first document and returns it with the number of bytes it
+    // took up, leaving whatever follows alone.
+    pub fn parse_partial(&mut self) -> Result<(JsonExpression, usize), JsonError> {
+        let root = self.parse_root()?;
+        return Ok((root, self.consumed));
+    }
+
+    fn parse_root(&mut self) -> Result<JsonExpression, JsonError> {
+        if self.options.scalar_root {
+            return self.parse_expression();
+        }
+
+        match self.peek()? {
+            Token::LeftBrace => return self.parse_object(),
+            Token::LeftBracket => return self.parse_array(),
+            _ => return Err(self.unexpected("object or array"))
+        }
+    }
+
+    // Called on the opening bracket or brace of a container, which is blamed
+    // when it goes past the depth limit.
+    fn enter(&mut self) -> Result<(), JsonError> {
+        if self.options.max_depth.is_some_and(|max| self.depth >= max) {
+            return Err(self.error("Maximum nesting depth exceeded..."));
+        }
+        self.depth += 1;
+        return Ok(());
+    }
+
+    fn parse_expression(&mut self) -> Result<JsonExpression, JsonError> {
+        match self.peek()? {
+            Token::LeftBracket => return self.parse_array(),
+            Token::LeftBrace => return self.parse_object(),
+            _ => {}
+        }
+
+        let token = self.next()?;
+        match token.value {
+            Token::NumberLiteral(n) => return Ok(JsonExpression::Number(n)),
+            Token::StringLiteral(s) => {
+                #[cfg(feature = "unicode")]
+                let s = match self.options.normalize_strings {
+                    Some(form) => form.apply(s),
+                    None => s
+                };
+                return Ok(JsonExpression::String(s));
+            },
+            Token::BooleanLiteral(b) => return Ok(JsonExpression::Bool(b)),
+            Token::NullLiteral => return Ok(JsonExpression::Null),
+            other => {
+                // put it back so recovery can see a closing bracket
+                self.peeked = Some(Spanned { value: other, span: token.span });
+                return Err(self.unexpected("value"));
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonExpression, JsonError> {
+        match self.peek()? {
+            Token::LeftBracket => self.enter()?,
+            _ => return Err(self.unexpected("left bracket"))
+        }
+        self.advance();
+        let array = self.parse_elements();
+        self.depth -= 1;
+        return array;
+    }
+
+    fn parse_elements(&mut self) -> Result<JsonExpression, JsonError> {
+        // dont parse array as it is empty
+        let empty = match self.peek() {
+            Ok(token) => *token == Token::RightBracket,
+            Err(e) => {
+                self.recover(e)?;
+                false
+            }
+        };
+        if empty {
+            self.advance();
+            return Ok(JsonExpression::Array(Vec::new()))
+        }
+
+        let mark = self.elements.len();
+        loop {
+            match self.parse_expression() {
+                Ok(element) => self.elements.push(Box::new(element)),
+                Err(e) => self.recover(e)?
+            }
+
+            if !self.separator(&Token::RightBracket, "comma or right bracket")? {
+                break;
+            }
+        }
+
+        return Ok(JsonExpression::Array(self.elements.drain(mark..).collect()))
+    }
+
+    fn parse_object(&mut self) -> Result<JsonExpression, JsonError> {
+        match self.peek()? {
+            Token::LeftBrace => self.enter()?,
+            _ => return Err(self.unexpected("left brace"))
+        }
+        self.advance();
+        let object = self.parse_members();
+        self.depth -= 1;
+        return object;
+    }
+
+    fn parse_members(&mut self) -> Result<JsonExpression, JsonError> {
+        // dont parse object as it is empty
+        let empty = match self.peek() {
+            Ok(token) => *token == Token::RightBrace,
+            Err(e) => {
+                self.recover(e)?;
+                false
+            }
+        };
+        if empty {
+            self.advance();
+            return Ok(JsonExpression::Object(Vec::new()))
+        }
+
+        let mark = self.members.len();
+        // only built when repeated keys are an error
+        let mut seen: Option<HashSet<String>> = if self.options.duplicate_keys { None } else { Some(HashSet::new()) };
+        loop {
+            match self.parse_member() {
+                Ok((key, _, value)) if seen.as_mut().is_none_or(|seen| seen.insert(key.clone())) => self.members.push((key, Box::new(value))),
+                Ok((key, span, _)) => self.recover(JsonError::UnexpectedToken(format!("Duplicate key \"{}\"...", key), span))?,
+                Err(e) => self.recover(e)?
+            }
+
+            if !self.separator(&Token::RightBrace, "comma or right brace")? {
+                break;
+            }
+        }
+
+        return Ok(JsonExpression::Object(self.members.drain(mark..).collect()))
+    }
+
+    // The key comes back with its span so a duplicate can be pointed at.
+    fn parse_member(&mut self) -> Result<(String, Span, JsonExpression), JsonError> {
+        let token = self.next()?;
+        let key = match token.value {
+            Token::StringLiteral(s) => s,
+            other => {
+                self.peeked = Some(Spanned { value: other, span: token.span });
+                return Err(self.unexpected("string key"));
+            }
+        };
+        #[cfg(feature = "unicode")]
+        let key = match self.options.normalize_keys {
+            Some(form) => form.apply(key),
+            None => key
+        };
+
+        match self.peek()? {
+            Token::Colon => self.advance(),
+            _ => return Err(self.unexpected("colon"))
+        }
+
+        let value = self.parse_expression()?;
+        return Ok((key, token.span, value));
+    }
+}
+
+// For parsing many documents one after another, e.g. every message a service
+// receives. The stacks the parser collects elements and members on are kept
+// between calls, so once they have grown to fit the largest document seen a
+// parse only allocates for the values it returns.
+//
+//     let mut parser = JsonParser::new(ParserOptions::strict());
+//     for message in messages {
+//         handle(parser.parse(&message)?);
+//     }
+#[derive(Default)]
+pub struct JsonParser {
+    options: ParserOptions,
+    scratch: Scratch
+}
+
+impl JsonParser {
+    pub fn new(options: ParserOptions) -> Self {
+        return Self { options, scratch: Scratch::default() };
+    }
+
+    pub fn options(&self) -> &ParserOptions {
+        return &self.options;
+    }
+
+    // The same as from_slice_with.
+    pub fn parse(&mut self, bytes: &[u8]) -> Result<JsonExpression, JsonError> {
+        let bytes = transcode(bytes)?;
+        let lexer = lexer_for(&bytes, &self.options);
+        // with the tracing feature every parse reports its size, token count
+        // and time taken
+        #[cfg(feature = "tracing")]
+        return crate::telemetry::parse(bytes.len(), lexer, self.options, &mut self.scratch);
+        #[cfg(not(feature = "tracing"))]
+        {
+            let mut parser = Parser::with_scratch(lexer, self.options, std::mem::take(&mut self.scratch));
+            let result = parser.parse();
+            self.scratch = parser.into_scratch();
+            return result;
+        }
+    }
+}
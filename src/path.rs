@@ -0,0 +1,468 @@
+use std::fmt;
+
+use crate::lexer::{Lexer, Spanned, Token, TokenSource};
+use crate::traits::FromJson;
+use crate::JsonExpression;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize)
+}
+
+// Location of a node relative to the root, displayed as `a.b[2].c`. Keys
+// that would not read back that way, such as `unit price` or `a.b`, are
+// written as quoted JSON strings in brackets: `orders[0]["unit price"]`. The
+// root itself is the empty path.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>
+}
+
+impl JsonPath {
+    pub fn root() -> Self {
+        return Self { segments: vec![] };
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        return &self.segments;
+    }
+
+    pub fn is_root(&self) -> bool {
+        return self.segments.is_empty();
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        return self.segments.pop();
+    }
+
+    pub fn child(&self, segment: PathSegment) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        return path;
+    }
+
+    // The same location as an RFC 6901 JSON Pointer, e.g. `/a/0/b`.
+    pub fn to_pointer(&self) -> String {
+        let mut pointer = String::new();
+        for segment in &self.segments {
+            pointer.push('/');
+            match segment {
+                PathSegment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+                PathSegment::Index(index) => pointer.push_str(&index.to_string())
+            }
+        }
+        return pointer;
+    }
+}
+
+// Keys that need brackets and quotes to read back as a single key.
+fn needs_quotes(key: &str) -> bool {
+    return key.is_empty() || key.chars().any(|c| matches!(c, '.' | '[' | ']' | '"' | '\\') || c.is_whitespace() || c.is_control());
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if needs_quotes(key) => write!(f, "[{}]", JsonExpression::String(key.clone()))?,
+                PathSegment::Key(key) if i == 0 => write!(f, "{}", key)?,
+                PathSegment::Key(key) => write!(f, ".{}", key)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+// Parses the same `a.b[2].c` syntax that Display produces.
+impl std::str::FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut path = JsonPath::root();
+        let chars: Vec<char> = s.chars().collect();
+        let mut index: usize = 0;
+
+        while index < chars.len() {
+            match chars[index] {
+                '[' if chars.get(index + 1) == Some(&'"') => {
+                    // a quoted key is a JSON string, which the lexer reads
+                    let rest: String = chars[index + 1..].iter().collect();
+                    let token = Lexer::new(rest.as_bytes()).next_token();
+                    let (key, end) = match token {
+                        Ok(Some(Spanned { value: Token::StringLiteral(key), span })) => (key, index + 1 + rest[..span.end].chars().count()),
+                        _ => return Err(format!("Expected quoted key at {} in path...", index + 1))
+                    };
+                    if chars.get(end) != Some(&']') {
+                        return Err(format!("Expected ']' at {} in path...", end));
+                    }
+                    path.push(PathSegment::Key(key));
+                    index = end + 1;
+                },
+                '[' => {
+                    index += 1;
+                    let start: usize = index;
+                    while index < chars.len() && chars[index].is_ascii_digit() {
+                        index += 1;
+                    }
+
+                    if index == start || index >= chars.len() || chars[index] != ']' {
+                        return Err(format!("Expected array index at {} in path...", start));
+                    }
+
+                    let digits: String = chars[start..index].iter().collect();
+                    match digits.parse::<usize>() {
+                        Ok(i) => path.push(PathSegment::Index(i)),
+                        Err(_) => return Err(format!("Array index {} is too large...", digits))
+                    }
+                    index += 1;
+                },
+                '.' if index == 0 => return Err(String::from("Path cannot start with '.'...")),
+                c => {
+                    if c == '.' {
+                        index += 1;
+                    }
+
+                    let start: usize = index;
+                    while index < chars.len() && chars[index] != '.' && chars[index] != '[' {
+                        index += 1;
+                    }
+
+                    if index == start {
+                        return Err(format!("Expected key at {} in path...", start));
+                    }
+
+                    path.push(PathSegment::Key(chars[start..index].iter().collect()));
+                },
+            }
+        }
+
+        return Ok(path);
+    }
+}
+
+impl JsonExpression {
+    pub fn get_at(&self, path: &JsonPath) -> Option<&JsonExpression> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (current, segment) {
+                (JsonExpression::Object(pairs), PathSegment::Key(key)) => {
+                    pairs.iter().rev().find(|(k, _)| k == key)?.1.as_ref()
+                },
+                (JsonExpression::Array(elements), PathSegment::Index(i)) => elements.get(*i)?.as_ref(),
+                _ => return None
+            };
+        }
+
+        return Some(current);
+    }
+
+    // Replaces the value at the path, creating missing objects and arrays on
+    // the way. Nulls in the way are replaced, and an index just past the end
+    // of an array appends to it. Fails when the path runs into a value of the
+    // wrong kind, e.g. a key into an array, or an index further past the end,
+    // which would need the gap filled with something.
+    pub fn set_at(&mut self, path: &JsonPath, value: JsonExpression) -> Result<(), String> {
+        let mut current = self;
+        for (i, segment) in path.segments().iter().enumerate() {
+            if *current == JsonExpression::Null {
+                *current = match segment {
+                    PathSegment::Key(_) => JsonExpression::Object(vec![]),
+                    PathSegment::Index(_) => JsonExpression::Array(vec![])
+                };
+            }
+
+            current = match (current, segment) {
+                (JsonExpression::Object(pairs), PathSegment::Key(key)) => {
+                    let index = match pairs.iter().rposition(|(k, _)| k == key) {
+                        Some(index) => index,
+                        None => {
+                            pairs.push((key.clone(), Box::new(JsonExpression::Null)));
+                            pairs.len() - 1
+                        }
+                    };
+                    pairs[index].1.as_mut()
+                },
+                (JsonExpression::Array(elements), PathSegment::Index(index)) => {
+                    if *index > elements.len() {
+                        let at = JsonPath { segments: path.segments[..i].to_vec() };
+                        return Err(format!("Index {} is past the end of the array of {} at '{}'...", index, elements.len(), at));
+                    }
+                    if *index == elements.len() {
+                        elements.push(Box::new(JsonExpression::Null));
+                    }
+                    elements[*index].as_mut()
+                },
+                (found, _) => {
+                    let at = JsonPath { segments: path.segments[..i].to_vec() };
+                    return Err(format!("Cannot index into {} at '{}'...", found.type_name(), at));
+                }
+            };
+        }
+
+        *current = value;
+        return Ok(());
+    }
+
+    // Looks up a path written as `a.b[2].c`, a malformed path finds nothing.
+    pub fn get_path(&self, path: &str) -> Option<&JsonExpression> {
+        return self.get_at(&path.parse().ok()?);
+    }
+
+    // Lookup and conversion in one, so a setting reads as
+    // `config.get_path_as::<i64>("server.port").unwrap_or(8080)`. Fails when
+    // the path is malformed, leads nowhere or holds the wrong type.
+    pub fn get_path_as<T: FromJson>(&self, path: &str) -> Result<T, String> {
+        let parsed: JsonPath = path.parse()?;
+        match self.get_at(&parsed) {
+            Some(value) => return T::from_json(value),
+            None => return Err(format!("No value at {}...", parsed))
+        }
+    }
+
+    pub fn set_path(&mut self, path: &str, value: JsonExpression) -> Result<(), String> {
+        return self.set_at(&path.parse()?, value);
+    }
+
+    // Looks up a JSON Pointer (RFC 6901) such as `/data/items/0`. A token is
+    // a key in an object and an index in an array, so unlike a JsonPath the
+    // same pointer works whichever one is there.
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonExpression> {
+        let mut current = self;
+        for token in pointer_tokens(pointer).ok()? {
+            current = match current {
+                JsonExpression::Object(pairs) => pairs.iter().rev().find(|(k, _)| *k == token)?.1.as_ref(),
+                JsonExpression::Array(elements) => elements.get(array_index(&token)?)?.as_ref(),
+                _ => return None
+            };
+        }
+
+        return Some(current);
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonExpression> {
+        let mut current = self;
+        for token in pointer_tokens(pointer).ok()? {
+            current = match current {
+                JsonExpression::Object(pairs) => pairs.iter_mut().rev().find(|(k, _)| *k == token)?.1.as_mut(),
+                JsonExpression::Array(elements) => elements.get_mut(array_index(&token)?)?.as_mut(),
+                _ => return None
+            };
+        }
+
+        return Some(current);
+    }
+}
+
+// The unescaped tokens of a pointer, the empty pointer being the root.
+pub(crate) fn pointer_tokens(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(vec![]);
+    }
+
+    match pointer.strip_prefix('/') {
+        Some(rest) => return Ok(rest.split('/').map(|token| token.replace("~1", "/").replace("~0", "~")).collect()),
+        None => return Err(format!("Pointer {} must start with '/'...", pointer))
+    }
+}
+
+// Indices are plain decimal without leading zeros, so `01` finds nothing.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty() || !token.bytes().all(|b| b.is_ascii_digit()) || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+
+    return token.parse().ok();
+}
+
+// Converts just the subtree at a pointer, so a deeply nested payload can be
+// read without wrapper types for every envelope around it:
+// `from_json_at::<Vec<Item>>(&response, "/data/items")`.
+pub fn from_json_at<T: FromJson>(value: &JsonExpression, pointer: &str) -> Result<T, String> {
+    pointer_tokens(pointer)?;
+    match value.pointer(pointer) {
+        Some(found) => return T::from_json(found),
+        None => return Err(format!("No value at {}...", pointer))
+    }
+}
+
+// Depth-first, document-ordered walk over a tree. Leaves are scalars and empty
+// containers; with `leaves_only` off every node is yielded, parents before
+// their children.
+pub struct Paths<'a> {
+    stack: Vec<(JsonPath, &'a JsonExpression)>,
+    leaves_only: bool
+}
+
+impl<'a> Iterator for Paths<'a> {
+    type Item = (JsonPath, &'a JsonExpression);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, expression)) = self.stack.pop() {
+            let start = self.stack.len();
+            match expression {
+                JsonExpression::Array(elements) => {
+                    for (i, element) in elements.iter().enumerate() {
+                        self.stack.push((path.child(PathSegment::Index(i)), element));
+                    }
+                },
+                JsonExpression::Object(pairs) => {
+                    for (key, value) in pairs {
+                        self.stack.push((path.child(PathSegment::Key(key.clone())), value));
+                    }
+                },
+                _ => {}
+            }
+
+            // children were pushed in document order, flip them so they pop that way
+            self.stack[start..].reverse();
+
+            let is_leaf = self.stack.len() == start;
+            if is_leaf || !self.leaves_only {
+                return Some((path, expression));
+            }
+        }
+
+        return None;
+    }
+}
+
+impl JsonExpression {
+    pub fn leaves(&self) -> Paths<'_> {
+        return Paths { stack: vec![(JsonPath::root(), self)], leaves_only: true };
+    }
+
+    pub fn nodes(&self) -> Paths<'_> {
+        return Paths { stack: vec![(JsonPath::root(), self)], leaves_only: false };
+    }
+
+    // Every node the predicate accepts, in document order. A node matching
+    // inside another matching node is returned too.
+    pub fn find_all<F: FnMut(&JsonPath, &JsonExpression) -> bool>(&self, mut predicate: F) -> Vec<(JsonPath, &JsonExpression)> {
+        return self.nodes().filter(|(path, value)| predicate(path, value)).collect();
+    }
+
+    // Stops walking at the first match.
+    pub fn find_first<F: FnMut(&JsonPath, &JsonExpression) -> bool>(&self, mut predicate: F) -> Option<(JsonPath, &JsonExpression)> {
+        return self.nodes().find(|(path, value)| predicate(path, value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::path::from_json_at;
+    use crate::{json, JsonExpression, JsonPath, PathSegment};
+
+    #[test]
+    fn leaf_paths_in_document_order() {
+        let expr = json(String::from("{\"a\": {\"b\": [1, 2]}, \"c\": \"x\", \"d\": []}")).unwrap();
+        let paths: Vec<String> = expr.leaves().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(vec!["a.b[0]", "a.b[1]", "c", "d"], paths);
+    }
+
+    #[test]
+    fn node_paths_include_containers() {
+        let expr = json(String::from("[{\"a\": 1}]")).unwrap();
+        let paths: Vec<String> = expr.nodes().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(vec!["", "[0]", "[0].a"], paths);
+    }
+
+    #[test]
+    fn find_by_predicate() {
+        let expr = json(String::from("{\"user\": {\"password\": \"x\", \"keys\": [{\"password\": \"y\"}]}, \"n\": 3}")).unwrap();
+        let secrets: Vec<String> = expr.find_all(|path, _| matches!(path.segments().last(), Some(PathSegment::Key(k)) if k == "password"))
+            .into_iter()
+            .map(|(path, _)| path.to_string())
+            .collect();
+        assert_eq!(vec!["user.password", "user.keys[0].password"], secrets);
+
+        let (path, value) = expr.find_first(|_, value| matches!(value, JsonExpression::Number(_))).unwrap();
+        assert_eq!(("n", &JsonExpression::Number(3.0)), (path.to_string().as_str(), value));
+        assert!(expr.find_first(|_, value| *value == JsonExpression::Null).is_none());
+    }
+
+    #[test]
+    fn string_paths() {
+        let mut config = json(String::from("{\"server\": {\"ports\": [80]}, \"name\": \"x\"}")).unwrap();
+        assert_eq!(Some(&JsonExpression::Number(80.0)), config.get_path("server.ports[0]"));
+        assert_eq!(None, config.get_path("server..ports"));
+
+        config.set_path("server.ports[1]", JsonExpression::Number(443.0)).unwrap();
+        config.set_path("server.tls.cert", JsonExpression::from("a.pem")).unwrap();
+        config.set_path("extra[0].on", JsonExpression::Bool(true)).unwrap();
+        assert_eq!(
+            json(String::from("{\"server\": {\"ports\": [80, 443], \"tls\": {\"cert\": \"a.pem\"}}, \"name\": \"x\", \"extra\": [{\"on\": true}]}")).unwrap(),
+            config
+        );
+
+        // only appending grows an array
+        assert_eq!(
+            Err(String::from("Index 4000000000 is past the end of the array of 2 at 'server.ports'...")),
+            config.set_path("server.ports[4000000000]", JsonExpression::Null)
+        );
+        assert!(config.set_path("fresh[1]", JsonExpression::Null).is_err());
+
+        assert_eq!(Err(String::from("Cannot index into string at 'name'...")), config.set_path("name.first", JsonExpression::Null));
+        assert!(config.set_path("server[0]", JsonExpression::Null).is_err());
+        assert!(config.set_path("a[", JsonExpression::Null).is_err());
+    }
+
+    #[test]
+    fn typed_lookup() {
+        let config = json(String::from("{\"server\": {\"port\": 9000, \"host\": \"h\", \"tags\": [\"a\"]}}")).unwrap();
+        assert_eq!(9000, config.get_path_as::<i64>("server.port").unwrap_or(8080));
+        assert_eq!(8080, config.get_path_as::<i64>("server.timeout").unwrap_or(8080));
+        assert_eq!(vec![String::from("a")], config.get_path_as::<Vec<String>>("server.tags").unwrap());
+        assert_eq!(Err(String::from("No value at server.timeout...")), config.get_path_as::<i64>("server.timeout"));
+        assert!(config.get_path_as::<i64>("server.host").is_err());
+    }
+
+    #[test]
+    fn pointer_lookup() {
+        let response = json(String::from("{\"data\": {\"items\": [{\"id\": 7}], \"a/b\": 1, \"m~n\": 2, \"0\": 3}}")).unwrap();
+        assert_eq!(Ok(7), from_json_at::<u32>(&response, "/data/items/0/id"));
+        assert_eq!(7, from_json_at::<Vec<HashMap<String, u32>>>(&response, "/data/items").unwrap()[0]["id"]);
+        assert_eq!(Some(&JsonExpression::Number(1.0)), response.pointer("/data/a~1b"));
+        assert_eq!(Some(&JsonExpression::Number(2.0)), response.pointer("/data/m~0n"));
+        assert_eq!(Some(&JsonExpression::Number(3.0)), response.pointer("/data/0"));
+        assert_eq!(Some(&response), response.pointer(""));
+        assert_eq!(None, response.pointer("/data/items/00"));
+        assert_eq!(Err(String::from("No value at /data/items/1...")), from_json_at::<u32>(&response, "/data/items/1"));
+        assert_eq!(Err(String::from("Pointer data must start with '/'...")), from_json_at::<u32>(&response, "data"));
+    }
+
+    #[test]
+    fn parse_and_lookup() {
+        let expr = json(String::from("{\"a\": {\"b\": [1, {\"c\": true}]}}")).unwrap();
+        let path: JsonPath = "a.b[1].c".parse().unwrap();
+        assert_eq!("a.b[1].c", path.to_string());
+        assert_eq!(Some(&JsonExpression::Bool(true)), expr.get_at(&path));
+        assert_eq!(None, expr.get_at(&"a.x".parse().unwrap()));
+        assert!("a..b".parse::<JsonPath>().is_err());
+        assert!("a[x]".parse::<JsonPath>().is_err());
+    }
+
+    #[test]
+    fn quoted_keys() {
+        let mut path = JsonPath::root();
+        for key in ["a.b", "unit price", "", "q\"[0]", "plain"] {
+            path.push(PathSegment::Key(String::from(key)));
+        }
+        path.push(PathSegment::Index(3));
+        assert_eq!("[\"a.b\"][\"unit price\"][\"\"][\"q\\\"[0]\"].plain[3]", path.to_string());
+        assert_eq!(Ok(path.clone()), path.to_string().parse());
+
+        let expr = json(String::from("{\"a.b\": {\"c d\": 1}}")).unwrap();
+        assert_eq!(Some(&JsonExpression::Number(1.0)), expr.get_path("[\"a.b\"][\"c d\"]"));
+        assert!("[\"a\"".parse::<JsonPath>().is_err());
+        assert!("[\"a]".parse::<JsonPath>().is_err());
+    }
+}
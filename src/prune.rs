@@ -0,0 +1,96 @@
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+impl JsonExpression {
+    // Removes every member and element the predicate rejects, checking
+    // parents before their children so nothing inside a removed container is
+    // visited. Paths are those of the original tree, indexes are not shifted
+    // by earlier removals. The root itself is always kept.
+    pub fn retain<F: FnMut(&JsonPath, &JsonExpression) -> bool>(&mut self, mut predicate: F) {
+        retain_node(self, &mut JsonPath::root(), &mut predicate);
+    }
+
+    pub fn prune_nulls(&mut self) {
+        self.retain(|_, value| *value != JsonExpression::Null);
+    }
+
+    // Removes empty arrays and objects, including ones that only become empty
+    // once their own empty children are gone.
+    pub fn prune_empty(&mut self) {
+        match self {
+            JsonExpression::Array(elements) => {
+                elements.iter_mut().for_each(|e| e.prune_empty());
+                elements.retain(|e| !is_empty(e));
+            },
+            JsonExpression::Object(pairs) => {
+                pairs.iter_mut().for_each(|(_, v)| v.prune_empty());
+                pairs.retain(|(_, v)| !is_empty(v));
+            },
+            _ => {}
+        }
+    }
+}
+
+fn is_empty(expression: &JsonExpression) -> bool {
+    match expression {
+        JsonExpression::Array(elements) => return elements.is_empty(),
+        JsonExpression::Object(pairs) => return pairs.is_empty(),
+        _ => return false
+    }
+}
+
+fn retain_node<F: FnMut(&JsonPath, &JsonExpression) -> bool>(expression: &mut JsonExpression, path: &mut JsonPath, predicate: &mut F) {
+    match expression {
+        JsonExpression::Array(elements) => {
+            let mut i = 0;
+            elements.retain_mut(|element| {
+                path.push(PathSegment::Index(i));
+                i += 1;
+                let keep = predicate(path, element);
+                if keep {
+                    retain_node(element, path, predicate);
+                }
+                path.pop();
+                return keep;
+            });
+        },
+        JsonExpression::Object(pairs) => {
+            pairs.retain_mut(|(key, value)| {
+                path.push(PathSegment::Key(key.clone()));
+                let keep = predicate(path, value);
+                if keep {
+                    retain_node(value, path, predicate);
+                }
+                path.pop();
+                return keep;
+            });
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json;
+
+    #[test]
+    fn retain_with_paths() {
+        let mut expr = json(String::from("{\"id\": 1, \"secret\": \"x\", \"items\": [{\"secret\": 2, \"v\": 3}, 4, 5]}")).unwrap();
+        let mut seen: Vec<String> = Vec::new();
+        expr.retain(|path, value| {
+            seen.push(path.to_string());
+            return !path.to_string().ends_with("secret") && *value != crate::JsonExpression::Number(4.0);
+        });
+        assert_eq!(json(String::from("{\"id\": 1, \"items\": [{\"v\": 3}, 5]}")).unwrap(), expr);
+        assert_eq!(vec!["id", "secret", "items", "items[0]", "items[0].secret", "items[0].v", "items[1]", "items[2]"], seen);
+    }
+
+    #[test]
+    fn prunes_nulls_and_empties() {
+        let mut expr = json(String::from("{\"a\": null, \"b\": [null, 1, {\"c\": null}], \"d\": {\"e\": []}, \"f\": \"\"}")).unwrap();
+        expr.prune_nulls();
+        assert_eq!(json(String::from("{\"b\": [1, {}], \"d\": {\"e\": []}, \"f\": \"\"}")).unwrap(), expr);
+        expr.prune_empty();
+        assert_eq!(json(String::from("{\"b\": [1], \"f\": \"\"}")).unwrap(), expr);
+    }
+}
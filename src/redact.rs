@@ -0,0 +1,116 @@
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+// Which values to redact.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    // members whose key contains any of these `|` separated names, ignoring
+    // case, so `password|token` catches `accessToken` and `db_password`
+    Keys(String),
+    // the value at exactly this path
+    Path(JsonPath)
+}
+
+// What a redacted value becomes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Redaction {
+    // the string "***"
+    Mask,
+    // the hex HMAC-SHA256 of the value's canonical form under this key, so
+    // equal secrets can still be matched up across log lines without being
+    // revealed. The key has to stay secret, anyone holding it can test
+    // guesses of short values such as PINs against the output.
+    #[cfg(feature = "digest")]
+    Hash(Vec<u8>),
+    Null
+}
+
+impl Target {
+    fn matches(&self, path: &JsonPath) -> bool {
+        match self {
+            Target::Path(target) => return target == path,
+            Target::Keys(names) => match path.segments().last() {
+                Some(PathSegment::Key(key)) => {
+                    let key = key.to_lowercase();
+                    return names.split('|').filter(|name| !name.is_empty()).any(|name| key.contains(&name.to_lowercase()));
+                },
+                _ => return false
+            }
+        }
+    }
+}
+
+impl JsonExpression {
+    // Replaces every value a target matches, whatever its type, so payloads
+    // can be logged without their secrets. Nothing inside a redacted value is
+    // looked at again. Returns how many values were replaced.
+    pub fn redact(&mut self, targets: &[Target], redaction: &Redaction) -> usize {
+        return redact_node(self, &mut JsonPath::root(), targets, redaction);
+    }
+}
+
+fn redact_node(expression: &mut JsonExpression, path: &mut JsonPath, targets: &[Target], redaction: &Redaction) -> usize {
+    if targets.iter().any(|target| target.matches(path)) {
+        *expression = match redaction {
+            Redaction::Mask => JsonExpression::from("***"),
+            #[cfg(feature = "digest")]
+            Redaction::Hash(key) => JsonExpression::String(expression.keyed_digest(key).iter().map(|b| format!("{:02x}", b)).collect()),
+            Redaction::Null => JsonExpression::Null
+        };
+        return 1;
+    }
+
+    let mut count = 0;
+    match expression {
+        JsonExpression::Array(elements) => {
+            for (i, element) in elements.iter_mut().enumerate() {
+                path.push(PathSegment::Index(i));
+                count += redact_node(element, path, targets, redaction);
+                path.pop();
+            }
+        },
+        JsonExpression::Object(pairs) => {
+            for (key, value) in pairs.iter_mut() {
+                path.push(PathSegment::Key(key.clone()));
+                count += redact_node(value, path, targets, redaction);
+                path.pop();
+            }
+        },
+        _ => {}
+    }
+    return count;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::redact::{Redaction, Target};
+    use crate::{json, JsonPath};
+
+    #[test]
+    fn masks_matching_keys_and_paths() {
+        let mut payload = json(String::from("{\"user\": {\"name\": \"ada\", \"Password\": \"hunter2\", \"ids\": [1, 2]}, \"accessToken\": {\"value\": \"abc\"}, \"items\": [{\"ssn\": 123}]}")).unwrap();
+        let targets = [Target::Keys(String::from("password|token|ssn")), Target::Path("user.ids[1]".parse::<JsonPath>().unwrap())];
+        assert_eq!(4, payload.redact(&targets, &Redaction::Mask));
+        assert_eq!(json(String::from("{\"user\": {\"name\": \"ada\", \"Password\": \"***\", \"ids\": [1, \"***\"]}, \"accessToken\": \"***\", \"items\": [{\"ssn\": \"***\"}]}")).unwrap(), payload);
+
+        let mut nulled = json(String::from("{\"token\": 1}")).unwrap();
+        nulled.redact(&[Target::Keys(String::from("TOKEN"))], &Redaction::Null);
+        assert_eq!(json(String::from("{\"token\": null}")).unwrap(), nulled);
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn hashes() {
+        let mut a = json(String::from("[{\"secret\": \"x\"}, {\"secret\": \"x\"}, {\"secret\": \"y\"}]")).unwrap();
+        a.redact(&[Target::Keys(String::from("secret"))], &Redaction::Hash(b"key".to_vec()));
+        let hashes: Vec<&str> = a.iter().filter_map(|e| e.get_path("secret")).filter_map(|h| <&str>::try_from(h).ok()).collect();
+        assert_eq!(64, hashes[0].len());
+        assert_eq!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+
+        // a bare SHA-256 of a short secret could be found by hashing guesses
+        let mut b = json(String::from("{\"secret\": \"x\"}")).unwrap();
+        b.redact(&[Target::Keys(String::from("secret"))], &Redaction::Hash(b"other".to_vec()));
+        assert_ne!(hashes[0], <&str>::try_from(b.get_path("secret").unwrap()).unwrap());
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{self, Read, Write};
+
+use crate::error::JsonError;
+use crate::lexer::Token;
+use crate::stream::{walk, Event};
+use crate::writer::JsonWriter;
+
+// How reformat lays out what it writes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Style {
+    // no whitespace at all, the same as to_string
+    Compact,
+    // every member and element on a line of its own, indented by the given
+    // string per level, the same as to_string_pretty
+    Indent(String)
+}
+
+fn write_error(e: io::Error) -> JsonError {
+    return JsonError::Io(e.to_string());
+}
+
+// Rewrites a UTF-8 document from the reader in the given style as it is read,
+// passing each token straight on to a JsonWriter without building a tree.
+// Memory use is bounded by the nesting depth and the longest single token, so
+// files far larger than memory can be reformatted. The input is checked as it
+// goes, with the same errors parsing gives, but whatever came before an error
+// has already been written. Returns the writer once the document is done.
+pub fn reformat<R: Read, W: Write>(reader: R, writer: W, style: &Style) -> Result<W, JsonError> {
+    let out = io::BufWriter::new(writer);
+    let mut out = match style {
+        Style::Compact => JsonWriter::new(out),
+        Style::Indent(indent) => JsonWriter::pretty(out, indent)
+    };
+
+    walk(reader, |event, _| {
+        let written = match event {
+            Event::StartObject => out.start_object(),
+            Event::EndObject => out.end_object(),
+            Event::StartArray => out.start_array(),
+            Event::EndArray => out.end_array(),
+            Event::Key(key) => out.key(&key),
+            Event::Value(Token::NumberLiteral(n)) => out.number(n),
+            Event::Value(Token::StringLiteral(s)) => out.string(&s),
+            Event::Value(Token::BooleanLiteral(b)) => out.bool(b),
+            Event::Value(_) => out.null()
+        };
+        return written.map_err(write_error);
+    })?;
+
+    let out = out.finish().map_err(write_error)?;
+    return out.into_inner().map_err(|e| write_error(e.into_error()));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reformat::{reformat, Style};
+    use crate::{json, JsonError};
+
+    // hands out a few bytes per read so tokens straddle chunks
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            return Ok(n);
+        }
+    }
+
+    #[test]
+    fn matches_tree_serialization() {
+        let input = "\u{feff} {\"a\": [1, 2.5e3, \"x\\n\\u00e9,]\", true, null, {}], \"b\" : {\"c\": [[]], \"d\": -0.5}} ";
+        let expr = json(String::from(input)).unwrap();
+
+        let compact = reformat(Trickle(input.as_bytes()), Vec::new(), &Style::Compact).unwrap();
+        assert_eq!(expr.to_string(), String::from_utf8(compact).unwrap());
+        let pretty = reformat(Trickle(input.as_bytes()), Vec::new(), &Style::Indent(String::from("    "))).unwrap();
+        assert_eq!(expr.to_string_pretty(), String::from_utf8(pretty).unwrap());
+        assert_eq!("42", String::from_utf8(reformat("42".as_bytes(), Vec::new(), &Style::Compact).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn agrees_with_parser_on_errors() {
+        let inputs = ["", "[1 2]", "[1,]", "{\"a\" 1}", "{\"a\": 1,}", "{1: 2}", "[1]]", "[{\"a\": 1]", "[1, \"open", "[tru]", "[\"\\q\"] x", "[1,\n  {\"a\" 2}]"];
+        for input in inputs {
+            let error: Option<JsonError> = reformat(Trickle(input.as_bytes()), Vec::new(), &Style::Compact).err();
+            assert_eq!(json(String::from(input)).err(), error, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::infer::{merge, shape_of, Shape};
+use crate::JsonExpression;
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn object(pairs: Vec<(&str, JsonExpression)>) -> JsonExpression {
+    return JsonExpression::Object(pairs.into_iter().map(|(k, v)| (String::from(k), Box::new(v))).collect());
+}
+
+fn typed(name: &str) -> JsonExpression {
+    return object(vec![("type", JsonExpression::from(name))]);
+}
+
+fn schema_of(shape: &Shape) -> JsonExpression {
+    match shape {
+        Shape::Unknown | Shape::Any => return JsonExpression::Object(vec![]),
+        Shape::Null => return typed("null"),
+        Shape::Bool => return typed("boolean"),
+        Shape::Integer => return typed("integer"),
+        Shape::Float => return typed("number"),
+        Shape::String => return typed("string"),
+        Shape::Array(element) => {
+            let mut pairs = vec![("type", JsonExpression::from("array"))];
+            if **element != Shape::Unknown {
+                pairs.push(("items", schema_of(element)));
+            }
+            return object(pairs);
+        },
+        Shape::Object(fields) => {
+            let properties = fields.iter().map(|f| (f.key.clone(), Box::new(schema_of(&f.shape)))).collect();
+            let required = fields.iter().filter(|f| !f.optional).map(|f| Box::new(JsonExpression::from(f.key.as_str()))).collect();
+            return object(vec![
+                ("type", JsonExpression::from("object")),
+                ("properties", JsonExpression::Object(properties)),
+                ("required", JsonExpression::Array(required))
+            ]);
+        },
+        Shape::Optional(inner) => {
+            let mut schema = schema_of(inner);
+            if let JsonExpression::Object(pairs) = &mut schema {
+                // a plain type name widens to a list with null in it
+                if let Some((_, kind)) = pairs.iter_mut().find(|(k, _)| k == "type") {
+                    let name = std::mem::replace(kind.as_mut(), JsonExpression::Null);
+                    **kind = JsonExpression::Array(vec![Box::new(name), Box::new(JsonExpression::from("null"))]);
+                    return schema;
+                }
+            }
+            return object(vec![("anyOf", JsonExpression::Array(vec![Box::new(schema), Box::new(typed("null"))]))]);
+        }
+    }
+}
+
+// Infers a JSON Schema (draft 2020-12) that every sample satisfies. Object
+// properties present in all samples are required and values that disagree on
+// type get an empty schema, which accepts anything.
+pub fn infer_schema<'a, I: IntoIterator<Item = &'a JsonExpression>>(samples: I) -> JsonExpression {
+    let shape = samples.into_iter().fold(Shape::Unknown, |shape, sample| merge(shape, shape_of(sample)));
+    let mut schema = schema_of(&shape);
+    if let JsonExpression::Object(pairs) = &mut schema {
+        pairs.insert(0, (String::from("$schema"), Box::new(JsonExpression::from(DIALECT))));
+    }
+    return schema;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json;
+    use crate::schema::infer_schema;
+
+    #[test]
+    fn infers_from_samples() {
+        let a = json(String::from("{\"id\": 1, \"name\": \"a\", \"tags\": [], \"score\": 1.5}")).unwrap();
+        let b = json(String::from("{\"id\": 2, \"name\": null, \"tags\": [\"x\"], \"extra\": true, \"score\": \"n/a\"}")).unwrap();
+        let schema = infer_schema([&a, &b]);
+        assert_eq!(
+            json(String::from("{
+                \"$schema\": \"https://json-schema.org/draft/2020-12/schema\",
+                \"type\": \"object\",
+                \"properties\": {
+                    \"id\": {\"type\": \"integer\"},
+                    \"name\": {\"type\": [\"string\", \"null\"]},
+                    \"tags\": {\"type\": \"array\", \"items\": {\"type\": \"string\"}},
+                    \"score\": {},
+                    \"extra\": {\"type\": \"boolean\"}
+                },
+                \"required\": [\"id\", \"name\", \"tags\", \"score\"]
+            }")).unwrap(),
+            schema
+        );
+    }
+
+    #[test]
+    fn nullable_objects() {
+        let schema = infer_schema([&json(String::from("[{\"a\": 1}, null]")).unwrap()]);
+        assert_eq!(
+            "{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\",\"type\":\"array\",\"items\":{\"type\":[\"object\",\"null\"],\"properties\":{\"a\":{\"type\":\"integer\"}},\"required\":[\"a\"]}}",
+            schema.to_string()
+        );
+    }
+}
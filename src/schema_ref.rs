@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::JsonExpression;
+
+// Loads the schema documents a $ref points into. `uri` is the reference
+// joined onto the URI of the document it appeared in, without the fragment.
+pub trait SchemaResolver {
+    fn resolve(&self, uri: &str) -> Result<JsonExpression, String>;
+}
+
+// Schemas registered up front under their URIs.
+impl SchemaResolver for HashMap<String, JsonExpression> {
+    fn resolve(&self, uri: &str) -> Result<JsonExpression, String> {
+        return self.get(uri).cloned().ok_or_else(|| format!("Unknown schema {}...", uri));
+    }
+}
+
+// Schemas read from disk, a URI is taken as a path under the root directory.
+pub struct FileResolver {
+    root: PathBuf
+}
+
+impl FileResolver {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        return Self { root: root.into() };
+    }
+}
+
+impl SchemaResolver for FileResolver {
+    fn resolve(&self, uri: &str) -> Result<JsonExpression, String> {
+        let path = self.root.join(uri.strip_prefix("file://").unwrap_or(uri));
+        let bytes = std::fs::read(&path).map_err(|e| format!("Could not read {}: {}...", path.display(), e))?;
+        return crate::from_slice(&bytes).map_err(|e| format!("{}: {}", uri, e));
+    }
+}
+
+// Schemas fetched over http or https, a non 2xx response is an error.
+#[cfg(feature = "http")]
+pub struct HttpResolver;
+
+#[cfg(feature = "http")]
+impl SchemaResolver for HttpResolver {
+    fn resolve(&self, uri: &str) -> Result<JsonExpression, String> {
+        let mut response = ureq::get(uri)
+            .header("Accept", "application/schema+json, application/json;q=0.9")
+            .call()
+            .map_err(|e| format!("Could not fetch {}: {}...", uri, e))?;
+        let bytes = response.body_mut().read_to_vec().map_err(|e| format!("Could not fetch {}: {}...", uri, e))?;
+        return crate::from_slice(&bytes).map_err(|e| format!("{}: {}", uri, e));
+    }
+}
+
+// A reference split into the document it names, joined onto the base the way
+// a relative URL is, and the JSON Pointer after the `#`.
+fn join<'r>(base: &str, reference: &'r str) -> (String, &'r str) {
+    let (document, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+    if document.is_empty() {
+        return (String::from(base), fragment);
+    }
+    match base.rfind('/') {
+        Some(end) if !document.contains("://") && !document.starts_with('/') => return (format!("{}/{}", &base[..end], document), fragment),
+        _ => return (String::from(document), fragment)
+    }
+}
+
+// Keywords whose values are instance data rather than schemas, so a $ref
+// inside them is left alone.
+const DATA_KEYWORDS: [&str; 4] = ["const", "enum", "default", "examples"];
+
+struct Inliner<'r> {
+    resolver: &'r dyn SchemaResolver,
+    // every document loaded so far, by URI
+    documents: HashMap<String, JsonExpression>,
+    // the references being inlined, outermost first, to catch cycles
+    active: Vec<String>
+}
+
+impl Inliner<'_> {
+    fn inline(&mut self, node: &JsonExpression, base: &str) -> Result<JsonExpression, String> {
+        match node {
+            JsonExpression::Object(pairs) => {
+                if let Some((_, JsonExpression::String(reference))) = node.entries().filter(|(k, _)| *k == "$ref").last() {
+                    return self.follow(reference, base);
+                }
+                let mut inlined = Vec::with_capacity(pairs.len());
+                for (key, value) in pairs {
+                    let value = if DATA_KEYWORDS.contains(&key.as_str()) { value.as_ref().clone() } else { self.inline(value, base)? };
+                    inlined.push((key.clone(), Box::new(value)));
+                }
+                return Ok(JsonExpression::Object(inlined));
+            },
+            JsonExpression::Array(elements) => {
+                return elements.iter().map(|element| self.inline(element, base).map(Box::new)).collect::<Result<_, _>>().map(JsonExpression::Array);
+            },
+            other => return Ok(other.clone())
+        }
+    }
+
+    fn follow(&mut self, reference: &str, base: &str) -> Result<JsonExpression, String> {
+        let (uri, fragment) = join(base, reference);
+        let target = format!("{}#{}", uri, fragment);
+        if self.active.contains(&target) {
+            return Err(format!("Cyclic $ref to {}...", target));
+        }
+
+        if !self.documents.contains_key(&uri) {
+            let document = self.resolver.resolve(&uri)?;
+            self.documents.insert(uri.clone(), document);
+        }
+        let schema = self.documents[&uri].pointer(fragment).cloned().ok_or_else(|| format!("No schema at {}...", target))?;
+
+        self.active.push(target);
+        let inlined = self.inline(&schema, &uri);
+        self.active.pop();
+        return inlined;
+    }
+}
+
+// Replaces every $ref in a schema with the schema it points to, loading other
+// documents through the resolver, so the result stands on its own. `base` is
+// the schema's own URI, which relative references are joined onto. Fragments
+// are JSON Pointers, and keywords beside a $ref are dropped as in draft 7. A
+// reference that leads back to itself, as in a recursive tree, cannot be
+// inlined and is an error, as is one that points nowhere.
+pub fn resolve_refs(schema: &JsonExpression, base: &str, resolver: &dyn SchemaResolver) -> Result<JsonExpression, String> {
+    let mut inliner = Inliner { resolver, documents: HashMap::new(), active: vec![] };
+    inliner.documents.insert(String::from(base), schema.clone());
+    return inliner.inline(schema, base);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::json;
+    use crate::schema_ref::resolve_refs;
+
+    #[test]
+    fn inlines_across_documents() {
+        let mut schemas = HashMap::new();
+        schemas.insert(String::from("schemas/address.json"), json(String::from("{\"type\": \"object\", \"properties\": {\"zip\": {\"$ref\": \"#/$defs/zip\"}}, \"$defs\": {\"zip\": {\"type\": \"string\"}}}")).unwrap());
+
+        let person = json(String::from("{\"properties\": {\"home\": {\"$ref\": \"address.json\"}, \"age\": {\"$ref\": \"#/$defs/age\"}, \"kind\": {\"const\": {\"$ref\": \"x\"}}}, \"$defs\": {\"age\": {\"type\": \"integer\"}}}")).unwrap();
+        let resolved = resolve_refs(&person, "schemas/person.json", &schemas).unwrap();
+        assert_eq!(Some(&json(String::from("{\"type\": \"string\"}")).unwrap()), resolved.pointer("/properties/home/properties/zip"));
+        assert_eq!(Some(&json(String::from("{\"type\": \"integer\"}")).unwrap()), resolved.pointer("/properties/age"));
+        assert_eq!(Some(&json(String::from("{\"$ref\": \"x\"}")).unwrap()), resolved.pointer("/properties/kind/const"));
+
+        assert_eq!(Err(String::from("Unknown schema schemas/missing.json...")), resolve_refs(&json(String::from("{\"$ref\": \"missing.json\"}")).unwrap(), "schemas/a.json", &schemas));
+        assert_eq!(Err(String::from("No schema at a.json#/nope...")), resolve_refs(&json(String::from("{\"$ref\": \"#/nope\"}")).unwrap(), "a.json", &schemas));
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let tree = json(String::from("{\"$defs\": {\"node\": {\"items\": {\"$ref\": \"#/$defs/node\"}}}, \"$ref\": \"#/$defs/node\"}")).unwrap();
+        assert_eq!(Err(String::from("Cyclic $ref to tree.json#/$defs/node...")), resolve_refs(&tree, "tree.json", &HashMap::new()));
+
+        // the same schema used twice side by side is not a cycle
+        let pair = json(String::from("{\"$defs\": {\"n\": {\"type\": \"number\"}}, \"items\": [{\"$ref\": \"#/$defs/n\"}, {\"$ref\": \"#/$defs/n\"}]}")).unwrap();
+        assert!(resolve_refs(&pair, "pair.json", &HashMap::new()).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+use std::io::{self, BufRead, Split, Write};
+
+use crate::lexer::Span;
+use crate::{from_slice_with, JsonError, JsonExpression, ParserOptions};
+
+// The record separator that starts every record.
+const RS: u8 = 0x1E;
+
+// RFC 7464 JSON text sequences, where each record is an RS byte, a JSON text
+// and a line feed. Records are parsed one at a time and a bad one is reported
+// without ending the iteration. Runs of RS are skipped, and anything before
+// the first RS is an error. Spans in errors are relative to their record.
+pub struct Records<R: BufRead> {
+    records: Split<R>,
+    started: bool,
+    options: ParserOptions
+}
+
+pub fn records<R: BufRead>(reader: R) -> Records<R> {
+    return Records { records: reader.split(RS), started: false, options: ParserOptions::default() };
+}
+
+impl<R: BufRead> Records<R> {
+    // Replaces invalid UTF-8 in strings with U+FFFD rather than failing on
+    // the record.
+    pub fn lossy_utf8(mut self, on: bool) -> Self {
+        self.options.lossy_utf8 = on;
+        return self;
+    }
+}
+
+// A record may have been cut short when it was written. For a container or
+// string that is a syntax error anyway, but a number, true, false or null
+// still parses, so as the RFC asks those have to end in whitespace.
+fn truncated(record: &[u8]) -> bool {
+    return match record.last() {
+        Some(last) => !last.is_ascii_whitespace() && !matches!(last, b'}' | b']' | b'"'),
+        None => false
+    };
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<JsonExpression, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(JsonError::Io(format!("Could not read record: {}...", e))))
+            };
+            let blank = record.iter().all(u8::is_ascii_whitespace);
+            if !self.started {
+                self.started = true;
+                if !blank {
+                    return Some(Err(JsonError::UnexpectedToken(String::from("Expected record separator..."), Span::default())));
+                }
+                continue;
+            }
+            if blank {
+                continue;
+            }
+            if truncated(&record) {
+                return Some(Err(JsonError::UnexpectedEndOfInput));
+            }
+            return Some(from_slice_with(&record, &self.options));
+        }
+    }
+}
+
+// Writes a value as one record: RS, the compact JSON and a line feed.
+pub fn write_record<W: Write>(out: &mut W, value: &JsonExpression) -> io::Result<()> {
+    return writeln!(out, "\x1e{}", value);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::seq::{records, write_record};
+    use crate::{json, JsonError, JsonExpression};
+
+    #[test]
+    fn round_trip() {
+        let values = [json(String::from("{\"a\": [1, \"\\u001e\"]}")).unwrap(), JsonExpression::Number(2.0), JsonExpression::Null];
+        let mut out = Vec::new();
+        for value in &values {
+            write_record(&mut out, value).unwrap();
+        }
+        assert_eq!(b"\x1e{\"a\":[1,\"\\u001e\"]}\n\x1e2\n\x1enull\n", out.as_slice());
+
+        let read: Vec<JsonExpression> = records(out.as_slice()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(values.to_vec(), read);
+    }
+
+    #[test]
+    fn reports_bad_records() {
+        let mut read = records("\x1e\x1e[1]\n\x1e{\"a\" 1}\n\x1e123\x1etrue\n\x1e\"s\"".as_bytes());
+        assert_eq!(Some(Ok(json(String::from("[1]")).unwrap())), read.next());
+        assert!(matches!(read.next(), Some(Err(JsonError::UnexpectedToken(..)))));
+        // cut short, it might have been 1234
+        assert_eq!(Some(Err(JsonError::UnexpectedEndOfInput)), read.next());
+        assert_eq!(Some(Ok(JsonExpression::Bool(true))), read.next());
+        assert_eq!(Some(Ok(JsonExpression::from("s"))), read.next());
+        assert_eq!(None, read.next());
+
+        assert!(matches!(records("[1]\n".as_bytes()).next(), Some(Err(JsonError::UnexpectedToken(..)))));
+    }
+}
@@ -0,0 +1,425 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::JsonExpression;
+
+// The two character escape for a byte, if it has one. Other control
+// characters are written as \u00xx and everything else as it is.
+pub(crate) fn short_escape(b: u8) -> Option<&'static str> {
+    match b {
+        b'"' => return Some("\\\""),
+        b'\\' => return Some("\\\\"),
+        b'\n' => return Some("\\n"),
+        b'\r' => return Some("\\r"),
+        b'\t' => return Some("\\t"),
+        0x08 => return Some("\\b"),
+        0x0c => return Some("\\f"),
+        _ => return None
+    }
+}
+
+pub fn escape_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        let escape = if c.is_ascii() { short_escape(c as u8) } else { None };
+        match escape {
+            Some(escape) => out.push_str(escape),
+            None if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            None => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// How numbers are written. The default is the shortest form that reads back
+// as the same f64, switching to an exponent for very large or small values
+// and printing integral values as `1`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NumberFormat {
+    // always print this many digits after the point, rounding as needed
+    pub decimals: Option<usize>,
+    // print integral values as `1.0`, only applies when decimals is None
+    pub integral_point: bool,
+    // magnitudes of at least 10^n, or non zero ones below 10^-n, are written
+    // in scientific notation
+    pub exponent_threshold: Option<i32>
+}
+
+pub(crate) fn write_number(n: f64, format: &NumberFormat, out: &mut String) {
+    // NaN and the infinities have no JSON representation
+    if !n.is_finite() {
+        out.push_str("null");
+        return;
+    }
+
+    let start = out.len();
+    let scientific = match format.exponent_threshold {
+        Some(t) => n != 0.0 && (n.abs() >= 10f64.powi(t) || n.abs() < 10f64.powi(-t)),
+        None => false
+    };
+    match (format.decimals, scientific) {
+        (Some(d), true) => write!(out, "{:.*e}", d, n).unwrap(),
+        (None, true) => write!(out, "{:e}", n).unwrap(),
+        (Some(d), false) => write!(out, "{:.*}", d, n).unwrap(),
+        (None, false) => {
+            // ryu finds the shortest round trip digits much faster than fmt
+            let mut buffer = ryu::Buffer::new();
+            let s = buffer.format_finite(n);
+            out.push_str(s.strip_suffix(".0").unwrap_or(s));
+        }
+    }
+
+    if format.integral_point && format.decimals.is_none() && !out[start..].contains('.') {
+        match out[start..].find('e') {
+            Some(e) => out.insert_str(start + e, ".0"),
+            None => out.push_str(".0")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrettyOptions {
+    pub indent: String,
+    // arrays and objects that fit on the rest of the line within this many
+    // columns are kept on one line, 0 puts every element on its own line
+    pub max_width: usize,
+    pub numbers: NumberFormat
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        return Self { indent: String::from("    "), max_width: 80, numbers: NumberFormat::default() };
+    }
+}
+
+// Length of the single line form of a container, or None once it is longer
+// than limit.
+fn inline_len(expression: &JsonExpression, numbers: &NumberFormat, limit: usize) -> Option<usize> {
+    let mut len: usize = 0;
+    match expression {
+        JsonExpression::Array(elements) => {
+            len += 2;
+            for (i, element) in elements.iter().enumerate() {
+                len += if i > 0 { 2 } else { 0 } + inline_len(element, numbers, limit.checked_sub(len)?)?;
+            }
+        },
+        JsonExpression::Object(pairs) => {
+            len += 2;
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                let mut quoted = String::new();
+                escape_string(key, &mut quoted);
+                len += if i > 0 { 2 } else { 0 } + quoted.chars().count() + 2;
+                len += inline_len(value, numbers, limit.checked_sub(len)?)?;
+            }
+        },
+        _ => {
+            let mut scalar = String::new();
+            write_expression(expression, &mut scalar, None, 0, numbers, 0);
+            len = scalar.chars().count();
+        }
+    }
+
+    if len > limit {
+        return None;
+    }
+    return Some(len);
+}
+
+fn write_inline(expression: &JsonExpression, numbers: &NumberFormat, out: &mut String) {
+    match expression {
+        JsonExpression::Array(elements) => {
+            out.push('[');
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_inline(element, numbers, out);
+            }
+            out.push(']');
+        },
+        JsonExpression::Object(pairs) => {
+            out.push('{');
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                escape_string(key, out);
+                out.push_str(": ");
+                write_inline(value, numbers, out);
+            }
+            out.push('}');
+        },
+        _ => write_expression(expression, out, None, 0, numbers, 0),
+    }
+}
+
+// Whether a non empty container fits on the current line, leaving room for a
+// comma after it.
+fn fits(expression: &JsonExpression, out: &str, width: usize, numbers: &NumberFormat) -> bool {
+    if width == 0 {
+        return false;
+    }
+
+    let line_start = out.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = out[line_start..].chars().count();
+    return match width.checked_sub(column + 1) {
+        Some(limit) => inline_len(expression, numbers, limit).is_some(),
+        None => false
+    };
+}
+
+// indent is None for compact output, otherwise the string repeated once per
+// level of nesting. With an indent, containers that fit within width columns
+// stay on one line.
+pub(crate) fn write_expression(expression: &JsonExpression, out: &mut String, indent: Option<&str>, width: usize, numbers: &NumberFormat, depth: usize) {
+    if indent.is_some() && matches!(expression, JsonExpression::Array(_) | JsonExpression::Object(_)) && fits(expression, out, width, numbers) {
+        write_inline(expression, numbers, out);
+        return;
+    }
+
+    match expression {
+        JsonExpression::Number(n) => write_number(*n, numbers, out),
+        JsonExpression::String(s) => escape_string(s, out),
+        JsonExpression::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        JsonExpression::Null => out.push_str("null"),
+        JsonExpression::Array(elements) => {
+            if elements.is_empty() {
+                out.push_str("[]");
+                return;
+            }
+
+            out.push('[');
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, depth + 1);
+                write_expression(element, out, indent, width, numbers, depth + 1);
+            }
+            write_newline(out, indent, depth);
+            out.push(']');
+        },
+        JsonExpression::Object(pairs) => {
+            if pairs.is_empty() {
+                out.push_str("{}");
+                return;
+            }
+
+            out.push('{');
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_newline(out, indent, depth + 1);
+                escape_string(key, out);
+                out.push(':');
+                if indent.is_some() {
+                    out.push(' ');
+                }
+                write_expression(value, out, indent, width, numbers, depth + 1);
+            }
+            write_newline(out, indent, depth);
+            out.push('}');
+        },
+    }
+}
+
+// Keys sorted by their bytes with only the last of any duplicate kept, no
+// whitespace and numbers in their shortest form, so equal content always
+// gives the same text whatever its original member order and formatting.
+fn write_canonical(expression: &JsonExpression, out: &mut String) {
+    match expression {
+        JsonExpression::Array(elements) => {
+            out.push('[');
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(element, out);
+            }
+            out.push(']');
+        },
+        JsonExpression::Object(pairs) => {
+            // reversed before a stable sort, so the first of each run of equal
+            // keys is the last one in the object and dedup keeps it
+            let mut members: Vec<&(String, Box<JsonExpression>)> = pairs.iter().rev().collect();
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+            members.dedup_by(|a, b| a.0 == b.0);
+
+            out.push('{');
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                escape_string(key, out);
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        },
+        _ => write_expression(expression, out, None, 0, &NumberFormat::default(), 0)
+    }
+}
+
+fn write_newline(out: &mut String, indent: Option<&str>, depth: usize) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        for _ in 0..depth {
+            out.push_str(indent);
+        }
+    }
+}
+
+impl JsonExpression {
+    pub fn to_string_pretty(&self) -> String {
+        let mut out = String::new();
+        write_expression(self, &mut out, Some("    "), 0, &NumberFormat::default(), 0);
+        return out;
+    }
+
+    pub fn to_string_pretty_with(&self, options: &PrettyOptions) -> String {
+        let mut out = String::new();
+        write_expression(self, &mut out, Some(&options.indent), options.max_width, &options.numbers, 0);
+        return out;
+    }
+
+    pub fn to_string_canonical(&self) -> String {
+        let mut out = String::new();
+        write_canonical(self, &mut out);
+        return out;
+    }
+
+    // Compact output with numbers written as the format asks.
+    pub fn to_string_with(&self, numbers: &NumberFormat) -> String {
+        let mut out = String::new();
+        write_expression(self, &mut out, None, 0, numbers, 0);
+        return out;
+    }
+
+    // Appends the compact form to out, so a loop serializing many values can
+    // clear and reuse one buffer instead of allocating a string for each.
+    pub fn to_string_into(&self, out: &mut String) {
+        write_expression(self, out, None, 0, &NumberFormat::default(), 0);
+    }
+
+    // Appends the compact form to buf, reusing its allocation. Writing goes
+    // through a String, so bytes already in buf have to be valid UTF-8 for
+    // that to be free; anything else is written to a new string and copied.
+    pub fn to_vec_into(&self, buf: &mut Vec<u8>) {
+        match String::from_utf8(std::mem::take(buf)) {
+            Ok(mut out) => {
+                self.to_string_into(&mut out);
+                *buf = out.into_bytes();
+            },
+            Err(e) => {
+                *buf = e.into_bytes();
+                buf.extend_from_slice(self.to_string().as_bytes());
+            }
+        }
+    }
+}
+
+// Display gives the compact form, so `to_string()` is the minimal serialization
+impl fmt::Display for JsonExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write_expression(self, &mut out, None, 0, &NumberFormat::default(), 0);
+        return f.write_str(&out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::serialize::{NumberFormat, PrettyOptions};
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn compact() {
+        let expr = json(String::from("{\"a\": [1, 2.5, true, null], \"b\": {}}")).unwrap();
+        assert_eq!("{\"a\":[1,2.5,true,null],\"b\":{}}", expr.to_string());
+    }
+
+    #[test]
+    fn pretty() {
+        let expr = json(String::from("{\"a\": [1], \"b\": \"c\"}")).unwrap();
+        assert_eq!("{\n    \"a\": [\n        1\n    ],\n    \"b\": \"c\"\n}", expr.to_string_pretty());
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        let expr = json(String::from("[\"quote \\\" slash \\\\ tab \\t \\u00e9 \\ud83d\\ude00\"]")).unwrap();
+        assert_eq!(
+            JsonExpression::Array(vec![Box::new(JsonExpression::from("quote \" slash \\ tab \t é 😀"))]),
+            expr
+        );
+        assert_eq!(expr, json(expr.to_string()).unwrap());
+        assert_eq!("\"\\u0001\"", JsonExpression::from("\u{1}").to_string());
+    }
+
+    #[test]
+    fn pretty_within_width() {
+        let expr = json(String::from("{\"short\": [1, 2, 3], \"point\": {\"x\": 1, \"y\": 2}, \"long\": [\"aaaaaaaaaa\", \"bbbbbbbbbb\", \"cccccccccc\"], \"empty\": []}")).unwrap();
+        let options = PrettyOptions { indent: String::from("  "), max_width: 30, ..PrettyOptions::default() };
+        assert_eq!(
+            "{\n  \"short\": [1, 2, 3],\n  \"point\": {\"x\": 1, \"y\": 2},\n  \"long\": [\n    \"aaaaaaaaaa\",\n    \"bbbbbbbbbb\",\n    \"cccccccccc\"\n  ],\n  \"empty\": []\n}",
+            expr.to_string_pretty_with(&options)
+        );
+
+        let wide = PrettyOptions { max_width: 200, ..PrettyOptions::default() };
+        assert_eq!(expr, json(expr.to_string_pretty_with(&wide)).unwrap());
+        assert!(!expr.to_string_pretty_with(&wide).contains('\n'));
+    }
+
+    #[test]
+    fn canonical() {
+        let expr = json(String::from("{\"b\": [1.0, {\"z\": null, \"a\": 2}], \"a\": 1, \"b\": 3, \"é\": true}")).unwrap();
+        assert_eq!("{\"a\":1,\"b\":3,\"é\":true}", expr.to_string_canonical());
+
+        let nested = json(String::from("[{\"z\": 1e2, \"a\": \"x\"}]")).unwrap();
+        assert_eq!("[{\"a\":\"x\",\"z\":100}]", nested.to_string_canonical());
+
+        let repeated = JsonExpression::Object((0..10_000).map(|i| (format!("k{}", i % 3), Box::new(JsonExpression::Number(i as f64)))).collect());
+        assert_eq!("{\"k0\":9999,\"k1\":9997,\"k2\":9998}", repeated.to_string_canonical());
+    }
+
+    #[test]
+    fn number_formats() {
+        let expr = json(String::from("[1, 2.5, -0.125, 1500000, 0.0000002, 0]")).unwrap();
+        assert_eq!("[1,2.5,-0.125,1500000,2e-7,0]", expr.to_string());
+
+        let fixed = NumberFormat { decimals: Some(2), ..NumberFormat::default() };
+        assert_eq!("[1.00,2.50,-0.12,1500000.00,0.00,0.00]", expr.to_string_with(&fixed));
+
+        let point = NumberFormat { integral_point: true, exponent_threshold: Some(6), ..NumberFormat::default() };
+        assert_eq!("[1.0,2.5,-0.125,1.5e6,2.0e-7,0.0]", expr.to_string_with(&point));
+
+        let options = PrettyOptions { numbers: point, ..PrettyOptions::default() };
+        assert_eq!("[1.0, 2.5, -0.125, 1.5e6, 2.0e-7, 0.0]", expr.to_string_pretty_with(&options));
+        assert_eq!(expr, json(expr.to_string_pretty_with(&options)).unwrap());
+
+        let extremes = json(String::from("[1e300, -1e-300, 0.1, 123456789012345680000]")).unwrap();
+        assert_eq!("[1e300,-1e-300,0.1,1.2345678901234568e20]", extremes.to_string());
+        assert_eq!(extremes, json(extremes.to_string()).unwrap());
+    }
+
+    #[test]
+    fn into_buffers() {
+        let expr = json(String::from("{\"a\": [1, \"é\"]}")).unwrap();
+        let mut out = String::from("x=");
+        expr.to_string_into(&mut out);
+        assert_eq!("x={\"a\":[1,\"é\"]}", out);
+
+        let mut buf: Vec<u8> = Vec::with_capacity(64);
+        let capacity = buf.capacity();
+        for _ in 0..3 {
+            buf.clear();
+            expr.to_vec_into(&mut buf);
+            assert_eq!(expr.to_string().as_bytes(), buf.as_slice());
+        }
+        assert_eq!(capacity, buf.capacity());
+
+        let mut binary = vec![0xff];
+        JsonExpression::Null.to_vec_into(&mut binary);
+        assert_eq!(b"\xffnull", binary.as_slice());
+    }
+}
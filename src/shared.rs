@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+// An immutable tree whose strings and containers sit behind Arc, so cloning
+// is a reference count bump and clones can be sent to other threads. Editing
+// through `get_at_mut` or `set_at` copies only the containers on the path to
+// the change, everything else stays shared with the other clones.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedJson {
+    Number(f64),
+    String(Arc<str>),
+    Bool(bool),
+    Null,
+    Array(Arc<Vec<SharedJson>>),
+    Object(Arc<Vec<(Arc<str>, SharedJson)>>)
+}
+
+impl SharedJson {
+    pub fn get_at(&self, path: &JsonPath) -> Option<&SharedJson> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (current, segment) {
+                (SharedJson::Object(pairs), PathSegment::Key(key)) => &pairs.iter().rev().find(|(k, _)| **k == **key)?.1,
+                (SharedJson::Array(elements), PathSegment::Index(i)) => elements.get(*i)?,
+                _ => return None
+            };
+        }
+
+        return Some(current);
+    }
+
+    // Unshares every container along the path, copying any that another
+    // clone still points at.
+    pub fn get_at_mut(&mut self, path: &JsonPath) -> Option<&mut SharedJson> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (current, segment) {
+                (SharedJson::Object(pairs), PathSegment::Key(key)) => {
+                    let pairs = Arc::make_mut(pairs);
+                    let index = pairs.iter().rposition(|(k, _)| **k == **key)?;
+                    &mut pairs[index].1
+                },
+                (SharedJson::Array(elements), PathSegment::Index(i)) => Arc::make_mut(elements).get_mut(*i)?,
+                _ => return None
+            };
+        }
+
+        return Some(current);
+    }
+
+    // Replaces the value at path, or adds it when the last segment is a key
+    // missing from an object. Returns false when the path leads nowhere.
+    pub fn set_at(&mut self, path: &JsonPath, value: SharedJson) -> bool {
+        let mut parent = path.clone();
+        let last = match parent.pop() {
+            Some(last) => last,
+            None => {
+                *self = value;
+                return true;
+            }
+        };
+
+        match (self.get_at_mut(&parent), last) {
+            (Some(SharedJson::Object(pairs)), PathSegment::Key(key)) => {
+                let pairs = Arc::make_mut(pairs);
+                match pairs.iter().rposition(|(k, _)| **k == *key) {
+                    Some(index) => pairs[index].1 = value,
+                    None => pairs.push((Arc::from(key), value))
+                }
+                return true;
+            },
+            (Some(SharedJson::Array(elements)), PathSegment::Index(i)) if i < elements.len() => {
+                Arc::make_mut(elements)[i] = value;
+                return true;
+            },
+            _ => return false
+        }
+    }
+
+    // Whether both are the same node rather than just equal. Scalars other
+    // than strings are never shared.
+    pub fn ptr_eq(&self, other: &SharedJson) -> bool {
+        match (self, other) {
+            (SharedJson::String(a), SharedJson::String(b)) => return Arc::ptr_eq(a, b),
+            (SharedJson::Array(a), SharedJson::Array(b)) => return Arc::ptr_eq(a, b),
+            (SharedJson::Object(a), SharedJson::Object(b)) => return Arc::ptr_eq(a, b),
+            _ => return false
+        }
+    }
+}
+
+impl From<&JsonExpression> for SharedJson {
+    fn from(expression: &JsonExpression) -> Self {
+        match expression {
+            JsonExpression::Number(n) => return SharedJson::Number(*n),
+            JsonExpression::String(s) => return SharedJson::String(Arc::from(s.as_str())),
+            JsonExpression::Bool(b) => return SharedJson::Bool(*b),
+            JsonExpression::Null => return SharedJson::Null,
+            JsonExpression::Array(elements) => {
+                return SharedJson::Array(Arc::new(elements.iter().map(|e| SharedJson::from(e.as_ref())).collect()));
+            },
+            JsonExpression::Object(pairs) => {
+                return SharedJson::Object(Arc::new(pairs.iter().map(|(k, v)| (Arc::from(k.as_str()), SharedJson::from(v.as_ref()))).collect()));
+            }
+        }
+    }
+}
+
+impl From<JsonExpression> for SharedJson {
+    fn from(expression: JsonExpression) -> Self {
+        return SharedJson::from(&expression);
+    }
+}
+
+impl From<&SharedJson> for JsonExpression {
+    fn from(shared: &SharedJson) -> Self {
+        match shared {
+            SharedJson::Number(n) => return JsonExpression::Number(*n),
+            SharedJson::String(s) => return JsonExpression::String(String::from(&**s)),
+            SharedJson::Bool(b) => return JsonExpression::Bool(*b),
+            SharedJson::Null => return JsonExpression::Null,
+            SharedJson::Array(elements) => {
+                return JsonExpression::Array(elements.iter().map(|e| Box::new(JsonExpression::from(e))).collect());
+            },
+            SharedJson::Object(pairs) => {
+                return JsonExpression::Object(pairs.iter().map(|(k, v)| (String::from(&**k), Box::new(JsonExpression::from(v)))).collect());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::shared::SharedJson;
+    use crate::{json, JsonExpression, JsonPath};
+
+    #[test]
+    fn edits_copy_only_the_path() {
+        let original = SharedJson::from(json(String::from("{\"a\": {\"b\": 1}, \"c\": [1, 2, 3]}")).unwrap());
+        let mut edited = original.clone();
+        assert!(edited.ptr_eq(&original));
+
+        assert!(edited.set_at(&"a.b".parse().unwrap(), SharedJson::Bool(true)));
+        assert!(edited.set_at(&"a.new".parse().unwrap(), SharedJson::Null));
+        assert!(!edited.set_at(&"c[5]".parse().unwrap(), SharedJson::Null));
+
+        let c: JsonPath = "c".parse().unwrap();
+        assert!(edited.get_at(&c).unwrap().ptr_eq(original.get_at(&c).unwrap()));
+        assert!(!edited.get_at(&"a".parse().unwrap()).unwrap().ptr_eq(original.get_at(&"a".parse().unwrap()).unwrap()));
+
+        assert_eq!(json(String::from("{\"a\": {\"b\": 1}, \"c\": [1, 2, 3]}")).unwrap(), JsonExpression::from(&original));
+        assert_eq!(json(String::from("{\"a\": {\"b\": true, \"new\": null}, \"c\": [1, 2, 3]}")).unwrap(), JsonExpression::from(&edited));
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let shared = SharedJson::from(json(String::from("[\"x\", {\"y\": 2}]")).unwrap());
+        let clone = shared.clone();
+        let handle = std::thread::spawn(move || JsonExpression::from(clone.get_at(&"[1].y".parse().unwrap()).unwrap()));
+        assert_eq!(JsonExpression::Number(2.0), handle.join().unwrap());
+    }
+}
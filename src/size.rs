@@ -0,0 +1,51 @@
+use std::mem::size_of;
+
+use crate::JsonExpression;
+
+impl JsonExpression {
+    // Approximate heap bytes owned by this value: string and Vec capacities
+    // plus the boxed child nodes, not counting the value itself or allocator
+    // overhead. Good enough for cache budgets, not an exact accounting.
+    pub fn deep_size_of(&self) -> usize {
+        match self {
+            JsonExpression::String(s) => return s.capacity(),
+            JsonExpression::Array(elements) => {
+                let mut size = elements.capacity() * size_of::<Box<JsonExpression>>();
+                for element in elements {
+                    size += size_of::<JsonExpression>() + element.deep_size_of();
+                }
+                return size;
+            },
+            JsonExpression::Object(pairs) => {
+                let mut size = pairs.capacity() * size_of::<(String, Box<JsonExpression>)>();
+                for (key, value) in pairs {
+                    size += key.capacity() + size_of::<JsonExpression>() + value.deep_size_of();
+                }
+                return size;
+            },
+            _ => return 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn counts_children_and_strings() {
+        assert_eq!(0, JsonExpression::Number(1.0).deep_size_of());
+        assert_eq!(5, JsonExpression::String(String::from("hello")).deep_size_of());
+
+        let node = size_of::<JsonExpression>();
+        let array = JsonExpression::Array(vec![Box::new(JsonExpression::Number(1.0)), Box::new(JsonExpression::from("ab"))]);
+        assert_eq!(2 * size_of::<Box<JsonExpression>>() + 2 * node + 2, array.deep_size_of());
+
+        // parsed strings can have spare capacity, so only check the floor
+        let object = json(String::from("{\"key\": [null]}")).unwrap();
+        let pair = size_of::<(String, Box<JsonExpression>)>();
+        assert!(object.deep_size_of() >= pair + 3 + node + size_of::<Box<JsonExpression>>() + node);
+    }
+}
@@ -0,0 +1,81 @@
+use std::cmp::Ordering;
+
+use crate::JsonExpression;
+
+// Where a type sorts relative to the others, the same order jq uses.
+fn rank(value: &JsonExpression) -> u8 {
+    match value {
+        JsonExpression::Null => return 0,
+        JsonExpression::Bool(_) => return 1,
+        JsonExpression::Number(_) => return 2,
+        JsonExpression::String(_) => return 3,
+        JsonExpression::Array(_) => return 4,
+        JsonExpression::Object(_) => return 5
+    }
+}
+
+// Orders null, then false before true, then numbers by value, then strings
+// by code point, then arrays and then objects. Arrays and objects tie with
+// others of their type.
+fn compare(a: &JsonExpression, b: &JsonExpression) -> Ordering {
+    match (a, b) {
+        (JsonExpression::Bool(x), JsonExpression::Bool(y)) => return x.cmp(y),
+        (JsonExpression::Number(x), JsonExpression::Number(y)) => return x.total_cmp(y),
+        (JsonExpression::String(x), JsonExpression::String(y)) => return x.cmp(y),
+        _ => return rank(a).cmp(&rank(b))
+    }
+}
+
+// The value of a key in an element, the last one when it repeats.
+fn field<'a>(element: &'a JsonExpression, key: &str) -> Option<&'a JsonExpression> {
+    match element {
+        JsonExpression::Object(pairs) => return pairs.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_ref()),
+        _ => return None
+    }
+}
+
+impl JsonExpression {
+    // Sorts the array at a JSON Pointer by the value each element has for a
+    // key. Values of one type compare naturally and different types sort
+    // null, booleans, numbers, strings, arrays, objects. Elements without the
+    // key, including ones that are not objects, go after all the others. The
+    // sort is stable, so elements that tie keep their order.
+    pub fn sort_array_by_key(&mut self, pointer: &str, key: &str) -> Result<(), String> {
+        match self.pointer_mut(pointer) {
+            Some(JsonExpression::Array(elements)) => {
+                elements.sort_by(|a, b| match (field(a, key), field(b, key)) {
+                    (Some(x), Some(y)) => compare(x, y),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal
+                });
+                return Ok(());
+            },
+            Some(other) => return Err(format!("Expected array at {}, found {}...", pointer, other.type_name())),
+            None => return Err(format!("No value at {}...", pointer))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json;
+
+    #[test]
+    fn sorts_by_key() {
+        let mut doc = json(String::from("{\"rows\": [{\"n\": 10, \"id\": 1}, {\"n\": \"b\", \"id\": 2}, {\"id\": 3}, {\"n\": 9, \"id\": 4}, 7, {\"n\": null, \"id\": 5}, {\"n\": 9, \"id\": 6}, {\"n\": true, \"id\": 7}]}")).unwrap();
+        doc.sort_array_by_key("/rows", "n").unwrap();
+        let ids: Vec<String> = doc.pointer("/rows").unwrap().iter().map(|row| row.get_path("id").map(|id| id.to_string()).unwrap_or_default()).collect();
+        assert_eq!(vec!["5", "7", "4", "6", "1", "2", "3", ""], ids);
+    }
+
+    #[test]
+    fn needs_an_array() {
+        let mut doc = json(String::from("{\"a\": {}}")).unwrap();
+        assert_eq!(Err(String::from("Expected array at /a, found object...")), doc.sort_array_by_key("/a", "n"));
+        assert_eq!(Err(String::from("No value at /b...")), doc.sort_array_by_key("/b", "n"));
+        let mut root = json(String::from("[{\"n\": 2}, {\"n\": 1}]")).unwrap();
+        root.sort_array_by_key("", "n").unwrap();
+        assert_eq!(json(String::from("[{\"n\": 1}, {\"n\": 2}]")).unwrap(), root);
+    }
+}
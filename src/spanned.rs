@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+
+use crate::error::JsonError;
+use crate::lexer::{Span, Spanned, Token, TokenSource};
+use crate::options::ParserOptions;
+use crate::path::{JsonPath, PathSegment};
+use crate::{lexer_for, transcode, JsonExpression};
+
+// A parsed value where every node and every object key carries the span it
+// came from, so linters, validators and editors can point at the exact place
+// in question. A container spans from its opening bracket or brace to its
+// closing one. Spans are byte offsets into the input, or into its UTF-8 form
+// when the input was UTF-16.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedValue {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Null,
+    Array(Vec<Spanned<SpannedValue>>),
+    Object(Vec<(Spanned<String>, Spanned<SpannedValue>)>)
+}
+
+pub fn parse_spanned(bytes: &[u8]) -> Result<Spanned<SpannedValue>, JsonError> {
+    return parse_spanned_with(bytes, &ParserOptions::default());
+}
+
+// Follows the options the same way from_slice_with does.
+pub fn parse_spanned_with(bytes: &[u8], options: &ParserOptions) -> Result<Spanned<SpannedValue>, JsonError> {
+    let bytes = transcode(bytes)?;
+    let mut parser = SpannedParser { source: lexer_for(&bytes, options), options: *options, peeked: None, depth: 0 };
+    let root = parser.root()?;
+
+    match parser.peek() {
+        Err(JsonError::UnexpectedEndOfInput) => return Ok(root),
+        Err(e) => return Err(e),
+        Ok(_) => return Err(parser.error("Unexpected content after document..."))
+    }
+}
+
+// Builds the tree in one pass like Parser, without recovery. Spans are kept
+// for every node so there is nothing to gain from Parser's shared stacks.
+struct SpannedParser<S: TokenSource> {
+    source: S,
+    options: ParserOptions,
+    peeked: Option<Spanned<Token>>,
+    depth: usize
+}
+
+impl<S: TokenSource> SpannedParser<S> {
+    fn peek(&mut self) -> Result<&Spanned<Token>, JsonError> {
+        if self.peeked.is_none() {
+            self.peeked = self.source.next_token()?;
+        }
+        return self.peeked.as_ref().ok_or(JsonError::UnexpectedEndOfInput);
+    }
+
+    fn next(&mut self) -> Result<Spanned<Token>, JsonError> {
+        self.peek()?;
+        return Ok(self.peeked.take().unwrap());
+    }
+
+    // error pointing at the peeked token
+    fn error(&self, message: &str) -> JsonError {
+        let span = self.peeked.as_ref().map(|t| t.span).unwrap_or_default();
+        return JsonError::UnexpectedToken(String::from(message), span);
+    }
+
+    fn root(&mut self) -> Result<Spanned<SpannedValue>, JsonError> {
+        let scalar_root = self.options.scalar_root;
+        let token = self.peek()?;
+        if !scalar_root && !matches!(token.value, Token::LeftBrace | Token::LeftBracket) {
+            return Err(JsonError::expected("object or array", token));
+        }
+        return self.value();
+    }
+
+    fn value(&mut self) -> Result<Spanned<SpannedValue>, JsonError> {
+        let token = self.next()?;
+        let value = match token.value {
+            Token::LeftBracket => return self.array(token.span),
+            Token::LeftBrace => return self.object(token.span),
+            Token::NumberLiteral(n) => SpannedValue::Number(n),
+            Token::StringLiteral(s) => {
+                #[cfg(feature = "unicode")]
+                let s = match self.options.normalize_strings {
+                    Some(form) => form.apply(s),
+                    None => s
+                };
+                SpannedValue::String(s)
+            },
+            Token::BooleanLiteral(b) => SpannedValue::Bool(b),
+            Token::NullLiteral => SpannedValue::Null,
+            _ => return Err(JsonError::expected("value", &token))
+        };
+        return Ok(Spanned { value, span: token.span });
+    }
+
+    // open is the opening bracket or brace, which is blamed when it goes past
+    // the depth limit.
+    fn enter(&mut self, open: Span) -> Result<(), JsonError> {
+        if self.options.max_depth.is_some_and(|max| self.depth >= max) {
+            return Err(JsonError::UnexpectedToken(String::from("Maximum nesting depth exceeded..."), open));
+        }
+        self.depth += 1;
+        return Ok(());
+    }
+
+    // Called after each element. Consumes a comma and returns None when
+    // another element follows, or consumes the close and returns its span.
+    fn separator(&mut self, close: &Token, expected: &str) -> Result<Option<Span>, JsonError> {
+        let token = self.next()?;
+        if token.value == Token::Comma {
+            if self.options.trailing_commas && self.peek()?.value == *close {
+                return Ok(Some(self.next()?.span));
+            }
+            return Ok(None);
+        }
+        if token.value == *close {
+            return Ok(Some(token.span));
+        }
+        return Err(JsonError::expected(expected, &token));
+    }
+
+    fn array(&mut self, open: Span) -> Result<Spanned<SpannedValue>, JsonError> {
+        self.enter(open)?;
+        let mut elements = Vec::new();
+        let close = if self.peek()?.value == Token::RightBracket {
+            self.next()?.span
+        } else {
+            loop {
+                elements.push(self.value()?);
+                if let Some(close) = self.separator(&Token::RightBracket, "comma or right bracket")? {
+                    break close;
+                }
+            }
+        };
+        self.depth -= 1;
+        return Ok(Spanned { value: SpannedValue::Array(elements), span: join(open, close) });
+    }
+
+    fn object(&mut self, open: Span) -> Result<Spanned<SpannedValue>, JsonError> {
+        self.enter(open)?;
+        let mut members = Vec::new();
+        // only built when repeated keys are an error
+        let mut seen: Option<HashSet<String>> = if self.options.duplicate_keys { None } else { Some(HashSet::new()) };
+        let close = if self.peek()?.value == Token::RightBrace {
+            self.next()?.span
+        } else {
+            loop {
+                let key = self.key()?;
+                if seen.as_mut().is_some_and(|seen| !seen.insert(key.value.clone())) {
+                    return Err(JsonError::UnexpectedToken(format!("Duplicate key \"{}\"...", key.value), key.span));
+                }
+
+                let colon = self.next()?;
+                if colon.value != Token::Colon {
+                    return Err(JsonError::expected("colon", &colon));
+                }
+                members.push((key, self.value()?));
+
+                if let Some(close) = self.separator(&Token::RightBrace, "comma or right brace")? {
+                    break close;
+                }
+            }
+        };
+        self.depth -= 1;
+        return Ok(Spanned { value: SpannedValue::Object(members), span: join(open, close) });
+    }
+
+    fn key(&mut self) -> Result<Spanned<String>, JsonError> {
+        let token = self.next()?;
+        let key = match token.value {
+            Token::StringLiteral(key) => key,
+            _ => return Err(JsonError::expected("string key", &token))
+        };
+        #[cfg(feature = "unicode")]
+        let key = match self.options.normalize_keys {
+            Some(form) => form.apply(key),
+            None => key
+        };
+        return Ok(Spanned { value: key, span: token.span });
+    }
+}
+
+// From the start of open to the end of close, placed where open is.
+fn join(open: Span, close: Span) -> Span {
+    return Span { end: close.end, ..open };
+}
+
+impl Spanned<SpannedValue> {
+    // The node at the path, a repeated key resolves to its last value as
+    // JsonExpression::get_at does.
+    pub fn get_at(&self, path: &JsonPath) -> Option<&Spanned<SpannedValue>> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (&current.value, segment) {
+                (SpannedValue::Object(members), PathSegment::Key(key)) => &members.iter().rev().find(|(k, _)| k.value == *key)?.1,
+                (SpannedValue::Array(elements), PathSegment::Index(i)) => elements.get(*i)?,
+                _ => return None
+            };
+        }
+
+        return Some(current);
+    }
+}
+
+// Drops the spans.
+impl From<Spanned<SpannedValue>> for JsonExpression {
+    fn from(spanned: Spanned<SpannedValue>) -> Self {
+        match spanned.value {
+            SpannedValue::Number(n) => return JsonExpression::Number(n),
+            SpannedValue::String(s) => return JsonExpression::String(s),
+            SpannedValue::Bool(b) => return JsonExpression::Bool(b),
+            SpannedValue::Null => return JsonExpression::Null,
+            SpannedValue::Array(elements) => return JsonExpression::Array(elements.into_iter().map(|e| Box::new(e.into())).collect()),
+            SpannedValue::Object(members) => return JsonExpression::Object(members.into_iter().map(|(k, v)| (k.value, Box::new(v.into()))).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::spanned::{parse_spanned, parse_spanned_with, SpannedValue};
+    use crate::{json, JsonError, JsonExpression, JsonPath, ParserOptions, Span};
+
+    #[test]
+    fn spans_every_node() {
+        let source = "{\n  \"a\": [1, true],\n  \"é\": {\"b\": null}\n}";
+        let root = parse_spanned(source.as_bytes()).unwrap();
+        assert_eq!(Span { start: 0, end: source.len(), line: 1, column: 1 }, root.span);
+
+        let SpannedValue::Object(members) = &root.value else { panic!() };
+        assert_eq!(Span { start: 4, end: 7, line: 2, column: 3 }, members[0].0.span);
+        assert_eq!(Span { start: 9, end: 18, line: 2, column: 8 }, members[0].1.span);
+        assert_eq!("é", members[1].0.value);
+
+        let path: JsonPath = "a[1]".parse().unwrap();
+        let element = root.get_at(&path).unwrap();
+        assert_eq!((SpannedValue::Bool(true), Span { start: 13, end: 17, line: 2, column: 12 }), (element.value.clone(), element.span));
+        let nested = root.get_at(&"é.b".parse().unwrap()).unwrap();
+        assert_eq!((3, 14), (nested.span.line, nested.span.column));
+        assert!(root.get_at(&"a[2]".parse().unwrap()).is_none());
+
+        assert_eq!(json(String::from(source)).unwrap(), JsonExpression::from(root));
+    }
+
+    #[test]
+    fn follows_options() {
+        assert!(parse_spanned(b"[1,]").is_err());
+        assert!(parse_spanned_with(b"{'a': [1,],}", &ParserOptions::lenient()).is_ok());
+        assert_eq!(
+            Err(JsonError::UnexpectedToken(String::from("Duplicate key \"a\"..."), Span { start: 9, end: 12, line: 1, column: 10 })),
+            parse_spanned_with(b"{\"a\": 1, \"a\": 2}", &ParserOptions::strict())
+        );
+        assert!(parse_spanned_with(b"[[[]]]", &ParserOptions::default().max_depth(Some(2))).is_err());
+        assert!(parse_spanned_with(b"1", &ParserOptions::default().scalar_root(false)).is_err());
+        assert_eq!(
+            Err(JsonError::UnexpectedToken(String::from("Unexpected content after document..."), Span { start: 4, end: 5, line: 1, column: 5 })),
+            parse_spanned(b"[1] 2")
+        );
+        assert_eq!(Err(JsonError::UnexpectedEndOfInput), parse_spanned(b"{\"a\": [1"));
+    }
+}
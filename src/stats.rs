@@ -0,0 +1,110 @@
+use crate::visitor::{walk_array, walk_object, Visitor};
+use crate::JsonExpression;
+
+// Shape of a document, for capacity planning or turning away pathological
+// input once it has been parsed. Depth counts nested containers, so a scalar
+// is depth 0 and `[[]]` is depth 2. Byte counts are UTF-8 lengths.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub numbers: usize,
+    pub strings: usize,
+    pub bools: usize,
+    pub nulls: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    pub max_depth: usize,
+    pub string_bytes: usize,
+    pub key_bytes: usize,
+    pub largest_array: usize,
+    pub largest_object: usize
+}
+
+impl Stats {
+    pub fn nodes(&self) -> usize {
+        return self.numbers + self.strings + self.bools + self.nulls + self.arrays + self.objects;
+    }
+}
+
+struct Collector {
+    stats: Stats,
+    depth: usize
+}
+
+impl Visitor for Collector {
+    fn visit_object(&mut self, pairs: &[(String, Box<JsonExpression>)]) {
+        self.stats.objects += 1;
+        self.stats.largest_object = self.stats.largest_object.max(pairs.len());
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+        walk_object(self, pairs);
+        self.depth -= 1;
+    }
+
+    fn visit_key(&mut self, key: &str) {
+        self.stats.key_bytes += key.len();
+    }
+
+    fn visit_array(&mut self, elements: &[Box<JsonExpression>]) {
+        self.stats.arrays += 1;
+        self.stats.largest_array = self.stats.largest_array.max(elements.len());
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+        walk_array(self, elements);
+        self.depth -= 1;
+    }
+
+    fn visit_string(&mut self, value: &str) {
+        self.stats.strings += 1;
+        self.stats.string_bytes += value.len();
+    }
+
+    fn visit_number(&mut self, _value: f64) {
+        self.stats.numbers += 1;
+    }
+
+    fn visit_bool(&mut self, _value: bool) {
+        self.stats.bools += 1;
+    }
+
+    fn visit_null(&mut self) {
+        self.stats.nulls += 1;
+    }
+}
+
+impl JsonExpression {
+    pub fn stats(&self) -> Stats {
+        let mut collector = Collector { stats: Stats::default(), depth: 0 };
+        collector.visit_expression(self);
+        return collector.stats;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::Stats;
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn counts_document() {
+        let expr = json(String::from("{\"name\": \"é\", \"tags\": [1, 2, [true, null]], \"empty\": {}}")).unwrap();
+        assert_eq!(Stats {
+            numbers: 2,
+            strings: 1,
+            bools: 1,
+            nulls: 1,
+            arrays: 2,
+            objects: 2,
+            max_depth: 3,
+            string_bytes: 2,
+            key_bytes: 13,
+            largest_array: 3,
+            largest_object: 3
+        }, expr.stats());
+        assert_eq!(9, expr.stats().nodes());
+    }
+
+    #[test]
+    fn scalar_root() {
+        assert_eq!(Stats { nulls: 1, ..Stats::default() }, JsonExpression::Null.stats());
+    }
+}
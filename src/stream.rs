@@ -0,0 +1,764 @@
+use std::io::Read;
+use std::marker::PhantomData;
+
+use crate::error::JsonError;
+use crate::lexer::{is_delim, Lexer, Span, Spanned, Token, TokenSource};
+use crate::traits::FromJson;
+use crate::options::ParserOptions;
+use crate::path::{JsonPath, PathSegment};
+use crate::{from_slice_with, JsonExpression};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// A reader's bytes, read a chunk at a time and dropped once consumed, with
+// the position of what is left tracked in the whole input.
+pub(crate) struct Input<R: Read> {
+    reader: R,
+    pub buffer: Vec<u8>,
+    // bytes of buffer before this are finished with
+    pub pos: usize,
+    // how far past pos the caller has looked, kept in place across refills
+    pub scan: usize,
+    eof: bool,
+    // position of buffer[pos] in the whole input
+    offset: usize,
+    line: usize,
+    column: usize
+}
+
+impl<R: Read> Input<R> {
+    pub fn new(reader: R) -> Self {
+        return Self { reader, buffer: Vec::new(), pos: 0, scan: 0, eof: false, offset: 0, line: 1, column: 1 };
+    }
+
+    // A byte order mark may lead the document, only call this at the start.
+    pub fn skip_bom(&mut self) -> Result<(), JsonError> {
+        while self.buffer.len() < 3 && self.fill()? {}
+        if self.buffer.starts_with(b"\xef\xbb\xbf") {
+            self.pos = 3;
+            self.offset = 3;
+            self.scan = 3;
+        }
+        return Ok(());
+    }
+
+    // Reads another chunk, dropping the finished bytes first. False at the
+    // end of input.
+    pub fn fill(&mut self) -> Result<bool, JsonError> {
+        if self.eof {
+            return Ok(false);
+        }
+
+        self.buffer.drain(..self.pos);
+        self.scan -= self.pos;
+        self.pos = 0;
+
+        let start = self.buffer.len();
+        self.buffer.resize(start + CHUNK_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[start..]) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(JsonError::Io(e.to_string()))
+            }
+        };
+        self.buffer.truncate(start + read);
+        self.eof = read == 0;
+        return Ok(read > 0);
+    }
+
+    // Moves past n bytes, keeping the line and column up to date.
+    pub fn consume(&mut self, n: usize) {
+        for &b in &self.buffer[self.pos..self.pos + n] {
+            if b == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if b & 0xC0 != 0x80 {
+                self.column += 1;
+            }
+        }
+        self.pos += n;
+        self.offset += n;
+        self.scan = self.scan.max(self.pos);
+    }
+
+    // Skips whitespace and returns the next byte without consuming it.
+    pub fn peek_byte(&mut self) -> Result<Option<u8>, JsonError> {
+        loop {
+            while self.pos < self.buffer.len() {
+                match self.buffer[self.pos] {
+                    b' ' | b'\n' | b'\t' | b'\r' => self.consume(1),
+                    b => return Ok(Some(b))
+                }
+            }
+            if !self.fill()? {
+                return Ok(None);
+            }
+        }
+    }
+
+    pub fn span(&self, len: usize) -> Span {
+        return Span { start: self.offset, end: self.offset + len, line: self.line, column: self.column };
+    }
+
+    pub fn unexpected(&self, message: &str) -> JsonError {
+        return JsonError::UnexpectedToken(String::from(message), self.span(1));
+    }
+
+    // Errors from lexing or parsing bytes taken from pos come back relative
+    // to them, moves them to where they sit in the whole input.
+    pub fn relocate(&self, error: JsonError) -> JsonError {
+        let Some(span) = error.span() else {
+            return error;
+        };
+        let column = if span.line == 1 { self.column + span.column - 1 } else { span.column };
+        return error.with_span(Span {
+            start: self.offset + span.start,
+            end: self.offset + span.end,
+            line: self.line + span.line - 1,
+            column
+        });
+    }
+}
+
+// Tokens read from a stream, each one's bytes found by scanning ahead and
+// then lexed on their own, so only the token being read is held.
+pub struct ReaderTokens<R: Read> {
+    input: Input<R>,
+    started: bool
+}
+
+impl<R: Read> ReaderTokens<R> {
+    pub fn new(reader: R) -> Self {
+        return Self { input: Input::new(reader), started: false };
+    }
+
+    // Where the token starting at pos ends. A string runs to its closing
+    // quote, anything else to the next delimiter or the start of a string.
+    fn token_end(&mut self, first: u8) -> Result<usize, JsonError> {
+        let input = &mut self.input;
+        input.scan = input.pos + 1;
+        if matches!(first, b'{' | b'}' | b'[' | b']' | b',' | b':') {
+            return Ok(input.scan);
+        }
+
+        let mut escaped = false;
+        loop {
+            while input.scan < input.buffer.len() {
+                let b = input.buffer[input.scan];
+                if first != b'"' {
+                    if is_delim(b) || b == b'"' {
+                        return Ok(input.scan);
+                    }
+                } else if escaped {
+                    escaped = false;
+                } else if b == b'\\' {
+                    escaped = true;
+                } else if b == b'"' {
+                    return Ok(input.scan + 1);
+                }
+                input.scan += 1;
+            }
+            if !input.fill()? {
+                // a word can end with the input, a string cannot
+                if first != b'"' {
+                    return Ok(input.scan);
+                }
+                return Err(JsonError::UnexpectedEndOfInput);
+            }
+        }
+    }
+}
+
+impl<R: Read> TokenSource for ReaderTokens<R> {
+    fn next_token(&mut self) -> Result<Option<Spanned<Token>>, JsonError> {
+        if !self.started {
+            self.input.skip_bom()?;
+            self.started = true;
+        }
+        let first = match self.input.peek_byte()? {
+            Some(b) => b,
+            None => return Ok(None)
+        };
+
+        let end = self.token_end(first)?;
+        let input = &mut self.input;
+        let bytes = &input.buffer[input.pos..end];
+        let token = match Lexer::new(bytes).next_token() {
+            // the lexer would skip a byte order mark, which only belongs at
+            // the very start
+            Ok(Some(token)) if token.span.start == 0 => Spanned { value: token.value, span: input.span(bytes.len()) },
+            Ok(_) => return Err(JsonError::InvalidLiteral(String::from_utf8_lossy(bytes).into_owned(), input.span(bytes.len()))),
+            Err(e) => return Err(input.relocate(e))
+        };
+        input.consume(end - input.pos);
+        return Ok(Some(token));
+    }
+}
+
+// A step through a document, as EventReader hands them out. Value is any
+// token that is a complete value on its own: a number, string, bool or null.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(String),
+    Value(Token)
+}
+
+// Where the reader is within the grammar, the same states validate uses.
+#[derive(Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    ValueOrClose,
+    Key,
+    KeyOrClose,
+    After
+}
+
+// A pull parser: the events of one document in order, checked with the same
+// errors parsing gives, without building anything. Only the nesting is kept,
+// so memory use is bounded by the depth and the longest token however large
+// the input is. `path` is where the latest event happened: the value for a
+// start, end or value event, and the value to come for a key. Stops after the
+// first error.
+pub struct EventReader<S: TokenSource> {
+    tokens: S,
+    // the open containers, true for an object, with how many values each has
+    // had started in it
+    stack: Vec<(bool, usize)>,
+    expect: Expect,
+    path: JsonPath,
+    // the latest event's segment is dropped when the next event is read
+    leave: bool,
+    // tracking the path costs a copy of every key, which walk does without
+    track_path: bool,
+    done: bool
+}
+
+// The events of a document read from a stream.
+pub fn events<R: Read>(reader: R) -> EventReader<ReaderTokens<R>> {
+    return EventReader::new(ReaderTokens::new(reader));
+}
+
+impl<S: TokenSource> EventReader<S> {
+    pub fn new(tokens: S) -> Self {
+        return Self { tokens, stack: vec![], expect: Expect::Value, path: JsonPath::root(), leave: false, track_path: true, done: false };
+    }
+
+    pub fn path(&self) -> &JsonPath {
+        return &self.path;
+    }
+
+    // The path as a JSON Pointer, e.g. `/users/0/name`.
+    pub fn pointer(&self) -> String {
+        return self.path.to_pointer();
+    }
+
+    // A value is starting in the innermost container, counted and, in an
+    // array, given its index. In an object its key was already pushed.
+    fn enter(&mut self) {
+        if let Some((object, count)) = self.stack.last_mut() {
+            if !*object && self.track_path {
+                self.path.push(PathSegment::Index(*count));
+            }
+            *count += 1;
+        }
+    }
+
+    // The value that just ended leaves the path before the next event.
+    fn exit(&mut self) -> Expect {
+        self.leave = self.track_path && !self.stack.is_empty();
+        return Expect::After;
+    }
+
+    fn read(&mut self) -> Result<Option<Spanned<Event>>, JsonError> {
+        if self.leave {
+            self.path.pop();
+            self.leave = false;
+        }
+        let Spanned { value, span } = match self.tokens.next_token()? {
+            Some(token) => token,
+            None if self.expect == Expect::After && self.stack.is_empty() => return Ok(None),
+            None => return Err(JsonError::UnexpectedEndOfInput)
+        };
+
+        let (event, expect) = match (self.expect, value) {
+            (Expect::Value | Expect::ValueOrClose, Token::LeftBracket) => {
+                self.enter();
+                self.stack.push((false, 0));
+                (Event::StartArray, Expect::ValueOrClose)
+            },
+            (Expect::Value | Expect::ValueOrClose, Token::LeftBrace) => {
+                self.enter();
+                self.stack.push((true, 0));
+                (Event::StartObject, Expect::KeyOrClose)
+            },
+            (Expect::Value | Expect::ValueOrClose, value @ (Token::NumberLiteral(_) | Token::StringLiteral(_) | Token::BooleanLiteral(_) | Token::NullLiteral)) => {
+                self.enter();
+                (Event::Value(value), self.exit())
+            },
+            (Expect::ValueOrClose | Expect::After, Token::RightBracket) if matches!(self.stack.last(), Some((false, _))) => {
+                self.stack.pop();
+                (Event::EndArray, self.exit())
+            },
+            (Expect::KeyOrClose | Expect::After, Token::RightBrace) if matches!(self.stack.last(), Some((true, _))) => {
+                self.stack.pop();
+                (Event::EndObject, self.exit())
+            },
+            (Expect::Value | Expect::ValueOrClose, value) => return Err(JsonError::expected("value", &Spanned { value, span })),
+            (Expect::Key | Expect::KeyOrClose, Token::StringLiteral(key)) => {
+                match self.tokens.next_token()? {
+                    Some(Spanned { value: Token::Colon, .. }) => {},
+                    Some(other) => return Err(JsonError::expected("colon", &other)),
+                    None => return Err(JsonError::UnexpectedEndOfInput)
+                }
+                if self.track_path {
+                    self.path.push(PathSegment::Key(key.clone()));
+                }
+                (Event::Key(key), Expect::Value)
+            },
+            (Expect::Key | Expect::KeyOrClose, value) => return Err(JsonError::expected("string key", &Spanned { value, span })),
+            (Expect::After, value) => {
+                self.expect = match (self.stack.last(), value) {
+                    (None, _) => return Err(JsonError::UnexpectedToken(String::from("Unexpected content after document..."), span)),
+                    (Some((true, _)), Token::Comma) => Expect::Key,
+                    (Some((false, _)), Token::Comma) => Expect::Value,
+                    (Some((true, _)), value) => return Err(JsonError::expected("comma or right brace", &Spanned { value, span })),
+                    (Some((false, _)), value) => return Err(JsonError::expected("comma or right bracket", &Spanned { value, span }))
+                };
+                return self.read();
+            }
+        };
+        self.expect = expect;
+        return Ok(Some(Spanned { value: event, span }));
+    }
+
+    // The next event with its span in the input, None after the document.
+    pub fn next_event(&mut self) -> Result<Option<Spanned<Event>>, JsonError> {
+        if self.done {
+            return Ok(None);
+        }
+        let event = self.read();
+        self.done = !matches!(event, Ok(Some(_)));
+        return event;
+    }
+}
+
+impl<S: TokenSource> Iterator for EventReader<S> {
+    type Item = Result<Spanned<Event>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.next_event().transpose();
+    }
+}
+
+// Hands every event of a document read from the reader to `sink`, for the
+// consumers inside the crate that have no use for the path.
+pub(crate) fn walk<R: Read, F: FnMut(Event, Span) -> Result<(), JsonError>>(reader: R, sink: F) -> Result<(), JsonError> {
+    return walk_tokens(ReaderTokens::new(reader), sink);
+}
+
+// The same walk over tokens from anywhere, such as a lexer over a slice.
+pub(crate) fn walk_tokens<S: TokenSource, F: FnMut(Event, Span) -> Result<(), JsonError>>(tokens: S, mut sink: F) -> Result<(), JsonError> {
+    let mut events = EventReader { track_path: false, ..EventReader::new(tokens) };
+    while let Some(Spanned { value, span }) = events.next_event()? {
+        sink(value, span)?;
+    }
+    return Ok(());
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Start,
+    Elements,
+    // after the closing bracket, only whitespace may follow
+    End,
+    Done
+}
+
+// Reads the elements of a top level array one at a time, holding only the
+// element being read in memory. Each element's bytes are found by tracking
+// bracket depth and strings, then parsed on their own. Stops after the first
+// error. Spans in errors are relative to the whole input.
+pub struct ArrayElements<R: Read> {
+    input: Input<R>,
+    // the state where the current element has been scanned up to
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    state: State,
+    options: ParserOptions
+}
+
+pub fn array_elements<R: Read>(reader: R) -> ArrayElements<R> {
+    return ArrayElements {
+        input: Input::new(reader),
+        depth: 0,
+        in_string: false,
+        escaped: false,
+        state: State::Start,
+        options: ParserOptions::default()
+    };
+}
+
+impl<R: Read> ArrayElements<R> {
+    // Replaces invalid UTF-8 in strings with U+FFFD rather than failing on
+    // the element.
+    pub fn lossy_utf8(mut self, on: bool) -> Self {
+        self.options.lossy_utf8 = on;
+        return self;
+    }
+
+    // Finds where the element starting at pos ends, which is the first comma
+    // or closing bracket outside of any string or nested container.
+    fn element_end(&mut self) -> Result<usize, JsonError> {
+        let input = &mut self.input;
+        loop {
+            while input.scan < input.buffer.len() {
+                let b = input.buffer[input.scan];
+                if self.in_string {
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if b == b'\\' {
+                        self.escaped = true;
+                    } else if b == b'"' {
+                        self.in_string = false;
+                    }
+                } else {
+                    match b {
+                        b'"' => self.in_string = true,
+                        b'[' | b'{' => self.depth += 1,
+                        b',' | b']' if self.depth == 0 => return Ok(input.scan),
+                        b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+                input.scan += 1;
+            }
+            if !input.fill()? {
+                return Err(JsonError::UnexpectedEndOfInput);
+            }
+        }
+    }
+
+    fn next_element(&mut self) -> Result<Option<JsonExpression>, JsonError> {
+        loop {
+            match self.state {
+                State::Done => return Ok(None),
+                State::Start => {
+                    self.input.skip_bom()?;
+                    match self.input.peek_byte()? {
+                        Some(b'[') => self.input.consume(1),
+                        Some(_) => return Err(self.input.unexpected("Expected array...")),
+                        None => return Err(JsonError::UnexpectedEndOfInput)
+                    }
+                    match self.input.peek_byte()? {
+                        Some(b']') => {
+                            self.input.consume(1);
+                            self.state = State::End;
+                        },
+                        _ => self.state = State::Elements
+                    }
+                },
+                State::Elements => {
+                    if self.input.peek_byte()?.is_none() {
+                        return Err(JsonError::UnexpectedEndOfInput);
+                    }
+
+                    let end = self.element_end()?;
+                    let input = &mut self.input;
+                    let bytes = &input.buffer[input.pos..end];
+                    let len = bytes.iter().rposition(|b| !matches!(b, b' ' | b'\n' | b'\t' | b'\r')).map(|i| i + 1).unwrap_or(0);
+                    if len == 0 {
+                        let found = if input.buffer[end] == b',' { "comma" } else { "right bracket" };
+                        input.consume(end - input.pos);
+                        return Err(input.unexpected(&format!("Expected value, found {}...", found)));
+                    }
+
+                    let element = from_slice_with(&bytes[..len], &self.options).map_err(|e| input.relocate(e))?;
+                    let close = input.buffer[end] == b']';
+                    input.consume(end + 1 - input.pos);
+                    if close {
+                        self.state = State::End;
+                    }
+                    return Ok(Some(element));
+                },
+                State::End => {
+                    self.state = State::Done;
+                    if self.input.peek_byte()?.is_some() {
+                        return Err(self.input.unexpected("Unexpected content after document..."));
+                    }
+                    return Ok(None);
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for ArrayElements<R> {
+    type Item = Result<JsonExpression, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_element() {
+            Ok(element) => return element.map(Ok),
+            Err(e) => {
+                self.state = State::Done;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+// The elements of a top level array converted to T as they are read, so a
+// huge array of records takes only as much memory as one of them. A syntax
+// error ends the iteration like it does for array_elements, but an element
+// that parses and fails to convert is reported and the rest still follow.
+pub struct ArrayElementsAs<R: Read, T: FromJson> {
+    elements: ArrayElements<R>,
+    index: usize,
+    ty: PhantomData<fn() -> T>
+}
+
+pub fn array_elements_as<T: FromJson, R: Read>(reader: R) -> ArrayElementsAs<R, T> {
+    return ArrayElementsAs { elements: array_elements(reader), index: 0, ty: PhantomData };
+}
+
+impl<R: Read, T: FromJson> Iterator for ArrayElementsAs<R, T> {
+    type Item = Result<T, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        let index = self.index;
+        self.index += 1;
+        return Some(element.and_then(|value| {
+            return T::from_json(&value).map_err(|e| JsonError::Conversion(format!("Element {}: {}", index, e)));
+        }));
+    }
+}
+
+// Every top level value in an input holding several of them, as written by
+// `jq -c` or streaming APIs: `{"a":1}{"b":2}`, or the same apart on lines.
+// Only the document being read is held in memory. Containers and strings
+// need nothing between them, numbers and literals have to be split by
+// whitespace or a neighbour's bracket or quote. Stops after the first error.
+pub struct Documents<R: Read> {
+    input: Input<R>,
+    started: bool,
+    done: bool,
+    options: ParserOptions
+}
+
+pub fn documents<R: Read>(reader: R) -> Documents<R> {
+    return Documents { input: Input::new(reader), started: false, done: false, options: ParserOptions::default() };
+}
+
+impl<R: Read> Documents<R> {
+    // Replaces invalid UTF-8 in strings with U+FFFD rather than failing on
+    // the document.
+    pub fn lossy_utf8(mut self, on: bool) -> Self {
+        self.options.lossy_utf8 = on;
+        return self;
+    }
+
+    // Where the document starting at pos with `first` ends: after the bracket
+    // closing its outermost container, after the quote closing a string, or
+    // at the delimiter ending anything else.
+    fn document_end(&mut self, first: u8) -> Result<usize, JsonError> {
+        let input = &mut self.input;
+        input.scan = input.pos + 1;
+        let container = matches!(first, b'{' | b'[');
+        if !container && is_delim(first) {
+            return Ok(input.scan);
+        }
+
+        let mut depth = if container { 1 } else { 0 };
+        let mut in_string = first == b'"';
+        let mut escaped = false;
+        loop {
+            while input.scan < input.buffer.len() {
+                let b = input.buffer[input.scan];
+                input.scan += 1;
+                if in_string {
+                    if escaped {
+                        escaped = false;
+                    } else if b == b'\\' {
+                        escaped = true;
+                    } else if b == b'"' {
+                        in_string = false;
+                        if !container {
+                            return Ok(input.scan);
+                        }
+                    }
+                } else if !container {
+                    if is_delim(b) || b == b'"' {
+                        return Ok(input.scan - 1);
+                    }
+                } else {
+                    match b {
+                        b'"' => in_string = true,
+                        b'[' | b'{' => depth += 1,
+                        b']' | b'}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                return Ok(input.scan);
+                            }
+                        },
+                        _ => {}
+                    }
+                }
+            }
+            if !input.fill()? {
+                if !container && !in_string {
+                    return Ok(input.scan);
+                }
+                return Err(JsonError::UnexpectedEndOfInput);
+            }
+        }
+    }
+
+    fn next_document(&mut self) -> Result<Option<JsonExpression>, JsonError> {
+        if !self.started {
+            self.input.skip_bom()?;
+            self.started = true;
+        }
+        let first = match self.input.peek_byte()? {
+            Some(b) => b,
+            None => return Ok(None)
+        };
+
+        let end = self.document_end(first)?;
+        let input = &mut self.input;
+        let document = from_slice_with(&input.buffer[input.pos..end], &self.options).map_err(|e| input.relocate(e))?;
+        input.consume(end - input.pos);
+        return Ok(Some(document));
+    }
+}
+
+impl<R: Read> Iterator for Documents<R> {
+    type Item = Result<JsonExpression, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_document().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        return result;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Span;
+    use crate::stream::{array_elements, array_elements_as, documents, events, Event};
+    use crate::{json, JsonError, JsonExpression, Token};
+
+    // hands out a few bytes per read to exercise refilling
+    struct Trickle<'a>(&'a [u8]);
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            return Ok(n);
+        }
+    }
+
+    #[test]
+    fn yields_elements() {
+        let input = " [1, \"a,]\\\"b\", {\"c\": [2, {}]}, [], null ] ";
+        let elements: Vec<JsonExpression> = array_elements(Trickle(input.as_bytes())).collect::<Result<_, _>>().unwrap();
+        let expected = json(String::from(input)).unwrap();
+        assert_eq!(expected, JsonExpression::Array(elements.into_iter().map(Box::new).collect()));
+
+        assert_eq!(0, array_elements("[]".as_bytes()).count());
+    }
+
+    #[test]
+    fn reports_errors_in_place() {
+        let mut elements = array_elements("[1,\n  {\"a\" 2}]".as_bytes());
+        assert_eq!(Some(Ok(JsonExpression::Number(1.0))), elements.next());
+        assert_eq!(
+            Some(Err(JsonError::UnexpectedToken(String::from("Expected colon, found number..."), Span { start: 11, end: 12, line: 2, column: 8 }))),
+            elements.next()
+        );
+        assert_eq!(None, elements.next());
+
+        assert!(matches!(array_elements("{}".as_bytes()).next(), Some(Err(_))));
+        assert_eq!(Some(Err(JsonError::UnexpectedEndOfInput)), array_elements("[1, 2".as_bytes()).nth(1));
+        assert!(array_elements("[1,]".as_bytes()).nth(1).unwrap().is_err());
+        assert!(array_elements("[1] x".as_bytes()).nth(1).unwrap().is_err());
+    }
+
+    #[test]
+    fn converts_elements() {
+        let records: Vec<Vec<u8>> = array_elements_as(Trickle(b"[[1, 2], [], [3]]")).collect::<Result<_, _>>().unwrap();
+        assert_eq!(vec![vec![1, 2], vec![], vec![3]], records);
+
+        let mut elements = array_elements_as::<u8, _>("[1, 300, \"x\", 4, ]".as_bytes());
+        assert_eq!(Some(Ok(1)), elements.next());
+        assert_eq!(Some(Err(JsonError::Conversion(String::from("Element 1: Expected u8, found 300...")))), elements.next());
+        assert!(matches!(elements.next(), Some(Err(JsonError::Conversion(_)))));
+        assert_eq!(Some(Ok(4)), elements.next());
+        assert!(matches!(elements.next(), Some(Err(JsonError::UnexpectedToken(..)))));
+        assert_eq!(None, elements.next());
+    }
+
+    #[test]
+    fn lossy_utf8() {
+        let input: &[u8] = b"[\"ok\", \"bad \xff byte\"]";
+        assert_eq!(Some(Err(JsonError::InvalidUtf8(Span { start: 12, end: 13, line: 1, column: 13 }))), array_elements(input).nth(1));
+        let elements: Vec<JsonExpression> = array_elements(input).lossy_utf8(true).collect::<Result<_, _>>().unwrap();
+        assert_eq!(vec![JsonExpression::from("ok"), JsonExpression::from("bad \u{fffd} byte")], elements);
+    }
+
+    #[test]
+    fn concatenated_documents() {
+        let input = "{\"a\":1}{\"b\":[2, \"}\"]}\n3 \"x\"[]true\n-4.5e1 null";
+        let found: Vec<JsonExpression> = documents(Trickle(input.as_bytes())).collect::<Result<_, _>>().unwrap();
+        let expected = ["{\"a\": 1}", "{\"b\": [2, \"}\"]}", "3", "\"x\"", "[]", "true", "-45", "null"].map(|s| json(String::from(s)).unwrap());
+        assert_eq!(expected.to_vec(), found);
+        assert_eq!(0, documents(" \n".as_bytes()).count());
+
+        let mut bad = documents("[1]\n{\"a\" 2} [3]".as_bytes());
+        assert_eq!(Some(Ok(json(String::from("[1]")).unwrap())), bad.next());
+        assert_eq!(
+            Some(Err(JsonError::UnexpectedToken(String::from("Expected colon, found number..."), Span { start: 9, end: 10, line: 2, column: 6 }))),
+            bad.next()
+        );
+        assert_eq!(None, bad.next());
+        assert_eq!(vec![Err(JsonError::UnexpectedEndOfInput)], documents("{\"a\": [1".as_bytes()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn event_paths() {
+        let mut reader = events(Trickle(b"{\"a\": [1, {\"b/c\": null}], \"d\": {}}"));
+        let mut seen: Vec<(Event, String)> = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            seen.push((event.value, reader.pointer()));
+        }
+        let expected = [
+            (Event::StartObject, ""),
+            (Event::Key(String::from("a")), "/a"),
+            (Event::StartArray, "/a"),
+            (Event::Value(Token::NumberLiteral(1.0)), "/a/0"),
+            (Event::StartObject, "/a/1"),
+            (Event::Key(String::from("b/c")), "/a/1/b~1c"),
+            (Event::Value(Token::NullLiteral), "/a/1/b~1c"),
+            (Event::EndObject, "/a/1"),
+            (Event::EndArray, "/a"),
+            (Event::Key(String::from("d")), "/d"),
+            (Event::StartObject, "/d"),
+            (Event::EndObject, "/d"),
+            (Event::EndObject, "")
+        ].map(|(event, pointer)| (event, String::from(pointer)));
+        assert_eq!(expected.to_vec(), seen);
+
+        let errors: Vec<_> = events("[1 2]".as_bytes()).filter_map(Result::err).collect();
+        assert_eq!(json(String::from("[1 2]")).err().into_iter().collect::<Vec<_>>(), errors);
+    }
+}
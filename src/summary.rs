@@ -0,0 +1,75 @@
+use crate::JsonExpression;
+
+fn more(hidden: usize) -> JsonExpression {
+    return JsonExpression::String(format!("(+{} more)", hidden));
+}
+
+// depth is how many more levels of containers may be shown below this value.
+fn summarize_node(expression: &JsonExpression, max_items: usize, max_string_len: usize, depth: usize) -> JsonExpression {
+    let child = |value: &JsonExpression| -> Box<JsonExpression> {
+        match value {
+            JsonExpression::Array(elements) if !elements.is_empty() && depth == 0 => return Box::new(JsonExpression::from("[…]")),
+            JsonExpression::Object(pairs) if !pairs.is_empty() && depth == 0 => return Box::new(JsonExpression::from("{…}")),
+            _ => return Box::new(summarize_node(value, max_items, max_string_len, depth.saturating_sub(1)))
+        }
+    };
+
+    match expression {
+        JsonExpression::String(s) if s.chars().count() > max_string_len => {
+            let mut cut: String = s.chars().take(max_string_len).collect();
+            cut.push('…');
+            return JsonExpression::String(cut);
+        },
+        JsonExpression::Array(elements) => {
+            let mut kept: Vec<Box<JsonExpression>> = elements.iter().take(max_items).map(|e| child(e)).collect();
+            if elements.len() > max_items {
+                kept.push(Box::new(more(elements.len() - max_items)));
+            }
+            return JsonExpression::Array(kept);
+        },
+        JsonExpression::Object(pairs) => {
+            let mut kept: Vec<(String, Box<JsonExpression>)> = pairs.iter().take(max_items).map(|(k, v)| (k.clone(), child(v))).collect();
+            if pairs.len() > max_items {
+                kept.push((String::from("…"), Box::new(more(pairs.len() - max_items))));
+            }
+            return JsonExpression::Object(kept);
+        },
+        _ => return expression.clone()
+    }
+}
+
+impl JsonExpression {
+    // A cut down copy small enough for logs and error messages. Arrays and
+    // objects keep their first max_items elements or members, followed by a
+    // "(+n more)" element, or member keyed "…", saying how many were left
+    // out. Strings longer than max_string_len characters are cut there and
+    // end in "…". Non-empty containers more than max_depth levels below this
+    // value become the string "[…]" or "{…}". The result is still valid JSON
+    // but not the same shape, so it is only for reading.
+    pub fn summarize(&self, max_items: usize, max_string_len: usize, max_depth: usize) -> JsonExpression {
+        return summarize_node(self, max_items, max_string_len, max_depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json;
+
+    #[test]
+    fn truncates() {
+        let big = json(String::from("{\"id\": 7, \"blob\": \"abcdefghij\", \"items\": [1, 2, 3, 4, 5], \"nested\": {\"deep\": {\"x\": 1}, \"empty\": []}, \"extra\": true}")).unwrap();
+        let expected = json(String::from("{\"id\": 7, \"blob\": \"abcd…\", \"items\": [1, 2, 3, \"(+2 more)\"], \"…\": \"(+2 more)\"}")).unwrap();
+        assert_eq!(expected, big.summarize(3, 4, 5));
+
+        let nested = json(String::from("{\"items\": [[1, 2], [3], []], \"nested\": {\"deep\": {\"x\": 1}, \"empty\": {}}}")).unwrap();
+        let shallow = json(String::from("{\"items\": [\"[…]\", \"[…]\", []], \"nested\": {\"deep\": \"{…}\", \"empty\": {}}}")).unwrap();
+        assert_eq!(shallow, nested.summarize(5, 100, 1));
+    }
+
+    #[test]
+    fn leaves_small_values_alone() {
+        let small = json(String::from("{\"a\": [1, \"xy\", null], \"b\": {}}")).unwrap();
+        assert_eq!(small, small.summarize(3, 2, 2));
+        assert_eq!(json(String::from("[\"[…]\", 1]")).unwrap(), json(String::from("[[1], 1]")).unwrap().summarize(3, 2, 0));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::error::JsonError;
+use crate::lexer::{Lexer, Token};
+use crate::stream::{walk_tokens, Event};
+use crate::JsonExpression;
+
+// One entry of a tape. Containers know how many values they hold and the
+// index just past their last descendant, so a whole subtree can be skipped in
+// one step. An object's entries are its keys and values in turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Node {
+    Null,
+    Bool(bool),
+    Number(f64),
+    // a range of the tape's text
+    String { start: usize, end: usize },
+    Array { len: usize, end: usize },
+    Object { len: usize, end: usize }
+}
+
+// A document stored flat rather than as a tree of boxes: every value is one
+// entry in a single vector, in document order, and all string contents share
+// one buffer. Reading it walks memory front to back with no pointer chasing,
+// which suits large read only documents. Values are reached through TapeRef.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tape {
+    nodes: Vec<Node>,
+    text: String
+}
+
+// A value within a tape, cheap to copy.
+#[derive(Debug, Clone, Copy)]
+pub struct TapeRef<'t> {
+    tape: &'t Tape,
+    index: usize
+}
+
+impl Tape {
+    // Parses with the same rules and errors as from_slice.
+    pub fn parse(bytes: &[u8]) -> Result<Self, JsonError> {
+        let bytes = crate::transcode(bytes)?;
+        let mut tape = Tape { nodes: Vec::with_capacity(bytes.len() / 8), text: String::new() };
+        // the open containers, by index
+        let mut open: Vec<usize> = Vec::new();
+
+        walk_tokens(Lexer::new(&bytes), |event, _| {
+            // a key or a value starting counts towards its container
+            let counted = match &event {
+                Event::Key(_) => true,
+                Event::Value(_) | Event::StartArray | Event::StartObject => !matches!(open.last().map(|&i| tape.nodes[i]), Some(Node::Object { .. })),
+                Event::EndArray | Event::EndObject => false
+            };
+            if counted {
+                if let Some(Node::Array { len, .. } | Node::Object { len, .. }) = open.last().map(|&i| &mut tape.nodes[i]) {
+                    *len += 1;
+                }
+            }
+
+            match event {
+                Event::StartArray | Event::StartObject => {
+                    open.push(tape.nodes.len());
+                    tape.nodes.push(if matches!(event, Event::StartArray) { Node::Array { len: 0, end: 0 } } else { Node::Object { len: 0, end: 0 } });
+                },
+                Event::EndArray | Event::EndObject => {
+                    let end = tape.nodes.len();
+                    if let Some(Node::Array { end: e, .. } | Node::Object { end: e, .. }) = open.pop().map(|i| &mut tape.nodes[i]) {
+                        *e = end;
+                    }
+                },
+                Event::Key(s) | Event::Value(Token::StringLiteral(s)) => tape.push_string(&s),
+                Event::Value(Token::NumberLiteral(n)) => tape.nodes.push(Node::Number(n)),
+                Event::Value(Token::BooleanLiteral(b)) => tape.nodes.push(Node::Bool(b)),
+                Event::Value(_) => tape.nodes.push(Node::Null)
+            }
+            return Ok(());
+        })?;
+        return Ok(tape);
+    }
+
+    fn push_string(&mut self, s: &str) {
+        let start = self.text.len();
+        self.text.push_str(s);
+        self.nodes.push(Node::String { start, end: self.text.len() });
+    }
+
+    pub fn root(&self) -> TapeRef<'_> {
+        return TapeRef { tape: self, index: 0 };
+    }
+
+    // The index just past the value at `index` and everything inside it.
+    fn after(&self, index: usize) -> usize {
+        match self.nodes[index] {
+            Node::Array { end, .. } | Node::Object { end, .. } => return end,
+            _ => return index + 1
+        }
+    }
+}
+
+impl<'t> TapeRef<'t> {
+    fn node(&self) -> Node {
+        return self.tape.nodes[self.index];
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.node() {
+            Node::Null => return "null",
+            Node::Bool(_) => return "boolean",
+            Node::Number(_) => return "number",
+            Node::String { .. } => return "string",
+            Node::Array { .. } => return "array",
+            Node::Object { .. } => return "object"
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        return self.node() == Node::Null;
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.node() {
+            Node::Bool(b) => return Some(b),
+            _ => return None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.node() {
+            Node::Number(n) => return Some(n),
+            _ => return None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'t str> {
+        match self.node() {
+            Node::String { start, end } => return Some(&self.tape.text[start..end]),
+            _ => return None
+        }
+    }
+
+    // The number of elements or members, None for anything else.
+    pub fn len(&self) -> Option<usize> {
+        match self.node() {
+            Node::Array { len, .. } | Node::Object { len, .. } => return Some(len),
+            _ => return None
+        }
+    }
+
+    // True for an array or object with nothing in it.
+    pub fn is_empty(&self) -> bool {
+        return self.len() == Some(0);
+    }
+
+    // The elements of an array, nothing for anything else.
+    pub fn elements(&self) -> impl Iterator<Item = TapeRef<'t>> + 't {
+        let (tape, len) = match self.node() {
+            Node::Array { len, .. } => (self.tape, len),
+            _ => (self.tape, 0)
+        };
+        let mut next = self.index + 1;
+        return (0..len).map(move |_| {
+            let element = TapeRef { tape, index: next };
+            next = tape.after(next);
+            return element;
+        });
+    }
+
+    // The members of an object in order, nothing for anything else.
+    pub fn entries(&self) -> impl Iterator<Item = (&'t str, TapeRef<'t>)> + 't {
+        let (tape, len) = match self.node() {
+            Node::Object { len, .. } => (self.tape, len),
+            _ => (self.tape, 0)
+        };
+        let mut next = self.index + 1;
+        return (0..len).map(move |_| {
+            let key = TapeRef { tape, index: next }.as_str().unwrap_or_default();
+            let value = TapeRef { tape, index: next + 1 };
+            next = tape.after(next + 1);
+            return (key, value);
+        });
+    }
+
+    // The value of a key, the last one when it repeats as with lookups on a
+    // tree.
+    pub fn get(&self, key: &str) -> Option<TapeRef<'t>> {
+        return self.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v);
+    }
+
+    pub fn index(&self, i: usize) -> Option<TapeRef<'t>> {
+        return self.elements().nth(i);
+    }
+
+    // Builds the tree form of this value.
+    pub fn to_expression(&self) -> JsonExpression {
+        match self.node() {
+            Node::Null => return JsonExpression::Null,
+            Node::Bool(b) => return JsonExpression::Bool(b),
+            Node::Number(n) => return JsonExpression::Number(n),
+            Node::String { .. } => return JsonExpression::String(String::from(self.as_str().unwrap_or_default())),
+            Node::Array { .. } => return JsonExpression::Array(self.elements().map(|e| Box::new(e.to_expression())).collect()),
+            Node::Object { .. } => return JsonExpression::Object(self.entries().map(|(k, v)| (String::from(k), Box::new(v.to_expression()))).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tape::Tape;
+    use crate::json;
+
+    #[test]
+    fn navigates() {
+        let input = "{\"name\": \"ada\", \"langs\": [\"en\", {\"fr\": 2.5}, []], \"ok\": true, \"none\": null, \"name\": \"lovelace\"}";
+        let tape = Tape::parse(input.as_bytes()).unwrap();
+        let root = tape.root();
+        assert_eq!(Some(5), root.len());
+        assert_eq!(Some("lovelace"), root.get("name").and_then(|v| v.as_str()));
+        assert_eq!(Some(true), root.get("ok").and_then(|v| v.as_bool()));
+        assert!(root.get("none").unwrap().is_null());
+        assert!(root.get("missing").is_none());
+
+        let langs = root.get("langs").unwrap();
+        assert_eq!(vec!["string", "object", "array"], langs.elements().map(|e| e.type_name()).collect::<Vec<_>>());
+        assert_eq!(Some(2.5), langs.index(1).and_then(|v| v.get("fr")).and_then(|v| v.as_f64()));
+        assert!(langs.index(2).is_some_and(|v| v.is_empty()));
+        assert!(langs.index(3).is_none());
+
+        assert_eq!(json(String::from(input)).unwrap(), root.to_expression());
+    }
+
+    #[test]
+    fn errors_match_parser() {
+        for input in ["", "[1 2]", "{\"a\": }", "[1]]", "\"\\q\""] {
+            assert_eq!(json(String::from(input)).err(), Tape::parse(input.as_bytes()).err(), "{}", input);
+        }
+        assert_eq!(json(String::from("42")).unwrap(), Tape::parse(b"42").unwrap().root().to_expression());
+    }
+}
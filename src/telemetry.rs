@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::error::JsonError;
+use crate::lexer::{Spanned, Token, TokenSource};
+use crate::options::ParserOptions;
+use crate::parser::{Parser, Scratch};
+use crate::JsonExpression;
+
+// in microseconds
+static SLOW_PARSE: AtomicU64 = AtomicU64::new(100_000);
+
+// Parses that take at least this long are reported with a warning, 100ms
+// unless changed. Applies to every parse in the process.
+pub fn set_slow_parse_threshold(threshold: Duration) {
+    SLOW_PARSE.store(threshold.as_micros().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+}
+
+pub fn slow_parse_threshold() -> Duration {
+    return Duration::from_micros(SLOW_PARSE.load(Ordering::Relaxed));
+}
+
+// Passes tokens through, counting them.
+struct Counted<'c, S: TokenSource> {
+    source: S,
+    tokens: &'c mut u64
+}
+
+impl<S: TokenSource> TokenSource for Counted<'_, S> {
+    fn next_token(&mut self) -> Result<Option<Spanned<Token>>, JsonError> {
+        let token = self.source.next_token()?;
+        if token.is_some() {
+            *self.tokens += 1;
+        }
+        return Ok(token);
+    }
+}
+
+// Parses inside a `parse` span carrying the input size. A debug event marks
+// the start, another the end with the token count, time taken and any
+// error, and a warning follows when the parse was slow.
+pub(crate) fn parse<S: TokenSource>(bytes: usize, source: S, options: ParserOptions, scratch: &mut Scratch) -> Result<JsonExpression, JsonError> {
+    let span = tracing::debug_span!("parse", bytes);
+    let _entered = span.enter();
+    tracing::debug!("parse started");
+
+    let start = Instant::now();
+    let mut tokens: u64 = 0;
+    let mut parser = Parser::with_scratch(Counted { source, tokens: &mut tokens }, options, std::mem::take(scratch));
+    let result = parser.parse();
+    *scratch = parser.into_scratch();
+    let elapsed = start.elapsed();
+
+    let micros = elapsed.as_micros() as u64;
+    match &result {
+        Ok(_) => tracing::debug!(tokens, micros, "parse finished"),
+        Err(e) => tracing::debug!(tokens, micros, error = %e, "parse failed")
+    }
+    if elapsed >= slow_parse_threshold() {
+        tracing::warn!(bytes, tokens, micros, "slow parse");
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    use crate::json;
+
+    // counts events at each level
+    #[derive(Default)]
+    struct Counter {
+        debug: AtomicUsize,
+        warn: AtomicUsize
+    }
+
+    struct Shared(Arc<Counter>);
+
+    impl Subscriber for Shared {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            return true;
+        }
+
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            return Id::from_u64(1);
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            match *event.metadata().level() {
+                Level::WARN => self.0.warn.fetch_add(1, Ordering::Relaxed),
+                _ => self.0.debug.fetch_add(1, Ordering::Relaxed)
+            };
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn reports_parses() {
+        let counter = Arc::new(Counter::default());
+        tracing::subscriber::with_default(Shared(counter.clone()), || {
+            json(String::from("[1, 2, {\"a\": null}]")).unwrap();
+            assert!(json(String::from("[1,")).is_err());
+        });
+        assert_eq!(4, counter.debug.load(Ordering::Relaxed));
+        assert_eq!(0, counter.warn.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,101 @@
+use toml::{Table, Value};
+
+use crate::JsonExpression;
+
+// Datetimes become their RFC 3339 string and come back as plain strings, TOML
+// can't tell them apart from any other string on the way out. TOML has no
+// null so converting one is an error rather than silently dropping the key.
+
+fn from_toml_value(value: &Value) -> JsonExpression {
+    match value {
+        Value::String(s) => return JsonExpression::String(s.clone()),
+        Value::Integer(n) => return JsonExpression::Number(*n as f64),
+        Value::Float(n) => return JsonExpression::Number(*n),
+        Value::Boolean(b) => return JsonExpression::Bool(*b),
+        Value::Datetime(d) => return JsonExpression::String(d.to_string()),
+        Value::Array(elements) => return JsonExpression::Array(elements.iter().map(|e| Box::new(from_toml_value(e))).collect()),
+        Value::Table(table) => return from_toml_table(table),
+    }
+}
+
+fn from_toml_table(table: &Table) -> JsonExpression {
+    return JsonExpression::Object(table.iter().map(|(k, v)| (k.clone(), Box::new(from_toml_value(v)))).collect());
+}
+
+fn to_toml_value(expression: &JsonExpression) -> Result<Value, String> {
+    match expression {
+        JsonExpression::Number(n) => {
+            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 {
+                return Ok(Value::Integer(*n as i64));
+            }
+            return Ok(Value::Float(*n));
+        },
+        JsonExpression::String(s) => return Ok(Value::String(s.clone())),
+        JsonExpression::Bool(b) => return Ok(Value::Boolean(*b)),
+        JsonExpression::Null => return Err(String::from("TOML has no representation for null...")),
+        JsonExpression::Array(elements) => {
+            let mut out = Vec::new();
+            for element in elements {
+                out.push(to_toml_value(element)?);
+            }
+            return Ok(Value::Array(out));
+        },
+        JsonExpression::Object(pairs) => {
+            let mut table = Table::new();
+            for (key, value) in pairs {
+                table.insert(key.clone(), to_toml_value(value)?);
+            }
+            return Ok(Value::Table(table));
+        },
+    }
+}
+
+impl JsonExpression {
+    pub fn from_toml(source: &str) -> Result<JsonExpression, String> {
+        let table: Table = source.parse().map_err(|e: toml::de::Error| e.message().to_string())?;
+        return Ok(from_toml_table(&table));
+    }
+
+    // A TOML document is always a table, so only objects can be converted.
+    pub fn to_toml(&self) -> Result<String, String> {
+        match to_toml_value(self)? {
+            Value::Table(table) => return toml::to_string(&table).map_err(|e| e.to_string()),
+            _ => return Err(format!("Expected object for TOML document, found {}...", self.type_name()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn from_cargo_style_config() {
+        let expr = JsonExpression::from_toml("
+[package]
+name = \"jsonrs\"
+edition = \"2021\"
+released = 2023-01-02T03:04:05Z
+
+[[bin]]
+name = \"a\"
+test = false
+").unwrap();
+
+        assert_eq!(
+            json(String::from("{
+                \"package\": {\"name\": \"jsonrs\", \"edition\": \"2021\", \"released\": \"2023-01-02T03:04:05Z\"},
+                \"bin\": [{\"name\": \"a\", \"test\": false}]
+            }")).unwrap(),
+            expr
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let expr = json(String::from("{\"a\": 1, \"b\": 2.5, \"c\": [\"x\"], \"d\": {\"e\": true}}")).unwrap();
+        assert_eq!(Ok(expr.clone()), JsonExpression::from_toml(&expr.to_toml().unwrap()));
+        assert!(json(String::from("{\"a\": null}")).unwrap().to_toml().is_err());
+        assert!(JsonExpression::Number(1.0).to_toml().is_err());
+    }
+}
@@ -0,0 +1,338 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::path::{JsonPath, PathSegment};
+use crate::JsonExpression;
+
+// Conversion between Rust types and the value model. These are the traits
+// that typed extraction builds on, implement them for your own types to make
+// them usable anywhere a primitive is.
+pub trait ToJson {
+    fn to_json(&self) -> JsonExpression;
+}
+
+pub trait FromJson: Sized {
+    fn from_json(value: &JsonExpression) -> Result<Self, String>;
+
+    // The object keys this type reads, when it is flattened into a struct
+    // next to other flattened fields, so each of them is only given the keys
+    // the rest leave. Empty for types that take any key, like maps.
+    fn field_keys() -> Vec<&'static str> {
+        return Vec::new();
+    }
+}
+
+thread_local! {
+    // The path of the value being converted on this thread, and the last
+    // error that was given its path, which is passed up unchanged.
+    static LOCATION: RefCell<(JsonPath, Option<String>)> = RefCell::new((JsonPath::root(), None));
+}
+
+// Pops the segment read_at pushed, even when the read panics.
+struct Descent;
+
+impl Drop for Descent {
+    fn drop(&mut self) {
+        LOCATION.with_borrow_mut(|(path, reported)| {
+            path.pop();
+            if path.is_root() {
+                *reported = None;
+            }
+        });
+    }
+}
+
+// Reads the child of a container at `segment`. Containers read each child
+// through this so the conversion knows where it is as it descends, and an
+// error from deep in a structure is given the whole path once, where it
+// happens: `orders[3].items[0].price: Expected number, found string...`.
+pub fn read_at<T>(segment: PathSegment, read: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    LOCATION.with_borrow_mut(|(path, _)| path.push(segment));
+    let _descent = Descent;
+    return read().map_err(|error| LOCATION.with_borrow_mut(|(path, reported)| {
+        if reported.as_ref() == Some(&error) {
+            return error;
+        }
+        let error = format!("{}: {}", path, error);
+        *reported = Some(error.clone());
+        return error;
+    }));
+}
+
+// Edits to turn one string into the other, ignoring case, where an edit is
+// inserting, deleting or replacing a character or swapping two neighbours.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    // distances[i][j] is between the first i characters of a and the first j
+    // of b
+    let mut distances: Vec<Vec<usize>> = (0..=a.len()).map(|i| (0..=b.len()).map(|j| if i == 0 { j } else { i }).collect()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut best = (distances[i - 1][j] + 1).min(distances[i][j - 1] + 1).min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = best;
+        }
+    }
+    return distances[a.len()][b.len()];
+}
+
+// The candidate closest to a name that was not found, if one is near enough
+// to be a likely typo: at most one edit per three characters, and a change of
+// case alone always counts. The first wins a tie.
+pub fn suggest<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    let mut best: Option<(usize, &str)> = None;
+    for candidate in candidates.into_iter().filter(|c| *c != name) {
+        let distance = edit_distance(name, candidate);
+        if distance <= limit && best.is_none_or(|(d, _)| distance < d) {
+            best = Some((distance, candidate));
+        }
+    }
+    return best.map(|(_, candidate)| candidate);
+}
+
+// An error about a name that was not found, ending in a suggestion from the
+// candidates when there is one, e.g. `Missing field name, did you mean
+// nmae?...`. Derived FromJson impls build their key and variant errors with
+// it.
+pub fn did_you_mean<'a, I: IntoIterator<Item = &'a str>>(message: &str, name: &str, candidates: I) -> String {
+    match suggest(name, candidates) {
+        Some(candidate) => return format!("{}, did you mean {}?...", message, candidate),
+        None => return format!("{}...", message)
+    }
+}
+
+impl ToJson for JsonExpression {
+    fn to_json(&self) -> JsonExpression {
+        return self.clone();
+    }
+}
+
+impl FromJson for JsonExpression {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return Ok(value.clone());
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Bool(*self);
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return bool::try_from(value);
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::String(self.clone());
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::String(String::from(self));
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return String::try_from(value);
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Number(*self);
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return f64::try_from(value);
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Number(*self as f64);
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return f64::try_from(value).map(|n| n as f32);
+    }
+}
+
+// Integers go through i64/u64 and then narrow with a range check.
+macro_rules! integer_json {
+    ($($t:ty => $via:ty),*) => {
+        $(
+            impl ToJson for $t {
+                fn to_json(&self) -> JsonExpression {
+                    return JsonExpression::Number(*self as f64);
+                }
+            }
+
+            impl FromJson for $t {
+                fn from_json(value: &JsonExpression) -> Result<Self, String> {
+                    let n = <$via>::try_from(value)?;
+                    return <$t>::try_from(n).map_err(|_| format!("Expected {}, found {}...", stringify!($t), n));
+                }
+            }
+        )*
+    };
+}
+
+integer_json!(i8 => i64, i16 => i64, i32 => i64, i64 => i64, isize => i64, u8 => u64, u16 => u64, u32 => u64, u64 => u64, usize => u64);
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> JsonExpression {
+        match self {
+            Some(v) => return v.to_json(),
+            None => return JsonExpression::Null
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        match value {
+            JsonExpression::Null => return Ok(None),
+            _ => return T::from_json(value).map(Some)
+        }
+    }
+
+    fn field_keys() -> Vec<&'static str> {
+        return T::field_keys();
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Array(self.iter().map(|e| Box::new(e.to_json())).collect());
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        match value {
+            JsonExpression::Array(elements) => {
+                return elements.iter().enumerate().map(|(i, e)| read_at(PathSegment::Index(i), || T::from_json(e))).collect();
+            },
+            _ => return Err(format!("Expected array, found {}...", value.type_name()))
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for HashMap<String, T> {
+    fn to_json(&self) -> JsonExpression {
+        let mut pairs: Vec<(String, Box<JsonExpression>)> = self.iter()
+            .map(|(k, v)| (k.clone(), Box::new(v.to_json())))
+            .collect();
+
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        return JsonExpression::Object(pairs);
+    }
+}
+
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        match value {
+            JsonExpression::Object(pairs) => {
+                return pairs.iter().map(|(k, v)| Ok((k.clone(), read_at(PathSegment::Key(k.clone()), || T::from_json(v))?))).collect();
+            },
+            _ => return Err(format!("Expected object, found {}...", value.type_name()))
+        }
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> JsonExpression {
+        return (**self).to_json();
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> JsonExpression {
+        return (**self).to_json();
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return T::from_json(value).map(Box::new);
+    }
+}
+
+impl JsonExpression {
+    pub fn to<T: FromJson>(&self) -> Result<T, String> {
+        return T::from_json(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::traits::{did_you_mean, read_at, suggest, ToJson};
+    use crate::{json, PathSegment};
+
+    #[test]
+    fn nested_types() {
+        let expr = json(String::from("{\"a\": [1, 2], \"b\": [null, 3]}")).unwrap();
+        let typed: HashMap<String, Vec<Option<u8>>> = expr.to().unwrap();
+        assert_eq!(Some(&vec![Some(1), Some(2)]), typed.get("a"));
+        assert_eq!(Some(&vec![None, Some(3)]), typed.get("b"));
+        assert_eq!(json(String::from("{\"a\": [1, 2], \"b\": [null, 3]}")).unwrap(), typed.to_json());
+    }
+
+    #[test]
+    fn range_checked_integers() {
+        let expr = json(String::from("[300]")).unwrap();
+        assert_eq!(Err(String::from("[0]: Expected u8, found 300...")), expr.to::<Vec<u8>>());
+        assert_eq!(Ok(vec![300]), expr.to::<Vec<u16>>());
+    }
+
+    #[test]
+    fn error_paths() {
+        let expr = json(String::from("{\"orders\": [{\"price\": 1}, {\"price\": \"x\"}]}")).unwrap();
+        assert_eq!(
+            Err(String::from("orders[1].price: Expected number, found string...")),
+            expr.to::<HashMap<String, Vec<HashMap<String, f64>>>>()
+        );
+
+        // keys that would read as more than one are quoted
+        let expr = json(String::from("{\"orders\": [{\"unit price\": \"x\"}]}")).unwrap();
+        assert_eq!(
+            Err(String::from("orders[0][\"unit price\"]: Expected number, found string...")),
+            expr.to::<HashMap<String, Vec<HashMap<String, f64>>>>()
+        );
+        let expr = json(String::from("{\"a.b\": [\"x\"]}")).unwrap();
+        assert_eq!(Err(String::from("[\"a.b\"][0]: Expected number, found string...")), expr.to::<HashMap<String, Vec<f64>>>());
+
+        // a message that looks like it starts with a path is left alone
+        let read = read_at(PathSegment::Key(String::from("a")), || Err::<(), String>(String::from("Line 1, column 2: x...")));
+        assert_eq!(Err(String::from("a: Line 1, column 2: x...")), read);
+    }
+
+    #[test]
+    fn suggestions() {
+        let keys = ["name", "email", "timeout", "retries"];
+        assert_eq!(Some("name"), suggest("nmae", keys));
+        assert_eq!(Some("timeout"), suggest("timout", keys));
+        assert_eq!(Some("email"), suggest("Email", keys));
+        assert_eq!(None, suggest("id", keys));
+        assert_eq!(None, suggest("name", ["name"]));
+        assert_eq!("Missing field port, did you mean prot?...", did_you_mean("Missing field port", "port", ["prot", "host"]));
+        assert_eq!("Unknown variant x...", did_you_mean("Unknown variant x", "x", ["Circle"]));
+    }
+}
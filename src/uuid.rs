@@ -0,0 +1,71 @@
+use uuid::Uuid;
+
+use crate::{FromJson, JsonExpression, ToJson};
+
+// UUIDs are written in the hyphenated lowercase form. Reading accepts any
+// form the uuid crate parses (hyphenated, simple, braced or urn).
+impl JsonExpression {
+    pub fn as_uuid(&self) -> Result<Uuid, String> {
+        match self {
+            JsonExpression::String(s) => return Uuid::parse_str(s).map_err(|e| format!("Invalid UUID {:?}: {}...", s, e)),
+            _ => return Err(format!("Expected UUID string, found {}...", self.type_name()))
+        }
+    }
+}
+
+impl From<Uuid> for JsonExpression {
+    fn from(value: Uuid) -> Self {
+        return JsonExpression::String(value.hyphenated().to_string());
+    }
+}
+
+impl TryFrom<&JsonExpression> for Uuid {
+    type Error = String;
+
+    fn try_from(value: &JsonExpression) -> Result<Self, Self::Error> {
+        return value.as_uuid();
+    }
+}
+
+impl TryFrom<JsonExpression> for Uuid {
+    type Error = String;
+
+    fn try_from(value: JsonExpression) -> Result<Self, Self::Error> {
+        return value.as_uuid();
+    }
+}
+
+impl ToJson for Uuid {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::from(*self);
+    }
+}
+
+impl FromJson for Uuid {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        return value.as_uuid();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn round_trip() {
+        let id = Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        let expr = JsonExpression::from(id);
+        assert_eq!(JsonExpression::from("67e55044-10b1-426f-9247-bb680e5fe0c8"), expr);
+        assert_eq!(Ok(id), Uuid::try_from(&expr));
+    }
+
+    #[test]
+    fn malformed() {
+        let expr = json(String::from("{\"id\": \"not-a-uuid\"}")).unwrap();
+        let error = expr.values().next().unwrap().as_uuid().unwrap_err();
+        assert!(error.starts_with("Invalid UUID \"not-a-uuid\""));
+        assert!(JsonExpression::Null.as_uuid().is_err());
+    }
+}
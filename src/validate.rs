@@ -0,0 +1,140 @@
+use std::io::Read;
+
+use crate::error::JsonError;
+use crate::lexer::{Lexer, Spanned, Token, TokenSource};
+use crate::stream::ReaderTokens;
+
+// Where the validator is within the grammar, which decides the tokens allowed
+// next.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Value,
+    // just after `[`
+    ValueOrClose,
+    // after a comma in an object
+    Key,
+    // just after `{`
+    KeyOrClose,
+    // after a complete value
+    After
+}
+
+
+// Checks the input is a single well formed document without building it.
+// Strings are validated but never copied and nesting is tracked on a stack of
+// flags rather than by recursion, so the only allocation is that stack. Gives
+// the same result as parsing with the default options.
+pub fn validate(input: &str) -> Result<(), JsonError> {
+    return validate_slice(input.as_bytes());
+}
+
+pub fn validate_slice(bytes: &[u8]) -> Result<(), JsonError> {
+    let bytes = crate::transcode(bytes)?;
+    return validate_tokens(Lexer::new(&bytes).validating());
+}
+
+// Validates as the input is read, only the nesting and the token being read
+// are held so memory is bounded by the depth and the longest token. The
+// input has to be UTF-8, as for the other streaming readers.
+pub fn validate_reader<R: Read>(reader: R) -> Result<(), JsonError> {
+    return validate_tokens(ReaderTokens::new(reader));
+}
+
+fn validate_tokens<S: TokenSource>(mut lexer: S) -> Result<(), JsonError> {
+    // true for an object, false for an array
+    let mut stack: Vec<bool> = Vec::new();
+    let mut state = State::Value;
+    loop {
+        let token = match lexer.next_token()? {
+            Some(token) => token,
+            None if state == State::After && stack.is_empty() => return Ok(()),
+            None => return Err(JsonError::UnexpectedEndOfInput)
+        };
+
+        state = match (state, &token.value) {
+            (State::Value | State::ValueOrClose, Token::LeftBracket) => {
+                stack.push(false);
+                State::ValueOrClose
+            },
+            (State::Value | State::ValueOrClose, Token::LeftBrace) => {
+                stack.push(true);
+                State::KeyOrClose
+            },
+            (State::Value | State::ValueOrClose, Token::NumberLiteral(_) | Token::StringLiteral(_) | Token::BooleanLiteral(_) | Token::NullLiteral) => State::After,
+            (State::ValueOrClose, Token::RightBracket) | (State::KeyOrClose, Token::RightBrace) => {
+                stack.pop();
+                State::After
+            },
+            (State::Value | State::ValueOrClose, _) => return Err(JsonError::expected("value", &token)),
+            (State::Key | State::KeyOrClose, Token::StringLiteral(_)) => {
+                match lexer.next_token()? {
+                    Some(Spanned { value: Token::Colon, .. }) => State::Value,
+                    Some(other) => return Err(JsonError::expected("colon", &other)),
+                    None => return Err(JsonError::UnexpectedEndOfInput)
+                }
+            },
+            (State::Key | State::KeyOrClose, _) => return Err(JsonError::expected("string key", &token)),
+            (State::After, _) => match (stack.last(), &token.value) {
+                (None, _) => return Err(JsonError::UnexpectedToken(String::from("Unexpected content after document..."), token.span)),
+                (Some(true), Token::Comma) => State::Key,
+                (Some(false), Token::Comma) => State::Value,
+                (Some(true), Token::RightBrace) | (Some(false), Token::RightBracket) => {
+                    stack.pop();
+                    State::After
+                },
+                (Some(true), _) => return Err(JsonError::expected("comma or right brace", &token)),
+                (Some(false), _) => return Err(JsonError::expected("comma or right bracket", &token))
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::{validate, validate_reader};
+    use crate::{json, JsonError};
+
+    #[test]
+    fn agrees_with_parser() {
+        let inputs = [
+            "{\"a\": [1, true, null, {\"b\": \"\\u00e9\"}], \"c\": {}}",
+            "[]",
+            "  42  ",
+            "\"text\"",
+            "",
+            "[1 2]",
+            "[1,]",
+            "{\"a\" 1}",
+            "{\"a\": 1,}",
+            "{1: 2}",
+            "[1]]",
+            "[{\"a\": 1]",
+            "{\"a\": [1}",
+            "[1, \"open",
+            "[tru]",
+            "[\"\\q\"] x",
+            "[\"\\x41\"]",
+            "[\"\\uZZZZ\"]",
+            "[\"\\ud800\"]",
+            "[\"\\ud83d\\ude00 \\/\"]"
+        ];
+        for input in inputs {
+            assert_eq!(json(String::from(input)).err(), validate(input).err(), "{}", input);
+            assert_eq!(validate(input), validate_reader(input.as_bytes()), "{}", input);
+        }
+        for input in ["[\"\\x41\"]", "[\"\\uZZZZ\"]", "[\"\\ud800\"]"] {
+            assert!(validate(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn reader() {
+        assert_eq!(Ok(()), validate_reader("[1, 2]".as_bytes()));
+        assert_eq!(Err(JsonError::UnexpectedEndOfInput), validate_reader("[1, 2".as_bytes()));
+
+        // many chunks long, so tokens straddle the refills
+        let input = format!("[{}null]", "{\"a\": [1, \"x\"]}, ".repeat(100_000));
+        assert_eq!(Ok(()), validate_reader(input.as_bytes()));
+        assert!(validate_reader(input.replace("null]", "null").as_bytes()).is_err());
+    }
+}
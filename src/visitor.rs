@@ -0,0 +1,212 @@
+use crate::JsonExpression;
+
+// Each visit method defaults to walking into the children of the node, so an
+// implementation only overrides the node kinds it cares about. Call the
+// matching walk_* function from an override to keep recursing.
+pub trait Visitor {
+    fn visit_expression(&mut self, expression: &JsonExpression) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_object(&mut self, pairs: &[(String, Box<JsonExpression>)]) {
+        walk_object(self, pairs);
+    }
+
+    fn visit_key(&mut self, _key: &str) {}
+
+    fn visit_array(&mut self, elements: &[Box<JsonExpression>]) {
+        walk_array(self, elements);
+    }
+
+    fn visit_string(&mut self, _value: &str) {}
+
+    fn visit_number(&mut self, _value: f64) {}
+
+    fn visit_bool(&mut self, _value: bool) {}
+
+    fn visit_null(&mut self) {}
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &JsonExpression) {
+    match expression {
+        JsonExpression::Number(n) => visitor.visit_number(*n),
+        JsonExpression::String(s) => visitor.visit_string(s),
+        JsonExpression::Bool(b) => visitor.visit_bool(*b),
+        JsonExpression::Null => visitor.visit_null(),
+        JsonExpression::Array(elements) => visitor.visit_array(elements),
+        JsonExpression::Object(pairs) => visitor.visit_object(pairs),
+    }
+}
+
+pub fn walk_object<V: Visitor + ?Sized>(visitor: &mut V, pairs: &[(String, Box<JsonExpression>)]) {
+    for (key, value) in pairs {
+        visitor.visit_key(key);
+        visitor.visit_expression(value);
+    }
+}
+
+pub fn walk_array<V: Visitor + ?Sized>(visitor: &mut V, elements: &[Box<JsonExpression>]) {
+    for element in elements {
+        visitor.visit_expression(element);
+    }
+}
+
+// Same as Visitor but hands out mutable references so a pass can rewrite the
+// tree in place.
+pub trait VisitorMut {
+    fn visit_expression_mut(&mut self, expression: &mut JsonExpression) {
+        walk_expression_mut(self, expression);
+    }
+
+    fn visit_object_mut(&mut self, pairs: &mut Vec<(String, Box<JsonExpression>)>) {
+        walk_object_mut(self, pairs);
+    }
+
+    fn visit_key_mut(&mut self, _key: &mut String) {}
+
+    fn visit_array_mut(&mut self, elements: &mut Vec<Box<JsonExpression>>) {
+        walk_array_mut(self, elements);
+    }
+
+    fn visit_string_mut(&mut self, _value: &mut String) {}
+
+    fn visit_number_mut(&mut self, _value: &mut f64) {}
+
+    fn visit_bool_mut(&mut self, _value: &mut bool) {}
+
+    fn visit_null_mut(&mut self) {}
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut JsonExpression) {
+    match expression {
+        JsonExpression::Number(n) => visitor.visit_number_mut(n),
+        JsonExpression::String(s) => visitor.visit_string_mut(s),
+        JsonExpression::Bool(b) => visitor.visit_bool_mut(b),
+        JsonExpression::Null => visitor.visit_null_mut(),
+        JsonExpression::Array(elements) => visitor.visit_array_mut(elements),
+        JsonExpression::Object(pairs) => visitor.visit_object_mut(pairs),
+    }
+}
+
+pub fn walk_object_mut<V: VisitorMut + ?Sized>(visitor: &mut V, pairs: &mut [(String, Box<JsonExpression>)]) {
+    for (key, value) in pairs.iter_mut() {
+        visitor.visit_key_mut(key);
+        visitor.visit_expression_mut(value);
+    }
+}
+
+pub fn walk_array_mut<V: VisitorMut + ?Sized>(visitor: &mut V, elements: &mut [Box<JsonExpression>]) {
+    for element in elements.iter_mut() {
+        visitor.visit_expression_mut(element);
+    }
+}
+
+impl JsonExpression {
+    pub fn accept(&self, visitor: &mut impl Visitor) {
+        visitor.visit_expression(self);
+    }
+
+    pub fn accept_mut(&mut self, visitor: &mut impl VisitorMut) {
+        visitor.visit_expression_mut(self);
+    }
+
+    // Rebuilds the tree bottom up, children are mapped before the container
+    // holding them so f sees each container with its new children.
+    pub fn map_values<F: FnMut(JsonExpression) -> JsonExpression>(self, mut f: F) -> JsonExpression {
+        return map_node(self, &mut f);
+    }
+
+    // In place version of map_values, also bottom up.
+    pub fn transform<F: FnMut(&mut JsonExpression)>(&mut self, mut f: F) {
+        transform_node(self, &mut f);
+    }
+}
+
+fn map_node<F: FnMut(JsonExpression) -> JsonExpression>(expression: JsonExpression, f: &mut F) -> JsonExpression {
+    let mapped = match expression {
+        JsonExpression::Array(elements) => {
+            JsonExpression::Array(elements.into_iter().map(|e| Box::new(map_node(*e, f))).collect())
+        },
+        JsonExpression::Object(pairs) => {
+            JsonExpression::Object(pairs.into_iter().map(|(k, v)| (k, Box::new(map_node(*v, f)))).collect())
+        },
+        scalar => scalar
+    };
+    return f(mapped);
+}
+
+fn transform_node<F: FnMut(&mut JsonExpression)>(expression: &mut JsonExpression, f: &mut F) {
+    match expression {
+        JsonExpression::Array(elements) => elements.iter_mut().for_each(|e| transform_node(e, f)),
+        JsonExpression::Object(pairs) => pairs.iter_mut().for_each(|(_, v)| transform_node(v, f)),
+        _ => {}
+    }
+    f(expression);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression, Visitor, VisitorMut};
+
+    struct NumberSum {
+        total: f64
+    }
+
+    impl Visitor for NumberSum {
+        fn visit_number(&mut self, value: f64) {
+            self.total += value;
+        }
+    }
+
+    struct UppercaseStrings;
+
+    impl VisitorMut for UppercaseStrings {
+        fn visit_string_mut(&mut self, value: &mut String) {
+            *value = value.to_uppercase();
+        }
+    }
+
+    #[test]
+    fn sums_nested_numbers() {
+        let expr = json(String::from("{\"a\": 1, \"b\": [2, {\"c\": 3}]}")).unwrap();
+        let mut sum = NumberSum { total: 0.0 };
+        expr.accept(&mut sum);
+        assert_eq!(6.0, sum.total);
+    }
+
+    #[test]
+    fn maps_bottom_up() {
+        let expr = json(String::from("{\"Total\": 2, \"Items\": [{\"Price\": 1.5}]}")).unwrap();
+        let mapped = expr.map_values(|value| match value {
+            JsonExpression::Number(n) => JsonExpression::String(n.to_string()),
+            JsonExpression::Object(pairs) => JsonExpression::Object(pairs.into_iter().map(|(k, v)| (k.to_lowercase(), v)).collect()),
+            other => other
+        });
+        assert_eq!(json(String::from("{\"total\": \"2\", \"items\": [{\"price\": \"1.5\"}]}")).unwrap(), mapped);
+
+        let mut order: Vec<String> = Vec::new();
+        let mut expr = json(String::from("[[1], 2]")).unwrap();
+        expr.transform(|value| {
+            order.push(value.to_string());
+            if let JsonExpression::Number(n) = value {
+                *n *= 10.0;
+            }
+        });
+        assert_eq!(vec!["1", "[10]", "2", "[[10],20]"], order);
+    }
+
+    #[test]
+    fn rewrites_strings_in_place() {
+        let mut expr = json(String::from("[\"a\", [\"b\"]]")).unwrap();
+        expr.accept_mut(&mut UppercaseStrings);
+        assert_eq!(
+            JsonExpression::Array(vec![
+                Box::new(JsonExpression::String(String::from("A"))),
+                Box::new(JsonExpression::Array(vec![
+                    Box::new(JsonExpression::String(String::from("B"))),
+                ])),
+            ]),
+            expr
+        );
+    }
+}
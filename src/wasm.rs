@@ -0,0 +1,49 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{json, JsonExpression, JsonPath};
+
+// Parsed documents stay on the Rust side and JS holds an opaque handle, so
+// repeated queries don't pay for re-parsing or copying the tree across.
+#[wasm_bindgen]
+pub struct JsonValue {
+    expression: JsonExpression
+}
+
+#[wasm_bindgen]
+impl JsonValue {
+    pub fn stringify(&self, pretty: bool) -> String {
+        if pretty {
+            return self.expression.to_string_pretty();
+        }
+
+        return self.expression.to_string();
+    }
+
+    pub fn query(&self, path: &str) -> Result<Option<JsonValue>, JsError> {
+        let path: JsonPath = path.parse().map_err(|e: String| JsError::new(&e))?;
+        return Ok(self.expression.get_at(&path).map(|e| JsonValue { expression: e.clone() }));
+    }
+
+    #[wasm_bindgen(js_name = typeName)]
+    pub fn type_name(&self) -> String {
+        return String::from(self.expression.type_name());
+    }
+}
+
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<JsonValue, JsError> {
+    return match json(String::from(source)) {
+        Ok(expression) => Ok(JsonValue { expression }),
+        Err(e) => Err(JsError::new(&e.to_string()))
+    };
+}
+
+#[wasm_bindgen]
+pub fn stringify(value: &JsonValue, pretty: bool) -> String {
+    return value.stringify(pretty);
+}
+
+#[wasm_bindgen]
+pub fn query(value: &JsonValue, path: &str) -> Result<Option<JsonValue>, JsError> {
+    return value.query(path);
+}
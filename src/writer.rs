@@ -0,0 +1,391 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::serialize::{escape_string, short_escape, write_expression, write_number, NumberFormat};
+#[cfg(feature = "unicode")]
+use crate::normalize::Normalization;
+use crate::JsonExpression;
+
+enum Frame {
+    Array { count: usize },
+    // after_key is set between a key and its value
+    Object { count: usize, after_key: bool }
+}
+
+// Writes JSON from a sequence of events straight to an io::Write, so large
+// documents can be produced without building a tree first. Nesting is checked
+// as events arrive and a misplaced event is an InvalidInput error. The output
+// is the same as Display, or to_string_pretty when made with `pretty`.
+pub struct JsonWriter<W: Write> {
+    out: W,
+    indent: Option<String>,
+    stack: Vec<Frame>,
+    // a complete top level value has been written
+    done: bool,
+    numbers: NumberFormat,
+    scratch: String,
+    #[cfg(feature = "unicode")]
+    normalization: Option<Normalization>
+}
+
+fn invalid(message: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidInput, String::from(message));
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        return Self {
+            out,
+            indent: None,
+            stack: vec![],
+            done: false,
+            numbers: NumberFormat::default(),
+            scratch: String::new(),
+            #[cfg(feature = "unicode")]
+            normalization: None
+        };
+    }
+
+    pub fn pretty(out: W, indent: &str) -> Self {
+        return Self { indent: Some(String::from(indent)), ..Self::new(out) };
+    }
+
+    // Writes numbers, including those inside `value`, in the given format.
+    pub fn number_format(mut self, numbers: NumberFormat) -> Self {
+        self.numbers = numbers;
+        return self;
+    }
+
+    // Writes keys and strings, including those inside `value`, in the given
+    // normalization form. Strings written with string_with go out as given.
+    #[cfg(feature = "unicode")]
+    pub fn normalization(mut self, form: Option<Normalization>) -> Self {
+        self.normalization = form;
+        return self;
+    }
+
+    // Text as it should be written, normalized if that was asked for.
+    fn text<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        #[cfg(feature = "unicode")]
+        if let Some(form) = self.normalization {
+            return form.normalize(s);
+        }
+        return Cow::Borrowed(s);
+    }
+
+    fn newline(&mut self, depth: usize) -> io::Result<()> {
+        if let Some(indent) = &self.indent {
+            self.out.write_all(b"\n")?;
+            for _ in 0..depth {
+                self.out.write_all(indent.as_bytes())?;
+            }
+        }
+        return Ok(());
+    }
+
+    // Checks a value is allowed here and writes any separator before it.
+    fn begin_value(&mut self) -> io::Result<()> {
+        let depth = self.stack.len();
+        match self.stack.last() {
+            None if self.done => return Err(invalid("Only one top level value can be written...")),
+            None => {},
+            Some(Frame::Array { count }) => {
+                if *count > 0 {
+                    self.out.write_all(b",")?;
+                }
+                self.newline(depth)?;
+            },
+            Some(Frame::Object { after_key: false, .. }) => return Err(invalid("Expected key before value in object...")),
+            Some(Frame::Object { after_key: true, .. }) => {},
+        }
+        return Ok(());
+    }
+
+    fn end_value(&mut self) {
+        match self.stack.last_mut() {
+            None => self.done = true,
+            Some(Frame::Array { count }) => *count += 1,
+            Some(Frame::Object { count, after_key }) => {
+                *count += 1;
+                *after_key = false;
+            }
+        }
+    }
+
+    fn write_scalar(&mut self) -> io::Result<()> {
+        self.out.write_all(self.scratch.as_bytes())?;
+        self.scratch.clear();
+        self.end_value();
+        return Ok(());
+    }
+
+    pub fn start_object(&mut self) -> io::Result<()> {
+        self.begin_value()?;
+        self.out.write_all(b"{")?;
+        self.stack.push(Frame::Object { count: 0, after_key: false });
+        return Ok(());
+    }
+
+    pub fn end_object(&mut self) -> io::Result<()> {
+        let count = match self.stack.last() {
+            Some(Frame::Object { count, after_key: false }) => *count,
+            Some(Frame::Object { .. }) => return Err(invalid("Expected value after key...")),
+            _ => return Err(invalid("No object to end..."))
+        };
+
+        self.stack.pop();
+        if count > 0 {
+            self.newline(self.stack.len())?;
+        }
+        self.out.write_all(b"}")?;
+        self.end_value();
+        return Ok(());
+    }
+
+    pub fn start_array(&mut self) -> io::Result<()> {
+        self.begin_value()?;
+        self.out.write_all(b"[")?;
+        self.stack.push(Frame::Array { count: 0 });
+        return Ok(());
+    }
+
+    pub fn end_array(&mut self) -> io::Result<()> {
+        let count = match self.stack.last() {
+            Some(Frame::Array { count }) => *count,
+            _ => return Err(invalid("No array to end..."))
+        };
+
+        self.stack.pop();
+        if count > 0 {
+            self.newline(self.stack.len())?;
+        }
+        self.out.write_all(b"]")?;
+        self.end_value();
+        return Ok(());
+    }
+
+    pub fn key(&mut self, key: &str) -> io::Result<()> {
+        let count = match self.stack.last() {
+            Some(Frame::Object { count, after_key: false }) => *count,
+            Some(Frame::Object { .. }) => return Err(invalid("Expected value after key...")),
+            _ => return Err(invalid("Keys can only be written inside an object..."))
+        };
+
+        if count > 0 {
+            self.out.write_all(b",")?;
+        }
+        self.newline(self.stack.len())?;
+
+        let key = self.text(key);
+        escape_string(&key, &mut self.scratch);
+        self.scratch.push(':');
+        if self.indent.is_some() {
+            self.scratch.push(' ');
+        }
+        self.out.write_all(self.scratch.as_bytes())?;
+        self.scratch.clear();
+
+        if let Some(Frame::Object { after_key, .. }) = self.stack.last_mut() {
+            *after_key = true;
+        }
+        return Ok(());
+    }
+
+    pub fn string(&mut self, s: &str) -> io::Result<()> {
+        self.begin_value()?;
+        let s = self.text(s);
+        escape_string(&s, &mut self.scratch);
+        return self.write_scalar();
+    }
+
+    pub fn number(&mut self, n: f64) -> io::Result<()> {
+        self.begin_value()?;
+        write_number(n, &self.numbers, &mut self.scratch);
+        return self.write_scalar();
+    }
+
+    pub fn bool(&mut self, b: bool) -> io::Result<()> {
+        self.begin_value()?;
+        self.scratch.push_str(if b { "true" } else { "false" });
+        return self.write_scalar();
+    }
+
+    pub fn null(&mut self) -> io::Result<()> {
+        self.begin_value()?;
+        self.scratch.push_str("null");
+        return self.write_scalar();
+    }
+
+    // Writes a string value in pieces, for one too long to hold in memory.
+    // The closure is handed a JsonStringWriter for the string's content.
+    pub fn string_with<F: FnOnce(&mut JsonStringWriter<&mut W>) -> io::Result<()>>(&mut self, write: F) -> io::Result<()> {
+        self.begin_value()?;
+        let mut string = JsonStringWriter::new(&mut self.out);
+        write(&mut string)?;
+        string.finish()?;
+        self.end_value();
+        return Ok(());
+    }
+
+    // Writes a whole value as a single event.
+    pub fn value(&mut self, value: &JsonExpression) -> io::Result<()> {
+        self.begin_value()?;
+        #[cfg(feature = "unicode")]
+        let normalized;
+        #[cfg(feature = "unicode")]
+        let value = match self.normalization {
+            Some(form) => {
+                normalized = value.normalized(form);
+                &normalized
+            },
+            None => value
+        };
+        write_expression(value, &mut self.scratch, self.indent.as_deref(), 0, &self.numbers, self.stack.len());
+        return self.write_scalar();
+    }
+
+    // Fails if the document is incomplete, otherwise hands back the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.stack.is_empty() || !self.done {
+            return Err(invalid("Document is incomplete..."));
+        }
+        self.out.flush()?;
+        return Ok(self.out);
+    }
+}
+
+// Escapes whatever is written to it into a JSON string on `out`, so a long
+// string can be produced chunk by chunk. The opening quote goes out with the
+// first write and the closing one on finish. Bytes at or above 0x80 pass
+// through untouched, so a multi-byte character may be split across writes,
+// but the whole content should be UTF-8.
+pub struct JsonStringWriter<W: Write> {
+    out: W,
+    started: bool
+}
+
+impl<W: Write> JsonStringWriter<W> {
+    pub fn new(out: W) -> Self {
+        return Self { out, started: false };
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if !self.started {
+            self.out.write_all(b"\"")?;
+            self.started = true;
+        }
+        return Ok(());
+    }
+
+    // Closes the string and hands back the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start()?;
+        self.out.write_all(b"\"")?;
+        return Ok(self.out);
+    }
+}
+
+impl<W: Write> Write for JsonStringWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.start()?;
+        // runs of bytes that need no escaping are written in one go
+        let mut plain: usize = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            if b >= 0x20 && b != b'"' && b != b'\\' {
+                continue;
+            }
+
+            self.out.write_all(&buf[plain..i])?;
+            match short_escape(b) {
+                Some(escape) => self.out.write_all(escape.as_bytes())?,
+                None => write!(self.out, "\\u{:04x}", b)?
+            }
+            plain = i + 1;
+        }
+        self.out.write_all(&buf[plain..])?;
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::writer::{JsonStringWriter, JsonWriter};
+    use crate::json;
+
+    #[test]
+    fn matches_tree_serialization() {
+        let expr = json(String::from("{\"a\": [1, \"x\\n\", true, null, {}], \"b\": {\"c\": []}}")).unwrap();
+
+        let write = |writer: &mut JsonWriter<Vec<u8>>| {
+            writer.start_object().unwrap();
+            writer.key("a").unwrap();
+            writer.start_array().unwrap();
+            writer.number(1.0).unwrap();
+            writer.string("x\n").unwrap();
+            writer.bool(true).unwrap();
+            writer.null().unwrap();
+            writer.start_object().unwrap();
+            writer.end_object().unwrap();
+            writer.end_array().unwrap();
+            writer.key("b").unwrap();
+            writer.value(&json(String::from("{\"c\": []}")).unwrap()).unwrap();
+            writer.end_object().unwrap();
+        };
+
+        let mut compact = JsonWriter::new(Vec::new());
+        write(&mut compact);
+        assert_eq!(expr.to_string().into_bytes(), compact.finish().unwrap());
+
+        let mut pretty = JsonWriter::pretty(Vec::new(), "    ");
+        write(&mut pretty);
+        assert_eq!(expr.to_string_pretty().into_bytes(), pretty.finish().unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_nesting() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.start_object().unwrap();
+        assert!(writer.number(1.0).is_err());
+        assert!(writer.end_array().is_err());
+        writer.key("a").unwrap();
+        assert!(writer.key("b").is_err());
+        assert!(writer.end_object().is_err());
+        writer.null().unwrap();
+        writer.end_object().unwrap();
+        assert!(writer.null().is_err());
+
+        let mut incomplete = JsonWriter::new(Vec::new());
+        incomplete.start_array().unwrap();
+        assert!(incomplete.finish().is_err());
+    }
+
+    #[test]
+    fn escapes_in_chunks() {
+        let text = "quote \" slash \\ tab \t bell \u{7} é 😀";
+        let mut string = JsonStringWriter::new(Vec::new());
+        // single bytes split the multi-byte characters between writes
+        for b in text.as_bytes() {
+            string.write_all(&[*b]).unwrap();
+        }
+        let mut expected = String::new();
+        crate::serialize::escape_string(text, &mut expected);
+        assert_eq!(expected.into_bytes(), string.finish().unwrap());
+        assert_eq!(b"\"\"".to_vec(), JsonStringWriter::new(Vec::new()).finish().unwrap());
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.start_array().unwrap();
+        writer.string_with(|s| {
+            s.write_all(b"a\n")?;
+            return s.write_all(b"b");
+        }).unwrap();
+        writer.null().unwrap();
+        writer.end_array().unwrap();
+        assert_eq!(b"[\"a\\nb\",null]".to_vec(), writer.finish().unwrap());
+    }
+}
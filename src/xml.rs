@@ -0,0 +1,465 @@
+use crate::JsonExpression;
+
+// The mapping is the usual lossy one: a document becomes {"root": ...}, an
+// element without attributes or children becomes its text (null when empty),
+// otherwise an object with attributes under `@name`, text under `#text` and
+// child elements by name, repeated names collecting into an array. Comments,
+// processing instructions and the doctype are dropped, and mixed content
+// loses the position of text relative to child elements.
+#[derive(Debug, Clone)]
+pub struct XmlOptions {
+    pub attribute_prefix: String,
+    pub text_key: String,
+    // strip surrounding whitespace from text and ignore whitespace-only text
+    pub trim_text: bool
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        return Self {
+            attribute_prefix: String::from("@"),
+            text_key: String::from("#text"),
+            trim_text: true
+        };
+    }
+}
+
+// Elements nested deeper than this are rejected, the parser recurses once
+// per level and XML often comes from outside.
+const MAX_DEPTH: usize = 256;
+
+// Scans the source in place. Everything the grammar looks for is ASCII, so
+// `current` is a byte offset that only ever stops on a character boundary.
+struct XmlParser<'a> {
+    source: &'a str,
+    current: usize,
+    // elements around the one being parsed
+    depth: usize,
+    options: &'a XmlOptions
+}
+
+impl<'a> XmlParser<'a> {
+    fn peek_str(&self, s: &str) -> bool {
+        return self.source[self.current..].starts_with(s);
+    }
+
+    fn peek(&self) -> Option<u8> {
+        return self.source.as_bytes().get(self.current).copied();
+    }
+
+    fn skip_until(&mut self, end: &str) -> Result<&'a str, String> {
+        match self.source[self.current..].find(end) {
+            Some(n) => {
+                let s = &self.source[self.current..self.current + n];
+                self.current += n + end.len();
+                return Ok(s);
+            },
+            None => {
+                self.current = self.source.len();
+                return Err(format!("Expected '{}' before end of XML input...", end));
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.current += 1;
+        }
+    }
+
+    // Skips the prolog or epilog: whitespace, comments, PIs and the doctype.
+    fn skip_misc(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace();
+            if self.peek_str("<!--") {
+                self.skip_until("-->")?;
+            } else if self.peek_str("<?") {
+                self.skip_until("?>")?;
+            } else if self.peek_str("<!DOCTYPE") {
+                self.skip_doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_doctype(&mut self) -> Result<(), String> {
+        // the internal subset can contain '>' so track brackets
+        let mut depth: usize = 0;
+        while let Some(b) = self.peek() {
+            match b {
+                b'[' => depth += 1,
+                b']' => depth = depth.saturating_sub(1),
+                b'>' if depth == 0 => {
+                    self.current += 1;
+                    return Ok(());
+                },
+                _ => {}
+            }
+            self.current += 1;
+        }
+
+        return Err(String::from("Unterminated DOCTYPE..."));
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let start = self.current;
+        while let Some(b) = self.peek() {
+            if b.is_ascii_whitespace() || b == b'/' || b == b'>' || b == b'=' {
+                break;
+            }
+            self.current += 1;
+        }
+
+        if start == self.current {
+            return Err(format!("Expected XML name at {}...", start));
+        }
+
+        return Ok(String::from(&self.source[start..self.current]));
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.current += 1;
+            return Ok(());
+        }
+
+        return Err(format!("Expected '{}' at {} in XML...", c as char, self.current));
+    }
+
+    fn element(&mut self) -> Result<(String, JsonExpression), String> {
+        self.expect(b'<')?;
+        let name = self.name()?;
+        if self.depth == MAX_DEPTH {
+            return Err(format!("XML nested deeper than {} elements...", MAX_DEPTH));
+        }
+        self.depth += 1;
+        let element = self.content(name);
+        self.depth -= 1;
+        return element;
+    }
+
+    // The attributes and content of an element whose name has been read.
+    fn content(&mut self, name: String) -> Result<(String, JsonExpression), String> {
+        let mut pairs: Vec<(String, Box<JsonExpression>)> = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            if self.peek_str("/>") {
+                self.current += 2;
+                return Ok((name, self.finish(pairs, String::new())));
+            }
+            if self.peek_str(">") {
+                self.current += 1;
+                break;
+            }
+
+            let attribute = self.name()?;
+            self.skip_whitespace();
+            self.expect(b'=')?;
+            self.skip_whitespace();
+
+            let quote = match self.peek() {
+                Some(b'"') => "\"",
+                Some(b'\'') => "'",
+                _ => return Err(format!("Expected quoted value for attribute {}...", attribute))
+            };
+            self.current += 1;
+            let raw = self.skip_until(quote)?;
+            let key = format!("{}{}", self.options.attribute_prefix, attribute);
+            pairs.push((key, Box::new(JsonExpression::String(decode_entities(raw)?))));
+        }
+
+        let mut text = String::new();
+        loop {
+            if self.current >= self.source.len() {
+                return Err(format!("Unterminated element <{}>...", name));
+            }
+
+            if self.peek_str("</") {
+                self.current += 2;
+                let closing = self.name()?;
+                if closing != name {
+                    return Err(format!("Expected </{}>, found </{}>...", name, closing));
+                }
+                self.skip_whitespace();
+                self.expect(b'>')?;
+                return Ok((name, self.finish(pairs, text)));
+            } else if self.peek_str("<!--") {
+                self.skip_until("-->")?;
+            } else if self.peek_str("<?") {
+                self.skip_until("?>")?;
+            } else if self.peek_str("<![CDATA[") {
+                self.current += 9;
+                text.push_str(self.skip_until("]]>")?);
+            } else if self.peek_str("<") {
+                let (child, value) = self.element()?;
+                push_child(&mut pairs, child, value);
+            } else {
+                let start = self.current;
+                self.current = self.source[start..].find('<').map_or(self.source.len(), |n| start + n);
+                text.push_str(&decode_entities(&self.source[start..self.current])?);
+            }
+        }
+    }
+
+    fn finish(&self, mut pairs: Vec<(String, Box<JsonExpression>)>, text: String) -> JsonExpression {
+        let text = if self.options.trim_text { String::from(text.trim()) } else { text };
+
+        if pairs.is_empty() {
+            if text.is_empty() {
+                return JsonExpression::Null;
+            }
+            return JsonExpression::String(text);
+        }
+
+        if !text.is_empty() {
+            pairs.push((self.options.text_key.clone(), Box::new(JsonExpression::String(text))));
+        }
+        return JsonExpression::Object(pairs);
+    }
+}
+
+fn push_child(pairs: &mut Vec<(String, Box<JsonExpression>)>, name: String, value: JsonExpression) {
+    match pairs.iter_mut().find(|(k, _)| *k == name) {
+        Some((_, existing)) => {
+            if let JsonExpression::Array(elements) = existing.as_mut() {
+                elements.push(Box::new(value));
+                return;
+            }
+
+            let first = std::mem::replace(existing.as_mut(), JsonExpression::Null);
+            **existing = JsonExpression::Array(vec![Box::new(first), Box::new(value)]);
+        },
+        None => pairs.push((name, Box::new(value)))
+    }
+}
+
+fn decode_entities(raw: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = match rest[start..].find(';') {
+            Some(end) => start + end,
+            None => return Err(String::from("Unterminated XML entity..."))
+        };
+
+        let entity = &rest[start + 1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => entity[1..].parse::<u32>().ok().and_then(char::from_u32),
+            _ => None
+        };
+
+        match decoded {
+            Some(c) => out.push(c),
+            None => return Err(format!("Unknown XML entity &{};...", entity))
+        }
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    return Ok(out);
+}
+
+fn escape_xml(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn text_of(expression: &JsonExpression) -> Option<String> {
+    match expression {
+        JsonExpression::String(s) => return Some(s.clone()),
+        JsonExpression::Number(_) | JsonExpression::Bool(_) => return Some(expression.to_string()),
+        _ => return None
+    }
+}
+
+// the NameStartChar and NameChar productions of XML 1.0
+fn is_name_start(c: char) -> bool {
+    return matches!(c, ':' | 'A'..='Z' | '_' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}');
+}
+
+fn is_name_char(c: char) -> bool {
+    return is_name_start(c) || matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}');
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(first) => is_name_start(first) && chars.all(is_name_char),
+        None => false
+    };
+
+    if !valid {
+        return Err(format!("Key {:?} is not a valid XML name...", name));
+    }
+
+    return Ok(());
+}
+
+fn write_element(name: &str, value: &JsonExpression, options: &XmlOptions, out: &mut String) -> Result<(), String> {
+    if let JsonExpression::Array(elements) = value {
+        for element in elements {
+            write_element(name, element, options, out)?;
+        }
+        return Ok(());
+    }
+
+    check_name(name)?;
+    out.push('<');
+    out.push_str(name);
+
+    let mut text = String::new();
+    let mut children: Vec<(&str, &JsonExpression)> = Vec::new();
+    match value {
+        JsonExpression::Object(pairs) => {
+            for (key, child) in pairs {
+                if let Some(attribute) = key.strip_prefix(options.attribute_prefix.as_str()) {
+                    let attribute_value = match text_of(child) {
+                        Some(v) => v,
+                        None => return Err(format!("Attribute {} must be a scalar...", key))
+                    };
+                    check_name(attribute)?;
+                    out.push(' ');
+                    out.push_str(attribute);
+                    out.push_str("=\"");
+                    escape_xml(&attribute_value, out);
+                    out.push('"');
+                } else if *key == options.text_key {
+                    text = text_of(child).unwrap_or_default();
+                } else {
+                    children.push((key, child));
+                }
+            }
+        },
+        _ => text = text_of(value).unwrap_or_default()
+    }
+
+    if text.is_empty() && children.is_empty() {
+        out.push_str("/>");
+        return Ok(());
+    }
+
+    out.push('>');
+    escape_xml(&text, out);
+    for (child_name, child) in children {
+        write_element(child_name, child, options, out)?;
+    }
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+    return Ok(());
+}
+
+impl JsonExpression {
+    pub fn from_xml(source: &str, options: &XmlOptions) -> Result<JsonExpression, String> {
+        let mut parser = XmlParser { source, current: 0, depth: 0, options };
+        parser.skip_misc()?;
+        let (name, value) = parser.element()?;
+        parser.skip_misc()?;
+
+        if parser.current != parser.source.len() {
+            return Err(String::from("Unexpected content after the XML root element..."));
+        }
+
+        return Ok(JsonExpression::Object(vec![(name, Box::new(value))]));
+    }
+
+    // Expects an object with a single key naming the root element.
+    pub fn to_xml(&self, options: &XmlOptions) -> Result<String, String> {
+        match self {
+            JsonExpression::Object(pairs) if pairs.len() == 1 && !matches!(*pairs[0].1, JsonExpression::Array(_)) => {
+                let mut out = String::new();
+                write_element(&pairs[0].0, &pairs[0].1, options, &mut out)?;
+                return Ok(out);
+            },
+            _ => return Err(String::from("Expected an object with a single root element for XML..."))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xml::XmlOptions;
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn from_xml() {
+        let source = "<?xml version=\"1.0\"?>
+            <!-- catalog -->
+            <catalog id=\"c1\">
+                <book lang='en'>Dune &amp; Sons</book>
+                <book><![CDATA[<raw>]]></book>
+                <empty/>
+            </catalog>";
+
+        assert_eq!(
+            json(String::from("{\"catalog\": {
+                \"@id\": \"c1\",
+                \"book\": [{\"@lang\": \"en\", \"#text\": \"Dune & Sons\"}, \"<raw>\"],
+                \"empty\": null
+            }}")).unwrap(),
+            JsonExpression::from_xml(source, &XmlOptions::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let expr = json(String::from("{\"a\": {\"@x\": \"1 < 2\", \"b\": [\"one\", \"two\"], \"c\": {\"#text\": \"t\", \"@y\": \"z\"}}}")).unwrap();
+        let xml = expr.to_xml(&XmlOptions::default()).unwrap();
+        assert_eq!("<a x=\"1 &lt; 2\"><b>one</b><b>two</b><c y=\"z\">t</c></a>", xml);
+
+        // attribute and text keys come back in document order
+        let expected = json(String::from("{\"a\": {\"@x\": \"1 < 2\", \"b\": [\"one\", \"two\"], \"c\": {\"@y\": \"z\", \"#text\": \"t\"}}}")).unwrap();
+        assert_eq!(expected, JsonExpression::from_xml(&xml, &XmlOptions::default()).unwrap());
+    }
+
+    #[test]
+    fn limits_depth() {
+        let nested = |depth: usize| format!("{}x{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(JsonExpression::from_xml(&nested(256), &XmlOptions::default()).is_ok());
+        assert_eq!(Err(String::from("XML nested deeper than 256 elements...")), JsonExpression::from_xml(&nested(257), &XmlOptions::default()));
+        assert!(JsonExpression::from_xml(&"<a>".repeat(1_000_000), &XmlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn non_ascii() {
+        let xml = "<größe einheit=\"°C\">21 – 22<!-- ü --><wert>ä</wert></größe>";
+        let expected = json(String::from("{\"größe\": {\"@einheit\": \"°C\", \"wert\": \"ä\", \"#text\": \"21 – 22\"}}")).unwrap();
+        assert_eq!(expected, JsonExpression::from_xml(xml, &XmlOptions::default()).unwrap());
+        assert!(JsonExpression::from_xml("<größe>", &XmlOptions::default()).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(JsonExpression::from_xml("<a><b></a>", &XmlOptions::default()).is_err());
+        assert!(JsonExpression::from_xml("<a/><b/>", &XmlOptions::default()).is_err());
+        assert!(JsonExpression::Number(1.0).to_xml(&XmlOptions::default()).is_err());
+
+        // keys that are not XML names would produce broken or injected markup
+        for source in ["{\"a b><x\": 1}", "{\"\": 1}", "{\"1a\": 1}", "{\"a\": {\"@x=\\\"y\": 1}}", "{\"a\": {\"b<\": [1]}}"] {
+            assert!(json(String::from(source)).unwrap().to_xml(&XmlOptions::default()).is_err(), "{}", source);
+        }
+        assert!(json(String::from("{\"a:b\": {\"c-d.e_1\": 1, \"@é\": 2}}")).unwrap().to_xml(&XmlOptions::default()).is_ok());
+    }
+}
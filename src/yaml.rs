@@ -0,0 +1,115 @@
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader};
+
+use crate::JsonExpression;
+
+fn from_yaml_node(node: &Yaml) -> Result<JsonExpression, String> {
+    match node {
+        Yaml::Real(_) => match node.as_f64() {
+            Some(n) => return Ok(JsonExpression::Number(n)),
+            None => return Err(String::from("Invalid YAML float..."))
+        },
+        Yaml::Integer(n) => return Ok(JsonExpression::Number(*n as f64)),
+        Yaml::String(s) => return Ok(JsonExpression::String(s.clone())),
+        Yaml::Boolean(b) => return Ok(JsonExpression::Bool(*b)),
+        Yaml::Null => return Ok(JsonExpression::Null),
+        Yaml::Array(elements) => {
+            let mut out: Vec<Box<JsonExpression>> = Vec::new();
+            for element in elements {
+                out.push(Box::new(from_yaml_node(element)?));
+            }
+            return Ok(JsonExpression::Array(out));
+        },
+        Yaml::Hash(hash) => {
+            let mut pairs: Vec<(String, Box<JsonExpression>)> = Vec::new();
+            for (key, value) in hash {
+                pairs.push((yaml_key(key)?, Box::new(from_yaml_node(value)?)));
+            }
+            return Ok(JsonExpression::Object(pairs));
+        },
+        Yaml::Alias(_) => return Err(String::from("YAML aliases are not supported...")),
+        Yaml::BadValue => return Err(String::from("Invalid YAML value..."))
+    }
+}
+
+// JSON keys are always strings, so scalar YAML keys are stringified and
+// anything more complex is rejected.
+fn yaml_key(key: &Yaml) -> Result<String, String> {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => return Ok(s.clone()),
+        Yaml::Integer(n) => return Ok(n.to_string()),
+        Yaml::Boolean(b) => return Ok(b.to_string()),
+        Yaml::Null => return Ok(String::from("null")),
+        _ => return Err(String::from("Unsupported YAML mapping key..."))
+    }
+}
+
+fn to_yaml_node(expression: &JsonExpression) -> Yaml {
+    match expression {
+        JsonExpression::Number(n) => {
+            if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 {
+                return Yaml::Integer(*n as i64);
+            }
+            return Yaml::Real(n.to_string());
+        },
+        JsonExpression::String(s) => return Yaml::String(s.clone()),
+        JsonExpression::Bool(b) => return Yaml::Boolean(*b),
+        JsonExpression::Null => return Yaml::Null,
+        JsonExpression::Array(elements) => return Yaml::Array(elements.iter().map(|e| to_yaml_node(e)).collect()),
+        JsonExpression::Object(pairs) => {
+            let mut hash = Hash::new();
+            for (key, value) in pairs {
+                hash.insert(Yaml::String(key.clone()), to_yaml_node(value));
+            }
+            return Yaml::Hash(hash);
+        },
+    }
+}
+
+impl JsonExpression {
+    // Only the first document of a multi-document stream is converted, an
+    // empty stream is null.
+    pub fn from_yaml(source: &str) -> Result<JsonExpression, String> {
+        let documents = YamlLoader::load_from_str(source).map_err(|e| e.to_string())?;
+        match documents.first() {
+            Some(document) => return from_yaml_node(document),
+            None => return Ok(JsonExpression::Null)
+        }
+    }
+
+    pub fn to_yaml(&self) -> String {
+        let mut out = String::new();
+        YamlEmitter::new(&mut out).dump(&to_yaml_node(self)).unwrap();
+        out.push('\n');
+        return out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json, JsonExpression};
+
+    #[test]
+    fn from_yaml() {
+        let expr = JsonExpression::from_yaml("
+name: jsonrs
+version: 1.5
+tags: [a, b]
+nested:
+  enabled: true
+  port: 8080
+  missing: ~
+").unwrap();
+
+        assert_eq!(
+            json(String::from("{\"name\": \"jsonrs\", \"version\": 1.5, \"tags\": [\"a\", \"b\"], \"nested\": {\"enabled\": true, \"port\": 8080, \"missing\": null}}")).unwrap(),
+            expr
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let expr = json(String::from("{\"a\": [1, 2.5, \"x: y\"], \"b\": {\"c\": null, \"d\": \"true\"}}")).unwrap();
+        assert_eq!(Ok(expr.clone()), JsonExpression::from_yaml(&expr.to_yaml()));
+    }
+}
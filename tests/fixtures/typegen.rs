@@ -0,0 +1,141 @@
+use jsonrs::{FromJson, JsonExpression, ToJson};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Root {
+    pub self_: Self2,
+    pub self_2: i64,
+    pub a_b: String,
+    pub a_b_2: String,
+    pub field: bool,
+    pub field_2: bool,
+    pub r#type: f64,
+    pub string: String2,
+    pub field_1st: Option<JsonExpression>,
+    pub items: Vec<Item>,
+    pub item: Item2,
+}
+
+impl FromJson for Root {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        let field = |key: &str| value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v).unwrap_or(&JsonExpression::Null);
+        return Ok(Self {
+            self_: FromJson::from_json(field("self"))?,
+            self_2: FromJson::from_json(field("Self"))?,
+            a_b: FromJson::from_json(field("a-b"))?,
+            a_b_2: FromJson::from_json(field("a_b"))?,
+            field: FromJson::from_json(field(""))?,
+            field_2: FromJson::from_json(field("é"))?,
+            r#type: FromJson::from_json(field("type"))?,
+            string: FromJson::from_json(field("string"))?,
+            field_1st: FromJson::from_json(field("1st"))?,
+            items: FromJson::from_json(field("items"))?,
+            item: FromJson::from_json(field("Item"))?,
+        });
+    }
+}
+
+impl ToJson for Root {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Object(vec![
+            (String::from("self"), Box::new(self.self_.to_json())),
+            (String::from("Self"), Box::new(self.self_2.to_json())),
+            (String::from("a-b"), Box::new(self.a_b.to_json())),
+            (String::from("a_b"), Box::new(self.a_b_2.to_json())),
+            (String::from(""), Box::new(self.field.to_json())),
+            (String::from("é"), Box::new(self.field_2.to_json())),
+            (String::from("type"), Box::new(self.r#type.to_json())),
+            (String::from("string"), Box::new(self.string.to_json())),
+            (String::from("1st"), Box::new(self.field_1st.to_json())),
+            (String::from("items"), Box::new(self.items.to_json())),
+            (String::from("Item"), Box::new(self.item.to_json())),
+        ]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item2 {
+    pub n: i64,
+}
+
+impl FromJson for Item2 {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        let field = |key: &str| value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v).unwrap_or(&JsonExpression::Null);
+        return Ok(Self {
+            n: FromJson::from_json(field("n"))?,
+        });
+    }
+}
+
+impl ToJson for Item2 {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Object(vec![
+            (String::from("n"), Box::new(self.n.to_json())),
+        ]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub id: i64,
+}
+
+impl FromJson for Item {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        let field = |key: &str| value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v).unwrap_or(&JsonExpression::Null);
+        return Ok(Self {
+            id: FromJson::from_json(field("id"))?,
+        });
+    }
+}
+
+impl ToJson for Item {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Object(vec![
+            (String::from("id"), Box::new(self.id.to_json())),
+        ]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct String2 {
+    pub crate_: String,
+}
+
+impl FromJson for String2 {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        let field = |key: &str| value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v).unwrap_or(&JsonExpression::Null);
+        return Ok(Self {
+            crate_: FromJson::from_json(field("crate"))?,
+        });
+    }
+}
+
+impl ToJson for String2 {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Object(vec![
+            (String::from("crate"), Box::new(self.crate_.to_json())),
+        ]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Self2 {
+    pub super_: i64,
+}
+
+impl FromJson for Self2 {
+    fn from_json(value: &JsonExpression) -> Result<Self, String> {
+        let field = |key: &str| value.entries().filter(|(k, _)| *k == key).last().map(|(_, v)| v).unwrap_or(&JsonExpression::Null);
+        return Ok(Self {
+            super_: FromJson::from_json(field("super"))?,
+        });
+    }
+}
+
+impl ToJson for Self2 {
+    fn to_json(&self) -> JsonExpression {
+        return JsonExpression::Object(vec![
+            (String::from("super"), Box::new(self.super_.to_json())),
+        ]);
+    }
+}